@@ -0,0 +1,41 @@
+#![feature(test)]
+
+extern crate test;
+use dears::crossover::OnePoint;
+use dears::mutation::Gaussian;
+use dears::population::*;
+use dears::selection::SelBest;
+use test::Bencher;
+
+const POP_SIZE: usize = 1_000;
+
+/// A deliberately expensive fitness function
+fn rastrigin_slowly(genome: &[f64]) -> f64 {
+    (0..200).map(|_| {
+        genome.iter().map(|x| x * x - 10.0 * (2.0 * std::f64::consts::PI * x).cos() + 10.0).sum::<f64>()
+    }).sum()
+}
+
+fn population() -> Population<Vec<f64>, Gaussian, OnePoint, SelBest, f64> {
+    let individuals = (0..POP_SIZE).map(|i| vec![i as f64 / 100.0; 30]).collect();
+    let mutator = Gaussian { mu: 0.0, sigma: 1.0, indpb: 0.1 };
+    Population::new(individuals, mutator, OnePoint, SelBest::default())
+}
+
+#[bench]
+fn serial_evaluation(b: &mut Bencher) {
+    let mut pop = population();
+    b.iter(|| {
+        pop.invalidate_all();
+        pop.evaluate(|genome| rastrigin_slowly(genome))
+    });
+}
+
+#[bench]
+fn parallel_evaluation(b: &mut Bencher) {
+    let mut pop = population();
+    b.iter(|| {
+        pop.invalidate_all();
+        pop.evaluate_par(|genome| rastrigin_slowly(genome))
+    });
+}
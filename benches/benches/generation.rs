@@ -0,0 +1,31 @@
+#![feature(test)]
+
+extern crate test;
+use dears::algorithms::{ea_simple, Elitism};
+use dears::crossover::OnePoint;
+use dears::mutation::Gaussian;
+use dears::population::*;
+use dears::selection::TournamentSelection;
+use dears::termination::MaxGenerations;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use test::Bencher;
+
+const POP_SIZE: usize = 1_000;
+
+fn sphere(genome: &[f64]) -> f64 {
+    genome.iter().map(|x| x * x).sum()
+}
+
+/// Ten generations of large real-valued genomes, where copying parents dominates
+#[bench]
+fn ten_generations(b: &mut Bencher) {
+    let individuals: Vec<Vec<f64>> = (0..POP_SIZE).map(|i| vec![i as f64 / 100.0; 200]).collect();
+    let mut rng = StdRng::seed_from_u64(168);
+    b.iter(|| {
+        let mutator = Gaussian { mu: 0.0, sigma: 0.1, indpb: 0.05 };
+        let mut pop = Population::new(individuals.clone(), mutator, OnePoint, TournamentSelection::new(3).unwrap());
+        ea_simple(&mut pop, MaxGenerations(10), 0.5, 0.2, Elitism::default(), |g: &Vec<f64>| sphere(g), None, None,
+                  &mut rng)
+    });
+}
@@ -0,0 +1,30 @@
+#![feature(test)]
+
+extern crate test;
+use dears::mutation::{Gaussian, Mutator};
+use dears::rng::RngPool;
+use rayon::prelude::*;
+use test::Bencher;
+
+const POP_SIZE: usize = 10_000;
+
+fn population() -> Vec<Vec<f64>> {
+    vec![vec![0.0; 10]; POP_SIZE]
+}
+
+/// Every draw goes through the worker's thread-local generator
+#[bench]
+fn parallel_thread_rng(b: &mut Bencher) {
+    let mut pop = population();
+    let mutator = Gaussian { mu: 0.0, sigma: 1.0, indpb: 0.5 };
+    b.iter(|| pop.par_iter_mut().for_each(|genome| mutator.mutate(genome)));
+}
+
+/// Every individual draws from its own generator, seeded from the pool
+#[bench]
+fn parallel_rng_pool(b: &mut Bencher) {
+    let mut pop = population();
+    let mutator = Gaussian { mu: 0.0, sigma: 1.0, indpb: 0.5 };
+    let pool = RngPool { seed: 208 };
+    b.iter(|| pop.par_iter_mut().enumerate().for_each(|(i, genome)| mutator.mutate_with(genome, &mut pool.rng(i))));
+}
@@ -0,0 +1,46 @@
+#![feature(test)]
+
+extern crate test;
+use dears::selection::*;
+use test::Bencher;
+
+const POP_SIZE: usize = 100_000;
+
+fn fitnesses() -> Vec<f64> {
+    (0..POP_SIZE).map(|i| ((i * 7919) % 10_007) as f64).collect()
+}
+
+#[bench]
+fn sequential_tournament(b: &mut Bencher) {
+    let fitnesses = fitnesses();
+    let selector = TournamentSelection::new(64).unwrap();
+    b.iter(|| selector.select_n(&fitnesses, POP_SIZE));
+}
+
+#[bench]
+fn parallel_tournament(b: &mut Bencher) {
+    let fitnesses = fitnesses();
+    let selector = TournamentSelection::new(64).unwrap();
+    b.iter(|| selector.par_select_n(&fitnesses, POP_SIZE, 42));
+}
+
+const DRAWS: usize = 100;
+
+fn wheel_fitnesses() -> Vec<f64> {
+    fitnesses().into_iter().take(10_000).collect()
+}
+
+#[bench]
+fn roulette_rebuilt_per_draw(b: &mut Bencher) {
+    let fitnesses = wheel_fitnesses();
+    let selector = Roulette { scaling: Scaling::None, direction: Direction::Maximize };
+    b.iter(|| (0..DRAWS).map(|_| selector.select(&fitnesses)).sum::<usize>());
+}
+
+#[bench]
+fn prepared_wheel(b: &mut Bencher) {
+    let fitnesses = wheel_fitnesses();
+    let wheel = PreparedWheel::from_fitnesses(&fitnesses, &Scaling::None, Direction::Maximize).unwrap();
+    let mut rng = rand::thread_rng();
+    b.iter(|| (0..DRAWS).map(|_| wheel.sample(&mut rng)).sum::<usize>());
+}
@@ -0,0 +1,105 @@
+//! Derive macros for `dears`, re-exported by it with the `derive` feature
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parenthesized, parse_macro_input, parse_quote, Data, DeriveInput, Error, Expr, Field, Member, Result};
+
+/// Implements `Crossover` of the struct for `dears::crossover::FieldWise`
+///
+/// Every field needs a `#[crossover(...)]` attribute: `one_point`,
+/// `uniform(p)` or `skip`. See `dears::crossover::FieldWise`.
+#[proc_macro_derive(CrossoverFields, attributes(crossover))]
+pub fn derive_crossover_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    crossover_fields(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// How a field is crossed with the same field of the other parent
+enum FieldCrossover {
+    OnePoint,
+    Uniform(Expr),
+    Skip,
+}
+
+impl FieldCrossover {
+    /// The operator's type, or `None` for a skipped field
+    fn operator_type(&self) -> Option<TokenStream2> {
+        match self {
+            FieldCrossover::OnePoint => Some(quote!(::dears::crossover::OnePoint)),
+            FieldCrossover::Uniform(_) => Some(quote!(::dears::crossover::Uniform)),
+            FieldCrossover::Skip => None,
+        }
+    }
+
+    fn operator(&self) -> Option<TokenStream2> {
+        match self {
+            FieldCrossover::OnePoint => Some(quote!(::dears::crossover::OnePoint)),
+            FieldCrossover::Uniform(indpb) => Some(quote!(::dears::crossover::Uniform { indpb: #indpb })),
+            FieldCrossover::Skip => None,
+        }
+    }
+}
+
+fn field_crossover(field: &Field) -> Result<FieldCrossover> {
+    let mut found = None;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("crossover")) {
+        if found.is_some() {
+            return Err(Error::new_spanned(attr, "Only one #[crossover(...)] attribute per field"));
+        }
+        attr.parse_nested_meta(|meta| {
+            let parsed = if meta.path.is_ident("one_point") {
+                FieldCrossover::OnePoint
+            } else if meta.path.is_ident("skip") {
+                FieldCrossover::Skip
+            } else if meta.path.is_ident("uniform") {
+                let content;
+                parenthesized!(content in meta.input);
+                FieldCrossover::Uniform(content.parse()?)
+            } else {
+                return Err(meta.error("Expected one_point, uniform(p) or skip"));
+            };
+            if found.replace(parsed).is_some() {
+                return Err(meta.error("Only one crossover per field"));
+            }
+            Ok(())
+        })?;
+    }
+    let message = "Field needs a #[crossover(one_point | uniform(p) | skip)] attribute";
+    found.ok_or_else(|| Error::new_spanned(field, message))
+}
+
+fn crossover_fields(mut input: DeriveInput) -> Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input.ident, "CrossoverFields can only be derived for structs"));
+    };
+    let mut crossed = Vec::new();
+    for (field, member) in data.fields.iter().zip(data.fields.members()) {
+        let crossover = field_crossover(field)?;
+        if let (Some(ty), Some(operator)) = (crossover.operator_type(), crossover.operator()) {
+            crossed.push((field.ty.clone(), member, ty, operator));
+        }
+    }
+
+    let where_clause = input.generics.make_where_clause();
+    for (field_ty, _, ty, _) in &crossed {
+        where_clause.predicates.push(parse_quote!(#ty: ::dears::crossover::Crossover<#field_ty>));
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let steps = crossed.iter().map(|(field_ty, member, _, operator): &(_, Member, _, _)| {
+        quote! {
+            ::dears::crossover::Crossover::<#field_ty>::crossover_with(&#operator, &mut a.#member, &mut b.#member, rng);
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::dears::crossover::Crossover<#name #ty_generics> for ::dears::crossover::FieldWise
+        #where_clause
+        {
+            fn crossover_with(&self, a: &mut #name #ty_generics, b: &mut #name #ty_generics,
+                              rng: &mut dyn ::dears::__private::RngCore) {
+                #(#steps)*
+            }
+        }
+    })
+}
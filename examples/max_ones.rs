@@ -1,11 +1,11 @@
-use dears::mutation::*;
-use rand::{thread_rng, Rng};
+use dears::prelude::*;
+use rand::thread_rng;
 
-type Genome = [bool; 10];
-type Fitness = f64;
+type Genome = BitString;
+type Fitness = OrderedFitness;
 
-fn fitness(individual: Genome) -> Fitness {
-    individual.iter().filter(|&x| *x).count() as f64
+fn fitness(individual: &Genome) -> Fitness {
+    OrderedFitness(individual.count_ones() as f64)
 }
 
 fn main(){
@@ -15,23 +15,27 @@ fn main(){
 
     let mut rng = thread_rng();
 
-    let mut pop:Vec<Genome> = Vec::new();
-    for _ in 0..POP_SIZE {
-        pop.push([false; 10])
-    }
-
-    let mutator = FlipBit { indpb: 0.4 };
+    let mut pop = PopulationBuilder::new()
+        .init_from(POP_SIZE, &|rng: &mut dyn RngCore| BitString::random(10, 0.5, rng), &mut rng)
+        .mutator(FlipBit { indpb: 0.4 })
+        .crossover(OnePoint)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build::<Fitness>()
+        .unwrap();
 
     for gen in 0..N_GENS {
-        for ind in 0..POP_SIZE {
+        let fitnesses: Vec<Fitness> = pop.individuals().iter().map(fitness).collect();
+        let mut offspring = select_individuals(pop.selector(), pop.individuals(), &fitnesses, POP_SIZE);
+        for ind in offspring.iter_mut() {
             if rng.gen::<f64>() < MUTATE_PROB {
-                mutator.mutate(&mut pop[ind]);
+                pop.mutator().mutate(ind);
             }
         }
+        pop.individuals_mut().clone_from_slice(&offspring);
         println!("Completed gen {}", gen+1);
     }
 
-    for ind in 0..POP_SIZE {
-        println!("{:?}", fitness(pop[ind]));
+    for ind in pop.individuals() {
+        println!("{}", fitness(ind).0);
     }
 }
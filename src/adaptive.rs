@@ -0,0 +1,254 @@
+use rand::RngCore;
+
+use crate::algorithms::{Control, EvolutionObserver, Offspring};
+use crate::crossover::Crossover;
+use crate::mutation::Mutator;
+use crate::population::Population;
+use crate::selection::{Direction, SelectMany};
+use crate::stats::StatsRecord;
+
+/// How often an operator was applied, and how often its child beat its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Credit {
+    pub trials: usize,
+    pub successes: usize,
+}
+
+impl Credit {
+    /// The fraction of trials that succeeded, or 0 if there were none
+    pub fn rate(&self) -> f64 {
+        if self.trials == 0 { 0.0 } else { self.successes as f64 / self.trials as f64 }
+    }
+}
+
+/// Success counts for crossover and mutation over a generation
+///
+/// A child that was both crossed and mutated credits both operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OperatorStats {
+    pub crossover: Credit,
+    pub mutation: Credit,
+}
+
+impl OperatorStats {
+    /// Credits each operator used on a child with a success if the child is strictly better than its parent
+    ///
+    /// `parents[i]` indexes `parent_fitnesses` and the flags are as in
+    /// [`Offspring`]; NaN fitnesses never count as better.
+    pub fn record(&mut self, offspring: &Offspring<impl Sized>, parent_fitnesses: &[f64], child_fitnesses: &[f64],
+                  direction: Direction) {
+        let children = offspring.crossed.iter().zip(&offspring.mutated).zip(&offspring.parents).zip(child_fitnesses);
+        for (((&crossed, &mutated), &parent), child) in children {
+            let improved = direction.compare(child, &parent_fitnesses[parent]).is_gt();
+            for (used, credit) in [(crossed, &mut self.crossover), (mutated, &mut self.mutation)] {
+                if used {
+                    credit.trials += 1;
+                    credit.successes += improved as usize;
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = OperatorStats::default();
+    }
+}
+
+/// Adapts the crossover and mutation probabilities to how often each operator succeeds
+///
+/// After each generation, every operator's quality is moved towards its
+/// success rate by the learning rate, then the probabilities are set by
+/// probability matching: each gets `floor` plus a share of the rest in
+/// proportion to its quality, capped at `ceiling`. The two probabilities
+/// then sum to at most 1, as [`var_or`](crate::algorithms::var_or) needs.
+///
+/// As an [`EvolutionObserver`] it credits the operators from each evaluated
+/// generation, sets the probabilities [`evolve_with_hooks`] uses for the
+/// next, and logs the ones in use as `"cxpb"` and `"mutpb"`.
+///
+/// [`evolve_with_hooks`]: crate::algorithms::evolve_with_hooks
+///
+/// # Examples
+/// ```
+/// use dears::adaptive::*;
+/// let mut probs = AdaptiveProbabilities::new(0.5, 0.5);
+/// let mut stats = OperatorStats::default();
+/// stats.crossover = Credit { trials: 10, successes: 0 };
+/// stats.mutation = Credit { trials: 10, successes: 5 };
+/// probs.update(&stats);
+/// assert!(probs.mutpb() > probs.cxpb());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveProbabilities {
+    cxpb: f64,
+    mutpb: f64,
+    floor: f64,
+    ceiling: f64,
+    learning_rate: f64,
+    direction: Direction,
+    quality: [f64; 2],
+    stats: OperatorStats,
+    parent_fitnesses: Vec<f64>,
+    pending: Option<Offspring<()>>,
+}
+
+impl AdaptiveProbabilities {
+    /// Starts from the given probabilities, with a floor of 0.05, a ceiling of 0.9 and a learning rate of 0.3
+    pub fn new(cxpb: f64, mutpb: f64) -> Self {
+        AdaptiveProbabilities {
+            cxpb,
+            mutpb,
+            floor: 0.05,
+            ceiling: 0.9,
+            learning_rate: 0.3,
+            direction: Direction::default(),
+            quality: [cxpb, mutpb],
+            stats: OperatorStats::default(),
+            parent_fitnesses: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// Panics unless `0 <= floor <= 0.5` and `floor <= ceiling <= 1`
+    pub fn with_bounds(mut self, floor: f64, ceiling: f64) -> Self {
+        assert!((0.0..=0.5).contains(&floor) && (floor..=1.0).contains(&ceiling),
+                "Invalid probability bounds [{}, {}]", floor, ceiling);
+        self.floor = floor;
+        self.ceiling = ceiling;
+        self
+    }
+
+    /// How far each quality moves towards the latest success rate
+    ///
+    /// Panics unless `0 < learning_rate <= 1`, which also rules out NaN.
+    pub fn with_learning_rate(mut self, learning_rate: f64) -> Self {
+        assert!(learning_rate > 0.0 && learning_rate <= 1.0, "Invalid learning rate {}", learning_rate);
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    /// Sets whether bigger or smaller fitnesses count as success
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn cxpb(&self) -> f64 {
+        self.cxpb
+    }
+
+    pub fn mutpb(&self) -> f64 {
+        self.mutpb
+    }
+
+    /// Moves the probabilities towards the operators' success rates in `stats`
+    ///
+    /// Operators that weren't tried keep their quality. If neither operator
+    /// has any quality, the probabilities are left as they are.
+    pub fn update(&mut self, stats: &OperatorStats) {
+        for (quality, credit) in self.quality.iter_mut().zip([stats.crossover, stats.mutation]) {
+            if credit.trials > 0 {
+                *quality += self.learning_rate * (credit.rate() - *quality);
+            }
+        }
+        let total: f64 = self.quality.iter().sum();
+        if total <= 0.0 {
+            return;
+        }
+        let share = 1.0 - 2.0 * self.floor;
+        let matched = self.quality.map(|q| (self.floor + share * q / total).min(self.ceiling));
+        [self.cxpb, self.mutpb] = matched;
+    }
+}
+
+impl<G, M, C, S> EvolutionObserver<G, M, C, S, f64> for AdaptiveProbabilities
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<f64>
+{
+    fn on_generation_start(&mut self, pop: &mut Population<G, M, C, S, f64>, _rng: &mut dyn RngCore) {
+        self.parent_fitnesses = pop.fitnesses().to_vec();
+    }
+
+    fn adjust_probabilities(&mut self, cxpb: &mut f64, mutpb: &mut f64) {
+        *cxpb = self.cxpb;
+        *mutpb = self.mutpb;
+    }
+
+    fn on_offspring(&mut self, offspring: &mut Offspring<G>, _rng: &mut dyn RngCore) {
+        self.pending = Some(Offspring {
+            genomes: Vec::new(),
+            changed: Vec::new(),
+            crossed: offspring.crossed.clone(),
+            mutated: offspring.mutated.clone(),
+            parents: offspring.parents.clone(),
+            mates: Vec::new(),
+        });
+    }
+
+    fn on_evaluated(&mut self, pop: &mut Population<G, M, C, S, f64>) {
+        if let Some(offspring) = self.pending.take() {
+            self.stats.clear();
+            self.stats.record(&offspring, &self.parent_fitnesses, pop.fitnesses(), self.direction);
+        }
+    }
+
+    fn on_generation_end(&mut self, _pop: &mut Population<G, M, C, S, f64>, stats: &mut StatsRecord) -> Control {
+        stats.insert("cxpb", vec![self.cxpb]);
+        stats.insert("mutpb", vec![self.mutpb]);
+        let latest = self.stats;
+        self.update(&latest);
+        Control::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adaptive::*;
+
+    #[test]
+    fn successful_operator_rises_to_ceiling() {
+        let mut probs = AdaptiveProbabilities::new(0.5, 0.5).with_bounds(0.1, 0.8);
+        let stats = OperatorStats {
+            crossover: Credit { trials: 20, successes: 20 },
+            mutation: Credit { trials: 20, successes: 0 },
+        };
+        let mut previous = probs.cxpb();
+        for _ in 0..30 {
+            probs.update(&stats);
+            assert!(probs.cxpb() >= previous);
+            previous = probs.cxpb();
+        }
+        assert!((probs.cxpb() - 0.8).abs() < 1e-12);
+        assert!((probs.mutpb() - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn credits_children_against_their_parents() {
+        let offspring = Offspring {
+            genomes: vec![(); 4],
+            changed: vec![true, true, true, false],
+            crossed: vec![true, true, false, false],
+            mutated: vec![false, true, true, false],
+            parents: vec![0, 1, 1, 0],
+            mates: vec![Some(1), Some(0), None, None],
+        };
+        let mut stats = OperatorStats::default();
+        stats.record(&offspring, &[1.0, 2.0], &[3.0, 1.0, 5.0, 1.0], Direction::Maximize);
+        assert_eq!(stats.crossover, Credit { trials: 2, successes: 1 });
+        assert_eq!(stats.mutation, Credit { trials: 2, successes: 1 });
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid learning rate NaN")]
+    fn nan_learning_rate_is_rejected() {
+        AdaptiveProbabilities::new(0.5, 0.5).with_learning_rate(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid learning rate 0")]
+    fn zero_learning_rate_is_rejected() {
+        AdaptiveProbabilities::new(0.5, 0.5).with_learning_rate(0.0);
+    }
+}
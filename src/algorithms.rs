@@ -0,0 +1,1519 @@
+#[cfg(feature = "parallel")]
+use rand::rngs::{SmallRng, StdRng};
+#[cfg(feature = "parallel")]
+use rand::SeedableRng;
+use std::convert::Infallible;
+use std::time::Instant;
+
+use rand::{Rng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::archive::{HallOfFame, ParetoFront};
+use crate::crossover::*;
+use crate::initializer::Init;
+use crate::multiobjective::{CrowdedTournament, Nsga2Selection};
+use crate::mutation::*;
+use crate::population::*;
+#[cfg(feature = "parallel")]
+use crate::rng::RngPool;
+use crate::selection::*;
+use crate::stats::*;
+use crate::termination::*;
+
+/// Adds the run's total `"evaluations"` so far and the `"elapsed"` seconds since it `started` to a row
+fn account(row: &mut StatsRecord, evaluations: usize, started: Instant) {
+    row.insert("evaluations", vec![evaluations as f64]);
+    row.insert("elapsed", vec![started.elapsed().as_secs_f64()]);
+}
+
+/// Records a generation in the logbook, compiling `stats` if given
+fn record<F, St>(logbook: &mut Logbook, gen: usize, nevals: usize, evaluations: usize, started: Instant,
+                 fitnesses: &[F], stats: Option<&St>)
+where
+    St: Compile<F> + ?Sized
+{
+    let mut row = stats.map(|s| s.compile(fitnesses)).unwrap_or_default();
+    account(&mut row, evaluations, started);
+    logbook.record(gen, nevals, row);
+}
+
+/// What the termination criteria see of a population
+fn progress<G, M, C, S, F>(pop: &Population<G, M, C, S, F>) -> Progress<'_, F>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone
+{
+    Progress { generation: pop.generation(), evaluations: pop.evaluations(), fitnesses: pop.fitnesses() }
+}
+
+fn update_hof<G: Clone, F: Clone + PartialOrd>(hof: &mut Option<&mut HallOfFame<G, F>>, individuals: &[G], fitnesses: &[F]) {
+    if let Some(hof) = hof {
+        hof.update(individuals, fitnesses);
+    }
+}
+
+/// The individuals a run hasn't seen as it starts: everyone at generation 0,
+/// even those that came with fitnesses, and later only those still to be evaluated
+fn entrants<G, M, C, S, F>(pop: &Population<G, M, C, S, F>) -> Vec<usize>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone
+{
+    if pop.generation() == 0 { (0..pop.len()).collect() } else { pop.invalid_indices() }
+}
+
+/// Offers the individuals at `idxs` to the hall of fame, if there is one
+fn offer<G: Clone, F: Clone + PartialOrd>(hof: &mut Option<&mut HallOfFame<G, F>>, individuals: &[G], fitnesses: &[F],
+                                          idxs: &[usize]) {
+    if let Some(hof) = hof {
+        for &i in idxs {
+            hof.insert(&individuals[i], &fitnesses[i]);
+        }
+    }
+}
+
+/// Children produced by [`var_and`] or [`var_or`], starting from [`spawn_offspring`]
+///
+/// `changed[i]` says whether `genomes[i]` was altered by crossover or
+/// mutation and so needs evaluating; unchanged children are exact copies of
+/// `parents[i]`, so can reuse its fitness.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Offspring<G> {
+    pub genomes: Vec<G>,
+    pub changed: Vec<bool>,
+    /// Whether each child took part in a crossover
+    pub crossed: Vec<bool>,
+    /// Whether each child was mutated
+    pub mutated: Vec<bool>,
+    /// Index of the (first) parent each child was copied from
+    pub parents: Vec<usize>,
+    /// Index of the other parent of each crossed child
+    pub mates: Vec<Option<usize>>,
+}
+
+impl<G> Offspring<G> {
+    /// Positions of the children that need evaluating
+    pub fn changed_indices(&self) -> Vec<usize> {
+        (0..self.changed.len()).filter(|&i| self.changed[i]).collect()
+    }
+
+    /// The age of each child, one more than its older parent's in `parent_ages`
+    pub fn ages(&self, parent_ages: &[u32]) -> Vec<u32> {
+        self.parents.iter().zip(&self.mates)
+            .map(|(&parent, mate)| parent_ages[parent].max(mate.map_or(0, |m| parent_ages[m])) + 1)
+            .collect()
+    }
+
+    /// Moves every child of `other` onto the end of these
+    fn append(&mut self, mut other: Offspring<G>) {
+        self.genomes.append(&mut other.genomes);
+        self.changed.append(&mut other.changed);
+        self.crossed.append(&mut other.crossed);
+        self.mutated.append(&mut other.mutated);
+        self.parents.append(&mut other.parents);
+        self.mates.append(&mut other.mates);
+    }
+}
+
+/// Copies of the parents at `picks`, ready to be varied into children
+///
+/// Every child starts as an exact copy of its parent with no mate, so nothing
+/// is flagged as `changed` and it can keep the parent's fitness until crossover
+/// or mutation flags it. [`Offspring::ages`] then makes it a generation older
+/// than its parent. [`var_and`] and [`var_or`] make their children with it;
+/// an [`Individual`](crate::individual::Individual) has
+/// [`spawn`](crate::individual::Individual::spawn) for the same. Lamarckian
+/// local search, which keeps the individual it improves, must not use either.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::spawn_offspring;
+/// let parents = vec![vec![1, 2], vec![3, 4]];
+/// let offspring = spawn_offspring(&parents, &[1, 0, 1]);
+/// assert_eq!(offspring.genomes, vec![vec![3, 4], vec![1, 2], vec![3, 4]]);
+/// assert!(offspring.changed_indices().is_empty());
+/// assert_eq!(offspring.ages(&[2, 5]), vec![6, 3, 6]);
+/// ```
+pub fn spawn_offspring<G: Clone>(parents: &[G], picks: &[usize]) -> Offspring<G> {
+    let n = picks.len();
+    Offspring {
+        genomes: picks.iter().map(|&parent| parents[parent].clone()).collect(),
+        changed: vec![false; n],
+        crossed: vec![false; n],
+        mutated: vec![false; n],
+        parents: picks.to_vec(),
+        mates: vec![None; n],
+    }
+}
+
+/// Clones the parents, then mates adjacent pairs and mutates each child
+///
+/// Each pair `(2k, 2k + 1)` is mated with probability `cxpb`, then every
+/// child is independently mutated with probability `mutpb`, so a child can
+/// be the product of both. The parents themselves are never modified.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::var_and;
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// let parents = vec![vec![false; 4], vec![true; 4]];
+/// let offspring = var_and(&parents, &OnePoint, &FlipBit { indpb: 0.5 }, 1.0, 0.0, &mut rand::thread_rng());
+/// assert_eq!(offspring.changed, vec![true, true]);
+/// ```
+pub fn var_and<G: Clone>(parents: &[G], crossover: &impl Crossover<G>, mutator: &impl Mutator<G>,
+                         cxpb: f64, mutpb: f64, rng: &mut dyn RngCore) -> Offspring<G> {
+    let mut offspring = spawn_offspring(parents, &(0..parents.len()).collect::<Vec<_>>());
+    let flags = vary_in_place(&mut offspring.genomes, crossover, mutator, cxpb, mutpb, rng);
+    paired_offspring(offspring, flags)
+}
+
+/// Whether each child of an in-place variation was crossed, and whether it was mutated
+type VariationFlags = (Vec<bool>, Vec<bool>);
+
+/// The variation of [`var_and`], applied to `genomes` where they are
+fn vary_in_place<G>(genomes: &mut [G], crossover: &impl Crossover<G>, mutator: &impl Mutator<G>, cxpb: f64,
+                    mutpb: f64, rng: &mut dyn RngCore) -> VariationFlags {
+    let mut crossed = vec![false; genomes.len()];
+    let mut mutated = vec![false; genomes.len()];
+    for (pair, flags) in genomes.chunks_exact_mut(2).zip(crossed.chunks_exact_mut(2)) {
+        if rng.gen::<f64>() < cxpb {
+            let (a, b) = pair.split_at_mut(1);
+            crossover.crossover_with(&mut a[0], &mut b[0], rng);
+            flags.fill(true);
+        }
+    }
+    for (child, flag) in genomes.iter_mut().zip(mutated.iter_mut()) {
+        if rng.gen::<f64>() < mutpb {
+            mutator.mutate_with(child, rng);
+            *flag = true;
+        }
+    }
+    (crossed, mutated)
+}
+
+/// Spawned offspring varied in place, each crossed child mated with its neighbour in the pair
+fn paired_offspring<G>(mut offspring: Offspring<G>, (crossed, mutated): VariationFlags) -> Offspring<G> {
+    offspring.changed = crossed.iter().zip(&mutated).map(|(&c, &m)| c || m).collect();
+    offspring.mates = crossed.iter().enumerate().map(|(i, &c)| c.then(|| offspring.parents[i ^ 1])).collect();
+    offspring.crossed = crossed;
+    offspring.mutated = mutated;
+    offspring
+}
+
+/// Makes `lambda` children, each by exactly one of crossover, mutation or reproduction
+///
+/// With probability `cxpb` a child is the first product of crossing two
+/// distinct random parents, with probability `mutpb` it's a mutated copy of a
+/// random parent, and otherwise it's a plain copy. `cxpb + mutpb` must not
+/// exceed 1. The parents themselves are never modified.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::var_or;
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// let parents = vec![vec![false; 4], vec![true; 4]];
+/// let offspring = var_or(&parents, &OnePoint, &FlipBit { indpb: 0.5 }, 5, 0.0, 0.0, &mut rand::thread_rng());
+/// assert_eq!(offspring.genomes.len(), 5);
+/// assert!(offspring.changed_indices().is_empty());
+/// ```
+pub fn var_or<G: Clone>(parents: &[G], crossover: &impl Crossover<G>, mutator: &impl Mutator<G>, lambda: usize,
+                        cxpb: f64, mutpb: f64, rng: &mut dyn RngCore) -> Offspring<G> {
+    assert!(cxpb + mutpb <= 1.0, "Crossover and mutation probabilities must sum to at most 1");
+    assert!(!parents.is_empty(), "Can't make offspring without parents");
+    let mut offspring = spawn_offspring(parents, &[]);
+    for _ in 0..lambda {
+        let roll = rng.gen::<f64>();
+        let first = rng.gen_range(0..parents.len());
+        let mut child = spawn_offspring(parents, &[first]);
+        if roll < cxpb && parents.len() > 1 {
+            let mut second = rng.gen_range(0..parents.len() - 1);
+            if second >= first {
+                second += 1;
+            }
+            // Only the first product of the crossover is kept
+            let mut other = parents[second].clone();
+            crossover.crossover_with(&mut child.genomes[0], &mut other, rng);
+            child.crossed[0] = true;
+            child.mates[0] = Some(second);
+        } else if roll < cxpb + mutpb {
+            mutator.mutate_with(&mut child.genomes[0], rng);
+            child.mutated[0] = true;
+        }
+        child.changed[0] = child.crossed[0] || child.mutated[0];
+        offspring.append(child);
+    }
+    offspring
+}
+
+/// [`var_and`] across the rayon thread pool, reproducible from a seed
+///
+/// Child `i` draws from generator `i` of the [`RngPool`] for stream
+/// `generation` of `master_seed`, and a mated pair uses the first
+/// child's generator for the crossover. The offspring are then a pure
+/// function of the seed, whatever the number of threads.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::parallel_vary;
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// let parents = vec![vec![false; 16], vec![true; 16], vec![false; 16]];
+/// let vary = || parallel_vary(&parents, &OnePoint, &FlipBit { indpb: 0.2 }, 0.5, 0.5, 42, 3);
+/// assert_eq!(vary(), vary());
+/// ```
+#[cfg(feature = "parallel")]
+pub fn parallel_vary<G, C, M>(parents: &[G], crossover: &C, mutator: &M, cxpb: f64, mutpb: f64, master_seed: u64,
+                              generation: usize) -> Offspring<G>
+where
+    G: Clone + Send,
+    C: Crossover<G> + Sync,
+    M: Mutator<G> + Sync
+{
+    let mut offspring = spawn_offspring(parents, &(0..parents.len()).collect::<Vec<_>>());
+    let flags = parallel_vary_in_place(&mut offspring.genomes, crossover, mutator, cxpb, mutpb, master_seed,
+                                       generation);
+    paired_offspring(offspring, flags)
+}
+
+/// The variation of [`parallel_vary`], applied to `genomes` where they are
+#[cfg(feature = "parallel")]
+fn parallel_vary_in_place<G, C, M>(genomes: &mut [G], crossover: &C, mutator: &M, cxpb: f64, mutpb: f64,
+                                   master_seed: u64, generation: usize) -> VariationFlags
+where
+    G: Send,
+    C: Crossover<G> + Sync,
+    M: Mutator<G> + Sync
+{
+    let pool = RngPool { seed: master_seed }.stream(generation as u64);
+    let flags: Vec<Vec<(bool, bool)>> = genomes.par_chunks_mut(2).enumerate().map(|(pair_idx, pair)| {
+        let mut rngs: Vec<SmallRng> = (0..pair.len()).map(|j| pool.rng(2 * pair_idx + j)).collect();
+        let crossed = rngs[0].gen::<f64>() < cxpb && pair.len() == 2;
+        if crossed {
+            let (a, b) = pair.split_at_mut(1);
+            crossover.crossover_with(&mut a[0], &mut b[0], &mut rngs[0]);
+        }
+        pair.iter_mut().zip(&mut rngs).map(|(child, rng)| {
+            let mutated = rng.gen::<f64>() < mutpb;
+            if mutated {
+                mutator.mutate_with(child, rng);
+            }
+            (crossed, mutated)
+        }).collect()
+    }).collect();
+    flags.into_iter().flatten().unzip()
+}
+
+/// How many of the best individuals survive each generation unchanged
+///
+/// Elites are copied into the next generation with their fitnesses, so they
+/// aren't re-evaluated, and the rest of the generation is filled with
+/// offspring. Which individuals are best depends on the direction, which has
+/// to be given alongside `k` as it can't be told from the selector. The
+/// default keeps none.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::Elitism;
+/// use dears::selection::Direction;
+/// let elitism = Elitism::new(2, Direction::Minimize);
+/// assert_eq!((elitism.k(), elitism.direction()), (2, Direction::Minimize));
+/// assert_eq!(Elitism::default(), Elitism::none());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Elitism {
+    k: usize,
+    direction: Direction,
+}
+
+impl Elitism {
+    /// Keeps the `k` best individuals, ranked by `direction`
+    pub fn new(k: usize, direction: Direction) -> Self {
+        Elitism { k, direction }
+    }
+
+    /// Keeps no individuals, so every generation is all offspring
+    pub fn none() -> Self {
+        Elitism::default()
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+}
+
+/// The simplest generational evolutionary algorithm, as in DEAP's `eaSimple`
+///
+/// Individuals without a fitness are evaluated first. Then, until `stop`
+/// fires, each generation a population of parents is picked by the
+/// population's selector and cloned. Adjacent pairs are mated with probability
+/// `cxpb` and each child is mutated with probability `mutpb`. Only changed
+/// children are re-evaluated before they replace the population, apart from
+/// the best `elitism.k`, which are carried over first. All randomness comes
+/// from `rng`, so a seeded generator gives a reproducible run as long as the
+/// operators draw from the generator they're given.
+///
+/// Returns why the run stopped, along with a [`Logbook`] with a row for the
+/// initial population and each generation after it, including the compiled
+/// `stats` if given. Rows are numbered by the population's generation
+/// counter, so a resumed run carries on where it left off. Every row also
+/// holds the population's total `"evaluations"` and the `"elapsed"` seconds
+/// since the call began. The hall of fame, if given, is updated after every
+/// evaluation.
+///
+/// Generation 0 is the initial population: it gets a row, and all of it goes
+/// to the hall of fame, even the individuals that already had fitnesses,
+/// which aren't evaluated again. A run resumed at a later generation, from a
+/// checkpoint say, only logs the generation it starts from and offers it to
+/// the hall of fame if some of it had to be evaluated, so the rows of the
+/// two runs join up without repeating a generation.
+///
+/// Budgets such as [`MaxEvaluations`] and [`WallClock`] are also checked
+/// after each evaluation, so they can stop the run partway through a
+/// generation with [`StopReason::MidGeneration`]. The individuals not yet
+/// evaluated are then left invalid, and the last row and the hall of fame
+/// only cover those that were.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{ea_simple, Elitism};
+/// use dears::archive::HallOfFame;
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::{Direction, TournamentSelection};
+/// use dears::stats::Statistics;
+/// use dears::termination::{MaxGenerations, StopReason};
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(20, || vec![false; 10])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap();
+/// let ones = |g: &Vec<bool>| g.iter().filter(|&&b| b).count() as f64;
+/// let stats = Statistics::new(|f: &f64| *f).with_defaults();
+/// let mut hof = HallOfFame::new(1);
+/// let elitism = Elitism::new(1, Direction::Maximize);
+/// let (logbook, reason) = ea_simple(&mut pop, MaxGenerations(5), 0.5, 0.2, elitism, ones, Some(&stats),
+///                                   Some(&mut hof), &mut rand::thread_rng());
+/// assert_eq!(reason, StopReason::MaxGenerations);
+/// assert_eq!(logbook.len(), 6);
+/// assert_eq!(hof.best().unwrap().1, &logbook.select("max").into_iter().fold(0.0, f64::max));
+/// assert_eq!(logbook.to_string().lines().count(), 7);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn ea_simple<G, M, C, S, F, E, T>(pop: &mut Population<G, M, C, S, F>, stop: T, cxpb: f64, mutpb: f64,
+                                      elitism: Elitism, eval: E, stats: Option<&Statistics<F>>,
+                                      hof: Option<&mut HallOfFame<G, F>>, rng: &mut dyn RngCore)
+                                      -> (Logbook, StopReason)
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    E: Fn(&G) -> F,
+    T: Termination<F>
+{
+    evolve_with_hooks(pop, stop, cxpb, mutpb, elitism, eval, stats, hof, &mut (), rng)
+}
+
+/// Whether an evolve loop should carry on after a generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/// Callbacks into [`evolve_with_hooks`], for custom behaviour each generation
+///
+/// Every method gets the population at a point where it's safe to change,
+/// for instance to log, retune operators or bring in immigrants. Individuals
+/// added or swapped in must come with their fitness, so the population stays
+/// evaluated. All methods do nothing by default, and `()` is the observer that
+/// never does anything.
+///
+/// Tuples of up to four observers are observers too, so several can share the
+/// one slot in [`evolve_with_hooks`]. Each hook is forwarded to every member in
+/// order, the first `Some` from [`select_parents`](EvolutionObserver::select_parents)
+/// wins, and the loop stops if any member returns [`Control::Stop`].
+pub trait EvolutionObserver<G, M, C, S, F>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone
+{
+    /// Called before parents are selected, once the generation counter has moved on
+    ///
+    /// Individuals it [invalidates](Population::invalidate) are evaluated
+    /// before selection, as part of the generation.
+    fn on_generation_start(&mut self, _pop: &mut Population<G, M, C, S, F>, _rng: &mut dyn RngCore) {}
+
+    /// Called after [`EvolutionObserver::on_generation_start`] with the
+    /// crossover and mutation probabilities, which it may change for this
+    /// generation and the ones after it
+    fn adjust_probabilities(&mut self, _cxpb: &mut f64, _mutpb: &mut f64) {}
+
+    /// Called in place of the population's selector to pick the `n` parents of the next generation
+    ///
+    /// Returns indices into the population, or `None` to leave it to the selector.
+    fn select_parents(&mut self, _pop: &Population<G, M, C, S, F>, _n: usize, _rng: &mut dyn RngCore)
+                      -> Option<Vec<usize>> {
+        None
+    }
+
+    /// Called with the next generation before it's evaluated: the elites, then the offspring
+    ///
+    /// `parents` holds population indices, and any genome changed here must be
+    /// flagged in `changed` so it gets evaluated.
+    fn on_offspring(&mut self, _offspring: &mut Offspring<G>, _rng: &mut dyn RngCore) {}
+
+    /// Called whenever new fitnesses have been computed, including the initial evaluation
+    fn on_evaluated(&mut self, _pop: &mut Population<G, M, C, S, F>) {}
+
+    /// Called with the initial population's statistics, before any generation
+    ///
+    /// Anything added to `stats` is recorded in the logbook. A run resumed at
+    /// a later generation that logs no initial row doesn't call it.
+    fn on_start(&mut self, _pop: &mut Population<G, M, C, S, F>, _stats: &mut StatsRecord) {}
+
+    /// Called with the generation's statistics once it's complete
+    ///
+    /// Anything added to `stats` is recorded in the logbook.
+    fn on_generation_end(&mut self, _pop: &mut Population<G, M, C, S, F>, _stats: &mut StatsRecord) -> Control {
+        Control::Continue
+    }
+}
+
+impl<G, M, C, S, F> EvolutionObserver<G, M, C, S, F> for ()
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone
+{}
+
+macro_rules! tuple_observer {
+    ($($observer:ident $idx:tt),+) => {
+        impl<G, M, C, S, F, $($observer),+> EvolutionObserver<G, M, C, S, F> for ($($observer,)+)
+        where
+            M: Mutator<G>,
+            C: Crossover<G>,
+            S: SelectMany<F>,
+            F: Clone,
+            $($observer: EvolutionObserver<G, M, C, S, F>),+
+        {
+            fn on_generation_start(&mut self, pop: &mut Population<G, M, C, S, F>, rng: &mut dyn RngCore) {
+                $(self.$idx.on_generation_start(pop, rng);)+
+            }
+
+            fn adjust_probabilities(&mut self, cxpb: &mut f64, mutpb: &mut f64) {
+                $(self.$idx.adjust_probabilities(cxpb, mutpb);)+
+            }
+
+            fn select_parents(&mut self, pop: &Population<G, M, C, S, F>, n: usize, rng: &mut dyn RngCore)
+                              -> Option<Vec<usize>> {
+                $(if let Some(parents) = self.$idx.select_parents(pop, n, rng) {
+                    return Some(parents);
+                })+
+                None
+            }
+
+            fn on_offspring(&mut self, offspring: &mut Offspring<G>, rng: &mut dyn RngCore) {
+                $(self.$idx.on_offspring(offspring, rng);)+
+            }
+
+            fn on_evaluated(&mut self, pop: &mut Population<G, M, C, S, F>) {
+                $(self.$idx.on_evaluated(pop);)+
+            }
+
+            fn on_start(&mut self, pop: &mut Population<G, M, C, S, F>, stats: &mut StatsRecord) {
+                $(self.$idx.on_start(pop, stats);)+
+            }
+
+            fn on_generation_end(&mut self, pop: &mut Population<G, M, C, S, F>, stats: &mut StatsRecord)
+                                 -> Control {
+                let mut control = Control::Continue;
+                $(if self.$idx.on_generation_end(pop, stats) == Control::Stop {
+                    control = Control::Stop;
+                })+
+                control
+            }
+        }
+    };
+}
+
+tuple_observer!(A 0, B 1);
+tuple_observer!(A 0, B 1, D 2);
+tuple_observer!(A 0, B 1, D 2, E 3);
+
+/// An [`EvolutionObserver`] that logs the mean and oldest [`Population::ages`]
+/// of each generation as `"age_mean"` and `"age_max"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AgeStats;
+
+impl<G, M, C, S, F> EvolutionObserver<G, M, C, S, F> for AgeStats
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone
+{
+    fn on_generation_end(&mut self, pop: &mut Population<G, M, C, S, F>, stats: &mut StatsRecord) -> Control {
+        let ages: Vec<f64> = pop.ages().iter().map(|&age| age as f64).collect();
+        stats.insert("age_mean", vec![mean(&ages)]);
+        stats.insert("age_max", vec![max(&ages)]);
+        Control::Continue
+    }
+}
+
+/// An [`EvolutionObserver`] that brings random immigrants into every generation
+///
+/// At the start of each generation the worst [`count`](RandomImmigrants::count)
+/// individuals, ranked by `direction`, make way for fresh genomes from
+/// `init` at age 0. They're evaluated with the generation, before parents are
+/// selected, and their number is logged as `"immigrants"`. The best
+/// individual is never replaced.
+///
+/// Immigrants are drawn from the loop's generator, so a seeded run stays
+/// reproducible. Pair it with other observers in a tuple to use them together.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::*;
+/// use dears::crossover::OnePoint;
+/// use dears::initializer::RandomBits;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::{Direction, TournamentSelection};
+/// use dears::termination::MaxGenerations;
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(20, || vec![false; 10])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap();
+/// let ones = |g: &Vec<bool>| g.iter().filter(|&&b| b).count() as f64;
+/// let mut immigrants = RandomImmigrants::new(0.1, RandomBits { len: 10, p_true: 0.5 }, Direction::Maximize);
+/// let (logbook, _) = evolve_with_hooks(&mut pop, MaxGenerations(5), 0.5, 0.2, Elitism::default(), ones, None,
+///                                      None, &mut immigrants, &mut rand::thread_rng());
+/// // Generation 0 has none
+/// assert_eq!(logbook.select("immigrants")[1..], [2.0; 5]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomImmigrants<I> {
+    pub rate: f64,
+    pub init: I,
+    pub direction: Direction,
+}
+
+impl<I> RandomImmigrants<I> {
+    /// Replaces a fraction `rate` of the population, the worst by `direction`; panics unless `0 <= rate <= 1`
+    pub fn new(rate: f64, init: I, direction: Direction) -> Self {
+        assert!((0.0..=1.0).contains(&rate), "Immigration rate {} must be between 0 and 1", rate);
+        RandomImmigrants { rate, init, direction }
+    }
+
+    /// How many of `len` individuals are replaced: `⌈rate · len⌉`, short of the whole population
+    pub fn count(&self, len: usize) -> usize {
+        ((self.rate * len as f64).ceil() as usize).min(len.saturating_sub(1))
+    }
+}
+
+impl<G, M, C, S, F, I> EvolutionObserver<G, M, C, S, F> for RandomImmigrants<I>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    I: Init<G>
+{
+    fn on_generation_start(&mut self, pop: &mut Population<G, M, C, S, F>, rng: &mut dyn RngCore) {
+        let k = self.count(pop.len());
+        let ranked = RankedFitnesses::new(pop.fitnesses(), &self.direction);
+        let worst = ranked.order()[pop.len() - k..].to_vec();
+        let mut ages = pop.ages().to_vec();
+        for &idx in &worst {
+            pop.individuals_mut()[idx] = self.init.init_with(rng);
+            ages[idx] = 0;
+        }
+        pop.set_ages(ages);
+        pop.invalidate(&worst);
+    }
+
+    fn on_generation_end(&mut self, pop: &mut Population<G, M, C, S, F>, stats: &mut StatsRecord) -> Control {
+        stats.insert("immigrants", vec![self.count(pop.len()) as f64]);
+        Control::Continue
+    }
+}
+
+/// [`ea_simple`] with an observer called at each stage of every generation
+///
+/// The run also ends, with [`StopReason::Observer`], as soon as
+/// [`EvolutionObserver::on_generation_end`] returns [`Control::Stop`].
+///
+/// A generation cut short by a budget still gets its
+/// [`on_evaluated`](EvolutionObserver::on_evaluated) and
+/// [`on_generation_end`](EvolutionObserver::on_generation_end) calls, with the
+/// individuals not yet evaluated left [invalid](Population::invalid_indices).
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::*;
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::{Population, PopulationBuilder};
+/// use dears::selection::TournamentSelection;
+/// use dears::stats::StatsRecord;
+/// use dears::termination::{MaxGenerations, StopReason};
+///
+/// type Bits = Population<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64>;
+///
+/// struct StopAtGeneration(usize);
+///
+/// impl EvolutionObserver<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64> for StopAtGeneration {
+///     fn on_generation_end(&mut self, pop: &mut Bits, _stats: &mut StatsRecord) -> Control {
+///         if pop.generation() == self.0 { Control::Stop } else { Control::Continue }
+///     }
+/// }
+///
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(10, || vec![false; 8])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap();
+/// let ones = |g: &Vec<bool>| g.iter().filter(|&&b| b).count() as f64;
+/// let (_, reason) = evolve_with_hooks(&mut pop, MaxGenerations(10), 0.5, 0.2, Elitism::default(), ones, None,
+///                                     None, &mut StopAtGeneration(4), &mut rand::thread_rng());
+/// assert_eq!((reason, pop.generation()), (StopReason::Observer, 4));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn evolve_with_hooks<G, M, C, S, F, E, T, O>(pop: &mut Population<G, M, C, S, F>, stop: T, cxpb: f64,
+                                                 mutpb: f64, elitism: Elitism, eval: E, stats: Option<&Statistics<F>>,
+                                                 hof: Option<&mut HallOfFame<G, F>>, observer: &mut O,
+                                                 rng: &mut dyn RngCore) -> (Logbook, StopReason)
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    E: Fn(&G) -> F,
+    T: Termination<F>,
+    O: EvolutionObserver<G, M, C, S, F> + ?Sized
+{
+    let vary = |genomes: &mut [G], crossover: &C, mutator: &M, cxpb, mutpb, _gen, rng: &mut dyn RngCore| {
+        vary_in_place(genomes, crossover, mutator, cxpb, mutpb, rng)
+    };
+    let evaluate = |pop: &mut Population<G, M, C, S, F>, interrupt: Interrupt<'_, G, M, C, S, F>| {
+        Ok::<_, Infallible>(Evaluation { evaluated: pop.evaluate_until(&eval, interrupt), failures: 0 })
+    };
+    evolve(pop, stop, cxpb, mutpb, elitism, evaluate, false, stats, hof, observer, rng, vary)
+        .unwrap_or_else(|e| match e {})
+}
+
+/// [`ea_simple`] with a fitness function that may fail, handling failures as `policy` says
+///
+/// Each row of the logbook also counts the generation's `"failures"`. With
+/// [`FailurePolicy::Propagate`], or once [`FailurePolicy::Retry`] runs out
+/// of attempts, the run stops partway through the generation and returns the
+/// error. The population then keeps the fitnesses computed before it, and
+/// the individuals still to be evaluated are left invalid.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{try_evolve, Elitism};
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::{FailurePolicy, PopulationBuilder};
+/// use dears::selection::TournamentSelection;
+/// use dears::termination::MaxGenerations;
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(20, || vec![false; 10])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap();
+/// // A simulator that can't cope with the first bit set
+/// let simulate = |g: &Vec<bool>| if g[0] { Err("crashed") } else { Ok(g.iter().filter(|&&b| b).count() as f64) };
+/// let policy = FailurePolicy::AssignWorst(-1.0);
+/// let (logbook, _) = try_evolve(&mut pop, MaxGenerations(5), 0.5, 0.2, Elitism::default(), simulate, &policy,
+///                               None, None, &mut rand::thread_rng()).unwrap();
+/// assert_eq!(logbook.select("failures").len(), 6);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn try_evolve<G, M, C, S, F, E, T, Err>(pop: &mut Population<G, M, C, S, F>, stop: T, cxpb: f64, mutpb: f64,
+                                            elitism: Elitism, eval: E, policy: &FailurePolicy<F>,
+                                            stats: Option<&Statistics<F>>, hof: Option<&mut HallOfFame<G, F>>,
+                                            rng: &mut dyn RngCore) -> Result<(Logbook, StopReason), Err>
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    E: Fn(&G) -> Result<F, Err>,
+    T: Termination<F>
+{
+    let vary = |genomes: &mut [G], crossover: &C, mutator: &M, cxpb, mutpb, _gen, rng: &mut dyn RngCore| {
+        vary_in_place(genomes, crossover, mutator, cxpb, mutpb, rng)
+    };
+    let evaluate = |pop: &mut Population<G, M, C, S, F>, interrupt: Interrupt<'_, G, M, C, S, F>| {
+        pop.try_evaluate_until(&eval, policy, interrupt)
+    };
+    evolve(pop, stop, cxpb, mutpb, elitism, evaluate, true, stats, hof, &mut (), rng, vary)
+}
+
+/// [`ea_simple`] with a fitness function that scores every individual needing it in one call
+///
+/// Each generation's new individuals go to `eval` together through
+/// [`Population::evaluate_batch`], so the run can't stop partway through a
+/// generation. It ends with an error as soon as `eval` returns the wrong
+/// number of fitnesses, leaving that generation's individuals unevaluated.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{ea_simple_batch, Elitism};
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::TournamentSelection;
+/// use dears::termination::MaxGenerations;
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(20, || vec![false; 10])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap();
+/// let ones = |batch: &[Vec<bool>]| batch.iter().map(|g| g.iter().filter(|&&b| b).count() as f64).collect();
+/// let (logbook, _) = ea_simple_batch(&mut pop, MaxGenerations(5), 0.5, 0.2, Elitism::default(), ones, None, None,
+///                                    &mut rand::thread_rng()).unwrap();
+/// assert_eq!(logbook.nevals()[0], 20);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn ea_simple_batch<G, M, C, S, F, E, T>(pop: &mut Population<G, M, C, S, F>, stop: T, cxpb: f64, mutpb: f64,
+                                            elitism: Elitism, eval: E, stats: Option<&Statistics<F>>,
+                                            hof: Option<&mut HallOfFame<G, F>>, rng: &mut dyn RngCore)
+                                            -> Result<(Logbook, StopReason), BatchLengthError>
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    E: Fn(&[G]) -> Vec<F>,
+    T: Termination<F>
+{
+    let vary = |genomes: &mut [G], crossover: &C, mutator: &M, cxpb, mutpb, _gen, rng: &mut dyn RngCore| {
+        vary_in_place(genomes, crossover, mutator, cxpb, mutpb, rng)
+    };
+    let evaluate = |pop: &mut Population<G, M, C, S, F>, _: Interrupt<'_, G, M, C, S, F>| {
+        pop.evaluate_batch(&eval).map(|evaluated| Evaluation { evaluated, failures: 0 })
+    };
+    evolve(pop, stop, cxpb, mutpb, elitism, evaluate, false, stats, hof, &mut (), rng, vary)
+}
+
+/// [`ea_simple`] with the offspring made by [`parallel_vary`] on the rayon thread pool
+///
+/// Selection draws from a generator seeded with `master_seed`, and each
+/// generation's variation from streams derived from `master_seed` and the
+/// generation number, so the run is a pure function of `master_seed`
+/// whatever the number of threads.
+#[cfg(feature = "parallel")]
+#[allow(clippy::too_many_arguments)]
+pub fn ea_simple_par<G, M, C, S, F, E, T>(pop: &mut Population<G, M, C, S, F>, stop: T, cxpb: f64, mutpb: f64,
+                                          elitism: Elitism, eval: E, stats: Option<&Statistics<F>>,
+                                          hof: Option<&mut HallOfFame<G, F>>, master_seed: u64)
+                                          -> (Logbook, StopReason)
+where
+    G: Clone + Send,
+    M: Mutator<G> + Sync,
+    C: Crossover<G> + Sync,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    E: Fn(&G) -> F,
+    T: Termination<F>
+{
+    let vary = |genomes: &mut [G], crossover: &C, mutator: &M, cxpb, mutpb, gen, _rng: &mut dyn RngCore| {
+        parallel_vary_in_place(genomes, crossover, mutator, cxpb, mutpb, master_seed, gen)
+    };
+    let mut rng = StdRng::seed_from_u64(master_seed);
+    let evaluate = |pop: &mut Population<G, M, C, S, F>, interrupt: Interrupt<'_, G, M, C, S, F>| {
+        Ok::<_, Infallible>(Evaluation { evaluated: pop.evaluate_until(&eval, interrupt), failures: 0 })
+    };
+    evolve(pop, stop, cxpb, mutpb, elitism, evaluate, false, stats, hof, &mut (), &mut rng, vary)
+        .unwrap_or_else(|e| match e {})
+}
+
+/// Asked by the evaluation step of [`evolve`] after each individual whether to stop there
+type Interrupt<'a, G, M, C, S, F> = &'a mut dyn FnMut(&Population<G, M, C, S, F>) -> bool;
+
+/// The loop behind [`evolve_with_hooks`], with `vary` turning each generation's parents into offspring in place
+///
+/// The next generation is built in buffers kept from the one before, with
+/// genomes copied by `clone_from`, so genomes that own memory can reuse it
+/// rather than allocate afresh every generation.
+///
+/// `evaluate` computes the fitnesses the population is missing, and may stop
+/// partway once its [`Interrupt`] says so. The `"failures"` it counts are only
+/// logged with `count_failures`.
+#[allow(clippy::too_many_arguments)]
+fn evolve<G, M, C, S, F, Ev, Err, T, O, V>(pop: &mut Population<G, M, C, S, F>, mut stop: T, mut cxpb: f64,
+                                           mut mutpb: f64, elitism: Elitism, mut evaluate: Ev, count_failures: bool,
+                                           stats: Option<&Statistics<F>>, mut hof: Option<&mut HallOfFame<G, F>>,
+                                           observer: &mut O, rng: &mut dyn RngCore, vary: V)
+                                           -> Result<(Logbook, StopReason), Err>
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    Ev: FnMut(&mut Population<G, M, C, S, F>, Interrupt<'_, G, M, C, S, F>) -> Result<Evaluation, Err>,
+    T: Termination<F>,
+    O: EvolutionObserver<G, M, C, S, F> + ?Sized,
+    V: Fn(&mut [G], &C, &M, f64, f64, usize, &mut dyn RngCore) -> VariationFlags
+{
+    let started = Instant::now();
+    let log_failures = |row: &mut StatsRecord, evaluation: Evaluation| {
+        if count_failures {
+            row.insert("failures", vec![evaluation.failures as f64]);
+        }
+    };
+    let mut genomes = Vec::new();
+    let mut fitnesses = Vec::new();
+    let mut logbook = Logbook::new();
+    let entrants = entrants(pop);
+    let evaluation = evaluate(pop, &mut |_| false)?;
+    offer(&mut hof, pop.individuals(), pop.fitnesses(), &entrants);
+    if evaluation.evaluated > 0 {
+        observer.on_evaluated(pop);
+    }
+    if !entrants.is_empty() {
+        let mut row = stats.map(|s| s.compile(pop.fitnesses())).unwrap_or_default();
+        account(&mut row, pop.evaluations(), started);
+        log_failures(&mut row, evaluation);
+        observer.on_start(pop, &mut row);
+        logbook.record(pop.generation(), evaluation.evaluated, row);
+    }
+
+    loop {
+        if let Some(reason) = stop.check(&progress(pop)) {
+            return Ok((logbook, reason));
+        }
+        let gen = pop.next_generation();
+        observer.on_generation_start(pop, rng);
+        // Newcomers from the observer need fitnesses before they can be selected
+        let arrivals = pop.invalid_indices();
+        let arrived = evaluate(pop, &mut |_| false)?;
+        if arrived.evaluated > 0 {
+            offer(&mut hof, pop.individuals(), pop.fitnesses(), &arrivals);
+            observer.on_evaluated(pop);
+        }
+        observer.adjust_probabilities(&mut cxpb, &mut mutpb);
+        let elites = elitism.k.min(pop.len());
+        let ranked = RankedFitnesses::new(pop.fitnesses(), &elitism.direction);
+        let mut sources = ranked.order()[..elites].to_vec();
+        let n = pop.len() - elites;
+        match observer.select_parents(pop, n, rng) {
+            Some(parents) => sources.extend(parents),
+            None => sources.extend(pop.selector().select_n_from_ranked_with(&ranked, n, rng)),
+        }
+        clone_selected_into(&mut genomes, pop.individuals(), &sources);
+        clone_selected_into(&mut fitnesses, pop.fitnesses(), &sources);
+        let (varied_crossed, varied_mutated) = vary(&mut genomes[elites..], pop.crossover(), pop.mutator(), cxpb,
+                                                    mutpb, gen, rng);
+        let (mut crossed, mut mutated) = (vec![false; elites], vec![false; elites]);
+        crossed.extend(varied_crossed);
+        mutated.extend(varied_mutated);
+        let changed = crossed.iter().zip(&mutated).map(|(&c, &m)| c || m).collect();
+        let mates = (0..sources.len())
+            .map(|i| (i >= elites && crossed[i]).then(|| sources[elites + ((i - elites) ^ 1)]))
+            .collect();
+        let mut next = Offspring { genomes, changed, crossed, mutated, parents: sources, mates };
+        observer.on_offspring(&mut next, rng);
+        let changed = next.changed_indices();
+        let ages = next.ages(pop.ages());
+        genomes = next.genomes;
+        pop.swap_all(&mut genomes, &mut fitnesses);
+        pop.set_ages(ages);
+        pop.invalidate(&changed);
+        let mut partial = None;
+        let offspring = evaluate(pop, &mut |pop| {
+            partial = stop.check_evaluation(&progress(pop));
+            partial.is_some()
+        })?;
+        let evaluation = Evaluation {
+            evaluated: arrived.evaluated + offspring.evaluated,
+            failures: arrived.failures + offspring.failures,
+        };
+        if let Some(reason) = partial {
+            // Stale fitnesses of the pending individuals mustn't reach the hall of fame or the stats
+            let pending = pop.invalid_indices();
+            let evaluated: Vec<usize> = (0..pop.len()).filter(|i| pending.binary_search(i).is_err()).collect();
+            let individuals: Vec<G> = evaluated.iter().map(|&i| pop.individuals()[i].clone()).collect();
+            let fitnesses: Vec<F> = evaluated.iter().map(|&i| pop.fitnesses()[i].clone()).collect();
+            update_hof(&mut hof, &individuals, &fitnesses);
+            observer.on_evaluated(pop);
+            let fitnesses: Vec<F> = evaluated.iter().map(|&i| pop.fitnesses()[i].clone()).collect();
+            let mut row = stats.map(|s| s.compile(&fitnesses)).unwrap_or_default();
+            account(&mut row, pop.evaluations(), started);
+            log_failures(&mut row, evaluation);
+            // The run is ending either way, so the observer can't keep it going
+            observer.on_generation_end(pop, &mut row);
+            logbook.record(gen, evaluation.evaluated, row);
+            let (reason, evaluated) = (Box::new(reason), evaluation.evaluated);
+            return Ok((logbook, StopReason::MidGeneration { reason, evaluated, pending: pending.len() }));
+        }
+        update_hof(&mut hof, pop.individuals(), pop.fitnesses());
+        observer.on_evaluated(pop);
+        let mut row = stats.map(|s| s.compile(pop.fitnesses())).unwrap_or_default();
+        account(&mut row, pop.evaluations(), started);
+        log_failures(&mut row, evaluation);
+        let control = observer.on_generation_end(pop, &mut row);
+        logbook.record(gen, evaluation.evaluated, row);
+        if control == Control::Stop {
+            return Ok((logbook, StopReason::Observer));
+        }
+    }
+}
+
+/// When [`with_restarts`] starts a run afresh
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestartPolicy {
+    /// Restarts the run when this fires; it starts counting again after every restart
+    pub stagnation: Stagnation,
+    /// How many of the best individuals are carried into each restarted population
+    pub keep_elites: usize,
+    pub max_restarts: usize,
+}
+
+/// The budget and the stagnation of the current attempt, checked together
+struct RestartStop<'a, T> {
+    budget: &'a mut T,
+    stagnation: Stagnation,
+    /// Whether the policy's stagnation ended the attempt, rather than a `Stagnation` inside `budget`
+    stagnated: bool,
+}
+
+impl<T: Termination<f64>> Termination<f64> for RestartStop<'_, T> {
+    fn check(&mut self, progress: &Progress<f64>) -> Option<StopReason> {
+        let stagnated = self.stagnation.check(progress);
+        let reason = self.budget.check(progress);
+        self.stagnated = reason.is_none() && stagnated.is_some();
+        reason.or(stagnated)
+    }
+
+    fn check_evaluation(&mut self, progress: &Progress<f64>) -> Option<StopReason> {
+        self.budget.check_evaluation(progress)
+    }
+}
+
+/// Runs `evolve` over and over, re-initialising the population whenever it stagnates
+///
+/// `evolve` is any evolve loop, such as a closure calling [`ea_simple`] with
+/// the criterion, hall of fame and generator it's given. Its criterion fires
+/// when either `stop` does, which ends the whole run, or the policy's
+/// stagnation does. Then, unless `policy.max_restarts` restarts have
+/// been made already, the population keeps its best `policy.keep_elites`,
+/// ranked by the stagnation's direction, and the rest are replaced from
+/// `init` and evaluated by the next attempt.
+///
+/// The population's generation and evaluation counters carry on across
+/// restarts, so a budget of either covers the whole run, and so does the
+/// hall of fame. Returns why the run ended, and the rows of every attempt
+/// in one [`Logbook`], each with the number of `"restarts"` before it. An
+/// attempt's first row shares its generation with the last row of the one
+/// before, counting the evaluations of its fresh individuals.
+#[allow(clippy::too_many_arguments)]
+pub fn with_restarts<G, M, C, S, T, I, E>(pop: &mut Population<G, M, C, S, f64>, mut stop: T, policy: &RestartPolicy,
+                                          init: &I, hof: &mut HallOfFame<G, f64>, rng: &mut dyn RngCore,
+                                          mut evolve: E) -> (Logbook, StopReason)
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<f64>,
+    T: Termination<f64>,
+    I: Init<G>,
+    E: FnMut(&mut Population<G, M, C, S, f64>, &mut dyn Termination<f64>, &mut HallOfFame<G, f64>, &mut dyn RngCore)
+             -> (Logbook, StopReason)
+{
+    let mut logbook = Logbook::new();
+    let mut restarts = 0;
+    loop {
+        let mut attempt = RestartStop { budget: &mut stop, stagnation: policy.stagnation.clone(), stagnated: false };
+        let (run, reason) = evolve(pop, &mut attempt, hof, rng);
+        let stagnated = attempt.stagnated;
+        for row in run.rows() {
+            let mut stats = row.stats.clone();
+            stats.insert("restarts", vec![restarts as f64]);
+            logbook.record(row.gen, row.nevals, stats);
+        }
+        if !stagnated || restarts == policy.max_restarts {
+            return (logbook, reason);
+        }
+        restarts += 1;
+        let kept = policy.keep_elites.min(pop.len());
+        pop.sort_by_fitness(policy.stagnation.direction);
+        for genome in &mut pop.individuals_mut()[kept..] {
+            *genome = init.init_with(rng);
+        }
+        let fresh: Vec<usize> = (kept..pop.len()).collect();
+        pop.invalidate(&fresh);
+        let mut ages = pop.ages().to_vec();
+        ages[kept..].fill(0);
+        pop.set_ages(ages);
+    }
+}
+
+/// Removes the candidates a budget left unevaluated, with their `ages`, returning how many there were
+///
+/// They only have their parents' fitnesses, so they can't take part in survivor selection.
+fn drop_pending<G, M, C, S, F>(pop: &mut Population<G, M, C, S, F>, ages: &mut Vec<u32>) -> usize
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone
+{
+    let pending = pop.invalid_indices();
+    if pending.is_empty() {
+        return 0;
+    }
+    let evaluated: Vec<usize> = (0..pop.len()).filter(|i| pending.binary_search(i).is_err()).collect();
+    let (mut individuals, mut fitnesses) = (Vec::new(), Vec::new());
+    clone_selected_into(&mut individuals, pop.individuals(), &evaluated);
+    clone_selected_into(&mut fitnesses, pop.fitnesses(), &evaluated);
+    pop.replace_all(individuals, fitnesses);
+    *ages = evaluated.iter().map(|&i| ages[i]).collect();
+    pending.len()
+}
+
+/// (mu + lambda): survivors are chosen from parents and offspring together
+///
+/// The population size is mu. Every generation `lambda` children are made by
+/// crossover with probability `cxpb`, mutation with probability `mutpb`, or
+/// otherwise by copying a parent, always from uniformly chosen parents. The
+/// population's selector then picks the next mu from the mu + lambda
+/// candidates, so with an elitist selector such as [`SelBest`] the best
+/// fitness never gets worse.
+///
+/// Returns why `stop` fired and a [`Logbook`] with a row for the initial
+/// population and each generation after it, and updates `hof` after every
+/// evaluation.
+///
+/// Budgets such as [`MaxEvaluations`] can stop the run partway through a
+/// generation, with [`StopReason::MidGeneration`]. The children left
+/// unevaluated are then dropped, and the survivors are selected from the
+/// rest, so the population keeps its size and every fitness is up to date.
+#[allow(clippy::too_many_arguments)]
+pub fn ea_mu_plus_lambda<G, M, C, S, F, E, T>(pop: &mut Population<G, M, C, S, F>, lambda: usize, stop: T,
+                                              cxpb: f64, mutpb: f64, eval: E, stats: Option<&Statistics<F>>,
+                                              hof: Option<&mut HallOfFame<G, F>>, rng: &mut dyn RngCore)
+                                              -> (Logbook, StopReason)
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    E: Fn(&G) -> F,
+    T: Termination<F>
+{
+    ea_mu_lambda(pop, lambda, stop, cxpb, mutpb, true, eval, stats, hof, rng)
+}
+
+/// (mu, lambda): survivors are chosen from the offspring only
+///
+/// As [`ea_mu_plus_lambda`], except the parents are always discarded, so the
+/// best fitness can get worse from one generation to the next. Panics if
+/// `lambda` is smaller than the population size mu. If a budget stops the
+/// run partway through a generation, fewer than mu children may have been
+/// evaluated, and the selector picks mu survivors from those.
+#[allow(clippy::too_many_arguments)]
+pub fn ea_mu_comma_lambda<G, M, C, S, F, E, T>(pop: &mut Population<G, M, C, S, F>, lambda: usize, stop: T,
+                                               cxpb: f64, mutpb: f64, eval: E, stats: Option<&Statistics<F>>,
+                                               hof: Option<&mut HallOfFame<G, F>>, rng: &mut dyn RngCore)
+                                               -> (Logbook, StopReason)
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    E: Fn(&G) -> F,
+    T: Termination<F>
+{
+    assert!(lambda >= pop.len(), "lambda ({}) must be at least mu ({})", lambda, pop.len());
+    ea_mu_lambda(pop, lambda, stop, cxpb, mutpb, false, eval, stats, hof, rng)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn ea_mu_lambda<G, M, C, S, F, E, T>(pop: &mut Population<G, M, C, S, F>, lambda: usize, mut stop: T,
+                                     cxpb: f64, mutpb: f64, plus: bool, eval: E, stats: Option<&Statistics<F>>,
+                                     mut hof: Option<&mut HallOfFame<G, F>>, rng: &mut dyn RngCore)
+                                     -> (Logbook, StopReason)
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    E: Fn(&G) -> F,
+    T: Termination<F>
+{
+    let started = Instant::now();
+    let mu = pop.len();
+    let mut logbook = Logbook::new();
+    let entrants = entrants(pop);
+    let nevals = pop.evaluate(&eval);
+    offer(&mut hof, pop.individuals(), pop.fitnesses(), &entrants);
+    if !entrants.is_empty() {
+        record(&mut logbook, pop.generation(), nevals, pop.evaluations(), started, pop.fitnesses(), stats);
+    }
+
+    loop {
+        if let Some(reason) = stop.check(&progress(pop)) {
+            return (logbook, reason);
+        }
+        let gen = pop.next_generation();
+        let offspring = var_or(pop.individuals(), pop.crossover(), pop.mutator(), lambda, cxpb, mutpb, rng);
+        // Children start with their first parent's fitness until re-evaluated
+        let offspring_fits: Vec<F> = offspring.parents.iter().map(|&i| pop.fitnesses()[i].clone()).collect();
+        let (mut candidates, mut fitnesses, mut ages, offset) = if plus {
+            let older = pop.ages().iter().map(|age| age + 1).collect();
+            (pop.individuals().to_vec(), pop.fitnesses().to_vec(), older, mu)
+        } else {
+            (Vec::with_capacity(lambda), Vec::with_capacity(lambda), Vec::with_capacity(lambda), 0)
+        };
+        let changed: Vec<usize> = offspring.changed_indices().into_iter().map(|i| i + offset).collect();
+        ages.extend(offspring.ages(pop.ages()));
+        candidates.extend(offspring.genomes);
+        fitnesses.extend(offspring_fits);
+        pop.replace_all(candidates, fitnesses);
+        pop.invalidate(&changed);
+        let mut partial = None;
+        let nevals = pop.evaluate_until(&eval, |pop| {
+            partial = stop.check_evaluation(&progress(pop));
+            partial.is_some()
+        });
+        let pending = drop_pending(pop, &mut ages);
+        update_hof(&mut hof, pop.individuals(), pop.fitnesses());
+
+        let (individuals, survivors) = select_indexed_with(pop.selector(), pop.individuals(), pop.fitnesses(), mu, rng);
+        let fitnesses = survivors.iter().map(|&i| pop.fitnesses()[i].clone()).collect();
+        pop.replace_all(individuals, fitnesses);
+        pop.set_ages(survivors.iter().map(|&i| ages[i]).collect());
+        record(&mut logbook, gen, nevals, pop.evaluations(), started, pop.fitnesses(), stats);
+        if let Some(reason) = partial {
+            return (logbook, StopReason::MidGeneration { reason: Box::new(reason), evaluated: nevals, pending });
+        }
+    }
+}
+
+/// NSGA-II: elitist multi-objective evolution by non-dominated sorting and crowding
+///
+/// Each generation a whole population of parents is picked by binary
+/// [`CrowdedTournament`], then mated and mutated as in [`ea_simple`] with the
+/// population's operators, typically [`SimulatedBinary`] and [`Polynomial`].
+/// Parents and offspring together are then cut back to the population size by [`Nsga2Selection`].
+/// The population's own selector isn't used. `direction` applies to every
+/// objective.
+///
+/// Returns why `stop` fired and a [`Logbook`] with a row for the initial
+/// population and each generation after it. The rows hold `stats` compiled
+/// per objective if given, or else a [`MultiStats`] in `direction`. `front` is
+/// updated after every evaluation. A budget that stops the run partway
+/// through a generation does so as for [`ea_mu_plus_lambda`].
+/// [`nsga2_with_stats`] takes a configured [`MultiStats`] or any other
+/// [`Compile`] instead.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::nsga2;
+/// use dears::archive::ParetoFront;
+/// use dears::crossover::SimulatedBinary;
+/// use dears::initializer::UniformFloat;
+/// use dears::mutation::Polynomial;
+/// use dears::population::PopulationBuilder;
+/// use dears::multiobjective::Nsga2Selection;
+/// use dears::selection::Direction;
+/// use dears::termination::MaxGenerations;
+/// let mut rng = rand::thread_rng();
+/// let mut pop = PopulationBuilder::new()
+///     .init_from(20, &UniformFloat { low: 0.0, up: 1.0, len: 2 }, &mut rng)
+///     .mutator(Polynomial { eta: 20.0, low: 0.0, up: 1.0, indpb: 0.5 })
+///     .crossover(SimulatedBinary { eta: 15.0, low: 0.0, up: 1.0 })
+///     .selector(Nsga2Selection { direction: Direction::Minimize })
+///     .build()
+///     .unwrap();
+/// let eval = |g: &Vec<f64>| [g[0], 1.0 - g[0] + g[1]];
+/// let mut front = ParetoFront::new().with_direction(Direction::Minimize);
+/// let (logbook, _) = nsga2(&mut pop, MaxGenerations(10), 0.9, 0.2, Direction::Minimize, eval, None,
+///                          Some(&mut front), &mut rng);
+/// assert_eq!((logbook.len(), pop.len()), (11, 20));
+/// assert!(logbook.select("front_size").iter().all(|&size| size >= 1.0));
+/// assert!(!front.is_empty());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn nsga2<G, M, C, S, E, T, const N: usize>(pop: &mut Population<G, M, C, S, [f64; N]>, stop: T, cxpb: f64,
+                                               mutpb: f64, direction: Direction, eval: E,
+                                               stats: Option<&Statistics<[f64; N]>>,
+                                               front: Option<&mut ParetoFront<G, N>>, rng: &mut dyn RngCore)
+                                               -> (Logbook, StopReason)
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<[f64; N]>,
+    E: Fn(&G) -> [f64; N],
+    T: Termination<[f64; N]>
+{
+    let defaults = MultiStats::new(direction);
+    let stats = stats.map_or(&defaults as &dyn Compile<[f64; N]>, |stats| stats);
+    nsga2_with_stats(pop, stop, cxpb, mutpb, direction, eval, Some(stats), front, rng)
+}
+
+/// [`nsga2`], with the logbook compiled by any [`Compile`], such as a [`MultiStats`]
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::nsga2_with_stats;
+/// use dears::crossover::SimulatedBinary;
+/// use dears::initializer::UniformFloat;
+/// use dears::mutation::Polynomial;
+/// use dears::population::PopulationBuilder;
+/// use dears::multiobjective::Nsga2Selection;
+/// use dears::selection::Direction;
+/// use dears::stats::MultiStats;
+/// use dears::termination::MaxGenerations;
+/// let mut rng = rand::thread_rng();
+/// let mut pop = PopulationBuilder::new()
+///     .init_from(20, &UniformFloat { low: 0.0, up: 1.0, len: 2 }, &mut rng)
+///     .mutator(Polynomial { eta: 20.0, low: 0.0, up: 1.0, indpb: 0.5 })
+///     .crossover(SimulatedBinary { eta: 15.0, low: 0.0, up: 1.0 })
+///     .selector(Nsga2Selection { direction: Direction::Minimize })
+///     .build()
+///     .unwrap();
+/// let eval = |g: &Vec<f64>| [g[0], 1.0 - g[0] + g[1]];
+/// let stats = MultiStats::new(Direction::Minimize);
+/// let (logbook, _) = nsga2_with_stats(&mut pop, MaxGenerations(10), 0.9, 0.2, Direction::Minimize, eval,
+///                                     Some(&stats), None, &mut rng);
+/// assert!(logbook.select("front_size").iter().all(|&size| size >= 1.0));
+/// assert_eq!(logbook.select("obj1_min").len(), 11);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn nsga2_with_stats<G, M, C, S, E, T, const N: usize>(pop: &mut Population<G, M, C, S, [f64; N]>, mut stop: T,
+                                                          cxpb: f64, mutpb: f64, direction: Direction, eval: E,
+                                                          stats: Option<&dyn Compile<[f64; N]>>,
+                                                          mut front: Option<&mut ParetoFront<G, N>>,
+                                                          rng: &mut dyn RngCore) -> (Logbook, StopReason)
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<[f64; N]>,
+    E: Fn(&G) -> [f64; N],
+    T: Termination<[f64; N]>
+{
+    let started = Instant::now();
+    let mu = pop.len();
+    let mut logbook = Logbook::new();
+    let entrants = entrants(pop);
+    let nevals = pop.evaluate(&eval);
+    if let Some(front) = &mut front {
+        for &i in &entrants {
+            front.insert(&pop.individuals()[i], &pop.fitnesses()[i]);
+        }
+    }
+    if !entrants.is_empty() {
+        record(&mut logbook, pop.generation(), nevals, pop.evaluations(), started, pop.fitnesses(), stats);
+    }
+
+    loop {
+        if let Some(reason) = stop.check(&progress(pop)) {
+            return (logbook, reason);
+        }
+        let gen = pop.next_generation();
+        let (parents, mates) = select_indexed_with(&CrowdedTournament { direction }, pop.individuals(),
+                                                   pop.fitnesses(), mu, rng);
+        let offspring = var_and(&parents, pop.crossover(), pop.mutator(), cxpb, mutpb, rng);
+        let offspring_fits: Vec<[f64; N]> = mates.iter().map(|&i| pop.fitnesses()[i]).collect();
+        let changed: Vec<usize> = offspring.changed_indices().into_iter().map(|i| i + mu).collect();
+        let mate_ages: Vec<u32> = mates.iter().map(|&i| pop.ages()[i]).collect();
+        let mut ages: Vec<u32> = pop.ages().iter().map(|age| age + 1).collect();
+        ages.extend(offspring.ages(&mate_ages));
+        let mut candidates = pop.individuals().to_vec();
+        let mut fitnesses = pop.fitnesses().to_vec();
+        candidates.extend(offspring.genomes);
+        fitnesses.extend(offspring_fits);
+        pop.replace_all(candidates, fitnesses);
+        pop.invalidate(&changed);
+        let mut partial = None;
+        let nevals = pop.evaluate_until(&eval, |pop| {
+            partial = stop.check_evaluation(&progress(pop));
+            partial.is_some()
+        });
+        let pending = drop_pending(pop, &mut ages);
+        if let Some(front) = &mut front {
+            front.update(&pop.individuals()[mu..], &pop.fitnesses()[mu..]);
+        }
+
+        let (individuals, survivors) = select_indexed_with(&Nsga2Selection { direction }, pop.individuals(),
+                                                           pop.fitnesses(), mu, rng);
+        let fitnesses = survivors.iter().map(|&i| pop.fitnesses()[i]).collect();
+        pop.replace_all(individuals, fitnesses);
+        pop.set_ages(survivors.iter().map(|&i| ages[i]).collect());
+        record(&mut logbook, gen, nevals, pop.evaluations(), started, pop.fitnesses(), stats);
+        if let Some(reason) = partial {
+            return (logbook, StopReason::MidGeneration { reason: Box::new(reason), evaluated: nevals, pending });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithms::*;
+
+    /// Genome recording which operators produced it
+    #[derive(Clone, Debug, PartialEq, Default)]
+    struct Tagged {
+        crossed: bool,
+        mutated: bool,
+    }
+
+    struct Tag;
+
+    impl Crossover<Tagged> for Tag {
+        fn crossover_with(&self, a: &mut Tagged, b: &mut Tagged, _rng: &mut dyn RngCore) {
+            a.crossed = true;
+            b.crossed = true;
+        }
+    }
+
+    impl Mutator<Tagged> for Tag {
+        fn mutate_with(&self, genome: &mut Tagged, _rng: &mut dyn RngCore) {
+            genome.mutated = true;
+        }
+    }
+
+    #[test]
+    fn var_or_uses_exactly_one_operator() {
+        let parents = vec![Tagged::default(); 6];
+        let mut rng = rand::thread_rng();
+        let crossed = var_or(&parents, &Tag, &Tag, 50, 1.0, 0.0, &mut rng);
+        assert!(crossed.genomes.iter().all(|g| g.crossed && !g.mutated));
+        let mutated = var_or(&parents, &Tag, &Tag, 50, 0.0, 1.0, &mut rng);
+        assert!(mutated.genomes.iter().all(|g| !g.crossed && g.mutated));
+        let mixed = var_or(&parents, &Tag, &Tag, 200, 0.3, 0.3, &mut rng);
+        for (genome, &changed) in mixed.genomes.iter().zip(&mixed.changed) {
+            assert!(!(genome.crossed && genome.mutated));
+            assert_eq!(changed, genome.crossed || genome.mutated);
+        }
+        assert_eq!(parents, vec![Tagged::default(); 6]);
+    }
+
+    #[test]
+    fn var_and_can_apply_both() {
+        let parents = vec![Tagged::default(); 5];
+        let offspring = var_and(&parents, &Tag, &Tag, 1.0, 1.0, &mut rand::thread_rng());
+        // The odd one out has no partner to mate with
+        assert!(offspring.genomes[..4].iter().all(|g| g.crossed && g.mutated));
+        assert_eq!(offspring.genomes[4], Tagged { crossed: false, mutated: true });
+        assert_eq!(offspring.changed_indices(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(parents, vec![Tagged::default(); 5]);
+
+        let untouched = var_and(&parents, &Tag, &Tag, 0.0, 0.0, &mut rand::thread_rng());
+        assert_eq!(untouched.genomes, parents);
+        assert!(untouched.changed_indices().is_empty());
+    }
+
+    #[test]
+    fn spawned_offspring_are_evaluated_afresh() {
+        use crate::individual::{Delegate, Individual};
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Scored {
+            value: u32,
+            fitness: Option<f64>,
+        }
+
+        impl Individual for Scored {
+            type Fitness = f64;
+            fn mutate(&mut self) {
+                self.value += 1;
+            }
+            fn crossover(&mut self, other: &mut Self) {
+                std::mem::swap(&mut self.value, &mut other.value);
+            }
+            fn fitness(&self) -> Option<&f64> {
+                self.fitness.as_ref()
+            }
+            fn set_fitness(&mut self, f: f64) {
+                self.fitness = Some(f);
+            }
+            fn invalidate(&mut self) {
+                self.fitness = None;
+            }
+        }
+
+        let parents: Vec<Scored> = (0..4).map(|value| Scored { value, fitness: Some(value as f64) }).collect();
+        let mut rng = rand::thread_rng();
+        for offspring in [var_and(&parents, &Delegate, &Delegate, 0.5, 0.5, &mut rng),
+                          var_or(&parents, &Delegate, &Delegate, 40, 0.4, 0.4, &mut rng)] {
+            for (i, child) in offspring.genomes.iter().enumerate() {
+                // Varied children have no fitness, and plain copies keep their parent's
+                let expected = (!offspring.changed[i]).then_some(offspring.parents[i] as f64);
+                assert_eq!(child.fitness().copied(), expected);
+            }
+            // Children are a generation older than their older parent
+            for (i, age) in offspring.ages(&[0, 3, 0, 0]).into_iter().enumerate() {
+                let of_old_parent = offspring.parents[i] == 1 || offspring.mates[i] == Some(1);
+                assert_eq!(age, if of_old_parent { 4 } else { 1 });
+            }
+        }
+        assert!(parents.iter().all(|p| p.fitness() == Some(&(p.value as f64))));
+    }
+
+    #[test]
+    fn ages_follow_the_oldest_parent() {
+        let mut pop = Population::new(vec![Tagged::default(); 4], Tag, Tag, SelBest::default());
+        pop.evaluate(|_| 0.0);
+        pop.set_ages(vec![2, 5, 0, 1]);
+
+        // Pairs (0, 1) and (2, 3) are mated, then everyone is mutated
+        let offspring = var_and(pop.individuals(), &Tag, &Tag, 1.0, 1.0, &mut rand::thread_rng());
+        assert_eq!(offspring.mates, vec![Some(1), Some(0), Some(3), Some(2)]);
+        let ages = offspring.ages(pop.ages());
+        assert_eq!(ages, vec![6, 6, 2, 2]);
+        pop.replace_all(offspring.genomes, vec![0.0; 4]);
+        pop.set_ages(ages);
+
+        // Plain copies and mutants just age by one
+        let offspring = var_or(pop.individuals(), &Tag, &Tag, 20, 0.0, 0.5, &mut rand::thread_rng());
+        assert!(offspring.mates.iter().all(Option::is_none));
+        let ages = offspring.ages(pop.ages());
+        for (&parent, &age) in offspring.parents.iter().zip(&ages) {
+            assert_eq!(age, pop.ages()[parent] + 1);
+        }
+    }
+}
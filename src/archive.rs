@@ -0,0 +1,517 @@
+use std::cmp::Ordering;
+
+use rand::{Rng, RngCore};
+
+use crate::csv::FitnessColumns;
+use crate::display::{DisplayGene, GenomeFormat};
+use crate::multiobjective::dominates;
+use crate::selection::Direction;
+use crate::stats::format_values;
+
+/// What to do with a behaviour descriptor that falls outside the grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutOfBounds {
+    /// Move each coordinate to the nearest edge cell
+    Clamp,
+    /// Refuse to insert the individual
+    Reject,
+}
+
+/// Result of offering an individual to a [`GridArchive`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Insertion {
+    /// The cell was empty and now holds the individual
+    Added,
+    /// The individual was strictly fitter than the previous occupant
+    Replaced,
+    /// The occupant was at least as fit, so the archive is unchanged
+    Kept,
+    /// The descriptor was outside the grid under [`OutOfBounds::Reject`]
+    OutOfBounds,
+}
+
+/// A MAP-Elites archive holding the best individual found in each grid cell
+///
+/// Cells are addressed by integer coordinates, one per dimension of `shape`,
+/// as worked out by a user behaviour descriptor. Parents for the next batch of
+/// variation are drawn uniformly from the filled cells.
+///
+/// # Examples
+/// ```
+/// use dears::archive::*;
+/// let mut archive = GridArchive::new(vec![4, 4], OutOfBounds::Clamp);
+/// assert_eq!(archive.insert("a", 1.0, &[0, 3]), Insertion::Added);
+/// assert_eq!(archive.insert("b", 0.5, &[0, 3]), Insertion::Kept);
+/// assert_eq!(archive.insert("c", 2.0, &[9, -1]), Insertion::Added);
+/// assert_eq!(archive.export(), vec![(vec![0, 3], 1.0), (vec![3, 0], 2.0)]);
+/// ```
+pub struct GridArchive<G, F = f64> {
+    shape: Vec<usize>,
+    out_of_bounds: OutOfBounds,
+    direction: Direction,
+    cells: Vec<Option<(G, F)>>,
+    filled: Vec<usize>,
+}
+
+impl<G, F: PartialOrd> GridArchive<G, F> {
+    /// Creates an empty archive with `shape[d]` cells along dimension `d`
+    ///
+    /// Panics if the grid has no cells.
+    pub fn new(shape: Vec<usize>, out_of_bounds: OutOfBounds) -> Self {
+        let size: usize = shape.iter().product();
+        assert!(!shape.is_empty() && size > 0, "Grid must have at least one cell");
+        GridArchive {
+            shape,
+            out_of_bounds,
+            direction: Direction::default(),
+            cells: (0..size).map(|_| None).collect(),
+            filled: Vec::new(),
+        }
+    }
+
+    /// Sets whether bigger or smaller fitnesses win a cell
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Number of filled cells
+    pub fn len(&self) -> usize {
+        self.filled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filled.is_empty()
+    }
+
+    /// Offers an individual for the cell at `descriptor`
+    ///
+    /// It only displaces an existing occupant if strictly fitter.
+    pub fn insert(&mut self, genome: G, fitness: F, descriptor: &[i64]) -> Insertion {
+        let cell = match self.flatten(descriptor) {
+            Some(cell) => cell,
+            None => return Insertion::OutOfBounds,
+        };
+        match &self.cells[cell] {
+            None => {
+                self.cells[cell] = Some((genome, fitness));
+                self.filled.push(cell);
+                Insertion::Added
+            }
+            Some((_, current)) if self.direction.compare(&fitness, current) == Ordering::Greater => {
+                self.cells[cell] = Some((genome, fitness));
+                Insertion::Replaced
+            }
+            Some(_) => Insertion::Kept,
+        }
+    }
+
+    /// Returns the genome and fitness stored at `descriptor`, if any
+    pub fn get(&self, descriptor: &[i64]) -> Option<(&G, &F)> {
+        let cell = self.flatten(descriptor)?;
+        self.cells[cell].as_ref().map(|(g, f)| (g, f))
+    }
+
+    /// Picks a uniformly random elite to use as a parent
+    pub fn select_random_elite(&self, rng: &mut dyn RngCore) -> Option<&G> {
+        if self.filled.is_empty() {
+            return None;
+        }
+        let cell = self.filled[rng.gen_range(0..self.filled.len())];
+        self.cells[cell].as_ref().map(|(g, _)| g)
+    }
+
+    /// Iterates over filled cells as `(coordinates, genome, fitness)`, in grid order
+    pub fn iter(&self) -> impl Iterator<Item = (Vec<usize>, &G, &F)> + '_ {
+        self.cells.iter().enumerate()
+            .filter_map(move |(cell, entry)| {
+                entry.as_ref().map(|(g, f)| (self.unflatten(cell), g, f))
+            })
+    }
+
+    /// Returns the coordinates and fitness of every filled cell, for plotting
+    pub fn export(&self) -> Vec<(Vec<usize>, F)>
+    where
+        F: Clone
+    {
+        self.iter().map(|(coords, _, f)| (coords, f.clone())).collect()
+    }
+
+    fn flatten(&self, descriptor: &[i64]) -> Option<usize> {
+        assert_eq!(descriptor.len(), self.shape.len(), "Descriptor must have one coordinate per dimension");
+        let mut cell = 0;
+        for (&coord, &size) in descriptor.iter().zip(&self.shape) {
+            let max = size as i64 - 1;
+            let coord = match self.out_of_bounds {
+                OutOfBounds::Clamp => coord.clamp(0, max),
+                OutOfBounds::Reject if (0..=max).contains(&coord) => coord,
+                OutOfBounds::Reject => return None,
+            };
+            cell = cell * size + coord as usize;
+        }
+        Some(cell)
+    }
+
+    fn unflatten(&self, mut cell: usize) -> Vec<usize> {
+        let mut coords = vec![0; self.shape.len()];
+        for (coord, &size) in coords.iter_mut().zip(&self.shape).rev() {
+            *coord = cell % size;
+            cell /= size;
+        }
+        coords
+    }
+}
+
+type Similarity<G> = Box<dyn Fn(&G, &G) -> bool + Send + Sync>;
+
+/// The best individuals seen over a whole run, kept in a fixed-size sorted list
+///
+/// Entries are cloned on insertion, so they survive the population being
+/// changed in place. The best entry comes first. Fitness ties keep the entry
+/// that arrived first ahead, and a full hall only accepts a newcomer that is
+/// strictly fitter than its worst entry. If a similarity predicate is set, a
+/// candidate similar to any entry already in the hall is rejected. The
+/// predicate isn't saved in checkpoints, so set it again after loading.
+/// Fitnesses are ranked by [`Direction::compare`], so a weighted
+/// [`Fitness`](crate::fitness::Fitness) ranks by its weighted values, with
+/// invalid ones last.
+///
+/// # Examples
+/// ```
+/// use dears::archive::HallOfFame;
+/// let mut hof = HallOfFame::new(2);
+/// hof.update(&["a", "b", "c"], &[1.0, 3.0, 2.0]);
+/// hof.update(&["d", "e", "f"], &[0.0, 0.5, 2.0]);
+/// assert_eq!(hof.best(), Some((&"b", &3.0)));
+/// assert_eq!(hof.iter().map(|(g, _)| *g).collect::<Vec<_>>(), vec!["b", "c"]);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "G: serde::Deserialize<'de>, F: serde::Deserialize<'de>")))]
+pub struct HallOfFame<G, F = f64> {
+    capacity: usize,
+    direction: Direction,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    similar: Option<Similarity<G>>,
+    entries: Vec<(G, F)>,
+}
+
+impl<G: Clone, F: Clone + PartialOrd> HallOfFame<G, F> {
+    /// Creates an empty hall holding at most `capacity` individuals
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Hall of fame must hold at least one individual");
+        HallOfFame {
+            capacity,
+            direction: Direction::default(),
+            similar: None,
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Sets whether bigger or smaller fitnesses are better
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Rejects candidates for which `similar` holds against any entry
+    pub fn with_similarity(mut self, similar: impl Fn(&G, &G) -> bool + Send + Sync + 'static) -> Self {
+        self.similar = Some(Box::new(similar));
+        self
+    }
+
+    /// Offers every individual of a population, returning how many were let in
+    pub fn update(&mut self, individuals: &[G], fitnesses: &[F]) -> usize {
+        assert_eq!(individuals.len(), fitnesses.len(), "Need exactly one fitness per individual");
+        individuals.iter().zip(fitnesses)
+            .filter(|(genome, fitness)| self.insert(genome, fitness))
+            .count()
+    }
+
+    /// Offers one individual, cloning it in if it makes the cut
+    pub fn insert(&mut self, genome: &G, fitness: &F) -> bool {
+        if self.is_full() {
+            let (_, worst) = self.entries.last().unwrap();
+            if self.direction.compare(fitness, worst) != Ordering::Greater {
+                return false;
+            }
+        }
+        if let Some(similar) = &self.similar {
+            if self.entries.iter().any(|(other, _)| similar(genome, other)) {
+                return false;
+            }
+        }
+        // After every entry at least as good, so earlier arrivals win ties
+        let pos = self.entries.iter()
+            .position(|(_, other)| self.direction.compare(fitness, other) == Ordering::Greater)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(pos, (genome.clone(), fitness.clone()));
+        self.entries.truncate(self.capacity);
+        true
+    }
+}
+
+impl<G, F> HallOfFame<G, F> {
+    /// The best individual seen so far
+    pub fn best(&self) -> Option<(&G, &F)> {
+        self.entries.first().map(|(g, f)| (g, f))
+    }
+
+    /// Iterates from best to worst
+    pub fn iter(&self) -> impl Iterator<Item = (&G, &F)> {
+        self.entries.iter().map(|(g, f)| (g, f))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.entries.len() == self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// One line per entry, best first, with its rank, fitness and genome written out in `format`
+    ///
+    /// Fitnesses are written as in the [`Logbook`](crate::stats::Logbook).
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::archive::HallOfFame;
+    /// use dears::display::GenomeFormat;
+    /// let mut hof = HallOfFame::new(2);
+    /// hof.update(&[vec![0.5; 100], vec![-0.0; 100]], &[2.0, 1.0]);
+    /// let format = GenomeFormat { precision: Some(1), max_elements: Some(2), indices: false };
+    /// let expected = "1. 2.0000  [0.5, 0.5, … 98 more]\n2. 1.0000  [0.0, 0.0, … 98 more]";
+    /// assert_eq!(hof.format_entries(format), expected);
+    /// ```
+    pub fn format_entries<T>(&self, format: GenomeFormat) -> String
+    where
+        G: AsRef<[T]>,
+        T: DisplayGene,
+        F: FitnessColumns
+    {
+        let lines: Vec<String> = self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, (genome, fitness))| {
+                format!("{}. {}  {}", i + 1, format_values(&fitness.columns()), format.display(genome.as_ref()))
+            })
+            .collect();
+        lines.join("\n")
+    }
+}
+
+/// Every mutually non-dominated individual seen over a multi-objective run
+///
+/// An unbounded [`HallOfFame`] for `N` objectives. A candidate dominated by
+/// any entry is rejected, and one that gets in removes every entry it
+/// dominates. Candidates with the same fitness as an entry are kept
+/// alongside it, unless a similarity predicate is set and holds against an
+/// entry the candidate doesn't dominate. Entries are kept sorted by
+/// objective values, first objective first, ready for plotting.
+///
+/// # Examples
+/// ```
+/// use dears::archive::ParetoFront;
+/// use dears::selection::Direction;
+/// let mut front = ParetoFront::new().with_direction(Direction::Minimize);
+/// front.update(&["a", "b", "c", "d"], &[[2.0, 1.0], [1.0, 3.0], [2.0, 2.0], [1.5, 1.5]]);
+/// assert_eq!(front.iter().map(|(g, _)| *g).collect::<Vec<_>>(), vec!["b", "d", "a"]);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "G: serde::Serialize", deserialize = "G: serde::Deserialize<'de>"))
+)]
+pub struct ParetoFront<G, const N: usize> {
+    direction: Direction,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    similar: Option<Similarity<G>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array::entries"))]
+    entries: Vec<(G, [f64; N])>,
+}
+
+impl<G: Clone, const N: usize> ParetoFront<G, N> {
+    pub fn new() -> Self {
+        ParetoFront {
+            direction: Direction::default(),
+            similar: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Sets whether bigger or smaller objective values are better
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Rejects candidates for which `similar` holds against an entry they don't dominate
+    pub fn with_similarity(mut self, similar: impl Fn(&G, &G) -> bool + Send + Sync + 'static) -> Self {
+        self.similar = Some(Box::new(similar));
+        self
+    }
+
+    /// Offers every individual of a population, returning how many were let in
+    pub fn update(&mut self, individuals: &[G], fitnesses: &[[f64; N]]) -> usize {
+        assert_eq!(individuals.len(), fitnesses.len(), "Need exactly one fitness per individual");
+        individuals.iter().zip(fitnesses)
+            .filter(|(genome, fitness)| self.insert(genome, fitness))
+            .count()
+    }
+
+    /// Offers one individual, cloning it in if nothing in the front dominates it
+    pub fn insert(&mut self, genome: &G, fitness: &[f64; N]) -> bool {
+        let direction = self.direction;
+        if self.entries.iter().any(|(_, other)| dominates(other, fitness, direction)) {
+            return false;
+        }
+        if let Some(similar) = &self.similar {
+            let twin = self.entries.iter()
+                .any(|(other, f)| !dominates(fitness, f, direction) && similar(genome, other));
+            if twin {
+                return false;
+            }
+        }
+        self.entries.retain(|(_, other)| !dominates(fitness, other, direction));
+        // After any equal entries, so iteration order is stable
+        let pos = self.entries.partition_point(|(_, other)| objective_order(other, fitness) != Ordering::Greater);
+        self.entries.insert(pos, (genome.clone(), *fitness));
+        true
+    }
+}
+
+impl<G: Clone, const N: usize> Default for ParetoFront<G, N> {
+    fn default() -> Self {
+        ParetoFront::new()
+    }
+}
+
+impl<G, const N: usize> ParetoFront<G, N> {
+    /// Iterates in order of the objective values, first objective first
+    pub fn iter(&self) -> impl Iterator<Item = (&G, &[f64; N])> {
+        self.entries.iter().map(|(g, f)| (g, f))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Lexicographic order on raw objective values, for plotting
+fn objective_order<const N: usize>(a: &[f64; N], b: &[f64; N]) -> Ordering {
+    a.iter().zip(b)
+        .map(|(x, y)| x.total_cmp(y))
+        .find(|&ordering| ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::archive::*;
+
+    #[test]
+    fn only_strictly_better_displaces() {
+        let mut archive = GridArchive::new(vec![3, 2], OutOfBounds::Reject);
+        assert_eq!(archive.insert(1, 1.0, &[2, 1]), Insertion::Added);
+        assert_eq!(archive.insert(2, 1.0, &[2, 1]), Insertion::Kept);
+        assert_eq!(archive.get(&[2, 1]), Some((&1, &1.0)));
+        assert_eq!(archive.insert(3, 1.5, &[2, 1]), Insertion::Replaced);
+        assert_eq!(archive.get(&[2, 1]), Some((&3, &1.5)));
+
+        let mut minimising = GridArchive::new(vec![2], OutOfBounds::Reject).with_direction(Direction::Minimize);
+        minimising.insert("a", 1.0, &[0]);
+        assert_eq!(minimising.insert("b", 2.0, &[0]), Insertion::Kept);
+        assert_eq!(minimising.insert("c", 0.5, &[0]), Insertion::Replaced);
+
+        let mut rng = StdRng::seed_from_u64(128);
+        assert_eq!(archive.select_random_elite(&mut rng), Some(&3));
+        assert_eq!(archive.len(), 1);
+    }
+
+    #[test]
+    fn out_of_bounds_clamps_or_rejects() {
+        let mut clamped = GridArchive::new(vec![3, 2], OutOfBounds::Clamp);
+        assert_eq!(clamped.insert('a', 1.0, &[-4, 7]), Insertion::Added);
+        assert_eq!(clamped.get(&[0, 1]), Some((&'a', &1.0)));
+
+        let mut rejecting = GridArchive::new(vec![3, 2], OutOfBounds::Reject);
+        assert_eq!(rejecting.insert('a', 1.0, &[3, 0]), Insertion::OutOfBounds);
+        assert_eq!(rejecting.insert('a', 1.0, &[0, -1]), Insertion::OutOfBounds);
+        assert!(rejecting.is_empty());
+        assert_eq!(rejecting.select_random_elite(&mut rand::thread_rng()), None);
+    }
+
+    #[test]
+    fn hall_of_fame_stays_sorted_and_bounded() {
+        let mut hof = HallOfFame::new(3).with_direction(Direction::Minimize);
+        // The NaN gets in while there's room, then is pushed out as the worst
+        assert_eq!(hof.update(&[1, 2, 3, 4], &[4.0, 2.0, f64::NAN, 3.0]), 4);
+        assert_eq!(hof.iter().map(|(g, _)| *g).collect::<Vec<_>>(), vec![2, 4, 1]);
+        // A tie with the worst entry isn't enough to get in
+        assert_eq!(hof.update(&[5], &[4.0]), 0);
+        assert_eq!(hof.update(&[6, 7], &[2.0, 0.0]), 2);
+        assert_eq!(hof.iter().map(|(g, _)| *g).collect::<Vec<_>>(), vec![7, 2, 6]);
+        assert_eq!(hof.len(), hof.capacity());
+
+        let mut distinct = HallOfFame::new(3).with_similarity(|a: &i32, b: &i32| a == b);
+        distinct.update(&[1, 1, 1, 2], &[1.0, 1.0, 1.0, 0.5]);
+        assert_eq!(distinct.iter().map(|(g, _)| *g).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn pareto_front_matches_non_dominated_subset() {
+        use crate::multiobjective::non_dominated_sort;
+
+        let mut rng = StdRng::seed_from_u64(145);
+        let fitnesses: Vec<[f64; 2]> = (0..200)
+            .map(|_| [rng.gen_range(0..20) as f64, rng.gen_range(0..20) as f64])
+            .collect();
+        let individuals: Vec<usize> = (0..fitnesses.len()).collect();
+        let mut front = ParetoFront::new();
+        // Offered in two batches, as over two generations
+        front.update(&individuals[..100], &fitnesses[..100]);
+        front.update(&individuals[100..], &fitnesses[100..]);
+
+        let mut expected = non_dominated_sort(&fitnesses, Direction::Maximize).swap_remove(0);
+        expected.sort_by(|&a, &b| objective_order(&fitnesses[a], &fitnesses[b]).then(a.cmp(&b)));
+        assert_eq!(front.iter().map(|(&g, _)| g).collect::<Vec<_>>(), expected);
+
+        // Identical points are all non-dominated, unless they're deduplicated
+        let mut same = ParetoFront::new();
+        assert_eq!(same.update(&[1, 1, 1], &[[1.0, 1.0]; 3]), 3);
+        let mut deduplicated = ParetoFront::new().with_similarity(|a: &i32, b: &i32| a == b);
+        assert_eq!(deduplicated.update(&[1, 1, 1], &[[1.0, 1.0]; 3]), 1);
+        // A twin that dominates still replaces the entry
+        assert!(deduplicated.insert(&1, &[2.0, 1.0]));
+        assert_eq!(deduplicated.iter().collect::<Vec<_>>(), vec![(&1, &[2.0, 1.0])]);
+    }
+}
@@ -0,0 +1,316 @@
+use std::ops::Range;
+
+use rand::{Rng, RngCore};
+
+use crate::crossover::{Crossover, OnePoint, Uniform};
+use crate::mutation::{FlipBit, Mutator};
+
+const WORD: usize = u64::BITS as usize;
+
+/// Bits packed 64 to a word
+///
+/// Bit `idx` is bit `idx % 64` of word `idx / 64`, and the bits of the last
+/// word past the end are always clear. [`FlipBit`], [`OnePoint`] and
+/// [`Uniform`] work on it a word at a time, drawing the same random numbers as
+/// they would for the same bits in a `Vec<bool>`.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{ea_simple, Elitism};
+/// use dears::bitstring::BitString;
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::TournamentSelection;
+/// use dears::termination::MaxGenerations;
+/// use rand::RngCore;
+/// let mut rng = rand::thread_rng();
+/// let mut pop = PopulationBuilder::new()
+///     .init_from(20, &|rng: &mut dyn RngCore| BitString::random(100, 0.5, rng), &mut rng)
+///     .mutator(FlipBit { indpb: 0.01 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap();
+/// let max_ones = |genome: &BitString| genome.count_ones() as f64;
+/// ea_simple(&mut pop, MaxGenerations(5), 0.5, 0.2, Elitism::default(), max_ones, None, None, &mut rng);
+///
+/// let bits = BitString::from(vec![true, false, true]);
+/// assert_eq!((bits.get(2), bits.count_ones()), (true, 2));
+/// assert_eq!(bits.iter_ones().collect::<Vec<_>>(), vec![0, 2]);
+/// assert_eq!(bits.hamming(&BitString::new(3)), 2);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<bool>", into = "Vec<bool>"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BitString {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitString {
+    /// `len` clear bits
+    pub fn new(len: usize) -> Self {
+        BitString { words: vec![0; len.div_ceil(WORD)], len }
+    }
+
+    /// `len` bits, each set with probability `p_true`
+    pub fn random(len: usize, p_true: f64, rng: &mut dyn RngCore) -> Self {
+        let mut bits = BitString::new(len);
+        for idx in 0..len {
+            bits.set(idx, rng.gen::<f64>() < p_true);
+        }
+        bits
+    }
+
+    /// The bits of `bytes`, the lowest bit of each byte first
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let words = bytes.chunks(WORD / 8).map(|chunk| {
+            let mut word = [0; WORD / 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        });
+        BitString { words: words.collect(), len: bytes.len() * 8 }
+    }
+
+    /// The bits in bytes, as [`BitString::from_bytes`] reads them, with the last byte padded with clear bits
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|word| word.to_le_bytes()).take(self.len.div_ceil(8)).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The packed bits, the first in the lowest bit of the first word
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Panics if `idx` is out of bounds
+    pub fn get(&self, idx: usize) -> bool {
+        self.check(idx);
+        self.words[idx / WORD] >> (idx % WORD) & 1 == 1
+    }
+
+    /// Panics if `idx` is out of bounds
+    pub fn set(&mut self, idx: usize, value: bool) {
+        self.check(idx);
+        let bit = 1 << (idx % WORD);
+        if value {
+            self.words[idx / WORD] |= bit;
+        } else {
+            self.words[idx / WORD] &= !bit;
+        }
+    }
+
+    /// Panics if `idx` is out of bounds
+    pub fn flip(&mut self, idx: usize) {
+        self.check(idx);
+        self.words[idx / WORD] ^= 1 << (idx % WORD);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// How many bits differ from `other`
+    ///
+    /// Panics if the lengths differ.
+    pub fn hamming(&self, other: &BitString) -> usize {
+        assert_eq!(self.len, other.len, "Genomes must be the same length");
+        self.words.iter().zip(&other.words).map(|(a, b)| (a ^ b).count_ones() as usize).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|idx| self.get(idx))
+    }
+
+    /// The index of every set bit, in order
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut rest = word;
+            std::iter::from_fn(move || {
+                (rest != 0).then(|| {
+                    let bit = rest.trailing_zeros() as usize;
+                    rest &= rest - 1;
+                    w * WORD + bit
+                })
+            })
+        })
+    }
+
+    fn check(&self, idx: usize) {
+        assert!(idx < self.len, "Bit {} is out of bounds of a bit string of length {}", idx, self.len);
+    }
+
+    /// Exchanges the bits set in each word's mask with `other`
+    fn swap_masked(&mut self, other: &mut BitString, masks: impl IntoIterator<Item = u64>) {
+        for ((a, b), mask) in self.words.iter_mut().zip(&mut other.words).zip(masks) {
+            let diff = (*a ^ *b) & mask;
+            *a ^= diff;
+            *b ^= diff;
+        }
+    }
+}
+
+/// The bits of word `w` that are in `range`
+fn range_mask(w: usize, range: &Range<usize>) -> u64 {
+    let (start, end) = (w * WORD, (w + 1) * WORD);
+    let (from, to) = (range.start.clamp(start, end) - start, range.end.clamp(start, end) - start);
+    match to - from {
+        0 => 0,
+        n => u64::MAX >> (WORD - n) << from,
+    }
+}
+
+impl From<&[bool]> for BitString {
+    fn from(bits: &[bool]) -> Self {
+        let mut words = vec![0; bits.len().div_ceil(WORD)];
+        for (idx, _) in bits.iter().enumerate().filter(|(_, &bit)| bit) {
+            words[idx / WORD] |= 1 << (idx % WORD);
+        }
+        BitString { words, len: bits.len() }
+    }
+}
+
+impl From<Vec<bool>> for BitString {
+    fn from(bits: Vec<bool>) -> Self {
+        BitString::from(&bits[..])
+    }
+}
+
+impl From<&[u8]> for BitString {
+    fn from(bytes: &[u8]) -> Self {
+        BitString::from_bytes(bytes)
+    }
+}
+
+impl From<&BitString> for Vec<bool> {
+    fn from(bits: &BitString) -> Self {
+        bits.iter().collect()
+    }
+}
+
+impl From<BitString> for Vec<bool> {
+    fn from(bits: BitString) -> Self {
+        Vec::from(&bits)
+    }
+}
+
+impl Mutator<BitString> for FlipBit {
+    fn mutate_with(&self, genome: &mut BitString, rng: &mut dyn RngCore) {
+        for w in 0..genome.words.len() {
+            let bits = (genome.len - w * WORD).min(WORD);
+            let flips = (0..bits).filter(|_| rng.gen::<f64>() < self.indpb).fold(0, |mask, bit| mask | 1 << bit);
+            genome.words[w] ^= flips;
+        }
+    }
+}
+
+impl Crossover<BitString> for OnePoint {
+    fn crossover_with(&self, a: &mut BitString, b: &mut BitString, rng: &mut dyn RngCore) {
+        let length = a.len.min(b.len);
+        assert!(length > 1, "Can't crossover individuals of length less than 2");
+        let range = rng.gen_range(1..length)..length;
+        let words = a.words.len().min(b.words.len());
+        a.swap_masked(b, (0..words).map(|w| range_mask(w, &range)));
+    }
+}
+
+impl Crossover<BitString> for Uniform {
+    fn crossover_with(&self, a: &mut BitString, b: &mut BitString, rng: &mut dyn RngCore) {
+        let length = a.len.min(b.len);
+        let masks = (0..length.div_ceil(WORD)).map(|w| {
+            let bits = (length - w * WORD).min(WORD);
+            (0..bits).filter(|_| rng.gen::<f64>() < self.indpb).fold(0, |mask, bit| mask | 1 << bit)
+        });
+        a.swap_masked(b, masks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::bitstring::*;
+    use crate::distance::hamming;
+    use crate::initializer::{Init, RandomBits};
+
+    const SEED: u64 = 205;
+
+    fn assert_matches(bits: &BitString, reference: &[bool]) {
+        assert_eq!(Vec::from(bits), reference);
+        assert_eq!(bits.count_ones(), reference.iter().filter(|&&bit| bit).count());
+        let ones: Vec<usize> = (0..reference.len()).filter(|&i| reference[i]).collect();
+        assert_eq!(bits.iter_ones().collect::<Vec<_>>(), ones);
+        // Nothing leaks into the padding past the last bit
+        assert_eq!(*bits, BitString::from(reference));
+    }
+
+    #[test]
+    fn matches_vec_of_bools() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        for len in [1, 2, 63, 64, 65, 127, 128, 130, 200] {
+            let mut reference: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            let mut bits = BitString::from(reference.clone());
+            let mut other_reference: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            let mut other = BitString::from(&other_reference[..]);
+            for _ in 0..100 {
+                let idx = rng.gen_range(0..len);
+                match rng.gen_range(0..6) {
+                    0 => {
+                        let value = rng.gen();
+                        bits.set(idx, value);
+                        reference[idx] = value;
+                    }
+                    1 => {
+                        bits.flip(idx);
+                        reference[idx] = !reference[idx];
+                    }
+                    2 => {
+                        let seed = rng.gen();
+                        FlipBit { indpb: 0.1 }.mutate_with(&mut bits, &mut StdRng::seed_from_u64(seed));
+                        FlipBit { indpb: 0.1 }.mutate_with(&mut reference, &mut StdRng::seed_from_u64(seed));
+                    }
+                    3 if len > 1 => {
+                        let seed = rng.gen();
+                        OnePoint.crossover_with(&mut bits, &mut other, &mut StdRng::seed_from_u64(seed));
+                        OnePoint.crossover_with(&mut reference, &mut other_reference, &mut StdRng::seed_from_u64(seed));
+                    }
+                    4 => {
+                        let seed = rng.gen();
+                        let uniform = Uniform { indpb: 0.5 };
+                        uniform.crossover_with(&mut bits, &mut other, &mut StdRng::seed_from_u64(seed));
+                        uniform.crossover_with(&mut reference, &mut other_reference, &mut StdRng::seed_from_u64(seed));
+                    }
+                    _ => assert_eq!(bits.get(idx), reference[idx]),
+                }
+                assert_matches(&bits, &reference);
+                assert_matches(&other, &other_reference);
+                assert_eq!(bits.hamming(&other) as f64, hamming(&reference, &other_reference));
+            }
+            let bytes = bits.to_bytes();
+            assert_eq!(bytes.len(), len.div_ceil(8));
+            let unpacked = BitString::from(&bytes[..]);
+            assert_eq!(Vec::from(&unpacked)[..len], reference[..]);
+            assert!(Vec::from(unpacked)[len..].iter().all(|&bit| !bit));
+        }
+        let random = BitString::random(70, 0.3, &mut StdRng::seed_from_u64(SEED));
+        let reference = RandomBits { len: 70, p_true: 0.3 }.init_with(&mut StdRng::seed_from_u64(SEED));
+        assert_matches(&random, &reference);
+    }
+
+    #[test]
+    fn bytes_are_read_lowest_bit_first() {
+        let bits = BitString::from_bytes(&[0b0000_0101, 0x80]);
+        assert_eq!(bits.iter_ones().collect::<Vec<_>>(), vec![0, 2, 15]);
+        assert_eq!(bits.to_bytes(), vec![0b0000_0101, 0x80]);
+        assert!(BitString::from_bytes(&[]).is_empty());
+    }
+}
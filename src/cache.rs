@@ -0,0 +1,550 @@
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::genome::Genome;
+use crate::stats::StatsRecord;
+use crate::termination::{Progress, StopReason, Termination};
+
+/// What a genome is looked up by in a [`FitnessCache`]
+///
+/// Every `Hash + Eq + Clone` genome is its own key. Genomes that can't be
+/// hashed, such as vectors of floats, can implement it themselves or be
+/// wrapped in a [`HashableGenome`].
+pub trait CacheKey {
+    type Key: Hash + Eq + Clone;
+
+    fn cache_key(&self) -> Self::Key;
+}
+
+impl<G: Hash + Eq + Clone> CacheKey for G {
+    type Key = G;
+
+    fn cache_key(&self) -> G {
+        self.clone()
+    }
+}
+
+/// Real genes that can be hashed and compared for equality, by their bit patterns
+///
+/// Genes are equal when their bits are, except that `-0.0` equals `0.0` and
+/// every NaN equals every other, so a NaN gene doesn't stop a genome equalling
+/// itself. With a quantisation step, genes are compared after rounding to the
+/// nearest multiple of it, so nearly identical genomes collide on purpose;
+/// genomes with different steps are never equal. Operators work on it
+/// directly, as it's a [`Genome`].
+///
+/// # Examples
+/// ```
+/// use dears::cache::HashableGenome;
+/// let a = HashableGenome::quantised(vec![0.1, 2.0], 0.01);
+/// let b = HashableGenome::quantised(vec![0.1001, 2.002], 0.01);
+/// assert_eq!(a, b);
+/// assert_ne!(HashableGenome::from(vec![0.1]), HashableGenome::from(vec![0.1001]));
+/// assert_eq!(HashableGenome::from(vec![f64::NAN]), HashableGenome::from(vec![-f64::NAN]));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HashableGenome {
+    genes: Vec<f64>,
+    step: Option<f64>,
+}
+
+impl HashableGenome {
+    /// Genes compared exactly
+    pub fn new(genes: Vec<f64>) -> Self {
+        HashableGenome { genes, step: None }
+    }
+
+    /// Genes compared after rounding to the nearest multiple of `step`
+    ///
+    /// Panics unless `step` is positive and finite.
+    pub fn quantised(genes: Vec<f64>, step: f64) -> Self {
+        assert!(step > 0.0 && step.is_finite(), "Quantisation step must be positive and finite, not {}", step);
+        HashableGenome { genes, step: Some(step) }
+    }
+
+    pub fn genes(&self) -> &[f64] {
+        &self.genes
+    }
+
+    pub fn step(&self) -> Option<f64> {
+        self.step
+    }
+
+    pub fn into_inner(self) -> Vec<f64> {
+        self.genes
+    }
+
+    /// The bit pattern each gene is compared by
+    fn keys(&self) -> impl Iterator<Item = u64> + '_ {
+        self.genes.iter().map(|&gene| {
+            let value = self.step.map_or(gene, |step| (gene / step).round());
+            // Adding zero turns -0.0 into 0.0
+            if value.is_nan() { f64::NAN.to_bits() } else { (value + 0.0).to_bits() }
+        })
+    }
+}
+
+impl PartialEq for HashableGenome {
+    fn eq(&self, other: &Self) -> bool {
+        self.step.map(f64::to_bits) == other.step.map(f64::to_bits)
+            && self.genes.len() == other.genes.len()
+            && self.keys().eq(other.keys())
+    }
+}
+
+impl Eq for HashableGenome {}
+
+impl Hash for HashableGenome {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.step.map(f64::to_bits).hash(state);
+        state.write_usize(self.genes.len());
+        self.keys().for_each(|key| state.write_u64(key));
+    }
+}
+
+impl From<Vec<f64>> for HashableGenome {
+    fn from(genes: Vec<f64>) -> Self {
+        HashableGenome::new(genes)
+    }
+}
+
+impl From<HashableGenome> for Vec<f64> {
+    fn from(genome: HashableGenome) -> Self {
+        genome.genes
+    }
+}
+
+impl AsRef<[f64]> for HashableGenome {
+    fn as_ref(&self) -> &[f64] {
+        &self.genes
+    }
+}
+
+impl Borrow<[f64]> for HashableGenome {
+    fn borrow(&self) -> &[f64] {
+        &self.genes
+    }
+}
+
+impl Genome for HashableGenome {
+    type Gene = f64;
+
+    fn len(&self) -> usize {
+        self.genes.len()
+    }
+
+    fn gene_mut(&mut self, idx: usize) -> &mut f64 {
+        &mut self.genes[idx]
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.genes.swap(i, j)
+    }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [f64]> {
+        Some(&mut self.genes)
+    }
+}
+
+thread_local! {
+    /// Outermost lookups answered by any cache on this thread, and whether one is being evaluated
+    static LOOKUPS: Cell<Lookups> = const { Cell::new(Lookups { hits: 0, misses: 0, evaluating: false }) };
+}
+
+#[derive(Clone, Copy)]
+struct Lookups {
+    hits: usize,
+    misses: usize,
+    evaluating: bool,
+}
+
+/// Calls `eval`, also returning whether a cache answered it without calling a fitness function
+///
+/// True when `eval` made at least one cache lookup and every one of them hit.
+/// Lookups made by a fitness function while it's evaluating a miss belong to
+/// that miss, so they're left out.
+pub(crate) fn answered_by_cache<T>(eval: impl FnOnce() -> T) -> (T, bool) {
+    let before = LOOKUPS.get();
+    let result = eval();
+    let after = LOOKUPS.get();
+    (result, after.hits > before.hits && after.misses == before.misses)
+}
+
+/// Cache lookups `eval` made that hit, as for [`answered_by_cache`]
+pub(crate) fn cache_hits<T>(eval: impl FnOnce() -> T) -> (T, usize) {
+    let before = LOOKUPS.get().hits;
+    let result = eval();
+    (result, LOOKUPS.get().hits - before)
+}
+
+/// Marks the thread as evaluating a miss until dropped, even if the fitness function panics
+struct Evaluating {
+    outermost: bool,
+}
+
+impl Evaluating {
+    fn start() -> Self {
+        let lookups = LOOKUPS.get();
+        LOOKUPS.set(Lookups { evaluating: true, ..lookups });
+        Evaluating { outermost: !lookups.evaluating }
+    }
+}
+
+impl Drop for Evaluating {
+    fn drop(&mut self) {
+        if self.outermost {
+            LOOKUPS.set(Lookups { evaluating: false, ..LOOKUPS.get() });
+        }
+    }
+}
+
+/// Counts a lookup towards this thread's totals, unless it was made while evaluating a miss
+fn count_lookup(hit: bool) {
+    let mut lookups = LOOKUPS.get();
+    if !lookups.evaluating {
+        if hit { lookups.hits += 1 } else { lookups.misses += 1 }
+        LOOKUPS.set(lookups);
+    }
+}
+
+/// Cached fitnesses with their last-used times, oldest first in `order`
+struct Entries<K, F> {
+    map: HashMap<K, (F, u64)>,
+    order: BTreeMap<u64, K>,
+    clock: u64,
+    hits: usize,
+    misses: usize,
+}
+
+/// Remembers fitnesses of recently seen genomes, so an expensive fitness
+/// function isn't called again for genomes that variation recreates
+///
+/// Genomes are looked up by a key, their [`CacheKey`] for
+/// [`FitnessCache::new`], which for hashable genomes is the genome itself.
+/// Vectors of floats can be kept as [`HashableGenome`]s, or use
+/// [`FitnessCache::with_key`] to hash a bit pattern instead.
+/// Once `capacity` genomes are cached the least recently used is dropped.
+///
+/// The cache is used through [`FitnessCache::evaluator`], which wraps a fitness
+/// function so it can be passed anywhere one is expected. Cache hits cost
+/// nothing: a population doesn't count them in its
+/// [`evaluations`](crate::population::Population::evaluations), so
+/// [`MaxEvaluations`](crate::termination::MaxEvaluations) only counts real
+/// calls. [`FitnessCache::budget`] counts the misses of one cache instead,
+/// wherever it's used.
+///
+/// The cache can be shared between threads, so its evaluator works with
+/// [`Population::evaluate_par`](crate::population::Population::evaluate_par).
+/// It's only locked for lookups, not while the fitness function runs, so a
+/// genome missed by two threads at once may be evaluated twice.
+///
+/// # Examples
+/// ```
+/// use std::cell::Cell;
+/// use dears::cache::FitnessCache;
+/// let calls = Cell::new(0);
+/// let cache = FitnessCache::new(100);
+/// let eval = cache.evaluator(|g: &Vec<u8>| { calls.set(calls.get() + 1); g.len() as f64 });
+/// assert_eq!(eval(&vec![1, 2]), 2.0);
+/// assert_eq!(eval(&vec![1, 2]), 2.0);
+/// assert_eq!((calls.get(), cache.hits(), cache.misses()), (1, 1, 1));
+/// ```
+pub struct FitnessCache<G, F, K = G, Q = fn(&G) -> G> {
+    key: Q,
+    capacity: usize,
+    entries: Mutex<Entries<K, F>>,
+    genome: PhantomData<fn(&G)>,
+}
+
+impl<G: CacheKey, F: Clone> FitnessCache<G, F, G::Key, fn(&G) -> G::Key> {
+    /// Creates an empty cache keyed by each genome's [`CacheKey`]
+    pub fn new(capacity: usize) -> Self {
+        FitnessCache::with_key(capacity, G::cache_key)
+    }
+}
+
+impl<G, F, K, Q> FitnessCache<G, F, K, Q>
+where
+    F: Clone,
+    K: Hash + Eq + Clone,
+    Q: Fn(&G) -> K
+{
+    /// Creates an empty cache that looks genomes up by `key(genome)`
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::cache::FitnessCache;
+    /// let bits = |g: &Vec<f64>| g.iter().map(|x| x.to_bits()).collect::<Vec<u64>>();
+    /// let cache = FitnessCache::with_key(100, bits);
+    /// let sphere = cache.evaluator(|g: &Vec<f64>| g.iter().map(|x| x * x).sum::<f64>());
+    /// assert_eq!(sphere(&vec![1.0, 2.0]), 5.0);
+    /// ```
+    pub fn with_key(capacity: usize, key: Q) -> Self {
+        assert!(capacity > 0, "Fitness cache must hold at least one genome");
+        FitnessCache {
+            key,
+            capacity,
+            entries: Mutex::new(Entries {
+                map: HashMap::with_capacity(capacity),
+                order: BTreeMap::new(),
+                clock: 0,
+                hits: 0,
+                misses: 0,
+            }),
+            genome: PhantomData,
+        }
+    }
+
+    /// Returns the cached fitness of `genome`, calling `eval` only on a miss
+    pub fn get_or_evaluate(&self, genome: &G, eval: impl FnOnce(&G) -> F) -> F {
+        let key = (self.key)(genome);
+        {
+            let mut entries = self.entries();
+            let entries = &mut *entries;
+            entries.clock += 1;
+            if let Some((fitness, last_used)) = entries.map.get_mut(&key) {
+                let key = entries.order.remove(last_used).unwrap();
+                *last_used = entries.clock;
+                entries.order.insert(entries.clock, key);
+                entries.hits += 1;
+                count_lookup(true);
+                return fitness.clone();
+            }
+            entries.misses += 1;
+            count_lookup(false);
+        }
+        // Not locked while evaluating, so other threads and the fitness function itself can use the cache
+        let fitness = {
+            let _evaluating = Evaluating::start();
+            eval(genome)
+        };
+        let mut entries = self.entries();
+        if let Some((_, last_used)) = entries.map.remove(&key) {
+            // Another lookup evaluated the same genome in the meantime
+            entries.order.remove(&last_used);
+        } else if entries.map.len() == self.capacity {
+            if let Some((_, oldest)) = entries.order.pop_first() {
+                entries.map.remove(&oldest);
+            }
+        }
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.order.insert(clock, key.clone());
+        entries.map.insert(key, (fitness.clone(), clock));
+        fitness
+    }
+
+    /// Wraps a fitness function so it goes through the cache
+    pub fn evaluator<'a, E>(&'a self, eval: E) -> impl Fn(&G) -> F + 'a
+    where
+        E: Fn(&G) -> F + 'a
+    {
+        move |genome| self.get_or_evaluate(genome, &eval)
+    }
+
+    /// The hit and miss counters, ready to go in a [`Logbook`](crate::stats::Logbook) chapter
+    pub fn stats(&self) -> StatsRecord {
+        let mut record = StatsRecord::default();
+        record.insert("hits", vec![self.hits() as f64]);
+        record.insert("misses", vec![self.misses() as f64]);
+        record
+    }
+}
+
+impl<G, F, K, Q> FitnessCache<G, F, K, Q> {
+    fn entries(&self) -> MutexGuard<'_, Entries<K, F>> {
+        self.entries.lock().unwrap()
+    }
+
+    /// Number of lookups answered from the cache
+    pub fn hits(&self) -> usize {
+        self.entries().hits
+    }
+
+    /// Number of lookups that had to call the fitness function
+    pub fn misses(&self) -> usize {
+        self.entries().misses
+    }
+
+    /// A termination criterion that stops after `max` misses, so cache hits cost nothing
+    ///
+    /// Like [`MaxEvaluations`](crate::termination::MaxEvaluations), it can stop
+    /// a run partway through a generation.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::cache::FitnessCache;
+    /// use dears::termination::*;
+    /// let cache = FitnessCache::new(10);
+    /// let eval = cache.evaluator(|g: &u32| *g as f64);
+    /// let mut budget = cache.budget(2);
+    /// let progress = Progress { generation: 0, evaluations: 3, fitnesses: &[0.0] };
+    /// eval(&1);
+    /// eval(&1);
+    /// assert_eq!(Termination::<f64>::check(&mut budget, &progress), None);
+    /// eval(&2);
+    /// assert_eq!(Termination::<f64>::check(&mut budget, &progress), Some(StopReason::MaxEvaluations));
+    /// ```
+    pub fn budget(&self, max: usize) -> CacheBudget<'_, G, F, K, Q> {
+        CacheBudget { cache: self, max }
+    }
+
+    /// Zeroes the hit and miss counters, keeping the cached fitnesses
+    pub fn reset_counters(&self) {
+        let mut entries = self.entries();
+        entries.hits = 0;
+        entries.misses = 0;
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries().map.is_empty()
+    }
+
+    /// Forgets every cached fitness
+    pub fn clear(&self) {
+        let mut entries = self.entries();
+        entries.map.clear();
+        entries.order.clear();
+    }
+}
+
+/// Stops once a [`FitnessCache`] has missed `max` times, made by [`FitnessCache::budget`]
+pub struct CacheBudget<'a, G, F, K, Q> {
+    cache: &'a FitnessCache<G, F, K, Q>,
+    max: usize,
+}
+
+impl<G, F, K, Q, P> Termination<P> for CacheBudget<'_, G, F, K, Q> {
+    fn check(&mut self, _progress: &Progress<P>) -> Option<StopReason> {
+        (self.cache.misses() >= self.max).then_some(StopReason::MaxEvaluations)
+    }
+
+    fn check_evaluation(&mut self, progress: &Progress<P>) -> Option<StopReason> {
+        self.check(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::cache::*;
+
+    #[test]
+    fn evaluates_each_distinct_genome_once() {
+        let calls = Cell::new(0);
+        let cache = FitnessCache::new(10);
+        let eval = cache.evaluator(|g: &u32| {
+            calls.set(calls.get() + 1);
+            *g as f64
+        });
+        let genomes = [1, 2, 1, 3, 2, 2, 1, 4];
+        let fitnesses: Vec<f64> = genomes.iter().map(&eval).collect();
+        assert_eq!(fitnesses, vec![1.0, 2.0, 1.0, 3.0, 2.0, 2.0, 1.0, 4.0]);
+        assert_eq!(calls.get(), 4);
+        assert_eq!((cache.hits(), cache.misses()), (4, 4));
+        assert_eq!(cache.stats().get("hits"), Some(4.0));
+    }
+
+    #[test]
+    fn hashable_genomes_hit_the_cache() {
+        let calls = Cell::new(0);
+        let cache = FitnessCache::new(10);
+        let eval = cache.evaluator(|g: &HashableGenome| {
+            calls.set(calls.get() + 1);
+            g.genes().iter().sum::<f64>()
+        });
+        let quantised = |genes: Vec<f64>| HashableGenome::quantised(genes, 0.1);
+        assert_eq!(eval(&quantised(vec![1.0, 2.0])), 3.0);
+        // Within half a step of the first, so its fitness is reused
+        assert_eq!(eval(&quantised(vec![1.04, 1.96])), 3.0);
+        assert_eq!(eval(&quantised(vec![1.06, 2.0])), 3.06);
+        assert_eq!(eval(&HashableGenome::new(vec![1.0, 2.0])), 3.0);
+        assert_eq!(calls.get(), 3);
+
+        let with_nan = HashableGenome::new(vec![f64::NAN, 0.0]);
+        assert!(eval(&with_nan).is_nan());
+        assert!(eval(&HashableGenome::new(vec![f64::NAN, -0.0])).is_nan());
+        assert_eq!((calls.get(), cache.hits()), (4, 2));
+        assert_eq!(with_nan, with_nan.clone());
+        assert_ne!(with_nan, HashableGenome::new(vec![f64::NAN]));
+        assert_ne!(HashableGenome::quantised(vec![0.0], 0.1), HashableGenome::quantised(vec![0.0], 0.2));
+        // Rounding is to the nearest step, so -0.04 and 0.04 both round to zero
+        assert_eq!(quantised(vec![-0.04]), quantised(vec![0.04]));
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let calls = Cell::new(0);
+        let cache = FitnessCache::new(3);
+        let eval = cache.evaluator(|g: &u32| {
+            calls.set(calls.get() + 1);
+            *g as f64
+        });
+        for g in 0..100 {
+            eval(&g);
+            assert!(cache.len() <= 3);
+        }
+        // 99, 98 and 97 are cached; touching 97 makes 98 the oldest
+        eval(&97);
+        eval(&0);
+        assert_eq!(calls.get(), 101);
+        eval(&97);
+        eval(&99);
+        assert_eq!(calls.get(), 101);
+        eval(&98);
+        assert_eq!(calls.get(), 102);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn fitness_function_can_use_its_own_cache() {
+        let cache = FitnessCache::new(2);
+        // Each genome's fitness is one more than its predecessor's
+        let eval = |g: &u32| {
+            cache.get_or_evaluate(g, |&g| if g == 0 { 0.0 } else { 1.0 + cache.get_or_evaluate(&(g - 1), |_| 0.0) })
+        };
+        assert_eq!(eval(&1), 1.0);
+        assert_eq!(eval(&0), 0.0);
+        assert_eq!((cache.len(), cache.hits(), cache.misses()), (2, 1, 2));
+        // The hit on 1 while evaluating 2 doesn't make 2 free
+        assert_eq!(answered_by_cache(|| eval(&2)), (2.0, false));
+        assert_eq!(answered_by_cache(|| eval(&2)), (2.0, true));
+        assert_eq!(answered_by_cache(|| 3.0), (3.0, false));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn shared_with_parallel_evaluation() {
+        use crate::crossover::OnePoint;
+        use crate::mutation::Shuffle;
+        use crate::population::PopulationBuilder;
+        use crate::selection::SelBest;
+
+        let mut pop = PopulationBuilder::new()
+            .individuals((0..1000u32).map(|i| vec![i % 10]).collect())
+            .mutator(Shuffle { indpb: 0.1 })
+            .crossover(OnePoint)
+            .selector(SelBest::default())
+            .build()
+            .unwrap();
+        let cache = FitnessCache::new(100);
+        pop.evaluate_par(cache.evaluator(|g: &Vec<u32>| g[0] as f64));
+        assert!(pop.fitnesses().iter().zip(pop.individuals()).all(|(&f, g)| f == g[0] as f64));
+        assert_eq!((cache.len(), cache.hits() + cache.misses()), (10, 1000));
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::archive::HallOfFame;
+use crate::crossover::*;
+use crate::mutation::*;
+use crate::population::*;
+use crate::rng::Pcg64;
+use crate::selection::*;
+use crate::stats::Logbook;
+
+/// How a checkpoint is written to disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Human readable, but large and slower for big populations
+    Json,
+    /// Compact binary, tied to the exact genome and fitness types
+    Bincode,
+}
+
+/// Errors from saving or loading a checkpoint
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Bincode(bincode::Error),
+    /// The checkpoint holds no individuals
+    EmptyPopulation,
+    /// The saved fitnesses or ages don't line up with the individuals
+    LengthMismatch { individuals: usize, fitnesses: usize, ages: usize },
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(err) => write!(f, "checkpoint file error: {}", err),
+            CheckpointError::Json(err) => write!(f, "invalid JSON checkpoint: {}", err),
+            CheckpointError::Bincode(err) => write!(f, "invalid bincode checkpoint: {}", err),
+            CheckpointError::EmptyPopulation => write!(f, "checkpoint holds no individuals"),
+            CheckpointError::LengthMismatch { individuals, fitnesses, ages } =>
+                write!(f, "checkpoint has {} individuals but {} fitnesses and {} ages", individuals, fitnesses, ages),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CheckpointError::Io(err) => Some(err),
+            CheckpointError::Json(err) => Some(err),
+            CheckpointError::Bincode(err) => Some(err),
+            CheckpointError::EmptyPopulation | CheckpointError::LengthMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(err: io::Error) -> Self {
+        CheckpointError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(err: serde_json::Error) -> Self {
+        CheckpointError::Json(err)
+    }
+}
+
+impl From<bincode::Error> for CheckpointError {
+    fn from(err: bincode::Error) -> Self {
+        CheckpointError::Bincode(err)
+    }
+}
+
+/// Everything about a run that lives outside the [`Population`]
+///
+/// The generator should be one whose state can be serialised, such as
+/// [`Pcg64`], so a resumed run draws the same numbers it would have without
+/// the interruption.
+#[derive(Serialize, Deserialize)]
+pub struct RunState<G, F, R> {
+    pub rng: R,
+    pub hall_of_fame: Option<HallOfFame<G, F>>,
+    pub logbook: Logbook,
+}
+
+/// What gets written, borrowing from the live run
+#[derive(Serialize)]
+struct Saved<'a, G, F, R> {
+    generation: usize,
+    evaluations: usize,
+    individuals: &'a [G],
+    fitnesses: &'a [F],
+    ages: &'a [u32],
+    population_rng: Option<Pcg64>,
+    state: &'a RunState<G, F, R>,
+}
+
+/// What gets read back, with the same layout as [`Saved`]
+#[derive(Deserialize)]
+struct Loaded<G, F, R> {
+    generation: usize,
+    evaluations: usize,
+    individuals: Vec<G>,
+    fitnesses: Vec<F>,
+    #[serde(default)]
+    ages: Vec<u32>,
+    #[serde(default)]
+    population_rng: Option<Pcg64>,
+    state: RunState<G, F, R>,
+}
+
+impl<G, M, C, S, F> Population<G, M, C, S, F>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone
+{
+    /// Writes the population and the rest of the run to `path`
+    ///
+    /// Operators aren't saved, since they may hold closures; the same ones
+    /// are given back to [`Population::load_checkpoint`]. The population's own
+    /// generator is saved if it was seeded.
+    pub fn save_checkpoint<R>(&self, path: impl AsRef<Path>, format: Format, state: &RunState<G, F, R>)
+                              -> Result<(), CheckpointError>
+    where
+        G: Serialize,
+        F: Serialize,
+        R: Serialize
+    {
+        let saved = Saved {
+            generation: self.generation(),
+            evaluations: self.evaluations(),
+            individuals: self.individuals(),
+            fitnesses: self.fitnesses(),
+            ages: self.ages(),
+            population_rng: self.rng_state(),
+            state,
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        match format {
+            Format::Json => serde_json::to_writer(&mut writer, &saved)?,
+            Format::Bincode => bincode::serialize_into(&mut writer, &saved)?,
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads back a checkpoint written by [`Population::save_checkpoint`]
+    ///
+    /// Fails if the checkpoint holds no individuals, or if its fitnesses or
+    /// ages don't line up with them.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::checkpoint::{Format, RunState};
+    /// use dears::crossover::OnePoint;
+    /// use dears::mutation::FlipBit;
+    /// use dears::population::{Population, PopulationBuilder};
+    /// use dears::rng::Pcg64;
+    /// use dears::selection::SelBest;
+    /// use dears::stats::Logbook;
+    /// use rand::SeedableRng;
+    /// let mut pop = PopulationBuilder::new()
+    ///     .init_with(4, || vec![true; 3])
+    ///     .mutator(FlipBit { indpb: 0.1 })
+    ///     .crossover(OnePoint)
+    ///     .selector(SelBest::default())
+    ///     .build()
+    ///     .unwrap();
+    /// pop.evaluate(|g| g.len() as f64);
+    /// let state = RunState { rng: Pcg64::seed_from_u64(0), hall_of_fame: None, logbook: Logbook::new() };
+    /// let path = std::env::temp_dir().join(format!("dears-doctest-{}.json", std::process::id()));
+    /// pop.save_checkpoint(&path, Format::Json, &state).unwrap();
+    ///
+    /// let (restored, _state): (Population<Vec<bool>, _, _, _, f64>, RunState<_, _, Pcg64>) =
+    ///     Population::load_checkpoint(&path, Format::Json, FlipBit { indpb: 0.1 }, OnePoint, SelBest::default())
+    ///         .unwrap();
+    /// std::fs::remove_file(&path).unwrap();
+    /// assert_eq!(restored.fitnesses(), pop.fitnesses());
+    /// ```
+    pub fn load_checkpoint<R>(path: impl AsRef<Path>, format: Format, mutator: M, crossover: C, selector: S)
+                              -> Result<(Self, RunState<G, F, R>), CheckpointError>
+    where
+        G: DeserializeOwned,
+        F: DeserializeOwned,
+        R: DeserializeOwned
+    {
+        let reader = BufReader::new(File::open(path)?);
+        let loaded: Loaded<G, F, R> = match format {
+            Format::Json => serde_json::from_reader(reader)?,
+            Format::Bincode => bincode::deserialize_from(reader)?,
+        };
+        let pop = Population::restore(loaded.individuals, loaded.fitnesses, loaded.ages, loaded.evaluations,
+                                      loaded.generation, loaded.population_rng, mutator, crossover, selector)?;
+        Ok((pop, loaded.state))
+    }
+}
+
+impl<G: DeserializeOwned, M, C, S> PopulationBuilder<G, M, C, S> {
+    /// Seeds the population with genomes read from a JSON array, as for
+    /// [`PopulationBuilder::seed_individuals`]
+    pub fn seed_from_json(self, path: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        let reader = BufReader::new(File::open(path)?);
+        let seeds: Vec<G> = serde_json::from_reader(reader)?;
+        Ok(self.seed_individuals(seeds))
+    }
+}
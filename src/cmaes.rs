@@ -0,0 +1,337 @@
+use rand::RngCore;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::selection::Direction;
+use crate::stats::{Logbook, Statistics};
+use crate::termination::{Progress, StopReason, Termination};
+
+/// Eigenvalues and eigenvectors of a symmetric matrix by cyclic Jacobi rotations
+///
+/// Eigenvector `k` is the column `vectors[..][k]`.
+fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut vectors = identity(n);
+    let scale: f64 = a.iter().flatten().map(|x| x * x).sum();
+    for _ in 0..100 {
+        let off: f64 = (0..n).flat_map(|i| ((i + 1)..n).map(move |j| (i, j))).map(|(i, j)| a[i][j] * a[i][j]).sum();
+        if off <= f64::EPSILON * f64::EPSILON * scale {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut().chain(vectors.iter_mut()) {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = c * kp - s * kq;
+                    row[q] = s * kp + c * kq;
+                }
+                let (upper, lower) = a.split_at_mut(q);
+                for (pk, qk) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    (*pk, *qk) = (c * *pk - s * *qk, s * *pk + c * *qk);
+                }
+            }
+        }
+    }
+    ((0..n).map(|i| a[i][i]).collect(), vectors)
+}
+
+/// The covariance matrix adaptation evolution strategy, (mu/mu_w, lambda)-CMA-ES
+///
+/// A multivariate normal distribution is adapted to the objective from the
+/// ranking of its samples: [`CmaEs::ask`] samples `lambda` candidates, and
+/// [`CmaEs::tell`] moves the mean towards the best `mu` of them, adapting the
+/// covariance matrix and step size with the standard parameter settings.
+/// Fitnesses are minimised unless told otherwise.
+///
+/// [`CmaEs::run`] drives the ask/tell loop under a [`Termination`]
+/// criterion and records a [`Logbook`].
+///
+/// # Examples
+/// ```
+/// use dears::cmaes::CmaEs;
+/// use dears::termination::MaxGenerations;
+/// let sphere = |x: &Vec<f64>| x.iter().map(|v| v * v).sum::<f64>();
+/// let mut es = CmaEs::new(vec![1.0; 3], 0.5);
+/// let (logbook, _) = es.run(MaxGenerations(50), sphere, None, &mut rand::thread_rng());
+/// assert_eq!(logbook.len(), 50);
+/// assert!(es.best().unwrap().1 < 0.01);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CmaEs {
+    direction: Direction,
+    lambda: usize,
+    weights: Vec<f64>,
+    mueff: f64,
+    cc: f64,
+    cs: f64,
+    c1: f64,
+    cmu: f64,
+    damps: f64,
+    chi_n: f64,
+    mean: Vec<f64>,
+    sigma: f64,
+    covariance: Vec<Vec<f64>>,
+    pc: Vec<f64>,
+    ps: Vec<f64>,
+    /// Eigenvectors of the covariance, as columns
+    b: Vec<Vec<f64>>,
+    /// Square roots of the covariance's eigenvalues
+    d: Vec<f64>,
+    generation: usize,
+    evaluations: usize,
+    best: Option<(Vec<f64>, f64)>,
+}
+
+impl CmaEs {
+    /// Starts from `mean` with step size `sigma` and the default `lambda = 4 + 3 ln(n)`
+    ///
+    /// Panics if `mean` is empty or `sigma` isn't positive.
+    pub fn new(mean: Vec<f64>, sigma: f64) -> Self {
+        assert!(!mean.is_empty(), "CMA-ES needs at least one dimension");
+        assert!(sigma > 0.0, "Step size must be positive, not {}", sigma);
+        let n = mean.len() as f64;
+        let lambda = 4 + (3.0 * n.ln()).floor() as usize;
+        let mut es = CmaEs {
+            direction: Direction::Minimize,
+            lambda,
+            weights: Vec::new(),
+            mueff: 0.0,
+            cc: 0.0,
+            cs: 0.0,
+            c1: 0.0,
+            cmu: 0.0,
+            damps: 0.0,
+            chi_n: n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n)),
+            pc: vec![0.0; mean.len()],
+            ps: vec![0.0; mean.len()],
+            covariance: identity(mean.len()),
+            b: identity(mean.len()),
+            d: vec![1.0; mean.len()],
+            mean,
+            sigma,
+            generation: 0,
+            evaluations: 0,
+            best: None,
+        };
+        es.set_parameters();
+        es
+    }
+
+    /// Samples `lambda` candidates a generation, recombining the best half
+    ///
+    /// Panics if `lambda` is less than 2.
+    pub fn with_lambda(mut self, lambda: usize) -> Self {
+        assert!(lambda >= 2, "CMA-ES needs lambda of at least 2, not {}", lambda);
+        self.lambda = lambda;
+        self.set_parameters();
+        self
+    }
+
+    /// Sets whether bigger or smaller fitnesses are better
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Recomputes the recombination weights and learning rates for `lambda`
+    fn set_parameters(&mut self) {
+        let n = self.mean.len() as f64;
+        let mu = self.lambda / 2;
+        let raw: Vec<f64> = (1..=mu).map(|i| (mu as f64 + 0.5).ln() - (i as f64).ln()).collect();
+        let total: f64 = raw.iter().sum();
+        self.weights = raw.iter().map(|w| w / total).collect();
+        self.mueff = 1.0 / self.weights.iter().map(|w| w * w).sum::<f64>();
+        let mueff = self.mueff;
+        self.cc = (4.0 + mueff / n) / (n + 4.0 + 2.0 * mueff / n);
+        self.cs = (mueff + 2.0) / (n + mueff + 5.0);
+        self.c1 = 2.0 / ((n + 1.3).powi(2) + mueff);
+        self.cmu = (1.0 - self.c1).min(2.0 * (mueff - 2.0 + 1.0 / mueff) / ((n + 2.0).powi(2) + mueff));
+        self.damps = 1.0 + 2.0 * (((mueff - 1.0) / (n + 1.0)).sqrt() - 1.0).max(0.0) + self.cs;
+    }
+
+    /// Samples `lambda` candidates from the current distribution
+    pub fn ask(&mut self, mut rng: &mut dyn RngCore) -> Vec<Vec<f64>> {
+        let n = self.mean.len();
+        (0..self.lambda)
+            .map(|_| {
+                let scaled: Vec<f64> = self.d.iter()
+                    .map(|d| {
+                        let z: f64 = StandardNormal.sample(&mut rng);
+                        d * z
+                    })
+                    .collect();
+                (0..n).map(|i| self.mean[i] + self.sigma * dot(&self.b[i], &scaled)).collect()
+            })
+            .collect()
+    }
+
+    /// Updates the distribution from evaluated candidates, usually those from the last [`CmaEs::ask`]
+    ///
+    /// Panics if given fewer solutions than the number recombined.
+    pub fn tell(&mut self, solutions: &[(Vec<f64>, f64)]) {
+        let mu = self.weights.len();
+        assert!(solutions.len() >= mu, "Need at least {} solutions, got {}", mu, solutions.len());
+        let n = self.mean.len();
+        let nf = n as f64;
+        let mut order: Vec<usize> = (0..solutions.len()).collect();
+        order.sort_by(|&a, &b| self.direction.compare(&solutions[b].1, &solutions[a].1));
+        self.generation += 1;
+        self.evaluations += solutions.len();
+        let (best_x, best_f) = &solutions[order[0]];
+        if self.best.as_ref().is_none_or(|(_, f)| self.direction.compare(best_f, f).is_gt()) {
+            self.best = Some((best_x.clone(), *best_f));
+        }
+
+        // Steps of the best mu from the old mean, in units of sigma
+        let steps: Vec<Vec<f64>> = order[..mu].iter()
+            .map(|&i| solutions[i].0.iter().zip(&self.mean).map(|(x, m)| (x - m) / self.sigma).collect())
+            .collect();
+        let step: Vec<f64> = (0..n).map(|j| self.weights.iter().zip(&steps).map(|(w, y)| w * y[j]).sum()).collect();
+        for (m, y) in self.mean.iter_mut().zip(&step) {
+            *m += self.sigma * y;
+        }
+
+        // C^(-1/2) * step = B * D^-1 * B^T * step
+        let whitened: Vec<f64> = (0..n)
+            .map(|k| (0..n).map(|i| self.b[i][k] * step[i]).sum::<f64>() / self.d[k])
+            .collect();
+        let inv_sqrt_step: Vec<f64> = (0..n).map(|i| dot(&self.b[i], &whitened)).collect();
+        let ps_rate = (self.cs * (2.0 - self.cs) * self.mueff).sqrt();
+        for (p, y) in self.ps.iter_mut().zip(&inv_sqrt_step) {
+            *p = (1.0 - self.cs) * *p + ps_rate * y;
+        }
+        let ps_norm_sq: f64 = self.ps.iter().map(|p| p * p).sum();
+        let decay = 1.0 - (1.0 - self.cs).powi(2 * self.generation as i32);
+        let hsig = if ps_norm_sq / nf / decay < 2.0 + 4.0 / (nf + 1.0) { 1.0 } else { 0.0 };
+        let pc_rate = (self.cc * (2.0 - self.cc) * self.mueff).sqrt();
+        for (p, y) in self.pc.iter_mut().zip(&step) {
+            *p = (1.0 - self.cc) * *p + hsig * pc_rate * y;
+        }
+
+        let c1a = self.c1 * (1.0 - (1.0 - hsig * hsig) * self.cc * (2.0 - self.cc));
+        let keep = 1.0 - c1a - self.cmu;
+        for i in 0..n {
+            for j in 0..=i {
+                let rank_mu: f64 = self.weights.iter().zip(&steps).map(|(w, y)| w * y[i] * y[j]).sum();
+                let c = keep * self.covariance[i][j] + self.c1 * self.pc[i] * self.pc[j] + self.cmu * rank_mu;
+                self.covariance[i][j] = c;
+                self.covariance[j][i] = c;
+            }
+        }
+        self.sigma *= ((self.cs / self.damps) * (ps_norm_sq.sqrt() / self.chi_n - 1.0)).min(1.0).exp();
+
+        let (values, vectors) = symmetric_eigen(&self.covariance);
+        self.d = values.iter().map(|v| v.max(f64::MIN_POSITIVE).sqrt()).collect();
+        self.b = vectors;
+    }
+
+    /// Runs ask/tell generations until `stop` fires, evaluating with `eval`
+    ///
+    /// `stop` is checked after each generation, against that generation's
+    /// fitnesses. The logbook has a row per generation with the compiled
+    /// `stats`, if given.
+    pub fn run<E, T>(&mut self, mut stop: T, eval: E, stats: Option<&Statistics<f64>>, rng: &mut dyn RngCore)
+                     -> (Logbook, StopReason)
+    where
+        E: Fn(&Vec<f64>) -> f64,
+        T: Termination<f64>
+    {
+        let mut logbook = Logbook::new();
+        loop {
+            let candidates = self.ask(rng);
+            let fitnesses: Vec<f64> = candidates.iter().map(&eval).collect();
+            let solutions: Vec<(Vec<f64>, f64)> = candidates.into_iter().zip(fitnesses.iter().copied()).collect();
+            self.tell(&solutions);
+            logbook.record(self.generation, fitnesses.len(), stats.map(|s| s.compile(&fitnesses)).unwrap_or_default());
+            let progress = Progress {
+                generation: self.generation,
+                evaluations: self.evaluations,
+                fitnesses: &fitnesses,
+            };
+            if let Some(reason) = stop.check(&progress) {
+                return (logbook, reason);
+            }
+        }
+    }
+
+    pub fn mean(&self) -> &[f64] {
+        &self.mean
+    }
+
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+
+    pub fn lambda(&self) -> usize {
+        self.lambda
+    }
+
+    /// Generations told so far
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Solutions told so far
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    /// The best solution told so far, with its fitness
+    pub fn best(&self) -> Option<(&[f64], f64)> {
+        self.best.as_ref().map(|(x, f)| (x.as_slice(), *f))
+    }
+}
+
+fn identity(n: usize) -> Vec<Vec<f64>> {
+    (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::cmaes::*;
+    use crate::stats;
+    use crate::termination::*;
+
+    #[test]
+    fn eigen_reconstructs_matrix() {
+        let matrix = vec![vec![4.0, 1.0, 0.5], vec![1.0, 3.0, 0.2], vec![0.5, 0.2, 1.0]];
+        let (values, vectors) = symmetric_eigen(&matrix);
+        for i in 0..3 {
+            for j in 0..3 {
+                let rebuilt: f64 = (0..3).map(|k| vectors[i][k] * values[k] * vectors[j][k]).sum();
+                assert!((rebuilt - matrix[i][j]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn minimises_sphere() {
+        let sphere = |x: &Vec<f64>| x.iter().map(|v| v * v).sum::<f64>();
+        let mut es = CmaEs::new(vec![3.0; 10], 1.0);
+        let stop = Any(vec![
+            Box::new(TargetFitness { target: 1e-8, direction: Direction::Minimize }),
+            Box::new(MaxEvaluations(4_000)),
+        ]);
+        let stats = Statistics::new(|f: &f64| *f).register("min", stats::min);
+        let (logbook, reason) = es.run(stop, sphere, Some(&stats), &mut StdRng::seed_from_u64(158));
+        assert_eq!(reason, StopReason::TargetFitness);
+        assert!(es.best().unwrap().1 < 1e-8);
+        assert!(es.evaluations() < 4_000);
+        assert_eq!(logbook.len(), es.generation());
+        assert_eq!(logbook.nevals().iter().sum::<usize>(), es.evaluations());
+    }
+}
@@ -0,0 +1,226 @@
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::SeedableRng;
+
+use crate::algorithms::{ea_simple, Elitism};
+use crate::archive::HallOfFame;
+use crate::crossover::Crossover;
+use crate::mutation::Mutator;
+use crate::population::Population;
+use crate::rng::derive_seed;
+use crate::selection::SelectMany;
+use crate::stats::{Logbook, Statistics};
+use crate::termination::MaxGenerations;
+
+/// Which members of the other population an individual is evaluated against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Opponents {
+    /// Every member of the other population
+    #[default]
+    All,
+    /// This many distinct members, sampled afresh each generation
+    Random(usize),
+    /// The other population's hall of fame, or its whole population while that's empty
+    HallOfFame,
+}
+
+/// When each population moves on a generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Schedule {
+    /// The first population evolves against the second, then the second against the new first
+    #[default]
+    Alternating,
+    /// Both populations evolve against the other as it was at the start of the generation
+    Simultaneous,
+}
+
+/// One of the two populations of a [`Coevolution`], with everything it keeps to itself
+pub struct Side<G, M, C, S>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<f64>
+{
+    pub population: Population<G, M, C, S, f64>,
+    pub hall_of_fame: HallOfFame<G, f64>,
+    pub stats: Option<Statistics<f64>>,
+    /// One row per generation, starting from generation 1
+    pub logbook: Logbook,
+    pub cxpb: f64,
+    pub mutpb: f64,
+    pub elitism: Elitism,
+}
+
+impl<G, M, C, S> Side<G, M, C, S>
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<f64>
+{
+    /// Evolves `population` with the given probabilities, keeping its best 5 in a hall of fame
+    pub fn new(population: Population<G, M, C, S, f64>, cxpb: f64, mutpb: f64) -> Self {
+        Side {
+            population,
+            hall_of_fame: HallOfFame::new(5),
+            stats: None,
+            logbook: Logbook::new(),
+            cxpb,
+            mutpb,
+            elitism: Elitism::default(),
+        }
+    }
+
+    pub fn with_stats(mut self, stats: Statistics<f64>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn with_hall_of_fame(mut self, hall_of_fame: HallOfFame<G, f64>) -> Self {
+        self.hall_of_fame = hall_of_fame;
+        self
+    }
+
+    pub fn with_elitism(mut self, elitism: Elitism) -> Self {
+        self.elitism = elitism;
+        self
+    }
+
+    /// Clones the members of this side that an opponent is evaluated against
+    fn opponents(&self, policy: Opponents, rng: &mut StdRng) -> Vec<G> {
+        let individuals = self.population.individuals();
+        match policy {
+            Opponents::HallOfFame if !self.hall_of_fame.is_empty() => {
+                self.hall_of_fame.iter().map(|(genome, _)| genome.clone()).collect()
+            }
+            Opponents::All | Opponents::HallOfFame => individuals.to_vec(),
+            Opponents::Random(k) => {
+                let picked = sample(rng, individuals.len(), k.min(individuals.len()));
+                picked.iter().map(|i| individuals[i].clone()).collect()
+            }
+        }
+    }
+
+    /// Re-scores the population against `opponents`, then evolves it one generation against them
+    fn step<O>(&mut self, opponents: &[O], eval: impl Fn(&G, &[&O]) -> f64, rng: &mut StdRng) {
+        let opponents: Vec<&O> = opponents.iter().collect();
+        let fitness = |genome: &G| eval(genome, &opponents);
+        // Fitnesses from the last generation were against other opponents
+        self.population.invalidate_all();
+        let stop = MaxGenerations(self.population.generation() + 1);
+        let (logbook, _) = ea_simple(&mut self.population, stop, self.cxpb, self.mutpb, self.elitism, fitness,
+                                     self.stats.as_ref(), Some(&mut self.hall_of_fame), rng);
+        if let Some(row) = logbook.rows().last() {
+            let nevals = logbook.nevals().iter().sum();
+            self.logbook.record(row.gen, nevals, row.stats.clone());
+        }
+    }
+}
+
+/// Competitive coevolution: two populations whose fitnesses come from playing each other
+///
+/// Each generation, every individual of one population is scored by its
+/// evaluation function against a sample of the other, picked by the
+/// [`Opponents`] policy. Since the opponents change, the whole population is
+/// re-scored before it's evolved a generation by
+/// [`ea_simple`] with its own operators, probabilities and elitism,
+/// updating its own hall of fame and logbook. The [`Schedule`] says whether
+/// the populations take turns or move together.
+///
+/// Each population draws from its own generator, derived from one master seed.
+///
+/// # Examples
+/// ```
+/// use dears::coevolution::*;
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::TournamentSelection;
+/// let side = || Side::new(PopulationBuilder::new()
+///     .init_with(10, || vec![false; 8])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap(), 0.5, 0.2);
+/// let ones = |g: &Vec<bool>| g.iter().filter(|&&b| b).count() as f64;
+/// // Each side scores by how many of its opponents it has more ones than
+/// let beats = |g: &Vec<bool>, opponents: &[&Vec<bool>]| opponents.iter().filter(|o| ones(g) > ones(o)).count() as f64;
+/// let mut coevolution = Coevolution::new(side(), side(), 42).with_opponents(Opponents::Random(4));
+/// coevolution.evolve(5, beats, beats);
+/// assert_eq!(coevolution.first.population.generation(), 5);
+/// assert_eq!(coevolution.second.logbook.len(), 5);
+/// ```
+pub struct Coevolution<G1, M1, C1, S1, G2, M2, C2, S2>
+where
+    M1: Mutator<G1>,
+    C1: Crossover<G1>,
+    S1: SelectMany<f64>,
+    M2: Mutator<G2>,
+    C2: Crossover<G2>,
+    S2: SelectMany<f64>
+{
+    pub first: Side<G1, M1, C1, S1>,
+    pub second: Side<G2, M2, C2, S2>,
+    opponents: Opponents,
+    schedule: Schedule,
+    rngs: [StdRng; 2],
+}
+
+impl<G1, M1, C1, S1, G2, M2, C2, S2> Coevolution<G1, M1, C1, S1, G2, M2, C2, S2>
+where
+    G1: Clone,
+    M1: Mutator<G1>,
+    C1: Crossover<G1>,
+    S1: SelectMany<f64>,
+    G2: Clone,
+    M2: Mutator<G2>,
+    C2: Crossover<G2>,
+    S2: SelectMany<f64>
+{
+    /// Plays every member of each population against all of the other, taking turns
+    ///
+    /// The first population draws from a generator seeded with
+    /// `derive_seed(master_seed, 0)`, and the second with `derive_seed(master_seed, 1)`.
+    pub fn new(first: Side<G1, M1, C1, S1>, second: Side<G2, M2, C2, S2>, master_seed: u64) -> Self {
+        Coevolution {
+            first,
+            second,
+            opponents: Opponents::default(),
+            schedule: Schedule::default(),
+            rngs: [0, 1].map(|stream| StdRng::seed_from_u64(derive_seed(master_seed, stream))),
+        }
+    }
+
+    pub fn with_opponents(mut self, opponents: Opponents) -> Self {
+        self.opponents = opponents;
+        self
+    }
+
+    pub fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Runs `generations` generations of both populations
+    ///
+    /// `eval_first` scores a member of the first population against a sample
+    /// of the second, and `eval_second` the other way round.
+    pub fn evolve<E1, E2>(&mut self, generations: usize, eval_first: E1, eval_second: E2)
+    where
+        E1: Fn(&G1, &[&G2]) -> f64,
+        E2: Fn(&G2, &[&G1]) -> f64
+    {
+        let [first_rng, second_rng] = &mut self.rngs;
+        for _ in 0..generations {
+            let opponents = self.second.opponents(self.opponents, first_rng);
+            let earlier = match self.schedule {
+                Schedule::Simultaneous => Some(self.first.opponents(self.opponents, second_rng)),
+                Schedule::Alternating => None,
+            };
+            self.first.step(&opponents, &eval_first, first_rng);
+            let opponents = earlier.unwrap_or_else(|| self.first.opponents(self.opponents, second_rng));
+            self.second.step(&opponents, &eval_second, second_rng);
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::cmp::Ordering;
+
+use crate::selection::{Comparator, Direction};
+
+/// Evaluation with a penalty for infeasible genomes, like DEAP's `DeltaPenalty`
+///
+/// Feasible genomes get their fitness from `eval` unchanged. Infeasible ones
+/// aren't evaluated at all, and get `delta` worsened by `weight` times their
+/// `distance` from the feasible region, so `delta` should be worse than any
+/// feasible fitness.
+///
+/// # Examples
+/// ```
+/// use dears::constraints::Penalized;
+/// let sum = |g: &Vec<f64>| g.iter().sum::<f64>();
+/// let penalized = Penalized::new(sum, |g: &Vec<f64>| g.iter().all(|&x| x <= 1.0), -10.0,
+///                                |g: &Vec<f64>| g.iter().map(|&x| (x - 1.0).max(0.0)).sum())
+///     .with_weight(2.0);
+/// assert_eq!(penalized.evaluate(&vec![0.5, 1.0]), 1.5);
+/// assert_eq!(penalized.evaluate(&vec![3.0, 1.0]), -14.0);
+/// ```
+pub struct Penalized<E, P, D> {
+    pub eval: E,
+    pub feasible: P,
+    pub delta: f64,
+    pub distance: D,
+    pub weight: f64,
+    pub direction: Direction,
+}
+
+impl<E, P, D> Penalized<E, P, D> {
+    /// A maximising penalty of `delta - distance`
+    pub fn new(eval: E, feasible: P, delta: f64, distance: D) -> Self {
+        Penalized { eval, feasible, delta, distance, weight: 1.0, direction: Direction::default() }
+    }
+
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets whether penalties should lower or raise the fitness
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn evaluate<G>(&self, genome: &G) -> f64
+    where
+        E: Fn(&G) -> f64,
+        P: Fn(&G) -> bool,
+        D: Fn(&G) -> f64
+    {
+        if (self.feasible)(genome) {
+            return (self.eval)(genome);
+        }
+        let penalty = self.weight * (self.distance)(genome);
+        match self.direction {
+            Direction::Maximize => self.delta - penalty,
+            Direction::Minimize => self.delta + penalty,
+        }
+    }
+}
+
+/// A fitness along with how far its genome is from satisfying the constraints
+///
+/// A `violation` of zero (or less) means the genome is feasible.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Constrained<F> {
+    pub fitness: F,
+    pub violation: f64,
+}
+
+impl<F> Constrained<F> {
+    pub fn is_feasible(&self) -> bool {
+        self.violation <= 0.0
+    }
+}
+
+/// Compares [`Constrained`] fitnesses feasibility first, as in Deb's constraint handling
+///
+/// A feasible fitness always beats an infeasible one. Two feasible fitnesses
+/// compare by `direction`, and two infeasible ones by their violation, the
+/// smaller winning. Pass it to
+/// [`TournamentSelection::with_comparator`](crate::selection::TournamentSelection::with_comparator).
+///
+/// # Examples
+/// ```
+/// use dears::constraints::*;
+/// use dears::selection::*;
+/// let fitnesses = vec![
+///     Constrained { fitness: 9.0, violation: 0.5 },
+///     Constrained { fitness: 1.0, violation: 0.0 },
+/// ];
+/// let selector = TournamentSelection::with_comparator(2, FeasibilityFirst::default()).unwrap()
+///     .without_replacement();
+/// assert_eq!(selector.select(&fitnesses), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FeasibilityFirst {
+    pub direction: Direction,
+}
+
+impl<F: PartialOrd> Comparator<Constrained<F>> for FeasibilityFirst {
+    fn compare(&self, a: &Constrained<F>, b: &Constrained<F>) -> Ordering {
+        match (a.is_feasible(), b.is_feasible()) {
+            (true, true) => self.direction.compare(&a.fitness, &b.fitness),
+            (false, false) => Direction::Minimize.compare(&a.violation, &b.violation),
+            (a_feasible, b_feasible) => a_feasible.cmp(&b_feasible),
+        }
+    }
+}
+
+/// A fitness to maximise that follows Deb's feasibility rules in its own ordering
+///
+/// Orders as [`Constrained`] does under [`FeasibilityFirst`], but through
+/// `PartialOrd`, so it works with any selector that maximises, such as the
+/// defaults of [`TournamentSelection`] and [`SelBest`], without a custom
+/// comparator. A feasible fitness (a `violation` of zero or less) beats any
+/// infeasible one, two infeasible fitnesses compare by violation, smaller
+/// being better, and two feasible ones by `objective`, bigger being better.
+/// Negate the objective to minimise it; selecting with
+/// [`Direction::Minimize`] would turn the feasibility rules around too.
+///
+/// Fitnesses are equal when neither is better, so two feasible fitnesses with
+/// the same objective are equal whatever their violations. A NaN objective
+/// or violation makes a fitness incomparable, and so the worst under
+/// [`cmp_fitness`](crate::selection::cmp_fitness).
+///
+/// [`TournamentSelection`]: crate::selection::TournamentSelection
+/// [`SelBest`]: crate::selection::SelBest
+///
+/// # Examples
+/// ```
+/// use dears::constraints::ConstrainedFitness;
+/// use dears::selection::*;
+/// let fitnesses = vec![
+///     ConstrainedFitness { objective: 9.0, violation: 0.5 },
+///     ConstrainedFitness { objective: 1.0, violation: 0.0 },
+///     ConstrainedFitness { objective: 5.0, violation: 0.2 },
+/// ];
+/// assert_eq!(SelBest::default().select(&fitnesses), 1);
+/// assert!(fitnesses[2] > fitnesses[0]);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConstrainedFitness {
+    pub objective: f64,
+    pub violation: f64,
+}
+
+impl ConstrainedFitness {
+    pub fn is_feasible(&self) -> bool {
+        self.violation <= 0.0
+    }
+}
+
+impl PartialEq for ConstrainedFitness {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for ConstrainedFitness {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.is_feasible(), other.is_feasible()) {
+            (true, true) => self.objective.partial_cmp(&other.objective),
+            (false, false) => other.violation.partial_cmp(&self.violation),
+            _ if self.violation.is_nan() || other.violation.is_nan() => None,
+            (feasible, other_feasible) => Some(feasible.cmp(&other_feasible)),
+        }
+    }
+}
+
+/// A fitness function scoring `objective` and summing how far each of `constraints` is broken
+///
+/// Each constraint gives a value that's zero or less when it's satisfied;
+/// only positive values count towards the violation.
+///
+/// # Examples
+/// ```
+/// use dears::constraints::with_constraints;
+/// let x_plus_y = |g: &[f64; 2]| g[0] + g[1];
+/// // x <= 1 and y <= 2
+/// let constraints: [&dyn Fn(&[f64; 2]) -> f64; 2] = [&|g| g[0] - 1.0, &|g| g[1] - 2.0];
+/// let eval = with_constraints(x_plus_y, &constraints);
+/// assert_eq!(eval(&[0.5, 3.0]).violation, 1.0);
+/// assert!(eval(&[0.5, 1.0]).is_feasible());
+/// ```
+pub fn with_constraints<'a, G, E, C>(objective: E, constraints: &'a [C]) -> impl Fn(&G) -> ConstrainedFitness + 'a
+where
+    G: ?Sized,
+    E: Fn(&G) -> f64 + 'a,
+    C: Fn(&G) -> f64
+{
+    move |genome| ConstrainedFitness {
+        objective: objective(genome),
+        violation: constraints.iter().map(|constraint| constraint(genome).max(0.0)).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::constraints::*;
+    use crate::selection::*;
+
+    #[test]
+    fn infeasible_never_beats_feasible() {
+        let mut rng = StdRng::seed_from_u64(156);
+        let fitnesses: Vec<Constrained<f64>> = (0..40)
+            .map(|i| Constrained {
+                fitness: rng.gen_range(0.0..100.0),
+                violation: if i % 4 == 0 { 0.0 } else { rng.gen_range(0.1..5.0) },
+            })
+            .collect();
+        let selector = TournamentSelection::with_comparator(3, FeasibilityFirst::default()).unwrap();
+        let comparator = selector.comparator();
+        for _ in 0..500 {
+            let (a, b) = (rng.gen_range(0..40), rng.gen_range(0..40));
+            if fitnesses[a].is_feasible() && !fitnesses[b].is_feasible() {
+                assert!(comparator.compare(&fitnesses[a], &fitnesses[b]).is_gt());
+            }
+        }
+        // Every feasible individual is worse than the infeasible ones, but wins anyway
+        let mixed = vec![
+            Constrained { fitness: 100.0, violation: 1.0 },
+            Constrained { fitness: 0.0, violation: 0.0 },
+            Constrained { fitness: 90.0, violation: 0.1 },
+        ];
+        let all = TournamentSelection::with_comparator(3, FeasibilityFirst::default()).unwrap().without_replacement();
+        assert!(all.select_n_with(&mixed, 20, &mut rng).iter().all(|&i| i == 1));
+        // Among the infeasible, the smaller violation wins
+        let infeasible = [mixed[0], mixed[2]];
+        let pair = TournamentSelection::with_comparator(2, FeasibilityFirst::default()).unwrap().without_replacement();
+        assert_eq!(pair.select_with(&infeasible, &mut rng), 1);
+    }
+
+    #[test]
+    fn penalty_leaves_feasible_fitness_alone() {
+        let eval = |x: &f64| x * x;
+        let penalized = Penalized::new(eval, |x: &f64| x.abs() <= 2.0, 100.0, |x: &f64| x.abs() - 2.0)
+            .with_weight(10.0)
+            .with_direction(Direction::Minimize);
+        for x in [-2.0, -0.5, 0.0, 1.5, 2.0] {
+            assert_eq!(penalized.evaluate(&x), eval(&x));
+        }
+        assert_eq!(penalized.evaluate(&3.0), 110.0);
+        assert!(penalized.evaluate(&5.0) > penalized.evaluate(&-3.0));
+    }
+
+    #[test]
+    fn constrained_fitness_follows_feasibility_rules() {
+        let fitness = |objective, violation| ConstrainedFitness { objective, violation };
+        // Feasible beats infeasible, whatever the objectives
+        assert!(fitness(-100.0, 0.0) > fitness(100.0, 0.1));
+        assert!(fitness(-100.0, -1.0) > fitness(100.0, 0.1));
+        // The smaller violation wins between infeasible ones
+        assert!(fitness(0.0, 0.5) > fitness(50.0, 2.0));
+        // Equal violations tie, however different the objectives
+        assert_eq!(fitness(0.0, 1.0), fitness(50.0, 1.0));
+        // Among the feasible, the objective decides
+        assert!(fitness(3.0, 0.0) > fitness(2.0, -5.0));
+        assert_eq!(fitness(3.0, 0.0), fitness(3.0, -5.0));
+        assert_eq!(fitness(f64::NAN, 0.0).partial_cmp(&fitness(1.0, 0.0)), None);
+        assert_eq!(fitness(1.0, f64::NAN).partial_cmp(&fitness(1.0, 0.0)), None);
+
+        let constraints: [fn(&f64) -> f64; 2] = [|x| x - 1.0, |x| -x - 3.0];
+        let eval = with_constraints(|x: &f64| -x * x, &constraints);
+        assert_eq!(eval(&2.0), fitness(-4.0, 1.0));
+        assert_eq!(eval(&-5.0).violation, 2.0);
+        let candidates = [-5.0, 2.0, 0.5, 1.0];
+        let fitnesses: Vec<ConstrainedFitness> = candidates.iter().map(&eval).collect();
+        assert_eq!(SelBest::default().select(&fitnesses), 2);
+        let selector = TournamentSelection::new(2).unwrap().without_replacement();
+        assert_eq!(selector.select(&fitnesses[..2]), 1);
+    }
+}
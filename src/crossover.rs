@@ -1,9 +1,18 @@
-use rand::Rng;
+use rand::{Rng, RngCore};
+
+use crate::genome::{for_each_gene_pair_mut, swap_genes, two_points, BoundedVec, Genome, OwnBounds, Permutation};
 
 /// Trait defining an in-place mutation function to be implemented
 /// by all mutation functions
+///
+/// As with [`crate::mutation::Mutator`], randomness comes from the `rng`
+/// given to [`Crossover::crossover_with`].
 pub trait Crossover<G: ?Sized> {
-    fn crossover(&self, a: &mut G, b: &mut G);
+    fn crossover_with(&self, a: &mut G, b: &mut G, rng: &mut dyn RngCore);
+
+    fn crossover(&self, a: &mut G, b: &mut G) {
+        self.crossover_with(a, b, &mut rand::thread_rng())
+    }
 }
 
 
@@ -22,15 +31,296 @@ pub trait Crossover<G: ?Sized> {
 /// let mut ind1 = vec![1; 4];
 /// let mut ind2 = vec![2; 7];
 /// crossover::one_point(&mut ind1, &mut ind2);
-/// println!("ind1 = {:?}, ind2 = {:?}", ind1, ind2);
-/// // ind1 = [1, 1, 2, 2] ind2 = [2, 2, 1, 1, 2, 2, 2]
+/// // e.g. ind1 = [1, 1, 2, 2] ind2 = [2, 2, 1, 1, 2, 2, 2]
+/// assert_eq!((ind1.len(), ind2.len()), (4, 7));
+/// assert_eq!(ind2[4..], [2, 2, 2]);
 /// ```
 pub fn one_point<T>(ind1: &mut [T], ind2: &mut [T]){
+    one_point_with(ind1, ind2, &mut rand::thread_rng())
+}
+
+/// [`one_point`] drawing the crossover point from `rng`
+pub fn one_point_with<T>(ind1: &mut [T], ind2: &mut [T], rng: &mut dyn RngCore){
     let length = std::cmp::min(ind1.len(), ind2.len());
     assert!(length > 1, "Can't crossover individuals of length less than 2");
-    let mut rng = rand::thread_rng();
     let crossover_point = rng.gen_range(1..length);
     for i in crossover_point..length {
         std::mem::swap(&mut ind1[i], &mut ind2[i]);
     }
-}
\ No newline at end of file
+}
+
+/// [`one_point`] crossover as a [`Crossover`] operator
+///
+/// Implemented for any [`Genome`].
+///
+/// # Examples
+/// ```
+/// use dears::crossover::*;
+/// let mut ind1 = [1; 4];
+/// let mut ind2 = [2; 4];
+/// OnePoint.crossover(&mut ind1, &mut ind2);
+/// assert_eq!(ind1[0], 1);
+/// assert_eq!(ind2[3], 1);
+/// ```
+pub struct OnePoint;
+
+impl<G: Genome + ?Sized> Crossover<G> for OnePoint {
+    fn crossover_with(&self, a: &mut G, b: &mut G, rng: &mut dyn RngCore) {
+        let length = std::cmp::min(a.len(), b.len());
+        assert!(length > 1, "Can't crossover individuals of length less than 2");
+        let crossover_point = rng.gen_range(1..length);
+        swap_genes(a, b, crossover_point..length);
+    }
+}
+
+/// Swaps each pair of genes with probability `indpb`
+///
+/// Implemented for any [`Genome`]. Genes past the end of the shorter
+/// individual are left alone.
+///
+/// # Examples
+/// ```
+/// use dears::crossover::*;
+/// let mut ind1 = vec![1; 4];
+/// let mut ind2 = vec![2; 4];
+/// Uniform { indpb: 1.0 }.crossover(&mut ind1, &mut ind2);
+/// assert_eq!((ind1, ind2), (vec![2; 4], vec![1; 4]));
+/// ```
+pub struct Uniform {
+    pub indpb: f64,
+}
+
+impl<G: Genome + ?Sized> Crossover<G> for Uniform {
+    fn crossover_with(&self, a: &mut G, b: &mut G, rng: &mut dyn RngCore) {
+        for_each_gene_pair_mut(a, b, |x, y| {
+            if rng.gen::<f64>() < self.indpb {
+                std::mem::swap(x, y);
+            }
+        });
+    }
+}
+
+/// Partially matched crossover (PMX) of two permutations of `0..len`
+///
+/// Both children keep the genes of a random section of the other parent,
+/// each gene that section displaces being swapped into the place it left, so
+/// the children are permutations too. Panics unless the parents are
+/// permutations of the same length.
+///
+/// # Examples
+/// ```
+/// use dears::crossover::*;
+/// let mut a = vec![0, 1, 2, 3, 4, 5];
+/// let mut b = vec![5, 4, 3, 2, 1, 0];
+/// PartiallyMatched.crossover(&mut a, &mut b);
+/// a.sort();
+/// assert_eq!(a, vec![0, 1, 2, 3, 4, 5]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PartiallyMatched;
+
+impl<G: Genome<Gene = usize> + ?Sized> Crossover<G> for PartiallyMatched {
+    fn crossover_with(&self, a: &mut G, b: &mut G, rng: &mut dyn RngCore) {
+        let size = a.len();
+        assert_eq!(size, b.len(), "Can't match permutations of different lengths");
+        if size < 2 {
+            return;
+        }
+        // Where each value sits in each parent
+        let (mut pos_a, mut pos_b) = (vec![0; size], vec![0; size]);
+        for i in 0..size {
+            pos_a[*a.gene_mut(i)] = i;
+            pos_b[*b.gene_mut(i)] = i;
+        }
+        let (start, end) = two_points(size, rng);
+        for i in start..end {
+            let (x, y) = (*a.gene_mut(i), *b.gene_mut(i));
+            a.swap(i, pos_a[y]);
+            b.swap(i, pos_b[x]);
+            pos_a.swap(x, y);
+            pos_b.swap(x, y);
+        }
+    }
+}
+
+impl Crossover<Permutation> for PartiallyMatched {
+    fn crossover_with(&self, a: &mut Permutation, b: &mut Permutation, rng: &mut dyn RngCore) {
+        a.rearrange(|a| b.rearrange(|b| self.crossover_with(a, b, rng)));
+    }
+}
+
+/// Ordered crossover (OX) of two permutations of `0..len`
+///
+/// Each child takes a random section of the other parent in place, then
+/// fills the rest of its places, from just after the section round to just
+/// before it, with the genes it doesn't have yet in the order they come in
+/// its own parent from that same place. Panics unless the parents are
+/// permutations of the same length.
+///
+/// # Examples
+/// ```
+/// use dears::crossover::*;
+/// let mut a = vec![0, 1, 2, 3, 4, 5];
+/// let mut b = vec![5, 4, 3, 2, 1, 0];
+/// Ordered.crossover(&mut a, &mut b);
+/// a.sort();
+/// assert_eq!(a, vec![0, 1, 2, 3, 4, 5]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ordered;
+
+impl Ordered {
+    /// The child of `own` that takes `other`'s genes in `start..end`
+    fn child(own: &[usize], other: &[usize], start: usize, end: usize) -> Vec<usize> {
+        let size = own.len();
+        let mut taken = vec![false; size];
+        for &gene in &other[start..end] {
+            taken[gene] = true;
+        }
+        let mut child = other.to_vec();
+        let rest = (0..size).map(|i| own[(end + i) % size]).filter(|&gene| !taken[gene]);
+        for (i, gene) in rest.enumerate() {
+            child[(end + i) % size] = gene;
+        }
+        child
+    }
+}
+
+impl<G: Genome<Gene = usize> + ?Sized> Crossover<G> for Ordered {
+    fn crossover_with(&self, a: &mut G, b: &mut G, rng: &mut dyn RngCore) {
+        let size = a.len();
+        assert_eq!(size, b.len(), "Can't order permutations of different lengths");
+        if size < 2 {
+            return;
+        }
+        let (start, end) = two_points(size, rng);
+        let parent_a: Vec<usize> = (0..size).map(|i| *a.gene_mut(i)).collect();
+        let parent_b: Vec<usize> = (0..size).map(|i| *b.gene_mut(i)).collect();
+        let child_a = Ordered::child(&parent_a, &parent_b, start, end);
+        let child_b = Ordered::child(&parent_b, &parent_a, start, end);
+        for i in 0..size {
+            (*a.gene_mut(i), *b.gene_mut(i)) = (child_a[i], child_b[i]);
+        }
+    }
+}
+
+impl Crossover<Permutation> for Ordered {
+    fn crossover_with(&self, a: &mut Permutation, b: &mut Permutation, rng: &mut dyn RngCore) {
+        a.rearrange(|a| b.rearrange(|b| self.crossover_with(a, b, rng)));
+    }
+}
+
+/// Bounded simulated binary crossover (SBX) for real-valued genomes
+///
+/// Each pair of genes is crossed with probability 0.5, spreading the
+/// children around their parents as one-point crossover would on a binary
+/// encoding. A bigger `eta` keeps children closer to their parents. Children
+/// are kept within `[low, up]`.
+///
+/// On a [`BoundedVec`] it uses its own `low` and `up`, ignoring the bounds the
+/// genomes carry; [`SimulatedBinary::own_bounds`] uses those instead.
+///
+/// # Examples
+/// ```
+/// use dears::crossover::*;
+/// let mut a = vec![0.2, 0.4, 0.6];
+/// let mut b = vec![0.8, 0.5, 0.1];
+/// SimulatedBinary { eta: 15.0, low: 0.0, up: 1.0 }.crossover(&mut a, &mut b);
+/// assert!(a.iter().chain(&b).all(|x| (0.0..=1.0).contains(x)));
+/// ```
+pub struct SimulatedBinary {
+    pub eta: f64,
+    pub low: f64,
+    pub up: f64,
+}
+
+impl SimulatedBinary {
+    /// The spread factor for a child on the side with `room` to its bound
+    fn beta_q(&self, room: f64, roll: f64) -> f64 {
+        let beta = 1.0 + 2.0 * room;
+        let alpha = 2.0 - beta.powf(-(self.eta + 1.0));
+        if roll <= 1.0 / alpha {
+            (roll * alpha).powf(1.0 / (self.eta + 1.0))
+        } else {
+            (1.0 / (2.0 - roll * alpha)).powf(1.0 / (self.eta + 1.0))
+        }
+    }
+
+    /// Crosses one pair of genes within `[low, up]`
+    fn cross_genes(&self, x: &mut f64, y: &mut f64, low: f64, up: f64, rng: &mut dyn RngCore) {
+        if rng.gen::<f64>() > 0.5 || (*x - *y).abs() <= 1e-14 {
+            return;
+        }
+        let (x1, x2) = if x < y { (*x, *y) } else { (*y, *x) };
+        let roll = rng.gen::<f64>();
+        let c1 = 0.5 * (x1 + x2 - self.beta_q((x1 - low) / (x2 - x1), roll) * (x2 - x1));
+        let c2 = 0.5 * (x1 + x2 + self.beta_q((up - x2) / (x2 - x1), roll) * (x2 - x1));
+        let (c1, c2) = (c1.clamp(low, up), c2.clamp(low, up));
+        if rng.gen::<f64>() <= 0.5 {
+            (*x, *y) = (c2, c1);
+        } else {
+            (*x, *y) = (c1, c2);
+        }
+    }
+
+    /// SBX within the bounds each [`BoundedVec`] carries
+    ///
+    /// Each pair of genes is spread within the first parent's bounds for
+    /// that gene, then each child's gene is kept within its own.
+    pub fn own_bounds(eta: f64) -> OwnBounds<SimulatedBinary> {
+        OwnBounds { op: SimulatedBinary { eta, low: f64::NEG_INFINITY, up: f64::INFINITY } }
+    }
+}
+
+impl<G: AsMut<[f64]> + ?Sized> Crossover<G> for SimulatedBinary {
+    fn crossover_with(&self, a: &mut G, b: &mut G, rng: &mut dyn RngCore) {
+        for (x, y) in a.as_mut().iter_mut().zip(b.as_mut().iter_mut()) {
+            self.cross_genes(x, y, self.low, self.up, rng);
+        }
+    }
+}
+
+impl Crossover<BoundedVec> for OwnBounds<SimulatedBinary> {
+    fn crossover_with(&self, a: &mut BoundedVec, b: &mut BoundedVec, rng: &mut dyn RngCore) {
+        for idx in 0..a.len().min(b.len()) {
+            let (low, up) = a.bounds(idx);
+            let (mut x, mut y) = (a.values()[idx], b.values()[idx]);
+            self.op.cross_genes(&mut x, &mut y, low, up, rng);
+            let (b_low, b_up) = b.bounds(idx);
+            *a.gene_mut(idx) = x;
+            *b.gene_mut(idx) = y.clamp(b_low, b_up);
+        }
+    }
+}
+
+/// Crossover of struct genomes field by field
+///
+/// `#[derive(CrossoverFields)]`, with the `derive` feature, implements
+/// [`Crossover`] of the struct for `FieldWise`. Every field says how it's
+/// crossed: `#[crossover(one_point)]` for [`OnePoint`],
+/// `#[crossover(uniform(p))]` for [`Uniform`] with `indpb` `p`, or
+/// `#[crossover(skip)]` to leave it with its parent. Each field is only ever
+/// crossed with the same field of the other parent.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "derive")] {
+/// use dears::crossover::*;
+/// use dears::CrossoverFields;
+///
+/// #[derive(CrossoverFields)]
+/// struct Genome {
+///     #[crossover(uniform(1.0))]
+///     weights: Vec<f64>,
+///     #[crossover(skip)]
+///     id: u32,
+/// }
+///
+/// let mut a = Genome { weights: vec![0.0; 3], id: 1 };
+/// let mut b = Genome { weights: vec![1.0; 3], id: 2 };
+/// FieldWise.crossover(&mut a, &mut b);
+/// assert_eq!((a.weights, a.id), (vec![1.0; 3], 1));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FieldWise;
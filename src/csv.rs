@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+
+use crate::fitness::{Fitness, MultiFitness, OrderedFitness, ScalarFitness};
+
+/// Fitnesses that can be written out as CSV, one column per objective
+pub trait FitnessColumns {
+    fn columns(&self) -> Vec<f64>;
+}
+
+impl FitnessColumns for f64 {
+    fn columns(&self) -> Vec<f64> {
+        vec![*self]
+    }
+}
+
+impl<const N: usize> FitnessColumns for [f64; N] {
+    fn columns(&self) -> Vec<f64> {
+        self.to_vec()
+    }
+}
+
+impl FitnessColumns for ScalarFitness {
+    fn columns(&self) -> Vec<f64> {
+        vec![self.0]
+    }
+}
+
+impl FitnessColumns for OrderedFitness {
+    fn columns(&self) -> Vec<f64> {
+        vec![self.0]
+    }
+}
+
+impl<const N: usize> FitnessColumns for MultiFitness<N> {
+    fn columns(&self) -> Vec<f64> {
+        self.0.to_vec()
+    }
+}
+
+/// The unweighted values, or NaN for each objective while invalid
+impl<const N: usize> FitnessColumns for Fitness<N> {
+    fn columns(&self) -> Vec<f64> {
+        self.values().map_or(vec![f64::NAN; N], |values| values.to_vec())
+    }
+}
+
+/// Quotes a field if it holds a comma, quote or line break, doubling any quotes
+pub fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes one line of CSV, escaping every field
+pub fn write_record<W: Write, T: AsRef<str>>(w: &mut W, fields: &[T]) -> io::Result<()> {
+    let fields: Vec<String> = fields.iter().map(|field| escape(field.as_ref())).collect();
+    writeln!(w, "{}", fields.join(","))
+}
+
+/// The column names `prefix_0` to `prefix_{n-1}`, or just `single` if there's only one
+pub(crate) fn numbered(single: &str, prefix: &str, n: usize) -> Vec<String> {
+    match n {
+        1 => vec![single.to_string()],
+        _ => (0..n).map(|i| format!("{}_{}", prefix, i)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csv::*;
+
+    #[test]
+    fn quotes_only_when_needed() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        let mut out = Vec::new();
+        write_record(&mut out, &["1", "two\nlines", ""]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "1,\"two\nlines\",\n");
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use rand::RngCore;
+
+use crate::algorithms::{Control, EvolutionObserver, Offspring};
+use crate::crossover::Crossover;
+use crate::mutation::Mutator;
+use crate::population::Population;
+use crate::selection::SelectMany;
+use crate::stats::StatsRecord;
+
+/// A way of telling which genomes repeat an earlier one
+pub trait Duplicates<G> {
+    /// The indices of every genome matching one before it, in ascending order
+    fn duplicates(&self, genomes: &[G]) -> Vec<usize>;
+}
+
+/// Genomes that are exactly equal
+///
+/// Real-valued genomes can be wrapped in a
+/// [`HashableGenome`](crate::cache::HashableGenome) to be compared here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Exact;
+
+impl<G: Eq + Hash> Duplicates<G> for Exact {
+    fn duplicates(&self, genomes: &[G]) -> Vec<usize> {
+        let mut seen = HashSet::with_capacity(genomes.len());
+        (0..genomes.len()).filter(|&i| !seen.insert(&genomes[i])).collect()
+    }
+}
+
+/// Genomes within `threshold` of an earlier genome that wasn't itself a duplicate
+///
+/// # Examples
+/// ```
+/// use dears::dedup::*;
+/// let near = Within { threshold: 0.5, dist: |a: &f64, b: &f64| (a - b).abs() };
+/// assert_eq!(near.duplicates(&[0.0, 0.3, 0.6, 2.0]), vec![1]);
+/// ```
+pub struct Within<D> {
+    pub threshold: f64,
+    pub dist: D,
+}
+
+impl<G, D: Fn(&G, &G) -> f64> Duplicates<G> for Within<D> {
+    fn duplicates(&self, genomes: &[G]) -> Vec<usize> {
+        let mut kept: Vec<usize> = Vec::with_capacity(genomes.len());
+        let mut duplicates = Vec::new();
+        for i in 0..genomes.len() {
+            if kept.iter().any(|&k| (self.dist)(&genomes[k], &genomes[i]) <= self.threshold) {
+                duplicates.push(i);
+            } else {
+                kept.push(i);
+            }
+        }
+        duplicates
+    }
+}
+
+/// Rewrites every duplicate in `genomes` with `refill`, keeping first occurrences
+///
+/// Use a [`Reset`](crate::mutation::Reset) to re-initialise duplicates, or
+/// any heavy mutator to move them away. Each duplicate is rewritten once, so
+/// a weak mutator may leave some duplicated. Returns the indices rewritten.
+///
+/// # Examples
+/// ```
+/// use dears::dedup::*;
+/// use dears::initializer::RandomBits;
+/// use dears::mutation::Reset;
+/// let mut genomes = vec![vec![true; 4]; 3];
+/// let fresh = Reset { init: RandomBits { len: 4, p_true: 0.0 } };
+/// let replaced = dedup(&mut genomes, &Exact, &fresh, &mut rand::thread_rng());
+/// assert_eq!(replaced, vec![1, 2]);
+/// assert_eq!(genomes, vec![vec![true; 4], vec![false; 4], vec![false; 4]]);
+/// ```
+pub fn dedup<G>(genomes: &mut [G], duplicates: &impl Duplicates<G>, refill: &impl Mutator<G>,
+                rng: &mut dyn RngCore) -> Vec<usize> {
+    let replaced = duplicates.duplicates(genomes);
+    for &i in &replaced {
+        refill.mutate_with(&mut genomes[i], rng);
+    }
+    replaced
+}
+
+/// An [`EvolutionObserver`] that [`dedup`]s each new generation before it's evaluated
+///
+/// Rewritten genomes are evaluated along with the rest of the offspring, and
+/// the number rewritten is added to each generation's statistics as
+/// `"duplicates"`.
+pub struct Dedup<D, R> {
+    pub duplicates: D,
+    pub refill: R,
+    replaced: usize,
+}
+
+impl<D, R> Dedup<D, R> {
+    pub fn new(duplicates: D, refill: R) -> Self {
+        Dedup { duplicates, refill, replaced: 0 }
+    }
+}
+
+impl<G, M, C, S, F, D, R> EvolutionObserver<G, M, C, S, F> for Dedup<D, R>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone,
+    D: Duplicates<G>,
+    R: Mutator<G>
+{
+    fn on_offspring(&mut self, offspring: &mut Offspring<G>, rng: &mut dyn RngCore) {
+        let replaced = dedup(&mut offspring.genomes, &self.duplicates, &self.refill, rng);
+        for &i in &replaced {
+            offspring.changed[i] = true;
+        }
+        self.replaced = replaced.len();
+    }
+
+    fn on_generation_end(&mut self, _pop: &mut Population<G, M, C, S, F>, stats: &mut StatsRecord) -> Control {
+        stats.insert("duplicates", vec![self.replaced as f64]);
+        Control::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::cache::HashableGenome;
+    use crate::dedup::*;
+    use crate::distance::hamming;
+    use crate::initializer::RandomBits;
+    use crate::mutation::Reset;
+
+    #[test]
+    fn clones_are_reinitialised() {
+        let original = vec![true; 16];
+        let mut genomes = vec![original.clone(); 10];
+        let fresh = Reset { init: RandomBits { len: 16, p_true: 0.5 } };
+        let replaced = dedup(&mut genomes, &Exact, &fresh, &mut StdRng::seed_from_u64(155));
+        assert_eq!(replaced, (1..10).collect::<Vec<_>>());
+        assert_eq!(genomes.len(), 10);
+        assert_eq!(genomes.iter().filter(|&g| *g == original).count(), 1);
+        assert_eq!(genomes[0], original);
+        assert!(Exact.duplicates(&genomes).is_empty());
+    }
+
+    #[test]
+    fn near_duplicates_within_threshold() {
+        let genomes = vec![
+            vec![false, false, false, false],
+            vec![true, false, false, false],
+            vec![true, true, true, true],
+            vec![true, true, true, false],
+            vec![false, false, false, false],
+        ];
+        let near = Within { threshold: 1.0, dist: |a: &Vec<bool>, b: &Vec<bool>| hamming(a, b) };
+        assert_eq!(near.duplicates(&genomes), vec![1, 3, 4]);
+        assert_eq!(Exact.duplicates(&genomes), vec![4]);
+    }
+
+    #[test]
+    fn float_genomes_through_hashable_wrapper() {
+        let genomes: Vec<HashableGenome> = [vec![0.5, 1.0], vec![0.5, 1.001], vec![f64::NAN], vec![f64::NAN]]
+            .into_iter()
+            .map(HashableGenome::from)
+            .collect();
+        assert_eq!(Exact.duplicates(&genomes), vec![3]);
+        let quantised: Vec<_> = genomes.into_iter().map(|g| HashableGenome::quantised(g.into_inner(), 0.01)).collect();
+        assert_eq!(Exact.duplicates(&quantised), vec![1, 3]);
+    }
+}
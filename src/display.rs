@@ -0,0 +1,159 @@
+use std::fmt;
+
+/// Writes `value` to `precision` decimal places, without the sign on a value that rounds to zero
+///
+/// # Examples
+/// ```
+/// use dears::display::format_number;
+/// assert_eq!(format_number(-1.256, 2), "-1.26");
+/// assert_eq!(format_number(-0.0, 2), "0.00");
+/// assert_eq!(format_number(-0.001, 2), "0.00");
+/// assert_eq!(format_number(f64::NEG_INFINITY, 2), "-inf");
+/// ```
+pub fn format_number(value: f64, precision: usize) -> String {
+    let formatted = format!("{:.*}", precision, value);
+    match formatted.strip_prefix('-') {
+        Some(magnitude) if magnitude.chars().all(|c| c == '0' || c == '.') => magnitude.to_string(),
+        _ => formatted,
+    }
+}
+
+/// A gene that [`DisplayGenome`] can write out
+///
+/// Floats are written to the precision given, if any, and everything else
+/// ignores it.
+pub trait DisplayGene {
+    fn format_gene(&self, precision: Option<usize>) -> String;
+}
+
+impl DisplayGene for f64 {
+    fn format_gene(&self, precision: Option<usize>) -> String {
+        match precision {
+            Some(precision) => format_number(*self, precision),
+            None if *self == 0.0 => "0".to_string(),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl DisplayGene for f32 {
+    fn format_gene(&self, precision: Option<usize>) -> String {
+        match precision {
+            Some(_) => f64::from(*self).format_gene(precision),
+            None if *self == 0.0 => "0".to_string(),
+            None => self.to_string(),
+        }
+    }
+}
+
+macro_rules! display_gene {
+    ($($t:ty),*) => {
+        $(impl DisplayGene for $t {
+            fn format_gene(&self, _precision: Option<usize>) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+display_gene!(bool, char, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// How [`DisplayGenome`] writes out a genome
+///
+/// By default every gene is written in full, without labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GenomeFormat {
+    /// Decimal places for float genes
+    pub precision: Option<usize>,
+    /// Genes written before the rest are summed up as `… n more`
+    pub max_elements: Option<usize>,
+    /// Whether each gene is labelled with its index, as `3: 0.25`
+    pub indices: bool,
+}
+
+impl GenomeFormat {
+    pub fn display<T: DisplayGene>(self, genes: &[T]) -> DisplayGenome<'_, T> {
+        DisplayGenome { genes, format: self }
+    }
+}
+
+/// A genome written out readably however long it is
+///
+/// # Examples
+/// ```
+/// use dears::display::DisplayGenome;
+/// let genome: Vec<f64> = (0..500).map(|i| i as f64 / 8.0).collect();
+/// let shown = DisplayGenome::new(&genome).with_precision(2).with_max_elements(3);
+/// assert_eq!(shown.to_string(), "[0.00, 0.12, 0.25, … 497 more]");
+/// let shown = DisplayGenome::new(&[true, false]).with_indices();
+/// assert_eq!(shown.to_string(), "[0: true, 1: false]");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayGenome<'a, T> {
+    genes: &'a [T],
+    format: GenomeFormat,
+}
+
+impl<'a, T: DisplayGene> DisplayGenome<'a, T> {
+    pub fn new(genes: &'a [T]) -> Self {
+        GenomeFormat::default().display(genes)
+    }
+
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.format.precision = Some(precision);
+        self
+    }
+
+    pub fn with_max_elements(mut self, max_elements: usize) -> Self {
+        self.format.max_elements = Some(max_elements);
+        self
+    }
+
+    pub fn with_indices(mut self) -> Self {
+        self.format.indices = true;
+        self
+    }
+}
+
+impl<T: DisplayGene> fmt::Display for DisplayGenome<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shown = self.format.max_elements.map_or(self.genes.len(), |max| max.min(self.genes.len()));
+        let mut parts: Vec<String> = self.genes[..shown]
+            .iter()
+            .enumerate()
+            .map(|(i, gene)| {
+                let gene = gene.format_gene(self.format.precision);
+                if self.format.indices { format!("{}: {}", i, gene) } else { gene }
+            })
+            .collect();
+        if shown < self.genes.len() {
+            parts.push(format!("… {} more", self.genes.len() - shown));
+        }
+        write!(f, "[{}]", parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::display::*;
+
+    #[test]
+    fn genomes_and_numbers() {
+        assert_eq!(format_number(0.125, 2), "0.12");
+        assert_eq!(format_number(-0.004, 2), "0.00");
+        assert_eq!(format_number(-0.005001, 2), "-0.01");
+        assert_eq!(format_number(-0.4, 0), "0");
+        assert_eq!(format_number(f64::NAN, 3), "NaN");
+
+        let genome = [1.5, -0.0, -2.25, 1e-9, 4.0];
+        assert_eq!(DisplayGenome::new(&genome).to_string(), "[1.5, 0, -2.25, 0.000000001, 4]");
+        assert_eq!(DisplayGenome::new(&genome).with_precision(1).to_string(), "[1.5, 0.0, -2.2, 0.0, 4.0]");
+        let truncated = DisplayGenome::new(&genome).with_precision(2).with_max_elements(2).with_indices();
+        assert_eq!(truncated.to_string(), "[0: 1.50, 1: 0.00, … 3 more]");
+        assert_eq!(DisplayGenome::new(&genome).with_max_elements(0).to_string(), "[… 5 more]");
+        let untruncated = DisplayGenome::new(&genome).with_max_elements(9);
+        assert_eq!(untruncated.to_string(), DisplayGenome::new(&genome).to_string());
+        assert_eq!(DisplayGenome::new(&[3u8, 1]).with_precision(2).to_string(), "[3, 1]");
+        assert_eq!(DisplayGenome::<f64>::new(&[]).to_string(), "[]");
+    }
+}
@@ -0,0 +1,28 @@
+/// Number of positions where two bit strings differ
+///
+/// Works on anything that views as a `bool` slice, so it can be passed straight
+/// to distance-based operators over `Vec<bool>` genomes.
+///
+/// # Examples
+/// ```
+/// use dears::distance::hamming;
+/// assert_eq!(hamming(&vec![true, false, true], &vec![true, true, false]), 2.0);
+/// ```
+pub fn hamming<G: AsRef<[bool]> + ?Sized>(a: &G, b: &G) -> f64 {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    assert_eq!(a.len(), b.len(), "Genomes must be the same length");
+    a.iter().zip(b).filter(|(x, y)| x != y).count() as f64
+}
+
+/// Straight-line distance between two real-valued genomes
+///
+/// # Examples
+/// ```
+/// use dears::distance::euclidean;
+/// assert_eq!(euclidean(&vec![0.0, 3.0], &vec![4.0, 0.0]), 5.0);
+/// ```
+pub fn euclidean<G: AsRef<[f64]> + ?Sized>(a: &G, b: &G) -> f64 {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    assert_eq!(a.len(), b.len(), "Genomes must be the same length");
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt()
+}
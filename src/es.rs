@@ -0,0 +1,215 @@
+use rand::RngCore;
+
+use crate::mutation::{Boundary, Bounded, Gaussian, Mutator};
+use crate::selection::Direction;
+use crate::stats::{Logbook, Statistics};
+use crate::termination::{Progress, StopReason, Termination};
+
+/// The (1+1) evolution strategy with Rechenberg's one-fifth success rule
+///
+/// Each [`OnePlusOneEs::step`] mutates every coordinate of the current
+/// solution with [`Gaussian`] noise of standard deviation `sigma` and keeps
+/// the mutant if it's no worse. Every `interval` steps, `sigma` is divided by
+/// `factor` if more than a fifth of them improved the solution, and multiplied
+/// by it if fewer did. Fitnesses are minimised unless told otherwise.
+///
+/// # Examples
+/// ```
+/// use dears::es::OnePlusOneEs;
+/// use dears::termination::MaxGenerations;
+/// let quadratic = |x: &Vec<f64>| (x[0] - 2.0).powi(2);
+/// let mut es = OnePlusOneEs::new(vec![0.0], 1.0);
+/// let (logbook, _) = es.run(MaxGenerations(200), quadratic, None, &mut rand::thread_rng());
+/// assert_eq!(logbook.len(), 201);
+/// assert!((es.solution()[0] - 2.0).abs() < 0.1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnePlusOneEs {
+    solution: Vec<f64>,
+    fitness: Option<f64>,
+    sigma: f64,
+    direction: Direction,
+    bounds: Option<(f64, f64, Boundary)>,
+    interval: usize,
+    factor: f64,
+    successes: usize,
+    trials: usize,
+    generation: usize,
+    evaluations: usize,
+}
+
+impl OnePlusOneEs {
+    /// Starts from `solution` with step size `sigma`, adapting it every 10 steps by a factor of 0.85
+    pub fn new(solution: Vec<f64>, sigma: f64) -> Self {
+        OnePlusOneEs {
+            solution,
+            fitness: None,
+            sigma,
+            direction: Direction::Minimize,
+            bounds: None,
+            interval: 10,
+            factor: 0.85,
+            successes: 0,
+            trials: 0,
+            generation: 0,
+            evaluations: 0,
+        }
+    }
+
+    /// Sets whether bigger or smaller fitnesses are better
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Adapts `sigma` every `interval` steps by `factor`, which should be just under 1
+    ///
+    /// Panics if `interval` is 0 or `factor` isn't in `(0, 1]`.
+    pub fn with_adaptation(mut self, interval: usize, factor: f64) -> Self {
+        assert!(interval > 0, "Need at least one step between adaptations");
+        assert!(factor > 0.0 && factor <= 1.0, "Adaptation factor {} must be in (0, 1]", factor);
+        self.interval = interval;
+        self.factor = factor;
+        self
+    }
+
+    /// Keeps every coordinate of mutants within `[low, up]`
+    pub fn with_bounds(mut self, low: f64, up: f64, boundary: Boundary) -> Self {
+        self.bounds = Some((low, up, boundary));
+        self
+    }
+
+    /// Makes and evaluates one mutant, returning whether it replaced the solution
+    ///
+    /// The starting solution is evaluated first if it hasn't been.
+    pub fn step<E: Fn(&Vec<f64>) -> f64>(&mut self, eval: E, rng: &mut dyn RngCore) -> bool {
+        let current = self.evaluate(&eval);
+        let mut mutant = self.solution.clone();
+        let gaussian = Gaussian { mu: 0.0, sigma: self.sigma, indpb: 1.0 };
+        match self.bounds {
+            Some((low, up, boundary)) => Bounded { mutator: gaussian, low, up, boundary }.mutate_with(&mut mutant, rng),
+            None => gaussian.mutate_with(&mut mutant, rng),
+        }
+        let fitness = eval(&mutant);
+        self.evaluations += 1;
+        self.generation += 1;
+        let ordering = self.direction.compare(&fitness, &current);
+        let accepted = ordering.is_ge();
+        if accepted {
+            self.solution = mutant;
+            self.fitness = Some(fitness);
+        }
+        self.successes += ordering.is_gt() as usize;
+        self.trials += 1;
+        if self.trials == self.interval {
+            let rate = self.successes as f64 / self.trials as f64;
+            if rate > 0.2 {
+                self.sigma /= self.factor;
+            } else if rate < 0.2 {
+                self.sigma *= self.factor;
+            }
+            self.successes = 0;
+            self.trials = 0;
+        }
+        accepted
+    }
+
+    /// Steps until `stop` fires, evaluating with `eval`
+    ///
+    /// The logbook has a row for the starting solution and each step after
+    /// it, with `stats` compiled over the current fitness if given.
+    pub fn run<E, T>(&mut self, mut stop: T, eval: E, stats: Option<&Statistics<f64>>, rng: &mut dyn RngCore)
+                     -> (Logbook, StopReason)
+    where
+        E: Fn(&Vec<f64>) -> f64,
+        T: Termination<f64>
+    {
+        let mut logbook = Logbook::new();
+        let before = self.evaluations;
+        let fitness = [self.evaluate(&eval)];
+        let row = stats.map(|s| s.compile(&fitness)).unwrap_or_default();
+        logbook.record(self.generation, self.evaluations - before, row);
+        loop {
+            let fitness = [self.evaluate(&eval)];
+            let progress = Progress { generation: self.generation, evaluations: self.evaluations, fitnesses: &fitness };
+            if let Some(reason) = stop.check(&progress) {
+                return (logbook, reason);
+            }
+            self.step(&eval, rng);
+            let fitness = [self.evaluate(&eval)];
+            logbook.record(self.generation, 1, stats.map(|s| s.compile(&fitness)).unwrap_or_default());
+        }
+    }
+
+    /// The current solution's fitness, evaluating it if it hasn't been
+    fn evaluate<E: Fn(&Vec<f64>) -> f64>(&mut self, eval: E) -> f64 {
+        match self.fitness {
+            Some(fitness) => fitness,
+            None => {
+                let fitness = eval(&self.solution);
+                self.evaluations += 1;
+                self.fitness = Some(fitness);
+                fitness
+            }
+        }
+    }
+
+    pub fn solution(&self) -> &[f64] {
+        &self.solution
+    }
+
+    /// The current solution's fitness, once it's been evaluated
+    pub fn fitness(&self) -> Option<f64> {
+        self.fitness
+    }
+
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+
+    /// Steps taken so far
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::es::*;
+    use crate::termination::*;
+
+    #[test]
+    fn minimises_quadratic_and_shrinks_sigma() {
+        let quadratic = |x: &Vec<f64>| x.iter().enumerate().map(|(i, v)| (v - i as f64).powi(2)).sum::<f64>();
+        let mut es = OnePlusOneEs::new(vec![0.0; 5], 1.0);
+        let stop = Any(vec![
+            Box::new(TargetFitness { target: 1e-6, direction: Direction::Minimize }),
+            Box::new(MaxGenerations(5_000)),
+        ]);
+        let (logbook, reason) = es.run(stop, quadratic, None, &mut StdRng::seed_from_u64(159));
+        assert_eq!(reason, StopReason::TargetFitness);
+        assert!(es.fitness().unwrap() <= 1e-6);
+        assert!(es.sigma() < 0.01);
+        assert_eq!(logbook.len(), es.generation() + 1);
+        assert_eq!(logbook.nevals().iter().sum::<usize>(), es.evaluations());
+    }
+
+    #[test]
+    fn bounds_hold_the_optimum_at_the_edge() {
+        let linear = |x: &Vec<f64>| x[0] + x[1];
+        let mut es = OnePlusOneEs::new(vec![0.5, 0.5], 0.5).with_bounds(0.0, 1.0, Boundary::Reflect);
+        let mut rng = StdRng::seed_from_u64(159);
+        for _ in 0..500 {
+            es.step(linear, &mut rng);
+            assert!(es.solution().iter().all(|x| (0.0..=1.0).contains(x)));
+        }
+        assert!(es.fitness().unwrap() < 0.01);
+    }
+}
@@ -0,0 +1,497 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use rand::RngCore;
+
+use crate::crossover::Crossover;
+use crate::display::format_number;
+use crate::multiobjective::dominates;
+use crate::mutation::Mutator;
+use crate::selection::{Comparator, Direction, TotalFitness};
+
+/// A single-objective fitness
+///
+/// Works anywhere a plain `f64` fitness does: it orders like one, so
+/// [`Direction`] and the tournament selectors apply, and it converts into one
+/// for the proportional selectors. NaN is treated as the worst fitness.
+/// [`OrderedFitness`] does the same with a total order, and is usually the
+/// better choice.
+///
+/// # Examples
+/// ```
+/// use dears::fitness::ScalarFitness;
+/// use dears::selection::*;
+/// let fitnesses = vec![ScalarFitness(1.0), ScalarFitness(4.0), ScalarFitness(2.0)];
+/// assert_eq!(SelBest::default().select(&fitnesses), 1);
+/// let roulette = Roulette { scaling: Scaling::None, direction: Direction::Maximize };
+/// assert_eq!(roulette.select_n(&fitnesses, 4).len(), 4);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct ScalarFitness(pub f64);
+
+impl From<f64> for ScalarFitness {
+    fn from(value: f64) -> Self {
+        ScalarFitness(value)
+    }
+}
+
+impl From<ScalarFitness> for f64 {
+    fn from(fitness: ScalarFitness) -> Self {
+        fitness.0
+    }
+}
+
+/// A single-objective fitness with a total order, the recommended scalar fitness
+///
+/// Implements `Ord`, so it sorts without any NaN policy to apply and works
+/// with [`TotalOrder`](crate::selection::TotalOrder). NaN is the worst
+/// fitness under maximisation: every NaN is smaller than any other value and
+/// equal to every other NaN. Everything else orders as
+/// [`f64::total_cmp`] does, so `-0.0` comes just before `0.0` and they
+/// aren't equal. A plain ascending sort puts NaN first, but
+/// [`TotalOrder`](crate::selection::TotalOrder) ranks it last when
+/// minimising too.
+///
+/// It converts to and from `f64` for the proportional selectors and
+/// statistics, and supports the basic arithmetic.
+///
+/// # Examples
+/// ```
+/// use dears::fitness::OrderedFitness;
+/// let mut fitnesses: Vec<OrderedFitness> = vec![2.0, f64::NAN, -1.0].into_iter().map(Into::into).collect();
+/// fitnesses.sort();
+/// assert!(fitnesses[0].0.is_nan());
+/// assert_eq!(fitnesses.iter().max(), Some(&OrderedFitness(2.0)));
+/// assert_eq!(fitnesses[1] + fitnesses[2], OrderedFitness(1.0));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderedFitness(pub f64);
+
+impl Ord for OrderedFitness {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0.is_nan(), other.0.is_nan()) {
+            (false, false) => self.0.total_cmp(&other.0),
+            (a_nan, b_nan) => b_nan.cmp(&a_nan),
+        }
+    }
+}
+
+impl PartialOrd for OrderedFitness {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for OrderedFitness {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedFitness {}
+
+/// NaN is a failed evaluation
+impl TotalFitness for OrderedFitness {
+    fn failed(&self) -> bool {
+        self.0.is_nan()
+    }
+}
+
+impl From<f64> for OrderedFitness {
+    fn from(value: f64) -> Self {
+        OrderedFitness(value)
+    }
+}
+
+impl From<OrderedFitness> for f64 {
+    fn from(fitness: OrderedFitness) -> Self {
+        fitness.0
+    }
+}
+
+impl Add for OrderedFitness {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        OrderedFitness(self.0 + other.0)
+    }
+}
+
+impl Sub for OrderedFitness {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        OrderedFitness(self.0 - other.0)
+    }
+}
+
+impl Mul<f64> for OrderedFitness {
+    type Output = Self;
+
+    fn mul(self, factor: f64) -> Self {
+        OrderedFitness(self.0 * factor)
+    }
+}
+
+impl Div<f64> for OrderedFitness {
+    type Output = Self;
+
+    fn div(self, divisor: f64) -> Self {
+        OrderedFitness(self.0 / divisor)
+    }
+}
+
+impl Neg for OrderedFitness {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        OrderedFitness(-self.0)
+    }
+}
+
+impl Sum for OrderedFitness {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        OrderedFitness(iter.map(|f| f.0).sum())
+    }
+}
+
+/// A fitness of `N` objectives
+///
+/// Orders lexicographically, first objective first, like a plain `[f64; N]`.
+/// Weighted comparison is up to the selector, as with
+/// [`WeightedTournament`](crate::selection::WeightedTournament), and Pareto
+/// dominance is available through [`MultiFitness::dominates`].
+///
+/// # Examples
+/// ```
+/// use dears::fitness::MultiFitness;
+/// use dears::selection::Direction;
+/// let a = MultiFitness([1.0, 2.0]);
+/// let b = MultiFitness([2.0, 2.0]);
+/// assert!(a < b);
+/// assert!(a.dominates(&b, Direction::Minimize));
+/// assert_eq!(a.weighted_sum(&[1.0, -0.5]), 0.0);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct MultiFitness<const N: usize>(
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))] pub [f64; N]
+);
+
+impl<const N: usize> MultiFitness<N> {
+    pub fn objectives(&self) -> &[f64; N] {
+        &self.0
+    }
+
+    /// Whether this is no worse in every objective and better in at least one
+    pub fn dominates(&self, other: &Self, direction: Direction) -> bool {
+        dominates(&self.0, &other.0, direction)
+    }
+
+    /// The objectives multiplied by their weights and summed
+    pub fn weighted_sum(&self, weights: &[f64; N]) -> f64 {
+        self.0.iter().zip(weights).map(|(f, w)| f * w).sum()
+    }
+}
+
+impl<const N: usize> Default for MultiFitness<N> {
+    fn default() -> Self {
+        MultiFitness([0.0; N])
+    }
+}
+
+impl<const N: usize> From<[f64; N]> for MultiFitness<N> {
+    fn from(objectives: [f64; N]) -> Self {
+        MultiFitness(objectives)
+    }
+}
+
+impl<const N: usize> From<MultiFitness<N>> for [f64; N] {
+    fn from(fitness: MultiFitness<N>) -> Self {
+        fitness.0
+    }
+}
+
+impl<const N: usize> AsRef<[f64]> for MultiFitness<N> {
+    fn as_ref(&self) -> &[f64] {
+        &self.0
+    }
+}
+
+/// A fitness of `N` objectives, each with a weight saying which way is better, as in DEAP
+///
+/// Positive weights maximise their objective and negative weights minimise it,
+/// so objectives with mixed directions can be ordered together. Fitnesses
+/// compare lexicographically by their [weighted values](Fitness::wvalues),
+/// bigger being better, so they work with the default [`Direction::Maximize`]
+/// of the selectors and the hall of fame.
+///
+/// A fitness starts out invalid, with no values. An invalid fitness can't be
+/// compared, not even with itself, just like NaN, so the selectors and the
+/// hall of fame rank it below every valid fitness whichever their direction.
+///
+/// # Examples
+/// ```
+/// use dears::fitness::Fitness;
+/// use dears::selection::{cmp_fitness, Direction, SelBest, SelectOne};
+/// // Maximise accuracy, then minimise size
+/// let weights = [1.0, -1.0];
+/// let small = Fitness::new(weights).with_values([0.9, 10.0]);
+/// let big = Fitness::new(weights).with_values([0.9, 50.0]);
+/// assert!(small > big);
+/// assert!(small.dominates(&big));
+/// assert!(cmp_fitness(&Fitness::new(weights), &big).is_lt());
+/// let fitnesses = [Fitness::new(weights), big];
+/// assert_eq!(SelBest { comparator: Direction::Minimize }.select(&fitnesses), 1);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct Fitness<const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array::option"))]
+    values: Option<[f64; N]>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    weights: [f64; N],
+}
+
+impl<const N: usize> Fitness<N> {
+    /// An invalid fitness that will weigh its objectives by `weights`
+    pub fn new(weights: [f64; N]) -> Self {
+        Fitness { values: None, weights }
+    }
+
+    pub fn with_values(mut self, values: [f64; N]) -> Self {
+        self.values = Some(values);
+        self
+    }
+
+    pub fn set_values(&mut self, values: [f64; N]) {
+        self.values = Some(values);
+    }
+
+    pub fn values(&self) -> Option<&[f64; N]> {
+        self.values.as_ref()
+    }
+
+    pub fn weights(&self) -> &[f64; N] {
+        &self.weights
+    }
+
+    /// The values multiplied by their weights, so bigger is better in every objective
+    pub fn wvalues(&self) -> Option<[f64; N]> {
+        self.values.map(|values| std::array::from_fn(|i| values[i] * self.weights[i]))
+    }
+
+    pub fn valid(&self) -> bool {
+        self.values.is_some()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.values = None;
+    }
+
+    /// Whether both are valid and this is no worse in every weighted objective and better in one
+    pub fn dominates(&self, other: &Self) -> bool {
+        match (self.wvalues(), other.wvalues()) {
+            (Some(a), Some(b)) => dominates(&a, &b, Direction::Maximize),
+            _ => false,
+        }
+    }
+}
+
+/// Equal when both are valid with equal weighted values
+impl<const N: usize> PartialEq for Fitness<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+/// Invalid fitnesses are incomparable, so [`cmp_fitness`](crate::selection::cmp_fitness) puts them last
+impl<const N: usize> PartialOrd for Fitness<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.wvalues()?.partial_cmp(&other.wvalues()?)
+    }
+}
+
+/// Writes the unweighted values as `(1.25, -0.30)`, to 2 decimal places unless given a precision
+///
+/// An invalid fitness is written as `(invalid)`.
+///
+/// # Examples
+/// ```
+/// use dears::fitness::Fitness;
+/// let fitness = Fitness::new([1.0, -1.0]).with_values([1.25, -0.3]);
+/// assert_eq!(fitness.to_string(), "(1.25, -0.30)");
+/// assert_eq!(format!("{:.1}", fitness), "(1.2, -0.3)");
+/// assert_eq!(Fitness::new([1.0]).to_string(), "(invalid)");
+/// ```
+impl<const N: usize> fmt::Display for Fitness<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(values) = self.values else {
+            return write!(f, "(invalid)");
+        };
+        let precision = f.precision().unwrap_or(2);
+        let values: Vec<String> = values.iter().map(|&v| format_number(v, precision)).collect();
+        write!(f, "({})", values.join(", "))
+    }
+}
+
+/// Orders [`Fitness`]es by their first objective, falling back on the next only for ties
+///
+/// Each objective goes the way its weight says, and one with a weight of 0
+/// is ignored. Objectives are compared on a grid the size of their
+/// tolerance, so two values in the same multiple of the tolerance tie and
+/// leave the decision to the next objective. A plain "closer than the
+/// tolerance" test would let small differences chain up into a cycle; the
+/// grid keeps the ordering transitive, at the cost of splitting some close
+/// pairs that straddle a grid line. A tolerance of 0 compares exactly.
+/// Invalid fitnesses, and NaN objectives, are the worst.
+///
+/// `compare(a, b)` is `Greater` when `a` is better, so this is a
+/// [`Comparator`] for [`TournamentSelection::with_comparator`].
+///
+/// [`TournamentSelection::with_comparator`]: crate::selection::TournamentSelection::with_comparator
+///
+/// # Examples
+/// ```
+/// use std::cmp::Ordering;
+/// use dears::fitness::*;
+/// use dears::selection::*;
+/// // Maximise accuracy to within 0.01, then minimise size
+/// let fitness = |accuracy, size| Fitness::new([1.0, -1.0]).with_values([accuracy, size]);
+/// let lexicographic = Lexicographic::new([0.01, 0.0]);
+/// assert_eq!(lexicographic.compare(&fitness(0.902, 20.0), &fitness(0.905, 50.0)), Ordering::Greater);
+/// assert_eq!(lexicographic.compare(&fitness(0.902, 20.0), &fitness(0.915, 50.0)), Ordering::Less);
+/// let selector = TournamentSelection::with_comparator(3, lexicographic).unwrap();
+/// assert!(selector.select(&[fitness(0.9, 1.0), fitness(0.8, 1.0)]) < 2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lexicographic<const N: usize> {
+    tolerances: [f64; N],
+}
+
+impl<const N: usize> Lexicographic<N> {
+    /// Panics unless every tolerance is a non-negative, finite number
+    pub fn new(tolerances: [f64; N]) -> Self {
+        assert!(tolerances.iter().all(|t| (0.0..f64::INFINITY).contains(t)), "Invalid tolerances {:?}", tolerances);
+        Lexicographic { tolerances }
+    }
+
+    pub fn tolerances(&self) -> &[f64; N] {
+        &self.tolerances
+    }
+
+    /// Where `value` of objective `i` sits on that objective's grid, bigger being better
+    fn key(&self, i: usize, value: f64, weight: f64) -> f64 {
+        let tolerance = self.tolerances[i];
+        let value = if tolerance > 0.0 { (value / tolerance).floor() } else { value };
+        value * weight.signum()
+    }
+}
+
+impl<const N: usize> Comparator<Fitness<N>> for Lexicographic<N> {
+    fn compare(&self, a: &Fitness<N>, b: &Fitness<N>) -> Ordering {
+        let (a_values, b_values) = match (a.values(), b.values()) {
+            (Some(a_values), Some(b_values)) => (a_values, b_values),
+            (a_values, b_values) => return a_values.is_some().cmp(&b_values.is_some()),
+        };
+        (0..N)
+            .filter(|&i| a.weights[i] != 0.0)
+            .map(|i| {
+                let (x, y) = (self.key(i, a_values[i], a.weights[i]), self.key(i, b_values[i], a.weights[i]));
+                Direction::Maximize.compare(&x, &y)
+            })
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// A fitness that can be out of date with its genome
+pub trait Validity {
+    fn valid(&self) -> bool;
+
+    fn invalidate(&mut self);
+}
+
+impl<const N: usize> Validity for Fitness<N> {
+    fn valid(&self) -> bool {
+        Fitness::valid(self)
+    }
+
+    fn invalidate(&mut self) {
+        Fitness::invalidate(self)
+    }
+}
+
+/// `None` is invalid
+impl<T> Validity for Option<T> {
+    fn valid(&self) -> bool {
+        self.is_some()
+    }
+
+    fn invalidate(&mut self) {
+        *self = None;
+    }
+}
+
+/// A genome that carries its own fitness
+///
+/// Vary it through [`Invalidating`], which invalidates the fitness of every
+/// genome an operator is given, so a stale fitness can't outlive a change.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Evaluated<G, F> {
+    pub genome: G,
+    pub fitness: F,
+}
+
+impl<G, F: Validity> Evaluated<G, F> {
+    pub fn new(genome: G, fitness: F) -> Self {
+        Evaluated { genome, fitness }
+    }
+
+    pub fn valid(&self) -> bool {
+        self.fitness.valid()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.fitness.invalidate();
+    }
+}
+
+/// Runs a genome operator on [`Evaluated`] individuals and invalidates their fitness
+///
+/// Works for any [`Mutator`] or [`Crossover`] of the genome. Operators don't
+/// report whether they changed anything, so a crossover invalidates both
+/// partners even if it happened to leave them as they were.
+///
+/// # Examples
+/// ```
+/// use dears::fitness::*;
+/// use dears::mutation::*;
+/// let mut individual = Evaluated::new(vec![false; 4], Fitness::new([1.0]).with_values([0.0]));
+/// assert!(individual.valid());
+/// Invalidating(FlipBit { indpb: 1.0 }).mutate(&mut individual);
+/// assert_eq!(individual.genome, vec![true; 4]);
+/// assert!(!individual.valid());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Invalidating<O>(pub O);
+
+impl<G, F: Validity, M: Mutator<G>> Mutator<Evaluated<G, F>> for Invalidating<M> {
+    fn mutate_with(&self, individual: &mut Evaluated<G, F>, rng: &mut dyn RngCore) {
+        self.0.mutate_with(&mut individual.genome, rng);
+        individual.invalidate();
+    }
+}
+
+impl<G, F: Validity, C: Crossover<G>> Crossover<Evaluated<G, F>> for Invalidating<C> {
+    fn crossover_with(&self, a: &mut Evaluated<G, F>, b: &mut Evaluated<G, F>, rng: &mut dyn RngCore) {
+        self.0.crossover_with(&mut a.genome, &mut b.genome, rng);
+        a.invalidate();
+        b.invalidate();
+    }
+}
@@ -0,0 +1,671 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::{Deref, Range};
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+/// A fixed sequence of genes that operators can read and rearrange in place
+///
+/// The operators that only move genes around, like
+/// [`Shuffle`](crate::mutation::Shuffle) and
+/// [`OnePoint`](crate::crossover::OnePoint), work on any `Genome`. Those that
+/// change gene values, like [`FlipBit`](crate::mutation::FlipBit) and
+/// [`Gaussian`](crate::mutation::Gaussian), take any `AsMut<[bool]>` or
+/// `AsMut<[f64]>` as they always have.
+/// Implemented for slices, arrays, `Vec`, `Box<[T]>` and `VecDeque`; other
+/// containers only need to give access to each gene by index. Genomes whose
+/// genes sit in one slice should return it from [`Genome::as_mut_slice`],
+/// which operators use to skip the per-gene indexing. Either way an operator
+/// visits the genes in the same order, so draws the same random numbers.
+///
+/// # Examples
+/// ```
+/// use std::collections::VecDeque;
+/// use dears::genome::Genome;
+/// let mut genome: VecDeque<u8> = VecDeque::from(vec![1, 2, 3]);
+/// genome.swap(0, 2);
+/// *genome.gene_mut(1) = 9;
+/// assert_eq!(genome, [3, 9, 1]);
+/// assert!(genome.as_mut_slice().is_none());
+/// ```
+pub trait Genome {
+    type Gene;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Panics if `idx` is out of bounds
+    fn gene_mut(&mut self, idx: usize) -> &mut Self::Gene;
+
+    /// Panics if `i` or `j` is out of bounds
+    fn swap(&mut self, i: usize, j: usize);
+
+    /// Every gene as one slice, if they're stored that way
+    fn as_mut_slice(&mut self) -> Option<&mut [Self::Gene]> {
+        None
+    }
+}
+
+impl<T> Genome for [T] {
+    type Gene = T;
+
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn gene_mut(&mut self, idx: usize) -> &mut T {
+        &mut self[idx]
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j)
+    }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [T]> {
+        Some(self)
+    }
+}
+
+impl<T, const N: usize> Genome for [T; N] {
+    type Gene = T;
+
+    fn len(&self) -> usize {
+        N
+    }
+
+    fn gene_mut(&mut self, idx: usize) -> &mut T {
+        &mut self[idx]
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j)
+    }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [T]> {
+        Some(self)
+    }
+}
+
+impl<T> Genome for Vec<T> {
+    type Gene = T;
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn gene_mut(&mut self, idx: usize) -> &mut T {
+        &mut self[idx]
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j)
+    }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [T]> {
+        Some(self)
+    }
+}
+
+impl<T> Genome for Box<[T]> {
+    type Gene = T;
+
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn gene_mut(&mut self, idx: usize) -> &mut T {
+        &mut self[idx]
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j)
+    }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [T]> {
+        Some(self)
+    }
+}
+
+impl<T> Genome for VecDeque<T> {
+    type Gene = T;
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn gene_mut(&mut self, idx: usize) -> &mut T {
+        &mut self[idx]
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        VecDeque::swap(self, i, j)
+    }
+}
+
+/// A [`BoundedVec`] was given bounds it can't have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundsError {
+    /// There wasn't one lower and one upper bound for every value
+    LengthMismatch { values: usize, low: usize, up: usize },
+    /// The lower bound of the gene at `index` is above its upper bound, or one of them is NaN
+    Inverted { index: usize },
+}
+
+impl fmt::Display for BoundsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoundsError::LengthMismatch { values, low, up } =>
+                write!(f, "{} values were given {} lower and {} upper bounds", values, low, up),
+            BoundsError::Inverted { index } =>
+                write!(f, "The lower bound of gene {} isn't at or below its upper bound", index),
+        }
+    }
+}
+
+impl std::error::Error for BoundsError {}
+
+/// Real values that carry their own bounds, `[low[i], up[i]]` for the gene at `i`
+///
+/// Wrap a bounded operator in [`OwnBounds`], with its `own_bounds`
+/// constructor, and it takes each gene's bounds from the genome rather than
+/// its own `low` and `up`. The bounds stay with the genome: crossover only
+/// exchanges values, so a child keeps its parent's bounds, which suits bounds
+/// that belong to each position of the problem rather than to each individual.
+/// Operators that know nothing of the bounds, like
+/// [`Gaussian`](crate::mutation::Gaussian), can leave values outside them
+/// until [`BoundedVec::clamp_self`].
+///
+/// # Examples
+/// ```
+/// use dears::genome::*;
+/// use dears::mutation::*;
+/// let mut genome = BoundedVec::new(vec![0.5, 5.0], vec![0.0, 4.0], vec![1.0, 6.0]).unwrap();
+/// Polynomial::own_bounds(20.0, 1.0).mutate(&mut genome);
+/// assert!((0.0..=1.0).contains(&genome.values()[0]) && (4.0..=6.0).contains(&genome.values()[1]));
+/// Gaussian { mu: 0.0, sigma: 10.0, indpb: 1.0 }.mutate(genome.as_mut());
+/// genome.clamp_self();
+/// assert!(genome.in_bounds());
+/// assert!(BoundedVec::new(vec![0.0], vec![1.0], vec![0.0]).is_err());
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundedVec<F = f64> {
+    values: Vec<F>,
+    low: Vec<F>,
+    up: Vec<F>,
+}
+
+impl<F: PartialOrd + Copy> BoundedVec<F> {
+    /// Values with a lower and upper bound each
+    ///
+    /// The values themselves may be outside their bounds.
+    pub fn new(values: Vec<F>, low: Vec<F>, up: Vec<F>) -> Result<Self, BoundsError> {
+        if low.len() != values.len() || up.len() != values.len() {
+            return Err(BoundsError::LengthMismatch { values: values.len(), low: low.len(), up: up.len() });
+        }
+        let ordered = |(low, up): (&F, &F)| matches!(low.partial_cmp(up), Some(Ordering::Less | Ordering::Equal));
+        match low.iter().zip(&up).position(|bounds| !ordered(bounds)) {
+            Some(index) => Err(BoundsError::Inverted { index }),
+            None => Ok(BoundedVec { values, low, up }),
+        }
+    }
+
+    /// Values that all share the bounds `[low, up]`
+    pub fn uniform(values: Vec<F>, low: F, up: F) -> Result<Self, BoundsError> {
+        let len = values.len();
+        BoundedVec::new(values, vec![low; len], vec![up; len])
+    }
+
+    pub fn values(&self) -> &[F] {
+        &self.values
+    }
+
+    pub fn low(&self) -> &[F] {
+        &self.low
+    }
+
+    pub fn up(&self) -> &[F] {
+        &self.up
+    }
+
+    /// The bounds of the gene at `idx`, as `(low, up)`
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn bounds(&self, idx: usize) -> (F, F) {
+        (self.low[idx], self.up[idx])
+    }
+
+    pub fn into_values(self) -> Vec<F> {
+        self.values
+    }
+
+    /// Whether every value is within its bounds
+    pub fn in_bounds(&self) -> bool {
+        (0..self.values.len()).all(|i| self.low[i] <= self.values[i] && self.values[i] <= self.up[i])
+    }
+
+    /// Moves every value outside its bounds to the nearest one
+    pub fn clamp_self(&mut self) {
+        for (i, value) in self.values.iter_mut().enumerate() {
+            if *value < self.low[i] {
+                *value = self.low[i];
+            } else if *value > self.up[i] {
+                *value = self.up[i];
+            }
+        }
+    }
+}
+
+impl<F> AsRef<[F]> for BoundedVec<F> {
+    fn as_ref(&self) -> &[F] {
+        &self.values
+    }
+}
+
+impl<F> AsMut<[F]> for BoundedVec<F> {
+    fn as_mut(&mut self) -> &mut [F] {
+        &mut self.values
+    }
+}
+
+impl<F> Genome for BoundedVec<F> {
+    type Gene = F;
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn gene_mut(&mut self, idx: usize) -> &mut F {
+        &mut self.values[idx]
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.values.swap(i, j)
+    }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [F]> {
+        Some(&mut self.values)
+    }
+}
+
+/// A bounded operator that takes the bounds of each gene from the [`BoundedVec`] it's applied to
+///
+/// The operator's own `low` and `up` are ignored. Made by the `own_bounds`
+/// constructors of [`Polynomial`](crate::mutation::Polynomial),
+/// [`Bounded`](crate::mutation::Bounded) and
+/// [`SimulatedBinary`](crate::crossover::SimulatedBinary).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OwnBounds<O> {
+    pub op: O,
+}
+
+/// A `Vec<usize>` that isn't a permutation of `0..len`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermutationError {
+    /// `value` is `len` or more
+    OutOfRange { value: usize, len: usize },
+    /// `value` appears more than once
+    Duplicate { value: usize },
+}
+
+impl fmt::Display for PermutationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PermutationError::OutOfRange { value, len } =>
+                write!(f, "{} is out of range for a permutation of length {}", value, len),
+            PermutationError::Duplicate { value } => write!(f, "{} appears more than once", value),
+        }
+    }
+}
+
+impl std::error::Error for PermutationError {}
+
+/// Each of `0..len` exactly once, in some order
+///
+/// Reads as a `[usize]`, but can only be changed by the operators that keep
+/// it a permutation: [`Shuffle`](crate::mutation::Shuffle),
+/// [`Inversion`](crate::mutation::Inversion),
+/// [`Scramble`](crate::mutation::Scramble),
+/// [`PartiallyMatched`](crate::crossover::PartiallyMatched),
+/// [`Ordered`](crate::crossover::Ordered) and [`Permutation::swap`]. Debug
+/// builds check it's still a permutation after each of them.
+///
+/// # Examples
+/// ```
+/// use dears::genome::*;
+/// use dears::mutation::*;
+/// let mut perm = Permutation::identity(5);
+/// Inversion.mutate(&mut perm);
+/// assert_eq!(perm.len(), 5);
+/// assert!(perm.contains(&4));
+/// assert_eq!(Permutation::try_from(vec![2, 0, 1]).unwrap()[0], 2);
+/// assert_eq!(Permutation::try_from(vec![0, 0]), Err(PermutationError::Duplicate { value: 0 }));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Vec<usize>", into = "Vec<usize>"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Permutation(Vec<usize>);
+
+impl Permutation {
+    /// `0..n` in order
+    pub fn identity(n: usize) -> Self {
+        Permutation((0..n).collect())
+    }
+
+    /// A uniformly random ordering of `0..n`
+    pub fn random(n: usize, rng: &mut dyn RngCore) -> Self {
+        let mut perm = Permutation::identity(n);
+        perm.0.shuffle(rng);
+        perm
+    }
+
+    /// Panics if `i` or `j` is out of bounds
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.0.swap(i, j)
+    }
+
+    pub fn into_inner(self) -> Vec<usize> {
+        self.0
+    }
+
+    /// Rearranges the values with `f`, which must leave them a permutation
+    pub(crate) fn rearrange(&mut self, f: impl FnOnce(&mut Vec<usize>)) {
+        f(&mut self.0);
+        debug_assert!(check_permutation(&self.0).is_ok(), "{:?} is no longer a permutation", self.0);
+    }
+}
+
+fn check_permutation(values: &[usize]) -> Result<(), PermutationError> {
+    let mut seen = vec![false; values.len()];
+    for &value in values {
+        match seen.get_mut(value) {
+            None => return Err(PermutationError::OutOfRange { value, len: values.len() }),
+            Some(true) => return Err(PermutationError::Duplicate { value }),
+            Some(seen) => *seen = true,
+        }
+    }
+    Ok(())
+}
+
+impl TryFrom<Vec<usize>> for Permutation {
+    type Error = PermutationError;
+
+    fn try_from(values: Vec<usize>) -> Result<Self, PermutationError> {
+        check_permutation(&values).map(|_| Permutation(values))
+    }
+}
+
+impl From<Permutation> for Vec<usize> {
+    fn from(perm: Permutation) -> Self {
+        perm.0
+    }
+}
+
+impl Deref for Permutation {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl AsRef<[usize]> for Permutation {
+    fn as_ref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+/// Two different positions below `size`, the smaller first
+///
+/// Panics if `size` is less than 2.
+pub(crate) fn two_points(size: usize, rng: &mut dyn RngCore) -> (usize, usize) {
+    let start = rng.gen_range(0..size);
+    let mut end = rng.gen_range(0..size - 1);
+    if end >= start {
+        end += 1;
+    }
+    (start.min(end), start.max(end))
+}
+
+/// Calls `f` on every gene in order
+pub fn for_each_gene_mut<G, F>(genome: &mut G, mut f: F)
+where
+    G: Genome + ?Sized,
+    F: FnMut(&mut G::Gene)
+{
+    if let Some(genes) = genome.as_mut_slice() {
+        genes.iter_mut().for_each(f);
+        return;
+    }
+    for idx in 0..genome.len() {
+        f(genome.gene_mut(idx));
+    }
+}
+
+/// Calls `f` on the genes of `a` and `b` at each position in order, up to the shorter length
+pub fn for_each_gene_pair_mut<G, F>(a: &mut G, b: &mut G, mut f: F)
+where
+    G: Genome + ?Sized,
+    F: FnMut(&mut G::Gene, &mut G::Gene)
+{
+    let len = a.len().min(b.len());
+    if let (Some(a), Some(b)) = (a.as_mut_slice(), b.as_mut_slice()) {
+        a.iter_mut().zip(b.iter_mut()).for_each(|(x, y)| f(x, y));
+        return;
+    }
+    for idx in 0..len {
+        f(a.gene_mut(idx), b.gene_mut(idx));
+    }
+}
+
+/// Exchanges the genes of `a` and `b` at every position in `range`
+pub fn swap_genes<G: Genome + ?Sized>(a: &mut G, b: &mut G, range: Range<usize>) {
+    if let (Some(a), Some(b)) = (a.as_mut_slice(), b.as_mut_slice()) {
+        a[range.clone()].swap_with_slice(&mut b[range]);
+        return;
+    }
+    for idx in range {
+        std::mem::swap(a.gene_mut(idx), b.gene_mut(idx));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::crossover::*;
+    use crate::genome::*;
+    use crate::mutation::*;
+
+    const SEED: u64 = 193;
+
+    /// The genes after `mutator` runs on them as a `Vec`, an array and a `Box<[T]>`, all from one seed
+    fn mutated<T, M, const N: usize>(genes: [T; N], mutator: &M) -> [Vec<T>; 3]
+    where
+        T: Clone,
+        M: Mutator<Vec<T>> + Mutator<[T; N]> + Mutator<Box<[T]>>
+    {
+        let (mut vec, mut array, mut boxed): (_, _, Box<[T]>) = (genes.to_vec(), genes.clone(), genes.to_vec().into());
+        mutator.mutate_with(&mut vec, &mut StdRng::seed_from_u64(SEED));
+        mutator.mutate_with(&mut array, &mut StdRng::seed_from_u64(SEED));
+        mutator.mutate_with(&mut boxed, &mut StdRng::seed_from_u64(SEED));
+        [vec, array.to_vec(), boxed.into_vec()]
+    }
+
+    /// As [`mutated`], for both children of a crossover
+    fn crossed<T, C, const N: usize>(a: [T; N], b: [T; N], crossover: &C) -> [(Vec<T>, Vec<T>); 3]
+    where
+        T: Clone,
+        C: Crossover<Vec<T>> + Crossover<[T; N]> + Crossover<Box<[T]>>
+    {
+        let mut vecs = (a.to_vec(), b.to_vec());
+        let mut arrays = (a.clone(), b.clone());
+        let mut boxes: (Box<[T]>, Box<[T]>) = (a.to_vec().into(), b.to_vec().into());
+        crossover.crossover_with(&mut vecs.0, &mut vecs.1, &mut StdRng::seed_from_u64(SEED));
+        crossover.crossover_with(&mut arrays.0, &mut arrays.1, &mut StdRng::seed_from_u64(SEED));
+        crossover.crossover_with(&mut boxes.0, &mut boxes.1, &mut StdRng::seed_from_u64(SEED));
+        [vecs, (arrays.0.to_vec(), arrays.1.to_vec()), (boxes.0.into_vec(), boxes.1.into_vec())]
+    }
+
+    /// As [`crossed`], for a `VecDeque`, which only the operators that move genes around accept
+    fn crossed_deques<T: Clone, C: Crossover<VecDeque<T>>>(a: &[T], b: &[T], crossover: &C) -> (Vec<T>, Vec<T>) {
+        let mut deques: (VecDeque<T>, VecDeque<T>) = (a.to_vec().into(), b.to_vec().into());
+        crossover.crossover_with(&mut deques.0, &mut deques.1, &mut StdRng::seed_from_u64(SEED));
+        (deques.0.into(), deques.1.into())
+    }
+
+    /// A genome type of the user's own, which the value operators accept through `AsMut`
+    struct Reals(Vec<f64>);
+
+    impl AsMut<[f64]> for Reals {
+        fn as_mut(&mut self) -> &mut [f64] {
+            &mut self.0
+        }
+    }
+
+    fn all_equal<T: PartialEq + std::fmt::Debug>(results: &[T]) {
+        for result in &results[1..] {
+            assert_eq!(result, &results[0]);
+        }
+    }
+
+    #[test]
+    fn operators_agree_across_genome_types() {
+        let reals = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+        let bits = [true, false, true, true, false, false, true, false];
+        let polynomial = Polynomial { eta: 20.0, low: 0.0, up: 1.0, indpb: 0.5 };
+        let bounded = Bounded { mutator: Gaussian { mu: 0.0, sigma: 1.0, indpb: 0.5 }, low: 0.0, up: 1.0,
+                                boundary: Boundary::Reflect };
+        let shuffle = Shuffle { indpb: 0.5 };
+        for results in [mutated(reals, &polynomial), mutated(reals, &bounded), mutated(reals, &shuffle)] {
+            assert_ne!(results[0], reals);
+            all_equal(&results);
+        }
+        let mut deque = VecDeque::from(reals.to_vec());
+        shuffle.mutate_with(&mut deque, &mut StdRng::seed_from_u64(SEED));
+        assert_eq!(Vec::from(deque), mutated(reals, &shuffle)[0]);
+        let mut own = Reals(reals.to_vec());
+        bounded.mutate_with(&mut own, &mut StdRng::seed_from_u64(SEED));
+        assert_eq!(own.0, mutated(reals, &bounded)[0]);
+        let flipped = mutated(bits, &FlipBit { indpb: 0.5 });
+        assert_ne!(flipped[0], bits);
+        all_equal(&flipped);
+
+        let other = [0.9; 8];
+        let sbx = SimulatedBinary { eta: 15.0, low: 0.0, up: 1.0 };
+        for results in [crossed(reals, other, &OnePoint), crossed(reals, other, &Uniform { indpb: 0.5 }),
+                        crossed(reals, other, &sbx)] {
+            assert_ne!(results[0].0, reals);
+            all_equal(&results);
+        }
+        assert_eq!(crossed_deques(&reals, &other, &OnePoint), crossed(reals, other, &OnePoint)[0]);
+        let uniform = Uniform { indpb: 0.5 };
+        assert_eq!(crossed_deques(&reals, &other, &uniform), crossed(reals, other, &uniform)[0]);
+        let mut own = (Reals(reals.to_vec()), Reals(other.to_vec()));
+        sbx.crossover_with(&mut own.0, &mut own.1, &mut StdRng::seed_from_u64(SEED));
+        assert_eq!((own.0 .0, own.1 .0), crossed(reals, other, &sbx)[0]);
+
+        // A deque that wraps round its buffer has no single slice to hand out
+        let mut deque = VecDeque::from(vec![2, 3]);
+        deque.push_front(1);
+        assert!(deque.as_mut_slice().is_none());
+        for_each_gene_mut(&mut deque, |gene| *gene *= 10);
+        let mut other = VecDeque::from(vec![7; 3]);
+        swap_genes(&mut deque, &mut other, 1..3);
+        assert_eq!((deque, other), (VecDeque::from(vec![10, 7, 7]), VecDeque::from(vec![7, 20, 30])));
+    }
+
+    #[test]
+    fn permutations_reject_invalid_values() {
+        assert_eq!(Permutation::try_from(vec![0, 2, 0]), Err(PermutationError::Duplicate { value: 0 }));
+        assert_eq!(Permutation::try_from(vec![0, 3, 1]), Err(PermutationError::OutOfRange { value: 3, len: 3 }));
+        assert_eq!(Permutation::try_from(vec![]), Ok(Permutation::identity(0)));
+        let perm = Permutation::try_from(vec![1, 2, 0]).unwrap();
+        assert_eq!((&perm[..], Vec::from(perm.clone())), (&[1, 2, 0][..], vec![1, 2, 0]));
+        assert_eq!(Permutation::identity(3).into_inner(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn permutation_operators_keep_permutations() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        for len in [3, 4, 9, 30] {
+            let mut perms: Vec<Permutation> = (0..10).map(|_| Permutation::random(len, &mut rng)).collect();
+            for _ in 0..200 {
+                let (i, j) = (rng.gen_range(0..perms.len()), rng.gen_range(0..perms.len()));
+                let mut b = perms[j].clone();
+                let a = &mut perms[i];
+                match rng.gen_range(0..6) {
+                    0 => Shuffle { indpb: 0.2 }.mutate_with(a, &mut rng),
+                    1 => Inversion.mutate_with(a, &mut rng),
+                    2 => Scramble.mutate_with(a, &mut rng),
+                    3 => PartiallyMatched.crossover_with(a, &mut b, &mut rng),
+                    4 => Ordered.crossover_with(a, &mut b, &mut rng),
+                    _ => a.swap(0, len - 1),
+                }
+                assert!(Permutation::try_from(a.to_vec()).is_ok(), "{:?}", a);
+                assert!(Permutation::try_from(b.to_vec()).is_ok(), "{:?}", b);
+                perms[j] = b;
+            }
+        }
+        // The two children of ordered crossover swap sections and keep their own order elsewhere
+        let (mut a, mut b) = (vec![0, 1, 2, 3, 4, 5, 6], vec![6, 5, 4, 3, 2, 1, 0]);
+        Ordered.crossover_with(&mut a, &mut b, &mut StdRng::seed_from_u64(SEED));
+        let (start, end) = two_points(7, &mut StdRng::seed_from_u64(SEED));
+        assert_eq!(a[start..end], [6, 5, 4, 3, 2, 1, 0][start..end]);
+        assert_eq!(b[start..end], [0, 1, 2, 3, 4, 5, 6][start..end]);
+    }
+
+    #[test]
+    fn bounded_vec_validation() {
+        let inverted = BoundedVec::new(vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.5]);
+        assert_eq!(inverted, Err(BoundsError::Inverted { index: 1 }));
+        assert_eq!(BoundedVec::uniform(vec![0.0], f64::NAN, 1.0), Err(BoundsError::Inverted { index: 0 }));
+        let short = BoundedVec::new(vec![0.0, 0.0], vec![0.0], vec![1.0, 1.0]);
+        assert_eq!(short, Err(BoundsError::LengthMismatch { values: 2, low: 1, up: 2 }));
+
+        let mut genome = BoundedVec::new(vec![-3, 2, 9], vec![0, 0, 5], vec![1, 5, 5]).unwrap();
+        assert!(!genome.in_bounds());
+        genome.clamp_self();
+        assert_eq!(genome.values(), &[0, 2, 5]);
+        assert!(genome.in_bounds());
+    }
+
+    #[test]
+    fn operators_take_bounds_from_the_genome() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        // Gene i is bounded to [10i, 10i + 1], apart from every other gene's bounds
+        let low: Vec<f64> = (0..6).map(|i| i as f64 * 10.0).collect();
+        let up: Vec<f64> = low.iter().map(|low| low + 1.0).collect();
+        let genome = |value: f64| {
+            let values = low.iter().map(|low| low + value).collect();
+            BoundedVec::new(values, low.clone(), up.clone()).unwrap()
+        };
+        let polynomial = Polynomial::own_bounds(5.0, 1.0);
+        let bounded = Bounded::own_bounds(Gaussian { mu: 0.0, sigma: 5.0, indpb: 1.0 }, Boundary::Reflect);
+        let sbx = SimulatedBinary::own_bounds(2.0);
+        let (mut a, mut b) = (genome(0.1), genome(0.9));
+        for _ in 0..50 {
+            polynomial.mutate_with(&mut a, &mut rng);
+            bounded.mutate_with(&mut b, &mut rng);
+            sbx.crossover_with(&mut a, &mut b, &mut rng);
+            assert!(a.in_bounds() && b.in_bounds(), "{:?} {:?}", a, b);
+        }
+        assert_ne!(a.values(), genome(0.1).values());
+
+        // Crossover exchanges values but every genome keeps its own bounds
+        let mut other = BoundedVec::uniform(vec![0.5; 6], 0.0, 1.0).unwrap();
+        OnePoint.crossover_with(&mut a, &mut other, &mut rng);
+        assert_eq!((a.low(), a.up()), (&low[..], &up[..]));
+        assert_eq!((other.low(), other.up()), (&[0.0; 6][..], &[1.0; 6][..]));
+        a.clamp_self();
+        assert!(a.in_bounds());
+    }
+}
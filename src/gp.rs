@@ -0,0 +1,417 @@
+use std::fmt;
+use std::ops::Range;
+
+use rand::{Rng, RngCore};
+
+use crate::crossover::Crossover;
+use crate::initializer::Init;
+use crate::mutation::Mutator;
+
+/// How many times the tree operators try for a child within the [`TreeLimits`] before leaving the genome as it was
+const ATTEMPTS: usize = 10;
+
+/// An arithmetic function that can label an inner node of a [`TreeGenome`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Function {
+    Add,
+    Sub,
+    Mul,
+    /// Division that gives 1 for a zero divisor, so every tree evaluates
+    Div,
+    Neg,
+}
+
+impl Function {
+    /// How many arguments the function takes
+    pub fn arity(self) -> usize {
+        match self {
+            Function::Neg => 1,
+            Function::Add | Function::Sub | Function::Mul | Function::Div => 2,
+        }
+    }
+
+    /// Panics unless there are [`Function::arity`] arguments
+    pub fn apply(self, args: &[f64]) -> f64 {
+        match (self, args) {
+            (Function::Add, &[a, b]) => a + b,
+            (Function::Sub, &[a, b]) => a - b,
+            (Function::Mul, &[a, b]) => a * b,
+            (Function::Div, &[a, b]) => if b == 0.0 { 1.0 } else { a / b },
+            (Function::Neg, &[a]) => -a,
+            _ => panic!("{:?} takes {} arguments, not {}", self, self.arity(), args.len()),
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Function::Add => "+",
+            Function::Sub => "-",
+            Function::Mul => "*",
+            Function::Div => "/",
+            Function::Neg => "-",
+        }
+    }
+}
+
+/// A node of a [`TreeGenome`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Node {
+    Function(Function),
+    /// The input at this index
+    Var(usize),
+    Const(f64),
+}
+
+impl Node {
+    /// How many children the node has, 0 for the terminals
+    pub fn arity(&self) -> usize {
+        match self {
+            Node::Function(function) => function.arity(),
+            Node::Var(_) | Node::Const(_) => 0,
+        }
+    }
+}
+
+/// An expression tree, for genetic programming
+///
+/// The nodes are stored in prefix order, each function followed by its
+/// arguments, so every subtree is a contiguous run of nodes and the operators
+/// can swap or replace one by splicing. The depth of a lone terminal is 0.
+///
+/// # Examples
+/// ```
+/// use dears::gp::*;
+/// // x0 * x0 + x0
+/// let tree = TreeGenome::new(vec![
+///     Node::Function(Function::Add),
+///     Node::Function(Function::Mul), Node::Var(0), Node::Var(0),
+///     Node::Var(0),
+/// ]).unwrap();
+/// assert_eq!(tree.eval(&[3.0]), 12.0);
+/// assert_eq!((tree.len(), tree.depth()), (5, 2));
+/// assert_eq!(tree.subtree(1), 1..4);
+/// assert_eq!(tree.to_string(), "((x0 * x0) + x0)");
+/// assert!(TreeGenome::new(vec![Node::Function(Function::Add), Node::Var(0)]).is_none());
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeGenome {
+    nodes: Vec<Node>,
+}
+
+impl TreeGenome {
+    /// The tree with these nodes in prefix order, if they make exactly one tree
+    pub fn new(nodes: Vec<Node>) -> Option<Self> {
+        let mut open = 1usize;
+        for node in &nodes {
+            open = open.checked_sub(1)? + node.arity();
+        }
+        (open == 0).then_some(TreeGenome { nodes })
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The most edges from the root to any leaf
+    pub fn depth(&self) -> usize {
+        depth(&self.nodes)
+    }
+
+    /// The nodes of the subtree rooted at `idx`
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn subtree(&self, idx: usize) -> Range<usize> {
+        subtree(&self.nodes, idx)
+    }
+
+    /// The value of the expression with its variables set to `inputs`
+    ///
+    /// Panics if a variable's index is out of bounds of `inputs`.
+    pub fn eval(&self, inputs: &[f64]) -> f64 {
+        let mut stack = Vec::new();
+        // Backwards, every function's arguments are on the stack by the time it's reached
+        for node in self.nodes.iter().rev() {
+            let value = match *node {
+                Node::Var(idx) => inputs[idx],
+                Node::Const(value) => value,
+                Node::Function(function) => {
+                    let args: Vec<f64> = (0..function.arity()).map(|_| stack.pop().unwrap()).collect();
+                    function.apply(&args)
+                }
+            };
+            stack.push(value);
+        }
+        stack.pop().unwrap()
+    }
+
+    /// Replaces the subtree at `range` with `nodes`, which must make one tree
+    fn splice(&mut self, range: Range<usize>, nodes: &[Node]) {
+        self.nodes.splice(range, nodes.iter().copied());
+    }
+}
+
+impl fmt::Display for TreeGenome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write(nodes: &[Node], idx: usize, f: &mut fmt::Formatter<'_>) -> Result<usize, fmt::Error> {
+            match nodes[idx] {
+                Node::Var(input) => write!(f, "x{}", input).map(|_| idx + 1),
+                Node::Const(value) => write!(f, "{}", value).map(|_| idx + 1),
+                Node::Function(Function::Neg) => {
+                    write!(f, "-")?;
+                    write(nodes, idx + 1, f)
+                }
+                Node::Function(function) => {
+                    write!(f, "(")?;
+                    let next = write(nodes, idx + 1, f)?;
+                    write!(f, " {} ", function.symbol())?;
+                    let next = write(nodes, next, f)?;
+                    write!(f, ")").map(|_| next)
+                }
+            }
+        }
+        write(&self.nodes, 0, f).map(|_| ())
+    }
+}
+
+fn depth(nodes: &[Node]) -> usize {
+    let mut pending = vec![0];
+    let mut deepest = 0;
+    for node in nodes {
+        let depth = pending.pop().unwrap_or(0);
+        deepest = deepest.max(depth);
+        pending.extend(std::iter::repeat_n(depth + 1, node.arity()));
+    }
+    deepest
+}
+
+fn subtree(nodes: &[Node], idx: usize) -> Range<usize> {
+    let mut open = 1;
+    let mut end = idx;
+    while open > 0 {
+        open = open - 1 + nodes[end].arity();
+        end += 1;
+    }
+    idx..end
+}
+
+/// The functions and terminals trees are built from
+///
+/// The terminals are a variable for each of `inputs`, and the `constants`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrimitiveSet {
+    pub functions: Vec<Function>,
+    pub inputs: usize,
+    pub constants: Vec<f64>,
+}
+
+impl PrimitiveSet {
+    /// Every [`Function`], with `inputs` variables and the constant 1
+    pub fn arithmetic(inputs: usize) -> Self {
+        let functions = vec![Function::Add, Function::Sub, Function::Mul, Function::Div, Function::Neg];
+        PrimitiveSet { functions, inputs, constants: vec![1.0] }
+    }
+
+    fn terminals(&self) -> usize {
+        self.inputs + self.constants.len()
+    }
+
+    fn terminal(&self, rng: &mut dyn RngCore) -> Node {
+        let idx = rng.gen_range(0..self.terminals());
+        match idx.checked_sub(self.inputs) {
+            Some(constant) => Node::Const(self.constants[constant]),
+            None => Node::Var(idx),
+        }
+    }
+
+    /// A random tree of depth `depth`, with every leaf at that depth if `full`
+    ///
+    /// Otherwise each node above that depth is drawn from all the primitives
+    /// alike, so branches can stop early. Panics if there are no terminals, or
+    /// no functions and `depth` isn't 0.
+    pub fn generate(&self, depth: usize, full: bool, rng: &mut dyn RngCore) -> TreeGenome {
+        assert!(self.terminals() > 0, "A primitive set needs at least one terminal");
+        assert!(depth == 0 || !self.functions.is_empty(), "Trees deeper than 0 need at least one function");
+        let mut nodes = Vec::new();
+        let mut pending = vec![0];
+        while let Some(level) = pending.pop() {
+            let pick_function = level < depth
+                && (full || rng.gen_range(0..self.functions.len() + self.terminals()) < self.functions.len());
+            let node = match pick_function {
+                true => Node::Function(self.functions[rng.gen_range(0..self.functions.len())]),
+                false => self.terminal(rng),
+            };
+            pending.extend(std::iter::repeat_n(level + 1, node.arity()));
+            nodes.push(node);
+        }
+        TreeGenome { nodes }
+    }
+}
+
+/// The largest trees the operators will make
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeLimits {
+    pub max_depth: usize,
+    pub max_len: usize,
+}
+
+impl TreeLimits {
+    pub fn allow(&self, nodes: &[Node]) -> bool {
+        nodes.len() <= self.max_len && depth(nodes) <= self.max_depth
+    }
+}
+
+impl Default for TreeLimits {
+    /// Koza's depth limit of 17
+    fn default() -> Self {
+        TreeLimits { max_depth: 17, max_len: usize::MAX }
+    }
+}
+
+/// Koza's ramped half-and-half: trees of every depth from `min_depth` to `max_depth`, half full and half grown
+///
+/// # Examples
+/// ```
+/// use dears::gp::*;
+/// use dears::initializer::*;
+/// let init = RampedHalfAndHalf { pset: PrimitiveSet::arithmetic(1), min_depth: 1, max_depth: 4 };
+/// let trees = init_population(50, &init, &mut rand::thread_rng());
+/// assert!(trees.iter().all(|tree| tree.depth() <= 4));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RampedHalfAndHalf {
+    pub pset: PrimitiveSet,
+    pub min_depth: usize,
+    pub max_depth: usize,
+}
+
+impl Init<TreeGenome> for RampedHalfAndHalf {
+    fn init_with(&self, rng: &mut dyn RngCore) -> TreeGenome {
+        let depth = rng.gen_range(self.min_depth..=self.max_depth);
+        self.pset.generate(depth, rng.gen(), rng)
+    }
+}
+
+/// Replaces a random subtree with a new grown tree of depth up to `subtree_depth`
+///
+/// A mutant outside the `limits` is drawn again, up to ten times, and if
+/// none fit the genome is left as it was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtreeMutation {
+    pub pset: PrimitiveSet,
+    pub subtree_depth: usize,
+    pub limits: TreeLimits,
+}
+
+impl Mutator<TreeGenome> for SubtreeMutation {
+    fn mutate_with(&self, genome: &mut TreeGenome, rng: &mut dyn RngCore) {
+        for _ in 0..ATTEMPTS {
+            let range = genome.subtree(rng.gen_range(0..genome.len()));
+            let depth = rng.gen_range(0..=self.subtree_depth);
+            let replacement = self.pset.generate(depth, false, rng);
+            let mut mutant = genome.clone();
+            mutant.splice(range, &replacement.nodes);
+            if self.limits.allow(&mutant.nodes) {
+                *genome = mutant;
+                return;
+            }
+        }
+    }
+}
+
+/// Swaps a random subtree of each parent
+///
+/// A pair of children outside the `limits` is drawn again, up to ten times,
+/// and if none fit the parents are left as they were.
+///
+/// # Examples
+/// ```
+/// use dears::crossover::Crossover;
+/// use dears::gp::*;
+/// use dears::initializer::Init;
+/// let init = RampedHalfAndHalf { pset: PrimitiveSet::arithmetic(2), min_depth: 2, max_depth: 4 };
+/// let (mut a, mut b) = (init.init(), init.init());
+/// let limits = TreeLimits { max_depth: 5, max_len: 40 };
+/// SubtreeCrossover { limits }.crossover(&mut a, &mut b);
+/// assert!(limits.allow(a.nodes()) && limits.allow(b.nodes()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SubtreeCrossover {
+    pub limits: TreeLimits,
+}
+
+impl Crossover<TreeGenome> for SubtreeCrossover {
+    fn crossover_with(&self, a: &mut TreeGenome, b: &mut TreeGenome, rng: &mut dyn RngCore) {
+        for _ in 0..ATTEMPTS {
+            let range_a = a.subtree(rng.gen_range(0..a.len()));
+            let range_b = b.subtree(rng.gen_range(0..b.len()));
+            let (mut child_a, mut child_b) = (a.clone(), b.clone());
+            child_a.splice(range_a.clone(), &b.nodes[range_b.clone()]);
+            child_b.splice(range_b, &a.nodes[range_a]);
+            if self.limits.allow(&child_a.nodes) && self.limits.allow(&child_b.nodes) {
+                (*a, *b) = (child_a, child_b);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::gp::*;
+    use crate::initializer::init_population;
+
+    #[test]
+    fn operators_keep_trees_whole_and_within_limits() {
+        let mut rng = StdRng::seed_from_u64(202);
+        let pset = PrimitiveSet::arithmetic(2);
+        let init = RampedHalfAndHalf { pset: pset.clone(), min_depth: 1, max_depth: 4 };
+        let mut trees = init_population(100, &init, &mut rng);
+        let depths: Vec<usize> = trees.iter().map(TreeGenome::depth).collect();
+        assert!((1..=4).all(|d| depths.contains(&d)));
+        assert_eq!(pset.generate(3, true, &mut rng).depth(), 3);
+
+        let limits = TreeLimits { max_depth: 6, max_len: 40 };
+        let mutation = SubtreeMutation { pset, subtree_depth: 3, limits };
+        let crossover = SubtreeCrossover { limits };
+        for _ in 0..20 {
+            for pair in trees.chunks_mut(2) {
+                if let [a, b] = pair {
+                    crossover.crossover_with(a, b, &mut rng);
+                }
+            }
+            for tree in &mut trees {
+                mutation.mutate_with(tree, &mut rng);
+            }
+        }
+        assert!(trees.iter().any(|tree| tree.depth() > 4));
+        for tree in &trees {
+            assert_eq!(TreeGenome::new(tree.nodes().to_vec()).as_ref(), Some(tree));
+            assert!(limits.allow(tree.nodes()), "{} is too big", tree);
+            assert!(tree.eval(&[0.5, -2.0]).is_finite());
+        }
+    }
+
+    #[test]
+    fn protected_division() {
+        let tree = TreeGenome::new(vec![Node::Function(Function::Div), Node::Var(0), Node::Const(0.0)]).unwrap();
+        assert_eq!(tree.eval(&[5.0]), 1.0);
+        let neg = TreeGenome::new(vec![Node::Function(Function::Neg), Node::Const(2.5)]).unwrap();
+        assert_eq!((neg.eval(&[]), neg.to_string()), (-2.5, "-2.5".to_string()));
+    }
+}
@@ -0,0 +1,235 @@
+use rand::RngCore;
+
+use crate::algorithms::{EvolutionObserver, Offspring};
+use crate::crossover::Crossover;
+use crate::mutation::Mutator;
+use crate::population::Population;
+use crate::selection::{Direction, SelectMany};
+use crate::stats::mean;
+
+/// How the fitness samples of an individual are combined into one fitness
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Mean,
+    Median,
+    /// The best sample in the direction given
+    Best(Direction),
+    /// The mean of the last `k` samples
+    RecentMean(usize),
+}
+
+impl Aggregate {
+    /// Combines `samples`, oldest first, into one fitness, NaN if there are none
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::history::Aggregate;
+    /// use dears::selection::Direction;
+    /// let samples = [4.0, 1.0, 2.0, 5.0];
+    /// assert_eq!(Aggregate::Mean.apply(&samples), 3.0);
+    /// assert_eq!(Aggregate::Median.apply(&samples), 3.0);
+    /// assert_eq!(Aggregate::Best(Direction::Minimize).apply(&samples), 1.0);
+    /// assert_eq!(Aggregate::RecentMean(2).apply(&samples), 3.5);
+    /// ```
+    pub fn apply(&self, samples: &[f64]) -> f64 {
+        match *self {
+            Aggregate::Mean => mean(samples),
+            Aggregate::Median => {
+                let mut sorted = samples.to_vec();
+                sorted.sort_by(f64::total_cmp);
+                match sorted.len() {
+                    0 => f64::NAN,
+                    n if n % 2 == 1 => sorted[n / 2],
+                    n => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+                }
+            }
+            Aggregate::Best(direction) => samples.iter().copied()
+                .max_by(|a, b| direction.compare(a, b))
+                .unwrap_or(f64::NAN),
+            Aggregate::RecentMean(k) => mean(&samples[samples.len().saturating_sub(k)..]),
+        }
+    }
+}
+
+/// Every fitness sample of each individual, for noisy fitness functions
+///
+/// As an [`EvolutionObserver`], it records each new fitness of the
+/// population as a sample of that individual, then replaces the fitness with
+/// the [aggregate](Aggregate) of all its samples so far, which selection and
+/// the statistics then see. The hall of fame is offered each sample as it's
+/// evaluated. Samples are kept by position in the population: a child that
+/// passes into the next generation unchanged keeps its parent's samples, and
+/// any other child starts with none.
+///
+/// An unchanged individual is normally never evaluated again, so only ever
+/// has one sample. [`FitnessHistory::with_reevaluated_elites`] evaluates
+/// every one of them afresh each generation, the elites included, so their
+/// samples build up.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{evolve_with_hooks, Elitism};
+/// use dears::crossover::OnePoint;
+/// use dears::history::*;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::{Direction, TournamentSelection};
+/// use dears::termination::MaxGenerations;
+/// use rand::Rng;
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(10, || vec![false; 8])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap();
+/// let noisy = |g: &Vec<bool>| g.iter().filter(|&&b| b).count() as f64 + rand::thread_rng().gen_range(-1.0..1.0);
+/// let mut history = FitnessHistory::new(Aggregate::Mean).with_reevaluated_elites();
+/// let elitism = Elitism::new(2, Direction::Maximize);
+/// evolve_with_hooks(&mut pop, MaxGenerations(5), 0.5, 0.2, elitism, noisy, None, None, &mut history,
+///                   &mut rand::thread_rng());
+/// assert!(history.sample_counts().iter().any(|&n| n > 1));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitnessHistory {
+    aggregate: Aggregate,
+    reevaluate_elites: bool,
+    samples: Vec<Vec<f64>>,
+    /// Whether the fitness at each position is a new sample still to be recorded
+    pending: Vec<bool>,
+}
+
+impl FitnessHistory {
+    pub fn new(aggregate: Aggregate) -> Self {
+        FitnessHistory { aggregate, reevaluate_elites: false, samples: Vec::new(), pending: Vec::new() }
+    }
+
+    /// Evaluates every individual that passes into the next generation unchanged again
+    pub fn with_reevaluated_elites(mut self) -> Self {
+        self.reevaluate_elites = true;
+        self
+    }
+
+    /// The samples of the individual at `idx`, oldest first
+    pub fn samples(&self, idx: usize) -> &[f64] {
+        self.samples.get(idx).map_or(&[], Vec::as_slice)
+    }
+
+    /// How many samples each individual has
+    pub fn sample_counts(&self) -> Vec<usize> {
+        self.samples.iter().map(Vec::len).collect()
+    }
+}
+
+impl<G, M, C, S> EvolutionObserver<G, M, C, S, f64> for FitnessHistory
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<f64>
+{
+    fn on_offspring(&mut self, offspring: &mut Offspring<G>, _rng: &mut dyn RngCore) {
+        if self.reevaluate_elites {
+            offspring.changed.fill(true);
+        }
+        self.samples = offspring.parents.iter().zip(&offspring.crossed).zip(&offspring.mutated)
+            .map(|((&parent, &crossed), &mutated)| match crossed || mutated {
+                true => Vec::new(),
+                false => self.samples.get(parent).cloned().unwrap_or_default(),
+            })
+            .collect();
+        self.pending = offspring.changed.clone();
+    }
+
+    fn on_evaluated(&mut self, pop: &mut Population<G, M, C, S, f64>) {
+        // Individuals the history hasn't seen yet, such as the whole initial population, are new samples too
+        self.samples.resize(pop.len(), Vec::new());
+        self.pending.resize(pop.len(), true);
+        // Individuals a budget left unevaluated still hold a stale fitness, not a sample
+        let invalid = pop.invalid_indices();
+        for (idx, fitness) in pop.fitnesses_mut().iter_mut().enumerate() {
+            if invalid.binary_search(&idx).is_err() && std::mem::take(&mut self.pending[idx]) {
+                self.samples[idx].push(*fitness);
+                *fitness = self.aggregate.apply(&self.samples[idx]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use rand_distr::{Distribution, Normal};
+
+    use crate::algorithms::{evolve_with_hooks, Elitism};
+    use crate::history::*;
+    use crate::population::PopulationBuilder;
+    use crate::selection::TournamentSelection;
+    use crate::termination::MaxGenerations;
+
+    /// Each genome is its own true fitness, and is never varied
+    struct Fixed;
+
+    impl Mutator<f64> for Fixed {
+        fn mutate_with(&self, _genome: &mut f64, _rng: &mut dyn RngCore) {}
+    }
+
+    impl Crossover<f64> for Fixed {
+        fn crossover_with(&self, _a: &mut f64, _b: &mut f64, _rng: &mut dyn RngCore) {}
+    }
+
+    /// The mean absolute error of each fitness from its genome's true value, after `gens` generations
+    ///
+    /// Every individual is an elite, so each keeps its own samples with no
+    /// selection to favour the lucky ones.
+    fn error_after(gens: usize, aggregate: Aggregate, seed: u64) -> f64 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut pop = PopulationBuilder::new()
+            .init_from(20, &|rng: &mut dyn RngCore| rng.gen_range(0.0..1.0), &mut rng)
+            .mutator(Fixed)
+            .crossover(Fixed)
+            .selector(TournamentSelection::new(2).unwrap())
+            .build()
+            .unwrap();
+        let noise = Normal::new(0.0, 1.0).unwrap();
+        let noise_rng = std::cell::RefCell::new(StdRng::seed_from_u64(seed + 1));
+        let noisy = |&x: &f64| x + noise.sample(&mut *noise_rng.borrow_mut());
+        let mut history = FitnessHistory::new(aggregate).with_reevaluated_elites();
+        let elitism = Elitism::new(20, Direction::Maximize);
+        evolve_with_hooks(&mut pop, MaxGenerations(gens), 0.0, 0.0, elitism, noisy, None, None,
+                          &mut history, &mut rng);
+        assert_eq!(history.sample_counts(), vec![gens + 1; 20]);
+        pop.individuals().iter().zip(pop.fitnesses()).map(|(x, f)| (f - x).abs()).sum::<f64>() / pop.len() as f64
+    }
+
+    #[test]
+    fn aggregated_fitness_converges_on_the_true_mean() {
+        let single = error_after(100, Aggregate::RecentMean(1), 199);
+        let few = error_after(4, Aggregate::Mean, 199);
+        let many = error_after(100, Aggregate::Mean, 199);
+        // Standard normal noise averages out to sigma / sqrt(n) times sqrt(2 / pi)
+        assert!(single > 0.5, "single sample error {}", single);
+        assert!(many < 0.2, "100 sample error {}", many);
+        assert!(many < few && few < single);
+        assert!(error_after(100, Aggregate::Median, 199) < 0.25);
+    }
+
+    #[test]
+    fn samples_follow_their_individuals() {
+        let mut history = FitnessHistory::new(Aggregate::Mean);
+        history.samples = vec![vec![1.0], vec![2.0, 4.0], vec![5.0]];
+        let mut offspring = Offspring {
+            genomes: vec![0.0; 3],
+            changed: vec![false, true, false],
+            crossed: vec![false, true, false],
+            mutated: vec![false; 3],
+            parents: vec![1, 0, 1],
+            mates: vec![None, Some(2), None],
+        };
+        EvolutionObserver::<f64, Fixed, Fixed, TournamentSelection, f64>::on_offspring(
+            &mut history, &mut offspring, &mut StdRng::seed_from_u64(199));
+        assert_eq!(history.sample_counts(), vec![2, 0, 2]);
+        assert_eq!(history.samples(2), &[2.0, 4.0]);
+        assert_eq!(history.pending, vec![false, true, false]);
+    }
+}
@@ -0,0 +1,334 @@
+use rand::RngCore;
+
+use crate::crossover::{Crossover, PartiallyMatched, Uniform};
+use crate::initializer::{Init, RandomBits, RandomPermutation, UniformFloat};
+use crate::mutation::{FlipBit, Gaussian, Mutator, Shuffle};
+
+/// A genome that knows how to vary itself and keeps its own fitness
+///
+/// An alternative to pairing plain genomes with operators, for types that
+/// already carry their variation logic. Such types draw their own randomness,
+/// so runs over them can't be reproduced from a seed.
+///
+/// Everything but [`Individual::crossover`] can be called through a trait
+/// object.
+pub trait Individual {
+    type Fitness;
+
+    fn mutate(&mut self);
+
+    fn crossover(&mut self, other: &mut Self)
+    where
+        Self: Sized;
+
+    /// The stored fitness, or `None` if it hasn't been set since the last change
+    fn fitness(&self) -> Option<&Self::Fitness>;
+
+    fn set_fitness(&mut self, f: Self::Fitness);
+
+    fn invalidate(&mut self);
+
+    /// A copy to vary into a child, with no fitness until it's evaluated
+    ///
+    /// Lamarckian local search must not spawn: it improves the individual
+    /// itself and keeps the fitness it found, which a spawned copy would lose.
+    fn spawn(&self) -> Self
+    where
+        Self: Clone
+    {
+        let mut child = self.clone();
+        child.invalidate();
+        child
+    }
+}
+
+/// Mutation and crossover that hand over to the [`Individual`]'s own methods
+///
+/// Lets an [`Individual`] be the genome of a
+/// [`Population`](crate::population::Population). Varied individuals have
+/// their stored fitness invalidated, and the generator given is never used.
+///
+/// # Examples
+/// ```
+/// use dears::individual::*;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::TournamentSelection;
+///
+/// struct Counter { n: u32, fitness: Option<f64> }
+///
+/// impl Individual for Counter {
+///     type Fitness = f64;
+///     fn mutate(&mut self) { self.n += 1; }
+///     fn crossover(&mut self, other: &mut Self) { std::mem::swap(&mut self.n, &mut other.n); }
+///     fn fitness(&self) -> Option<&f64> { self.fitness.as_ref() }
+///     fn set_fitness(&mut self, f: f64) { self.fitness = Some(f); }
+///     fn invalidate(&mut self) { self.fitness = None; }
+/// }
+///
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(4, || Counter { n: 0, fitness: None })
+///     .mutator(Delegate)
+///     .crossover(Delegate)
+///     .selector(TournamentSelection::new(2).unwrap())
+///     .build()
+///     .unwrap();
+/// pop.evaluate(own_fitness(|c: &Counter| c.n as f64));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Delegate;
+
+impl<I: Individual> Mutator<I> for Delegate {
+    fn mutate_with(&self, genome: &mut I, _rng: &mut dyn RngCore) {
+        genome.mutate();
+        genome.invalidate();
+    }
+}
+
+impl<I: Individual> Crossover<I> for Delegate {
+    fn crossover_with(&self, a: &mut I, b: &mut I, _rng: &mut dyn RngCore) {
+        a.crossover(b);
+        a.invalidate();
+        b.invalidate();
+    }
+}
+
+/// A fitness function for a population of [`Individual`]s that uses their
+/// stored fitness when they have one, and `eval` otherwise
+pub fn own_fitness<I, E>(eval: E) -> impl Fn(&I) -> I::Fitness
+where
+    I: Individual + ?Sized,
+    I::Fitness: Clone,
+    E: Fn(&I) -> I::Fitness
+{
+    move |individual| individual.fitness().cloned().unwrap_or_else(|| eval(individual))
+}
+
+/// A genome of real, binary and permutation parts, for mixed-integer problems
+///
+/// Each part is varied by operators suited to its encoding:
+/// [`MixedMutation`] and [`MixedCrossover`], with [`MixedInit`] to create
+/// genomes. They also show how to write operators for a struct genome by
+/// hand, where `#[derive(CrossoverFields)]` can't express a field's operator.
+///
+/// # Examples
+/// ```
+/// use dears::crossover::*;
+/// use dears::individual::*;
+/// use dears::initializer::*;
+/// use dears::mutation::*;
+/// let init = MixedInit {
+///     reals: UniformFloat { low: -1.0, up: 1.0, len: 3 },
+///     bits: RandomBits { len: 4, p_true: 0.5 },
+///     perm: RandomPermutation { len: 5 },
+/// };
+/// let (mut a, mut b): (MixedGenome, MixedGenome) = (init.init(), init.init());
+/// let crossover = MixedCrossover {
+///     reals: Uniform { indpb: 0.5 },
+///     bits: Uniform { indpb: 0.5 },
+///     perm: PartiallyMatched,
+/// };
+/// crossover.crossover(&mut a, &mut b);
+/// a.perm.sort();
+/// assert_eq!(a.perm, vec![0, 1, 2, 3, 4]);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MixedGenome {
+    pub reals: Vec<f64>,
+    pub bits: Vec<bool>,
+    /// A permutation of `0..perm.len()`
+    pub perm: Vec<usize>,
+}
+
+/// Mutates each part of a [`MixedGenome`] with its own operator
+pub struct MixedMutation {
+    pub reals: Gaussian,
+    pub bits: FlipBit,
+    pub perm: Shuffle,
+}
+
+impl Mutator<MixedGenome> for MixedMutation {
+    fn mutate_with(&self, genome: &mut MixedGenome, rng: &mut dyn RngCore) {
+        self.reals.mutate_with(&mut genome.reals, rng);
+        self.bits.mutate_with(&mut genome.bits, rng);
+        self.perm.mutate_with(&mut genome.perm, rng);
+    }
+}
+
+/// Crosses each part of a [`MixedGenome`] with the same part of the other parent
+pub struct MixedCrossover {
+    pub reals: Uniform,
+    pub bits: Uniform,
+    pub perm: PartiallyMatched,
+}
+
+impl Crossover<MixedGenome> for MixedCrossover {
+    fn crossover_with(&self, a: &mut MixedGenome, b: &mut MixedGenome, rng: &mut dyn RngCore) {
+        self.reals.crossover_with(&mut a.reals, &mut b.reals, rng);
+        self.bits.crossover_with(&mut a.bits, &mut b.bits, rng);
+        self.perm.crossover_with(&mut a.perm, &mut b.perm, rng);
+    }
+}
+
+/// Creates each part of a [`MixedGenome`] with its own initializer
+pub struct MixedInit {
+    pub reals: UniformFloat,
+    pub bits: RandomBits,
+    pub perm: RandomPermutation,
+}
+
+impl Init<MixedGenome> for MixedInit {
+    fn init_with(&self, rng: &mut dyn RngCore) -> MixedGenome {
+        MixedGenome {
+            reals: self.reals.init_with(rng),
+            bits: self.bits.init_with(rng),
+            perm: self.perm.init_with(rng),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::algorithms::{ea_simple, Elitism};
+    use crate::individual::*;
+    use crate::population::PopulationBuilder;
+    use crate::selection::{Direction, TournamentSelection};
+    use crate::termination::MaxGenerations;
+
+    /// Steps along a line, scored by how far it got
+    #[derive(Debug, Clone, PartialEq)]
+    struct Walker {
+        position: i32,
+        fitness: Option<f64>,
+    }
+
+    impl Individual for Walker {
+        type Fitness = f64;
+
+        fn mutate(&mut self) {
+            self.position += 1;
+        }
+
+        fn crossover(&mut self, other: &mut Self) {
+            let mean = (self.position + other.position) / 2;
+            self.position = mean;
+            other.position = mean;
+        }
+
+        fn fitness(&self) -> Option<&f64> {
+            self.fitness.as_ref()
+        }
+
+        fn set_fitness(&mut self, f: f64) {
+            self.fitness = Some(f);
+        }
+
+        fn invalidate(&mut self) {
+            self.fitness = None;
+        }
+    }
+
+    fn walker(position: i32) -> Walker {
+        Walker { position, fitness: None }
+    }
+
+    #[test]
+    fn cycles_through_trait_objects() {
+        let distance = |w: &dyn Individual<Fitness = f64>| w.fitness().copied().unwrap_or(-1.0);
+        let mut walkers: Vec<Box<dyn Individual<Fitness = f64>>> = vec![Box::new(walker(0)), Box::new(walker(3))];
+        for (i, w) in walkers.iter_mut().enumerate() {
+            w.set_fitness(i as f64);
+            w.mutate();
+        }
+        assert_eq!(walkers.iter().map(|w| distance(w.as_ref())).collect::<Vec<_>>(), vec![0.0, 1.0]);
+        walkers[0].invalidate();
+        assert!(walkers[0].fitness().is_none());
+
+        let (mut a, mut b) = (walker(2), walker(6));
+        a.set_fitness(2.0);
+        Delegate.crossover_with(&mut a, &mut b, &mut StdRng::seed_from_u64(183));
+        assert_eq!((a.clone(), b), (walker(4), walker(4)));
+        let eval = own_fitness(|w: &Walker| w.position as f64);
+        assert_eq!(eval(&a), 4.0);
+        a.set_fitness(10.0);
+        assert_eq!(eval(&a), 10.0);
+    }
+
+    #[test]
+    fn spawned_children_start_unevaluated() {
+        let mut parent = walker(3);
+        parent.set_fitness(3.0);
+        let mut child = parent.spawn();
+        assert_eq!((child.position, child.fitness()), (3, None));
+        assert_eq!(parent.fitness(), Some(&3.0));
+        child.mutate();
+        assert_eq!((parent.position, child.position), (3, 4));
+    }
+
+    #[test]
+    fn evolves_as_a_population() {
+        let mut pop = PopulationBuilder::new()
+            .init_with(10, || walker(0))
+            .mutator(Delegate)
+            .crossover(Delegate)
+            .selector(TournamentSelection::new(3).unwrap())
+            .build()
+            .unwrap();
+        let eval = own_fitness(|w: &Walker| w.position as f64);
+        let mut rng = StdRng::seed_from_u64(183);
+        ea_simple(&mut pop, MaxGenerations(10), 0.5, 0.5, Elitism::default(), eval, None, None, &mut rng);
+        assert!(pop.individuals().iter().any(|w| w.position > 0));
+        for (w, &f) in pop.individuals().iter().zip(pop.fitnesses()) {
+            assert_eq!(f, w.position as f64);
+        }
+    }
+
+    #[test]
+    fn mixed_genomes_stay_valid_for_each_encoding() {
+        let mut rng = StdRng::seed_from_u64(197);
+        let init = MixedInit {
+            reals: UniformFloat { low: 0.0, up: 1.0, len: 4 },
+            bits: RandomBits { len: 6, p_true: 0.5 },
+            perm: RandomPermutation { len: 5 },
+        };
+        let mut pop = PopulationBuilder::new()
+            .init_from(20, &init, &mut rng)
+            .mutator(MixedMutation {
+                reals: Gaussian { mu: 0.0, sigma: 0.1, indpb: 0.5 },
+                bits: FlipBit { indpb: 0.2 },
+                perm: Shuffle { indpb: 0.2 },
+            })
+            .crossover(MixedCrossover {
+                reals: Uniform { indpb: 0.5 },
+                bits: Uniform { indpb: 0.5 },
+                perm: PartiallyMatched,
+            })
+            .selector(TournamentSelection::new(3).unwrap())
+            .build()
+            .unwrap();
+        // Reals near 0.5, as many bits set as possible, and the permutation sorted
+        let eval = |g: &MixedGenome| {
+            let reals: f64 = g.reals.iter().map(|x| (x - 0.5).powi(2)).sum();
+            let bits = g.bits.iter().filter(|&&bit| bit).count() as f64;
+            let sorted = g.perm.iter().enumerate().filter(|&(i, &v)| i == v).count() as f64;
+            bits + sorted - reals
+        };
+        let elitism = Elitism::new(1, Direction::Maximize);
+        let mut best = Vec::new();
+        for gen in 1..=30 {
+            ea_simple(&mut pop, MaxGenerations(gen), 0.6, 0.4, elitism, eval, None, None, &mut rng);
+            for genome in pop.individuals() {
+                assert_eq!((genome.reals.len(), genome.bits.len()), (4, 6));
+                assert!(genome.reals.iter().all(|x| x.is_finite()));
+                let mut perm = genome.perm.clone();
+                perm.sort();
+                assert_eq!(perm, vec![0, 1, 2, 3, 4]);
+            }
+            best.push(pop.fitnesses().iter().copied().fold(f64::MIN, f64::max));
+        }
+        assert!(best[29] > best[0], "best went from {} to {}", best[0], best[29]);
+    }
+}
@@ -0,0 +1,253 @@
+use std::marker::PhantomData;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use rand_distr::Distribution;
+
+use crate::genome::Permutation;
+
+/// Creates random genomes, for the initial population or to reset an individual
+///
+/// Implementors draw all their randomness from the `rng` given to
+/// [`Init::init_with`]; [`Init::init`] uses the thread-local generator.
+/// Closures taking a generator are initializers too.
+///
+/// # Examples
+/// ```
+/// use dears::initializer::Init;
+/// use rand::{Rng, RngCore};
+/// let dice = |rng: &mut dyn RngCore| rng.gen_range(1..=6u8);
+/// assert!((1..=6).contains(&dice.init()));
+/// ```
+pub trait Init<G> {
+    fn init_with(&self, rng: &mut dyn RngCore) -> G;
+
+    fn init(&self) -> G {
+        self.init_with(&mut rand::thread_rng())
+    }
+}
+
+impl<G, T: Fn(&mut dyn RngCore) -> G> Init<G> for T {
+    fn init_with(&self, rng: &mut dyn RngCore) -> G {
+        self(rng)
+    }
+}
+
+/// Creates `n` genomes from `init`
+///
+/// # Examples
+/// ```
+/// use dears::initializer::*;
+/// let genomes = init_population(10, &RandomBits { len: 8, p_true: 0.5 }, &mut rand::thread_rng());
+/// assert_eq!(genomes.len(), 10);
+/// ```
+pub fn init_population<G>(n: usize, init: &impl Init<G>, rng: &mut dyn RngCore) -> Vec<G> {
+    (0..n).map(|_| init.init_with(rng)).collect()
+}
+
+/// `len` floats drawn uniformly from `[low, up)`
+pub struct UniformFloat {
+    pub low: f64,
+    pub up: f64,
+    pub len: usize,
+}
+
+impl Init<Vec<f64>> for UniformFloat {
+    fn init_with(&self, rng: &mut dyn RngCore) -> Vec<f64> {
+        (0..self.len).map(|_| self.low + (self.up - self.low) * rng.gen::<f64>()).collect()
+    }
+}
+
+/// `len` bits, each set with probability `p_true`
+pub struct RandomBits {
+    pub len: usize,
+    pub p_true: f64,
+}
+
+impl Init<Vec<bool>> for RandomBits {
+    fn init_with(&self, rng: &mut dyn RngCore) -> Vec<bool> {
+        (0..self.len).map(|_| rng.gen::<f64>() < self.p_true).collect()
+    }
+}
+
+/// A uniformly random ordering of `0..len`
+pub struct RandomPermutation {
+    pub len: usize,
+}
+
+impl Init<Vec<usize>> for RandomPermutation {
+    fn init_with(&self, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut genome: Vec<usize> = (0..self.len).collect();
+        genome.shuffle(rng);
+        genome
+    }
+}
+
+/// A uniformly random [`Permutation`] of `0..len`
+///
+/// As [`RandomPermutation`], for the genome type that can only hold permutations.
+///
+/// # Examples
+/// ```
+/// use dears::initializer::*;
+/// let perm = RandomPermutationGenome { len: 5 }.init_with(&mut rand::thread_rng());
+/// let mut values = perm.into_inner();
+/// values.sort();
+/// assert_eq!(values, vec![0, 1, 2, 3, 4]);
+/// ```
+pub struct RandomPermutationGenome {
+    pub len: usize,
+}
+
+impl Init<Permutation> for RandomPermutationGenome {
+    fn init_with(&self, rng: &mut dyn RngCore) -> Permutation {
+        Permutation::random(self.len, rng)
+    }
+}
+
+/// `len` values sampled independently from any distribution
+///
+/// # Examples
+/// ```
+/// use dears::initializer::*;
+/// use rand_distr::Normal;
+/// let init = FromDistribution { dist: Normal::new(0.0, 1.0).unwrap(), len: 5 };
+/// let genome: Vec<f64> = init.init();
+/// assert_eq!(genome.len(), 5);
+/// ```
+pub struct FromDistribution<D> {
+    pub dist: D,
+    pub len: usize,
+}
+
+impl<T, D: Distribution<T>> Init<Vec<T>> for FromDistribution<D> {
+    fn init_with(&self, mut rng: &mut dyn RngCore) -> Vec<T> {
+        (0..self.len).map(|_| self.dist.sample(&mut rng)).collect()
+    }
+}
+
+/// Genomes of `len` genes, each made by a closure given the generator and the gene's index
+///
+/// Builds any genome that can be collected from its genes, such as `Vec<T>`;
+/// [`Builder::array`] builds arrays. For patterns the fixed initializers
+/// don't cover, like genes of different ranges or kinds at different
+/// positions.
+///
+/// # Examples
+/// ```
+/// use dears::initializer::*;
+/// use dears::mutation::{Mutator, Reset};
+/// use rand::{Rng, RngCore};
+/// // Even genes are signs and odd genes magnitudes
+/// let init = Builder::<Vec<f64>>::with(6, |rng: &mut dyn RngCore, idx| match idx % 2 {
+///     0 => if rng.gen() { 1.0 } else { -1.0 },
+///     _ => rng.gen_range(0.0..10.0),
+/// });
+/// let mut genomes = init_population(4, &init, &mut rand::thread_rng());
+/// assert!(genomes.iter().all(|g| g.len() == 6 && g[0].abs() == 1.0));
+/// Reset { init }.mutate(&mut genomes[0]);
+///
+/// let ramp = Builder::<[f64; 3]>::array(|rng: &mut dyn RngCore, idx| idx as f64 + rng.gen::<f64>());
+/// assert!(ramp.init()[2] >= 2.0);
+/// ```
+pub struct Builder<G, F = ()> {
+    pub len: usize,
+    pub gene: F,
+    genome: PhantomData<fn() -> G>,
+}
+
+impl<G> Builder<G> {
+    pub fn with<F>(len: usize, gene: F) -> Builder<G, F> {
+        Builder { len, gene, genome: PhantomData }
+    }
+}
+
+impl<T, const N: usize> Builder<[T; N]> {
+    /// Arrays of `N` genes, each made by `gene`
+    pub fn array<F: Fn(&mut dyn RngCore, usize) -> T>(gene: F) -> ArrayBuilder<F, N> {
+        ArrayBuilder { gene }
+    }
+}
+
+impl<G, T, F> Init<G> for Builder<G, F>
+where
+    G: FromIterator<T>,
+    F: Fn(&mut dyn RngCore, usize) -> T
+{
+    fn init_with(&self, rng: &mut dyn RngCore) -> G {
+        (0..self.len).map(|idx| (self.gene)(rng, idx)).collect()
+    }
+}
+
+/// Arrays of `N` genes, each made by a closure given the generator and the
+/// gene's index, as [`Builder`] makes other genomes
+pub struct ArrayBuilder<F, const N: usize> {
+    pub gene: F,
+}
+
+impl<T, F, const N: usize> Init<[T; N]> for ArrayBuilder<F, N>
+where
+    F: Fn(&mut dyn RngCore, usize) -> T
+{
+    fn init_with(&self, rng: &mut dyn RngCore) -> [T; N] {
+        std::array::from_fn(|idx| (self.gene)(rng, idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::initializer::*;
+
+    #[test]
+    fn values_stay_in_range() {
+        let mut rng = StdRng::seed_from_u64(151);
+        let floats = init_population(50, &UniformFloat { low: -2.0, up: 3.0, len: 10 }, &mut rng);
+        assert!(floats.iter().flatten().all(|x| (-2.0..3.0).contains(x)));
+        assert!(floats.iter().all(|g| g.len() == 10));
+
+        let none = RandomBits { len: 20, p_true: 0.0 }.init_with(&mut rng);
+        let all = RandomBits { len: 20, p_true: 1.0 }.init_with(&mut rng);
+        assert_eq!((none, all), (vec![false; 20], vec![true; 20]));
+
+        for genome in init_population(50, &RandomPermutation { len: 12 }, &mut rng) {
+            let mut sorted = genome.clone();
+            sorted.sort();
+            assert_eq!(sorted, (0..12).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn builder_genes_follow_their_index() {
+        let mut rng = StdRng::seed_from_u64(201);
+        let gene = |rng: &mut dyn RngCore, idx: usize| rng.gen_range(0.0..=idx as f64);
+        let genomes = init_population(2000, &Builder::<Vec<f64>>::with(5, gene), &mut rng);
+        let arrays = init_population(2000, &Builder::<[f64; 5]>::array(gene), &mut rng);
+        for idx in 0..5 {
+            let vec_column: Vec<f64> = genomes.iter().map(|g| g[idx]).collect();
+            for column in [vec_column, arrays.iter().map(|g| g[idx]).collect()] {
+                let max = column.iter().copied().fold(0.0, f64::max);
+                let mean = column.iter().sum::<f64>() / column.len() as f64;
+                assert!(column.iter().all(|&x| (0.0..=idx as f64).contains(&x)));
+                assert!(max >= 0.98 * idx as f64, "gene {} reached only {}", idx, max);
+                assert!((mean - idx as f64 / 2.0).abs() < 0.1, "gene {} mean {}", idx, mean);
+            }
+        }
+        assert!(genomes.iter().all(|g| g.len() == 5));
+
+        // Any genome that can be collected from its genes
+        let word: String = Builder::<String>::with(4, |_: &mut dyn RngCore, idx| (b'a' + idx as u8) as char).init();
+        assert_eq!(word, "abcd");
+    }
+
+    #[test]
+    fn seeded_initializer_is_reproducible() {
+        let init = UniformFloat { low: 0.0, up: 1.0, len: 4 };
+        let first = init_population(20, &init, &mut StdRng::seed_from_u64(7));
+        let second = init_population(20, &init, &mut StdRng::seed_from_u64(7));
+        assert_eq!(first, second);
+        assert_ne!(first[0], first[1]);
+    }
+}
@@ -0,0 +1,454 @@
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::{RngCore, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::crossover::*;
+use crate::mutation::*;
+use crate::population::*;
+use crate::replacement::{worst_k, Replacement};
+use crate::rng::derive_seed;
+use crate::selection::*;
+use crate::termination::MaxGenerations;
+
+/// Which islands send migrants to which
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Topology {
+    /// Island `i` sends to island `i + 1`, and the last to the first
+    Ring,
+    /// The hub exchanges with every other island, which only exchange with the hub
+    Star { hub: usize },
+    /// Every island sends to every other island
+    FullyConnected,
+    /// `adjacency[i]` lists the islands that island `i` sends to
+    Custom(Vec<Vec<usize>>),
+    /// Island `i` sends to island `j` when `matrix[i][j]` is true
+    Matrix(Vec<Vec<bool>>),
+}
+
+impl Topology {
+    /// The islands that `island` sends migrants to, out of `n`
+    pub fn destinations(&self, island: usize, n: usize) -> Vec<usize> {
+        match self {
+            Topology::Ring if n > 1 => vec![(island + 1) % n],
+            Topology::Ring => Vec::new(),
+            Topology::Star { hub } if island == *hub => (0..n).filter(|&other| other != island).collect(),
+            Topology::Star { hub } => vec![*hub],
+            Topology::FullyConnected => (0..n).filter(|&other| other != island).collect(),
+            Topology::Custom(adjacency) => adjacency[island].clone(),
+            Topology::Matrix(matrix) => (0..n).filter(|&other| matrix[island][other]).collect(),
+        }
+    }
+
+    /// Checks the topology fits `n` islands
+    pub fn validate(&self, n: usize) -> Result<(), MigrationError> {
+        match self {
+            Topology::Ring | Topology::FullyConnected => Ok(()),
+            Topology::Star { hub } if *hub >= n => Err(MigrationError::InvalidHub(*hub)),
+            Topology::Star { .. } => Ok(()),
+            Topology::Custom(adjacency) => {
+                if adjacency.len() != n {
+                    return Err(MigrationError::WrongSize { islands: n, rows: adjacency.len() });
+                }
+                for (island, destinations) in adjacency.iter().enumerate() {
+                    if let Some(&destination) = destinations.iter().find(|&&d| d >= n || d == island) {
+                        return Err(MigrationError::InvalidDestination { island, destination });
+                    }
+                }
+                Ok(())
+            }
+            Topology::Matrix(matrix) => {
+                if matrix.len() != n {
+                    return Err(MigrationError::WrongSize { islands: n, rows: matrix.len() });
+                }
+                if let Some(row) = matrix.iter().position(|row| row.len() != n) {
+                    return Err(MigrationError::NotSquare { row, len: matrix[row].len() });
+                }
+                match (0..n).find(|&island| matrix[island][island]) {
+                    Some(island) => Err(MigrationError::InvalidDestination { island, destination: island }),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// What happens to migrants at their source island
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MigrationMode {
+    /// The source keeps its migrants
+    #[default]
+    Copy,
+    /// Migrants leave their source, which shrinks until immigrants fill the gap
+    Move,
+}
+
+/// Which individuals an island sends as migrants
+#[derive(Default)]
+pub enum Emigration<F> {
+    /// The best, as ranked by [`MigrationConfig::direction`]
+    #[default]
+    Best,
+    /// Distinct individuals picked uniformly at random
+    Random,
+    /// Whatever a selector picks, possibly the same individual more than once
+    Selector(Box<dyn SelectMany<F>>),
+}
+
+/// Which individuals immigrants take the place of, once they've filled any gaps
+#[derive(Default)]
+pub enum Immigration<G, F> {
+    /// The worst, as ranked by [`MigrationConfig::direction`]
+    #[default]
+    Worst,
+    /// Distinct individuals picked uniformly at random
+    Random,
+    /// Whatever a [`Replacement`] writes the immigrants over
+    Replacement(Box<dyn Replacement<G, F>>),
+}
+
+/// How islands exchange individuals
+///
+/// The default is a ring sending the single best individual every 10
+/// generations to take the place of the worst, keeping the original at home.
+pub struct MigrationConfig<G, F> {
+    pub topology: Topology,
+    /// Generations evolved between migrations
+    pub interval: usize,
+    /// How many individuals each island sends to each of its destinations
+    pub migrants: usize,
+    pub emigration: Emigration<F>,
+    pub immigration: Immigration<G, F>,
+    pub mode: MigrationMode,
+    /// Whether bigger or smaller fitnesses make the best migrants and the worst residents
+    pub direction: Direction,
+}
+
+impl<G, F> Default for MigrationConfig<G, F> {
+    fn default() -> Self {
+        MigrationConfig {
+            topology: Topology::Ring,
+            interval: 10,
+            migrants: 1,
+            emigration: Emigration::default(),
+            immigration: Immigration::default(),
+            mode: MigrationMode::default(),
+            direction: Direction::default(),
+        }
+    }
+}
+
+impl<G: Clone, F: Clone + PartialOrd> MigrationConfig<G, F> {
+    /// Which of the individuals with `fitnesses` leave as migrants
+    fn emigrants(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> Vec<usize> {
+        let k = self.migrants;
+        match &self.emigration {
+            Emigration::Best => {
+                let ranked = RankedFitnesses::new(fitnesses, &self.direction);
+                ranked.order().iter().take(k).copied().collect()
+            }
+            Emigration::Random => sample(rng, fitnesses.len(), k.min(fitnesses.len())).into_vec(),
+            Emigration::Selector(selector) => selector.select_n_with(fitnesses, k, rng),
+        }
+    }
+
+    /// Where each of `arrivals` settles among the residents, as `(index, genome, fitness)`
+    fn places(&self, individuals: &[G], fitnesses: &[F], arrivals: Vec<(G, F)>, rng: &mut dyn RngCore)
+              -> Vec<(usize, G, F)> {
+        let idxs = match &self.immigration {
+            Immigration::Worst => worst_k(fitnesses, arrivals.len(), self.direction),
+            Immigration::Random => sample(rng, fitnesses.len(), arrivals.len()).into_vec(),
+            Immigration::Replacement(replacement) => {
+                let (mut individuals, mut fitnesses) = (individuals.to_vec(), fitnesses.to_vec());
+                let (genomes, fits) = arrivals.into_iter().unzip();
+                let mut replaced = replacement.replace_with(&mut individuals, &mut fitnesses, genomes, fits, rng);
+                replaced.sort_unstable();
+                replaced.dedup();
+                return replaced.into_iter()
+                    .map(|idx| (idx, individuals[idx].clone(), fitnesses[idx].clone()))
+                    .collect();
+            }
+        };
+        idxs.into_iter().zip(arrivals).map(|(idx, (genome, fitness))| (idx, genome, fitness)).collect()
+    }
+}
+
+impl<G, F> MigrationConfig<G, F> {
+    /// Checks the configuration fits `n` islands
+    pub fn validate(&self, n: usize) -> Result<(), MigrationError> {
+        if n == 0 {
+            return Err(MigrationError::NoIslands);
+        }
+        if self.interval == 0 {
+            return Err(MigrationError::ZeroInterval);
+        }
+        self.topology.validate(n)
+    }
+}
+
+/// Errors from a [`MigrationConfig`] that doesn't fit its islands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    NoIslands,
+    /// Islands must evolve for at least one generation between migrations
+    ZeroInterval,
+    /// The hub of a star isn't one of the islands
+    InvalidHub(usize),
+    /// A custom topology needs one row per island
+    WrongSize { islands: usize, rows: usize },
+    /// A row of an adjacency matrix has the wrong length
+    NotSquare { row: usize, len: usize },
+    /// An island sends to a missing island or to itself
+    InvalidDestination { island: usize, destination: usize },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::NoIslands => write!(f, "need at least one island"),
+            MigrationError::ZeroInterval => write!(f, "the migration interval must be at least 1"),
+            MigrationError::InvalidHub(hub) => write!(f, "the hub {} isn't one of the islands", hub),
+            MigrationError::WrongSize { islands, rows } => {
+                write!(f, "the topology has {} rows for {} islands", rows, islands)
+            }
+            MigrationError::NotSquare { row, len } => {
+                write!(f, "row {} of the adjacency matrix has {} entries, so it isn't square", row, len)
+            }
+            MigrationError::InvalidDestination { island, destination } => {
+                write!(f, "island {} has an invalid destination {}", island, destination)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// Several populations evolved separately, exchanging individuals
+///
+/// Each epoch, every island is evolved for `interval` generations with its
+/// own generator, then each sends copies of `migrants` of its individuals to
+/// its destinations under the [`Topology`], chosen by the [`Emigration`]
+/// policy. Immigrants first fill any places an island lost to
+/// [`MigrationMode::Move`], then take the places the [`Immigration`] policy
+/// picks. Every island's generator, and the one migration draws from, are
+/// derived from one master seed, so a run is reproducible whether or not the
+/// islands are evolved in parallel.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{ea_simple, Elitism};
+/// use dears::crossover::OnePoint;
+/// use dears::islands::Islands;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::TournamentSelection;
+/// let pops = (0..4).map(|_| PopulationBuilder::new()
+///     .init_with(10, || vec![false; 8])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap()
+/// ).collect();
+/// let ones = |g: &Vec<bool>| g.iter().filter(|&&b| b).count() as f64;
+/// let mut islands = Islands::new(pops, 42).with_migration(5, 2).unwrap();
+/// islands.evolve(4, |pop, stop, rng| {
+///     ea_simple(pop, stop, 0.5, 0.2, Elitism::default(), ones, None, None, rng);
+/// });
+/// assert!(islands.islands().iter().all(|pop| pop.generation() == 20));
+/// ```
+pub struct Islands<G, M, C, S, F>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone
+{
+    islands: Vec<Population<G, M, C, S, F>>,
+    rngs: Vec<StdRng>,
+    rng: StdRng,
+    sizes: Vec<usize>,
+    config: MigrationConfig<G, F>,
+}
+
+impl<G, M, C, S, F> Islands<G, M, C, S, F>
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd
+{
+    /// Groups `islands` into a ring that swaps its single best individual every 10 generations
+    ///
+    /// Island `i` gets a generator seeded with `derive_seed(master_seed, i)`,
+    /// and migration one seeded with `derive_seed(master_seed, u64::MAX)`.
+    /// Panics if there are no islands.
+    pub fn new(islands: Vec<Population<G, M, C, S, F>>, master_seed: u64) -> Self {
+        assert!(!islands.is_empty(), "Need at least one island");
+        Islands::with_config(islands, master_seed, MigrationConfig::default()).unwrap()
+    }
+
+    /// Groups `islands` to migrate as `config` says, checking it fits them
+    pub fn with_config(islands: Vec<Population<G, M, C, S, F>>, master_seed: u64, config: MigrationConfig<G, F>)
+                       -> Result<Self, MigrationError> {
+        config.validate(islands.len())?;
+        let rngs = (0..islands.len())
+            .map(|i| StdRng::seed_from_u64(derive_seed(master_seed, i as u64)))
+            .collect();
+        let sizes = islands.iter().map(Population::len).collect();
+        Ok(Islands {
+            islands,
+            rngs,
+            rng: StdRng::seed_from_u64(derive_seed(master_seed, u64::MAX)),
+            sizes,
+            config,
+        })
+    }
+
+    /// Panics if the topology doesn't fit the islands, as checked by [`Topology::validate`]
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        if let Err(err) = topology.validate(self.islands.len()) {
+            panic!("{}", err);
+        }
+        self.config.topology = topology;
+        self
+    }
+
+    /// Migrates `migrants` from each island every `interval` generations
+    ///
+    /// Fails with [`MigrationError::ZeroInterval`] if `interval` is 0, as
+    /// [`Islands::with_config`] does.
+    pub fn with_migration(mut self, interval: usize, migrants: usize) -> Result<Self, MigrationError> {
+        if interval == 0 {
+            return Err(MigrationError::ZeroInterval);
+        }
+        self.config.interval = interval;
+        self.config.migrants = migrants;
+        Ok(self)
+    }
+
+    pub fn with_mode(mut self, mode: MigrationMode) -> Self {
+        self.config.mode = mode;
+        self
+    }
+
+    /// Sets whether bigger or smaller fitnesses make the best migrants
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.config.direction = direction;
+        self
+    }
+
+    pub fn config(&self) -> &MigrationConfig<G, F> {
+        &self.config
+    }
+
+    pub fn islands(&self) -> &[Population<G, M, C, S, F>] {
+        &self.islands
+    }
+
+    pub fn islands_mut(&mut self) -> &mut [Population<G, M, C, S, F>] {
+        &mut self.islands
+    }
+
+    pub fn len(&self) -> usize {
+        self.islands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.islands.is_empty()
+    }
+
+    /// The best individual on any island, if they've been evaluated
+    pub fn best(&self) -> Option<(&G, &F)> {
+        self.islands.iter()
+            .filter(|pop| pop.is_evaluated())
+            .flat_map(|pop| pop.individuals().iter().zip(pop.fitnesses()))
+            .reduce(|best, candidate| {
+                if self.config.direction.compare(candidate.1, best.1).is_gt() { candidate } else { best }
+            })
+    }
+
+    /// Runs `epochs` rounds of evolution followed by migration
+    ///
+    /// `evolve` is called on each island in turn with a criterion that stops
+    /// it `interval` generations later, and the island's own generator.
+    pub fn evolve<E>(&mut self, epochs: usize, evolve: E)
+    where
+        E: Fn(&mut Population<G, M, C, S, F>, MaxGenerations, &mut StdRng)
+    {
+        for _ in 0..epochs {
+            for (pop, rng) in self.islands.iter_mut().zip(&mut self.rngs) {
+                let stop = MaxGenerations(pop.generation() + self.config.interval);
+                evolve(pop, stop, rng);
+            }
+            self.migrate();
+        }
+    }
+
+    /// As [`Islands::evolve`], with the islands evolved on the rayon thread pool
+    #[cfg(feature = "parallel")]
+    pub fn evolve_par<E>(&mut self, epochs: usize, evolve: E)
+    where
+        E: Fn(&mut Population<G, M, C, S, F>, MaxGenerations, &mut StdRng) + Sync,
+        Population<G, M, C, S, F>: Send
+    {
+        for _ in 0..epochs {
+            let interval = self.config.interval;
+            self.islands.par_iter_mut().zip(self.rngs.par_iter_mut()).for_each(|(pop, rng)| {
+                let stop = MaxGenerations(pop.generation() + interval);
+                evolve(pop, stop, rng);
+            });
+            self.migrate();
+        }
+    }
+
+    /// Sends migrants from each island to its destinations
+    ///
+    /// Every island must have been evaluated.
+    pub fn migrate(&mut self) {
+        let n = self.islands.len();
+        let mut incoming: Vec<Vec<(G, F)>> = vec![Vec::new(); n];
+        let mut emigrants: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (island, pop) in self.islands.iter().enumerate() {
+            assert!(pop.is_evaluated(), "Island {} must be evaluated before migration", island);
+            let destinations = self.config.topology.destinations(island, n);
+            if destinations.is_empty() {
+                continue;
+            }
+            emigrants[island] = self.config.emigrants(pop.fitnesses(), &mut self.rng);
+            for &destination in &destinations {
+                incoming[destination].extend(emigrants[island].iter()
+                    .map(|&i| (pop.individuals()[i].clone(), pop.fitnesses()[i].clone())));
+            }
+        }
+        if self.config.mode == MigrationMode::Move {
+            for (pop, leaving) in self.islands.iter_mut().zip(&mut emigrants) {
+                // Highest index first so the rest stay valid
+                leaving.sort_unstable_by(|a, b| b.cmp(a));
+                leaving.dedup();
+                for &idx in leaving.iter() {
+                    // Always keep one, since a population can't be empty
+                    if pop.len() > 1 {
+                        pop.remove(idx);
+                    }
+                }
+            }
+        }
+        for ((pop, &size), arrivals) in self.islands.iter_mut().zip(&self.sizes).zip(incoming) {
+            let mut arrivals = arrivals.into_iter();
+            let missing = size.saturating_sub(pop.len());
+            for (genome, fitness) in arrivals.by_ref().take(missing) {
+                pop.push(genome, fitness);
+            }
+            let arrivals = arrivals.take(pop.len()).collect();
+            let places = self.config.places(pop.individuals(), pop.fitnesses(), arrivals, &mut self.rng);
+            for (idx, genome, fitness) in places {
+                pop.replace_at(idx, genome, fitness);
+            }
+        }
+    }
+}
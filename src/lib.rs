@@ -1,14 +1,48 @@
 pub mod population;
+pub mod individual;
+pub mod fitness;
+pub mod genome;
+pub mod bitstring;
+pub mod gp;
+pub mod constraints;
+pub mod initializer;
 pub mod mutation;
 pub mod crossover;
 pub mod selection;
+pub mod niching;
+pub mod replacement;
+pub mod distance;
+pub mod dedup;
+pub mod archive;
+pub mod cache;
+pub mod islands;
+pub mod coevolution;
+pub mod multiobjective;
+pub mod scalarize;
+pub mod normalize;
+pub mod history;
+pub mod cmaes;
+pub mod es;
+pub mod algorithms;
+pub mod adaptive;
+pub mod report;
+pub mod display;
+pub mod stats;
+pub mod csv;
+pub mod rng;
+pub mod termination;
+pub mod prelude;
+#[cfg(feature = "serde")]
+pub mod checkpoint;
+#[cfg(feature = "serde")]
+mod serde_array;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[cfg(feature = "derive")]
+pub use dears_derive::CrossoverFields;
 
-    #[test]
-    fn example() {
-
-    }
+/// What code generated by `dears-derive` refers to
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use rand::RngCore;
 }
@@ -0,0 +1,563 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use rand::{Rng, RngCore};
+
+use crate::selection::*;
+
+/// Moves fitnesses into minimisation space, with NaN as the worst possible value
+fn minimised<const N: usize>(fitness: &[f64; N], direction: Direction) -> [f64; N] {
+    fitness.map(|f| if f.is_nan() { f64::INFINITY } else { -direction.signed(f) })
+}
+
+/// Whether `a` Pareto-dominates `b`, with `direction(i)` saying which way objective `i` is better
+///
+/// The one dominance test behind every helper here. A point with a NaN
+/// objective never dominates, and is dominated by every point without one.
+fn pareto(a: &[f64], b: &[f64], direction: impl Fn(usize) -> Direction) -> bool {
+    if a.iter().any(|x| x.is_nan()) {
+        return false;
+    }
+    if b.iter().any(|y| y.is_nan()) {
+        return true;
+    }
+    let mut better = false;
+    for (i, (x, y)) in a.iter().zip(b).enumerate() {
+        match direction(i).compare(x, y) {
+            Ordering::Less => return false,
+            Ordering::Greater => better = true,
+            Ordering::Equal => {}
+        }
+    }
+    better
+}
+
+fn has_nan(point: &[f64]) -> bool {
+    point.iter().any(|x| x.is_nan())
+}
+
+/// Whether fitness `a` Pareto-dominates `b`
+///
+/// `a` dominates `b` if it's no worse in any objective and strictly better in
+/// at least one. A fitness with a NaN objective never dominates, and is
+/// dominated by every fitness without one.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::dominates;
+/// use dears::selection::Direction;
+/// assert!(dominates(&[1.0, 2.0], &[1.0, 3.0], Direction::Minimize));
+/// assert!(!dominates(&[1.0, 2.0], &[2.0, 1.0], Direction::Minimize));
+/// assert!(!dominates(&[1.0, 2.0], &[1.0, 2.0], Direction::Minimize));
+/// assert!(dominates(&[9.0, 9.0], &[f64::NAN, 1.0], Direction::Minimize));
+/// ```
+pub fn dominates<const N: usize>(a: &[f64; N], b: &[f64; N], direction: Direction) -> bool {
+    pareto(a, b, |_| direction)
+}
+
+/// [`dominates`] with each objective going its own way
+///
+/// Panics unless `a`, `b` and `directions` are all the same length.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::dominates_mixed;
+/// use dears::selection::Direction::*;
+/// // Maximise the first, minimise the second
+/// assert!(dominates_mixed(&[3.0, 1.0], &[2.0, 1.0], &[Maximize, Minimize]));
+/// assert!(!dominates_mixed(&[3.0, 1.0], &[2.0, 1.0], &[Minimize, Minimize]));
+/// ```
+pub fn dominates_mixed(a: &[f64], b: &[f64], directions: &[Direction]) -> bool {
+    assert!(a.len() == directions.len() && b.len() == directions.len(), "Need one direction per objective");
+    pareto(a, b, |i| directions[i])
+}
+
+/// Whether `a` comes within `epsilon` of being at least as good as `b` in every objective
+///
+/// The additive epsilon-dominance of Laumanns et al: `a` may be worse than `b`
+/// by up to `epsilon` in each objective, and needn't be better in any. NaN is
+/// handled as in [`dominates`]. Panics unless `a`, `b` and `directions` are
+/// all the same length.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::epsilon_dominates;
+/// use dears::selection::Direction::*;
+/// assert!(epsilon_dominates(&[1.05, 2.0], &[1.0, 2.5], 0.1, &[Minimize, Minimize]));
+/// assert!(!epsilon_dominates(&[1.2, 2.0], &[1.0, 2.5], 0.1, &[Minimize, Minimize]));
+/// ```
+pub fn epsilon_dominates(a: &[f64], b: &[f64], epsilon: f64, directions: &[Direction]) -> bool {
+    assert!(a.len() == directions.len() && b.len() == directions.len(), "Need one direction per objective");
+    if has_nan(a) {
+        return false;
+    }
+    if has_nan(b) {
+        return true;
+    }
+    a.iter().zip(b).zip(directions).all(|((&x, &y), d)| d.signed(x) + epsilon >= d.signed(y))
+}
+
+/// Indices of the points no other point dominates, in order
+///
+/// Points are visited best first by their objectives in turn, since only an
+/// earlier point can dominate a later one, and each is only checked against
+/// the non-dominated points found so far. Points with NaN objectives are kept
+/// only if every point has one. Panics unless every point has one objective
+/// per direction.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::non_dominated_indices;
+/// use dears::selection::Direction::*;
+/// let points = vec![[1.0, 5.0], [2.0, 2.0], [3.0, 3.0], [4.0, 1.0]];
+/// assert_eq!(non_dominated_indices(&points, &[Minimize, Minimize]), vec![0, 1, 3]);
+/// ```
+pub fn non_dominated_indices<P: AsRef<[f64]>>(points: &[P], directions: &[Direction]) -> Vec<usize> {
+    let points: Vec<&[f64]> = points.iter().map(|p| p.as_ref()).collect();
+    assert!(points.iter().all(|p| p.len() == directions.len()), "Need one direction per objective");
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&i, &j| {
+        let (a, b) = (points[i], points[j]);
+        has_nan(a).cmp(&has_nan(b)).then_with(|| {
+            a.iter().zip(b).zip(directions).map(|((x, y), d)| d.compare(y, x)).find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        })
+    });
+    let mut front: Vec<usize> = Vec::new();
+    for i in order {
+        if !front.iter().any(|&j| pareto(points[j], points[i], |k| directions[k])) {
+            front.push(i);
+        }
+    }
+    front.sort_unstable();
+    front
+}
+
+/// [`non_dominated_indices`], refusing points with a NaN objective rather than putting them last
+pub fn try_non_dominated_indices<P: AsRef<[f64]>>(points: &[P], directions: &[Direction])
+                                                  -> Result<Vec<usize>, NanObjective> {
+    match points.iter().position(|p| has_nan(p.as_ref())) {
+        Some(point) => Err(NanObjective { point }),
+        None => Ok(non_dominated_indices(points, directions)),
+    }
+}
+
+/// A point given to [`try_non_dominated_indices`] had a NaN objective
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NanObjective {
+    pub point: usize,
+}
+
+impl fmt::Display for NanObjective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Point {} has a NaN objective, so can't be compared", self.point)
+    }
+}
+
+impl std::error::Error for NanObjective {}
+
+/// Splits the population into successive non-dominated fronts, best first
+///
+/// This is the simple `O(M N^2)` sort from NSGA-II, using [`dominates`], so
+/// fitnesses with NaN objectives end up behind all the others.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::non_dominated_sort;
+/// use dears::selection::Direction;
+/// let fitnesses = vec![[1.0, 2.0], [2.0, 1.0], [2.0, 2.0]];
+/// assert_eq!(non_dominated_sort(&fitnesses, Direction::Minimize), vec![vec![0, 1], vec![2]]);
+/// ```
+pub fn non_dominated_sort<const N: usize>(fitnesses: &[[f64; N]], direction: Direction) -> Vec<Vec<usize>> {
+    let len = fitnesses.len();
+    let mut dominated_by = vec![0usize; len];
+    let mut dominates: Vec<Vec<usize>> = vec![Vec::new(); len];
+    for i in 0..len {
+        for j in (i + 1)..len {
+            if pareto(&fitnesses[i], &fitnesses[j], |_| direction) {
+                dominates[i].push(j);
+                dominated_by[j] += 1;
+            } else if pareto(&fitnesses[j], &fitnesses[i], |_| direction) {
+                dominates[j].push(i);
+                dominated_by[i] += 1;
+            }
+        }
+    }
+    let mut fronts = Vec::new();
+    let mut current: Vec<usize> = (0..len).filter(|&i| dominated_by[i] == 0).collect();
+    while !current.is_empty() {
+        let mut next = Vec::new();
+        for &i in &current {
+            for &j in &dominates[i] {
+                dominated_by[j] -= 1;
+                if dominated_by[j] == 0 {
+                    next.push(j);
+                }
+            }
+        }
+        next.sort();
+        fronts.push(current);
+        current = next;
+    }
+    fronts
+}
+
+/// NSGA-II's crowding distance of each point of a front, in the same order
+///
+/// The distance is the sum over objectives of the gap between a point's
+/// neighbours in that objective, divided by the objective's range over the
+/// front. The extremes of each objective get an infinite distance, so they're
+/// always preferred. Of points tied at an extreme, only the first is counted
+/// as the extreme. An objective that's the same for every point, or that has
+/// a NaN, tells the points apart no better than chance, so it adds nothing;
+/// in particular a front of identical points, or a single point, gets all
+/// zeros. Panics unless every point has the same number of objectives.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::crowding_distance;
+/// let front: Vec<&[f64]> = vec![&[0.0, 4.0], &[1.0, 3.0], &[3.0, 1.0], &[4.0, 0.0]];
+/// let distances = crowding_distance(&front);
+/// assert_eq!(distances, vec![f64::INFINITY, 1.5, 1.5, f64::INFINITY]);
+/// assert_eq!(crowding_distance(&[&[2.0, 2.0], &[2.0, 2.0], &[2.0, 2.0]]), vec![0.0; 3]);
+/// ```
+pub fn crowding_distance(front: &[&[f64]]) -> Vec<f64> {
+    let objectives = front.first().map_or(0, |p| p.len());
+    assert!(front.iter().all(|p| p.len() == objectives), "Every point needs the same number of objectives");
+    let mut distances = vec![0.0; front.len()];
+    let mut order: Vec<usize> = (0..front.len()).collect();
+    let columns = (0..objectives).map(|m| front.iter().map(|p| p[m]).collect::<Vec<f64>>());
+    for values in columns {
+        order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+        let (first, last) = (order[0], order[order.len() - 1]);
+        let range = values[last] - values[first];
+        if range.is_nan() || range <= 0.0 {
+            continue;
+        }
+        distances[first] = f64::INFINITY;
+        distances[last] = f64::INFINITY;
+        for w in order.windows(3) {
+            distances[w[1]] += (values[w[2]] - values[w[0]]) / range;
+        }
+    }
+    distances
+}
+
+/// [`crowding_distance`] of the members of `front`, given as indices into `fitnesses`
+fn front_crowding<const N: usize>(fitnesses: &[[f64; N]], front: &[usize]) -> Vec<f64> {
+    let points: Vec<&[f64]> = front.iter().map(|&i| &fitnesses[i][..]).collect();
+    crowding_distance(&points)
+}
+
+/// The front each individual belongs to, and its crowding distance within that front
+fn rank_and_crowding<const N: usize>(fitnesses: &[[f64; N]], direction: Direction) -> (Vec<usize>, Vec<f64>) {
+    let mut ranks = vec![0; fitnesses.len()];
+    let mut crowding = vec![0.0; fitnesses.len()];
+    for (rank, front) in non_dominated_sort(fitnesses, direction).into_iter().enumerate() {
+        for (&i, distance) in front.iter().zip(front_crowding(fitnesses, &front)) {
+            ranks[i] = rank;
+            crowding[i] = distance;
+        }
+    }
+    (ranks, crowding)
+}
+
+/// NSGA-II's binary tournament under the crowded-comparison operator
+///
+/// Of two random individuals, the one on the better non-dominated front
+/// wins; on the same front, the one with the larger crowding distance wins.
+/// Fronts and distances are computed once per batch, so prefer selecting
+/// many at a time.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::CrowdedTournament;
+/// use dears::selection::*;
+/// let fitnesses = vec![[1.0, 1.0], [2.0, 2.0]];
+/// let selector = CrowdedTournament { direction: Direction::Minimize };
+/// assert_eq!(selector.select_n(&fitnesses, 10).len(), 10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CrowdedTournament {
+    pub direction: Direction,
+}
+
+impl<const N: usize> SelectOne<[f64; N]> for CrowdedTournament {
+    fn select_with(&self, fitnesses: &[[f64; N]], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[[f64; N]], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(!fitnesses.is_empty(), "Can't select from empty fitnesses vector");
+        let (ranks, crowding) = rank_and_crowding(fitnesses, self.direction);
+        (0..n)
+            .map(|_| {
+                let (a, b) = (rng.gen_range(0..fitnesses.len()), rng.gen_range(0..fitnesses.len()));
+                match ranks[a].cmp(&ranks[b]).then(crowding[b].total_cmp(&crowding[a])) {
+                    Ordering::Greater => b,
+                    _ => a,
+                }
+            })
+            .collect()
+    }
+}
+
+/// NSGA-II survivor selection by non-dominated front, then crowding distance
+///
+/// Whole fronts are kept while they fit in `n`, and the front that doesn't
+/// fit is cut down to its least crowded members.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::Nsga2Selection;
+/// use dears::selection::*;
+/// let fitnesses = vec![[1.0, 4.0], [2.0, 3.0], [2.1, 2.9], [4.0, 1.0], [5.0, 5.0]];
+/// let selector = Nsga2Selection { direction: Direction::Minimize };
+/// assert_eq!(selector.select_n_distinct(&fitnesses, 3).unwrap(), vec![0, 3, 2]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Nsga2Selection {
+    pub direction: Direction,
+}
+
+impl Nsga2Selection {
+    fn survivors<const N: usize>(&self, fitnesses: &[[f64; N]], n: usize) -> Vec<usize> {
+        let mut survivors = Vec::with_capacity(n);
+        for front in non_dominated_sort(fitnesses, self.direction) {
+            if survivors.len() + front.len() > n {
+                let distances = front_crowding(fitnesses, &front);
+                let mut order: Vec<usize> = (0..front.len()).collect();
+                order.sort_by(|&a, &b| distances[b].total_cmp(&distances[a]));
+                survivors.extend(order[..n - survivors.len()].iter().map(|&i| front[i]));
+                break;
+            }
+            survivors.extend(front);
+        }
+        survivors
+    }
+}
+
+impl<const N: usize> SelectOne<[f64; N]> for Nsga2Selection {
+    fn select_with(&self, fitnesses: &[[f64; N]], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[[f64; N]], n: usize, _rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(!fitnesses.is_empty(), "Can't select from empty fitnesses vector");
+        let survivors = self.survivors(fitnesses, n.min(fitnesses.len()));
+        survivors.into_iter().cycle().take(n).collect()
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[[f64; N]], n: usize, _rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        let len = fitnesses.len();
+        if n > len {
+            return Err(SelectionError::NotEnoughIndividuals { requested: n, available: len });
+        }
+        Ok(self.survivors(fitnesses, n))
+    }
+}
+
+/// Area dominated by a set of minimised points, bounded by `reference`
+///
+/// Sweeps the points in order of the first objective, so is `O(n log n)`.
+/// Points that don't strictly beat the reference point in both objectives
+/// contribute nothing.
+fn hypervolume_2d(points: &[[f64; 2]], reference: [f64; 2]) -> f64 {
+    let mut inside: Vec<[f64; 2]> = points.iter().copied()
+        .filter(|p| p[0] < reference[0] && p[1] < reference[1])
+        .collect();
+    inside.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    let mut volume = 0.0;
+    let mut ceiling = reference[1];
+    for p in inside {
+        if p[1] < ceiling {
+            volume += (reference[0] - p[0]) * (ceiling - p[1]);
+            ceiling = p[1];
+        }
+    }
+    volume
+}
+
+/// Volume dominated by a set of minimised points, bounded by `reference`
+///
+/// Sweeps the points in order of the last objective, summing the 2D area of
+/// the points seen so far over each slab, so is `O(n^2 log n)`.
+fn hypervolume_3d(points: &[[f64; 3]], reference: [f64; 3]) -> f64 {
+    let mut inside: Vec<[f64; 3]> = points.iter().copied()
+        .filter(|p| p.iter().zip(&reference).all(|(x, r)| x < r))
+        .collect();
+    inside.sort_by(|a, b| a[2].total_cmp(&b[2]));
+    let mut volume = 0.0;
+    let mut slab: Vec<[f64; 2]> = Vec::with_capacity(inside.len());
+    for (i, p) in inside.iter().enumerate() {
+        slab.push([p[0], p[1]]);
+        let top = inside.get(i + 1).map_or(reference[2], |next| next[2]);
+        volume += hypervolume_2d(&slab, [reference[0], reference[1]]) * (top - p[2]);
+    }
+    volume
+}
+
+/// Exact hypervolume of a front of 2 or 3 objectives
+///
+/// The hypervolume is the size of the region dominated by the front and
+/// bounded by `reference`, which should be worse than every point in every
+/// objective; parts of points beyond it are ignored. Two objectives take
+/// `O(n log n)` and three `O(n^2 log n)`. Panics for any other `N`.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::hypervolume;
+/// use dears::selection::Direction;
+/// let front = [[1.0, 3.0], [2.0, 2.0], [3.0, 1.0]];
+/// assert_eq!(hypervolume(&front, [4.0, 4.0], Direction::Minimize), 6.0);
+/// ```
+pub fn hypervolume<const N: usize>(front: &[[f64; N]], reference: [f64; N], direction: Direction) -> f64 {
+    let points: Vec<[f64; N]> = front.iter().map(|f| minimised(f, direction)).collect();
+    let reference = minimised(&reference, direction);
+    match N {
+        2 => hypervolume_2d(&points.iter().map(|p| [p[0], p[1]]).collect::<Vec<_>>(), [reference[0], reference[1]]),
+        3 => hypervolume_3d(&points.iter().map(|p| [p[0], p[1], p[2]]).collect::<Vec<_>>(),
+                            [reference[0], reference[1], reference[2]]),
+        _ => panic!("Hypervolume is only implemented for 2 or 3 objectives, not {}", N),
+    }
+}
+
+/// SMS-EMOA style survivor selection by hypervolume contribution
+///
+/// Whole non-dominated fronts are kept while they fit in `n`. The front that
+/// doesn't fit then has its least contributor to the hypervolume removed, one
+/// at a time, until the survivors number exactly `n`. Each removal recomputes
+/// every contribution, so the cost is `O(k^2)` hypervolume computations for a
+/// front of size `k`. Only 2 and 3 objectives are supported.
+///
+/// # Examples
+/// ```
+/// use dears::multiobjective::HypervolumeSelection;
+/// use dears::selection::*;
+/// let fitnesses = vec![[1.0, 3.0], [2.0, 2.9], [3.0, 1.0], [5.0, 5.0]];
+/// let selector = HypervolumeSelection { reference_point: [4.0, 4.0], direction: Direction::Minimize };
+/// assert_eq!(selector.select_n_distinct(&fitnesses, 2).unwrap(), vec![0, 2]);
+/// ```
+pub struct HypervolumeSelection<const N: usize> {
+    pub reference_point: [f64; N],
+    pub direction: Direction,
+}
+
+impl<const N: usize> HypervolumeSelection<N> {
+    fn survivors(&self, fitnesses: &[[f64; N]], n: usize) -> Vec<usize> {
+        assert!(N == 2 || N == 3, "Hypervolume selection needs 2 or 3 objectives, not {}", N);
+        let mut survivors = Vec::with_capacity(n);
+        for mut front in non_dominated_sort(fitnesses, self.direction) {
+            if survivors.len() + front.len() > n {
+                while survivors.len() + front.len() > n {
+                    let least = self.least_contributor(fitnesses, &front);
+                    front.remove(least);
+                }
+                survivors.extend(front);
+                break;
+            }
+            survivors.extend(front);
+        }
+        survivors
+    }
+
+    /// Position within `front` of the point adding least hypervolume
+    fn least_contributor(&self, fitnesses: &[[f64; N]], front: &[usize]) -> usize {
+        let points: Vec<[f64; N]> = front.iter().map(|&i| fitnesses[i]).collect();
+        let total = hypervolume(&points, self.reference_point, self.direction);
+        let mut without = Vec::with_capacity(points.len());
+        (0..points.len())
+            .map(|skip| {
+                without.clear();
+                without.extend(points.iter().enumerate().filter(|&(i, _)| i != skip).map(|(_, p)| *p));
+                total - hypervolume(&without, self.reference_point, self.direction)
+            })
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(pos, _)| pos)
+            .unwrap()
+    }
+}
+
+impl<const N: usize> SelectOne<[f64; N]> for HypervolumeSelection<N> {
+    fn select_with(&self, fitnesses: &[[f64; N]], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[[f64; N]], n: usize, _rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(!fitnesses.is_empty(), "Can't select from empty fitnesses vector");
+        // Everyone survives if asked for more than the population, so repeat them
+        let survivors = self.survivors(fitnesses, n.min(fitnesses.len()));
+        survivors.into_iter().cycle().take(n).collect()
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[[f64; N]], n: usize, _rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        let len = fitnesses.len();
+        if n > len {
+            return Err(SelectionError::NotEnoughIndividuals { requested: n, available: len });
+        }
+        Ok(self.survivors(fitnesses, n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::multiobjective::*;
+
+    #[test]
+    fn hypervolume_of_small_fronts() {
+        let front = [[1.0, 3.0], [2.0, 2.0], [3.0, 1.0]];
+        assert_eq!(hypervolume(&front, [4.0, 4.0], Direction::Minimize), 6.0);
+        // Same front maximised, mirrored about the origin
+        let mirrored = front.map(|p| p.map(|x: f64| -x));
+        assert_eq!(hypervolume(&mirrored, [-4.0, -4.0], Direction::Maximize), 6.0);
+        // Points beyond the reference point add nothing
+        assert_eq!(hypervolume(&[[1.0, 1.0], [5.0, 0.0]], [2.0, 2.0], Direction::Minimize), 1.0);
+
+        assert_eq!(hypervolume(&[[1.0, 1.0, 1.0]], [2.0, 2.0, 2.0], Direction::Minimize), 1.0);
+        // Two boxes of volume 4 and 2 overlapping in a unit cube
+        let front = [[0.0, 0.0, 1.0], [1.0, 1.0, 0.0]];
+        assert_eq!(hypervolume(&front, [2.0, 2.0, 2.0], Direction::Minimize), 5.0);
+    }
+
+    #[test]
+    fn least_contributor_is_removed() {
+        // The middle point only adds a 2 x 0.1 sliver
+        let fitnesses = vec![[1.0, 3.0], [2.0, 2.9], [3.0, 1.0], [5.0, 5.0], [3.5, 3.5]];
+        let selector = HypervolumeSelection { reference_point: [4.0, 4.0], direction: Direction::Minimize };
+        assert_eq!(selector.select_n_distinct(&fitnesses, 2).unwrap(), vec![0, 2]);
+        assert_eq!(selector.select_n_distinct(&fitnesses, 3).unwrap(), vec![0, 1, 2]);
+        assert_eq!(selector.select_n_distinct(&fitnesses, 4).unwrap(), vec![0, 1, 2, 4]);
+    }
+
+    #[test]
+    fn crowding_of_duplicates_and_flat_objectives() {
+        let front: Vec<&[f64]> = vec![&[0.0, 4.0], &[1.0, 3.0], &[1.0, 3.0], &[3.0, 1.0], &[4.0, 0.0]];
+        // Each duplicate gets the near gap in one objective and the far gap in the other
+        assert_eq!(crowding_distance(&front), vec![f64::INFINITY, 0.75, 0.75, 1.5, f64::INFINITY]);
+        // The flat second objective counts for nothing
+        let flat: Vec<&[f64]> = vec![&[1.0, 5.0], &[2.0, 5.0], &[4.0, 5.0]];
+        assert_eq!(crowding_distance(&flat), vec![f64::INFINITY, 1.0, f64::INFINITY]);
+        assert_eq!(crowding_distance(&[&[3.0, 3.0][..]; 4]), vec![0.0; 4]);
+        assert_eq!(crowding_distance(&[&[1.0, 2.0], &[2.0, 1.0]]), vec![f64::INFINITY; 2]);
+        assert!(crowding_distance(&[]).is_empty());
+    }
+
+    #[test]
+    fn nsga2_keeps_fronts_then_spread() {
+        let fitnesses = vec![[1.0, 4.0], [2.0, 3.0], [2.1, 2.9], [4.0, 1.0], [3.0, 3.0], [5.0, 5.0]];
+        let selector = Nsga2Selection { direction: Direction::Minimize };
+        // The crowded middle pair loses one member
+        assert_eq!(selector.select_n_distinct(&fitnesses, 3).unwrap(), vec![0, 3, 2]);
+        assert_eq!(selector.select_n_distinct(&fitnesses, 5).unwrap(), vec![0, 1, 2, 3, 4]);
+
+        // A tournament never picks the dominated point over the front
+        let pair = vec![[1.0, 1.0], [2.0, 2.0]];
+        let tournament = CrowdedTournament { direction: Direction::Minimize };
+        let picks = tournament.select_n(&pair, 200);
+        assert!(picks.contains(&1));
+        assert!(picks.iter().filter(|&&i| i == 0).count() > 120);
+    }
+}
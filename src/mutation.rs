@@ -1,10 +1,21 @@
-use rand::Rng;
+use rand::{Rng, RngCore};
 use rand_distr::{Distribution, Normal};
 
+use crate::genome::{two_points, BoundedVec, Genome, OwnBounds, Permutation};
+use crate::initializer::Init;
+
 /// Trait defining an in-place mutation function to be implemented
 /// by all mutation functions
+///
+/// Implementors draw all their randomness from the `rng` given to
+/// [`Mutator::mutate_with`], so runs can be reproduced from a seed;
+/// [`Mutator::mutate`] uses the thread-local generator.
 pub trait Mutator<G: ?Sized>{
-    fn mutate(&self, genome: &mut G);
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore);
+
+    fn mutate(&self, genome: &mut G) {
+        self.mutate_with(genome, &mut rand::thread_rng())
+    }
 }
 
 /// Applies a per-element gaussian mutation of mean `mu` and std dev `sigma`
@@ -25,7 +36,7 @@ pub trait Mutator<G: ?Sized>{
 /// };
 /// mutator.mutate(&mut vals);
 /// // Vals has now been mutated!
-/// println!("Gaussian: {:?}", vals);
+/// assert_eq!(vals.len(), 4);
 /// ```
 pub struct Gaussian {
     pub mu: f64,
@@ -33,18 +44,17 @@ pub struct Gaussian {
     pub indpb: f64,
 }
 
-impl Mutator<[f64]> for Gaussian {
-    fn mutate(&self, genome: &mut [f64]) {
+impl<G: AsMut<[f64]> + ?Sized> Mutator<G> for Gaussian {
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore) {
         // Initialize the random distribution
-        let mut rng = rand::thread_rng();
         let normal = Normal::new(self.mu, self.sigma).unwrap_or_else(|_| {
             panic!("Invalid args to Normal Distribution: sigma={} mu={}",
                     self.sigma, self.mu)
         });
         // Apply the random noise to selected genes
-        for ind in genome.iter_mut() {
+        for ind in genome.as_mut().iter_mut() {
             if rng.gen::<f64>() < self.indpb {
-                let val = normal.sample(&mut rng);
+                let val = normal.sample(rng);
                 *ind += val;
             }
         }
@@ -62,16 +72,16 @@ impl Mutator<[f64]> for Gaussian {
 /// let mut vals = vec![1.0, 2.0, 3.0, 4.0];
 /// let mutator = Shuffle { indpb: 0.4 };
 /// mutator.mutate(&mut vals);
-/// // Vals has now been mutated!
-/// println!("Shuffled: {:?}", vals);
+/// // Vals has now been mutated, but holds the same values
+/// vals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+/// assert_eq!(vals, vec![1.0, 2.0, 3.0, 4.0]);
 /// ```
 pub struct Shuffle {
     pub indpb: f64,
 }
 
-impl<T: Clone> Mutator<[T]> for Shuffle {
-    fn mutate(&self, genome: &mut [T]) {
-        let mut rng = rand::thread_rng();
+impl<G: Genome + ?Sized> Mutator<G> for Shuffle {
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore) {
         let size = genome.len();
         // For each index of the list, if indpb is met
         // Swap with another random index of the list
@@ -87,6 +97,81 @@ impl<T: Clone> Mutator<[T]> for Shuffle {
     }
 }
 
+impl Mutator<Permutation> for Shuffle {
+    fn mutate_with(&self, genome: &mut Permutation, rng: &mut dyn RngCore) {
+        genome.rearrange(|genome| self.mutate_with(genome, rng));
+    }
+}
+
+/// Reverses the order of the genes in a random section of the genome
+///
+/// Keeps every gene, so suits permutations; for a tour, it replaces two of
+/// its edges. Genomes shorter than 2 are left alone.
+///
+/// # Examples
+/// ```
+/// use dears::mutation::*;
+/// let mut vals = vec![0, 1, 2, 3, 4];
+/// Inversion.mutate(&mut vals);
+/// assert_ne!(vals, vec![0, 1, 2, 3, 4]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Inversion;
+
+impl<G: Genome + ?Sized> Mutator<G> for Inversion {
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore) {
+        if genome.len() < 2 {
+            return;
+        }
+        let (mut i, mut j) = two_points(genome.len(), rng);
+        while i < j {
+            genome.swap(i, j);
+            i += 1;
+            j -= 1;
+        }
+    }
+}
+
+impl Mutator<Permutation> for Inversion {
+    fn mutate_with(&self, genome: &mut Permutation, rng: &mut dyn RngCore) {
+        genome.rearrange(|genome| self.mutate_with(genome, rng));
+    }
+}
+
+/// Shuffles the genes in a random section of the genome
+///
+/// Keeps every gene, so suits permutations. Genomes shorter than 2 are left
+/// alone.
+///
+/// # Examples
+/// ```
+/// use dears::mutation::*;
+/// let mut vals = vec![0, 1, 2, 3, 4];
+/// Scramble.mutate(&mut vals);
+/// vals.sort();
+/// assert_eq!(vals, vec![0, 1, 2, 3, 4]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Scramble;
+
+impl<G: Genome + ?Sized> Mutator<G> for Scramble {
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore) {
+        if genome.len() < 2 {
+            return;
+        }
+        let (start, end) = two_points(genome.len(), rng);
+        for i in start..end {
+            genome.swap(i, rng.gen_range(i..=end));
+        }
+    }
+}
+
+impl Mutator<Permutation> for Scramble {
+    fn mutate_with(&self, genome: &mut Permutation, rng: &mut dyn RngCore) {
+        genome.rearrange(|genome| self.mutate_with(genome, rng));
+    }
+}
+
 /// Flips random items in a slice of `bool`
 ///
 /// Modifies an individual (a slice of bool) in place, flipping individual values with
@@ -99,29 +184,203 @@ impl<T: Clone> Mutator<[T]> for Shuffle {
 /// let mutator = FlipBit { indpb: 0.5 };
 /// mutator.mutate(&mut vals);
 /// // Vals has now been mutated!
-/// println!("Flipped:  {:?}", vals);
+/// assert_eq!(vals.len(), 4);
 /// ```
 pub struct FlipBit {
     pub indpb: f64,
 }
 
-impl Mutator<[bool]> for FlipBit {
-    fn mutate(&self, genome: &mut [bool]) {
-        let mut rng = rand::thread_rng();
-        for i in 0..(genome.len()) {
+impl<G: AsMut<[bool]> + ?Sized> Mutator<G> for FlipBit {
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore) {
+        for gene in genome.as_mut().iter_mut() {
             if rng.gen::<f64>() < self.indpb {
-                genome[i] = !genome[i];
+                *gene = !*gene;
             }
         }
     }
 }
 
+/// Bounded polynomial mutation for real-valued genomes, as used by NSGA-II
+///
+/// Each gene is perturbed with probability `indpb` by an amount drawn from a
+/// polynomial distribution, scaled to the bounds `[low, up]` and kept within
+/// them. A bigger `eta` makes smaller perturbations. Where `low` and `up` are
+/// equal the gene is set to that value.
+///
+/// On a [`BoundedVec`] it uses its own `low` and `up`, ignoring the bounds the
+/// genome carries; [`Polynomial::own_bounds`] uses those instead.
+///
+/// # Examples
+/// ```
+/// use dears::mutation::*;
+/// let mut vals = vec![0.0, 0.5, 1.0];
+/// Polynomial { eta: 20.0, low: 0.0, up: 1.0, indpb: 1.0 }.mutate(&mut vals);
+/// assert!(vals.iter().all(|x| (0.0..=1.0).contains(x)));
+/// ```
+pub struct Polynomial {
+    pub eta: f64,
+    pub low: f64,
+    pub up: f64,
+    pub indpb: f64,
+}
+
+impl Polynomial {
+    /// Polynomial mutation within the bounds each [`BoundedVec`] carries
+    pub fn own_bounds(eta: f64, indpb: f64) -> OwnBounds<Polynomial> {
+        OwnBounds { op: Polynomial { eta, low: f64::NEG_INFINITY, up: f64::INFINITY, indpb } }
+    }
+
+    fn mutate_gene(&self, gene: &mut f64, low: f64, up: f64, rng: &mut dyn RngCore) {
+        if rng.gen::<f64>() >= self.indpb {
+            return;
+        }
+        let range = up - low;
+        if range <= 0.0 {
+            *gene = low;
+            return;
+        }
+        let power = 1.0 / (self.eta + 1.0);
+        let roll = rng.gen::<f64>();
+        let delta_q = if roll < 0.5 {
+            let xy = 1.0 - (*gene - low) / range;
+            (2.0 * roll + (1.0 - 2.0 * roll) * xy.powf(self.eta + 1.0)).powf(power) - 1.0
+        } else {
+            let xy = 1.0 - (up - *gene) / range;
+            1.0 - (2.0 * (1.0 - roll) + 2.0 * (roll - 0.5) * xy.powf(self.eta + 1.0)).powf(power)
+        };
+        *gene = (*gene + delta_q * range).clamp(low, up);
+    }
+}
+
+impl<G: AsMut<[f64]> + ?Sized> Mutator<G> for Polynomial {
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore) {
+        for gene in genome.as_mut().iter_mut() {
+            self.mutate_gene(gene, self.low, self.up, rng);
+        }
+    }
+}
+
+impl Mutator<BoundedVec> for OwnBounds<Polynomial> {
+    fn mutate_with(&self, genome: &mut BoundedVec, rng: &mut dyn RngCore) {
+        for idx in 0..genome.len() {
+            let (low, up) = genome.bounds(idx);
+            self.op.mutate_gene(genome.gene_mut(idx), low, up, rng);
+        }
+    }
+}
+
+/// How [`Bounded`] brings genes that left their bounds back inside
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Boundary {
+    /// Moves the gene to the nearest bound
+    #[default]
+    Clamp,
+    /// Mirrors the gene back off the bound it crossed, as often as needed
+    Reflect,
+}
+
+impl Boundary {
+    /// Brings `x` into `[low, up]`
+    pub fn apply(&self, x: f64, low: f64, up: f64) -> f64 {
+        match self {
+            Boundary::Clamp => x.clamp(low, up),
+            Boundary::Reflect => {
+                let range = up - low;
+                if range <= 0.0 {
+                    return low;
+                }
+                let folded = (x - low).rem_euclid(2.0 * range);
+                low + if folded > range { 2.0 * range - folded } else { folded }
+            }
+        }
+    }
+}
+
+/// Keeps every gene of a real-valued genome within `[low, up]` after mutating it
+///
+/// On a [`BoundedVec`] it uses its own `low` and `up`, ignoring the bounds the
+/// genome carries; [`Bounded::own_bounds`] uses those instead.
+///
+/// # Examples
+/// ```
+/// use dears::mutation::*;
+/// let mut vals = vec![0.9, 0.1];
+/// let wide = Gaussian { mu: 0.0, sigma: 10.0, indpb: 1.0 };
+/// Bounded { mutator: wide, low: 0.0, up: 1.0, boundary: Boundary::Reflect }.mutate(&mut vals);
+/// assert!(vals.iter().all(|x| (0.0..=1.0).contains(x)));
+/// ```
+pub struct Bounded<M> {
+    pub mutator: M,
+    pub low: f64,
+    pub up: f64,
+    pub boundary: Boundary,
+}
+
+impl<M> Bounded<M> {
+    /// Keeps every gene within the bounds each [`BoundedVec`] carries
+    pub fn own_bounds(mutator: M, boundary: Boundary) -> OwnBounds<Bounded<M>> {
+        OwnBounds { op: Bounded { mutator, low: f64::NEG_INFINITY, up: f64::INFINITY, boundary } }
+    }
+}
+
+impl<G: AsMut<[f64]> + ?Sized, M: Mutator<G>> Mutator<G> for Bounded<M> {
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore) {
+        self.mutator.mutate_with(genome, rng);
+        for gene in genome.as_mut().iter_mut() {
+            *gene = self.boundary.apply(*gene, self.low, self.up);
+        }
+    }
+}
+
+impl<M: Mutator<BoundedVec>> Mutator<BoundedVec> for OwnBounds<Bounded<M>> {
+    fn mutate_with(&self, genome: &mut BoundedVec, rng: &mut dyn RngCore) {
+        self.op.mutator.mutate_with(genome, rng);
+        for idx in 0..genome.len() {
+            let (low, up) = genome.bounds(idx);
+            let gene = genome.gene_mut(idx);
+            *gene = self.op.boundary.apply(*gene, low, up);
+        }
+    }
+}
+
+/// Replaces the whole genome with a fresh one from an initializer
+///
+/// # Examples
+/// ```
+/// use dears::initializer::RandomPermutation;
+/// use dears::mutation::*;
+/// let mut genome = vec![0, 1, 2, 3];
+/// Reset { init: RandomPermutation { len: 6 } }.mutate(&mut genome);
+/// assert_eq!(genome.len(), 6);
+/// ```
+pub struct Reset<I> {
+    pub init: I,
+}
+
+impl<G, I: Init<G>> Mutator<G> for Reset<I> {
+    fn mutate_with(&self, genome: &mut G, rng: &mut dyn RngCore) {
+        *genome = self.init.init_with(rng);
+    }
+}
+
 // NB: These tests don't verify output, they just check the code compiles & runs
 // Run the tests manually and view the output to ensure the values look consistent
 #[cfg(test)]
 mod tests {
+    use crate::genome::BoundedVec;
     use crate::mutation::*;
 
+    #[test]
+    fn polynomial_with_equal_bounds_sets_the_bound() {
+        let mut vals = vec![0.5, 2.0];
+        Polynomial { eta: 20.0, low: 1.0, up: 1.0, indpb: 1.0 }.mutate(&mut vals);
+        assert_eq!(vals, vec![1.0, 1.0]);
+        let mut genome = BoundedVec::new(vec![0.5, 3.0], vec![0.0, 3.0], vec![1.0, 3.0]).unwrap();
+        Polynomial::own_bounds(20.0, 1.0).mutate(&mut genome);
+        assert!((0.0..=1.0).contains(&genome.values()[0]));
+        assert_eq!(genome.values()[1], 3.0);
+    }
+
     #[test]
     fn gaussian() {
         let mut test_input = vec![1.0, 2.0, 3.0, 4.0];
@@ -142,6 +401,15 @@ mod tests {
         println!("Shuffle:   {:?}", test_input);
     }
 
+    #[test]
+    fn boundary_handling() {
+        assert_eq!(Boundary::Clamp.apply(1.5, 0.0, 1.0), 1.0);
+        assert_eq!(Boundary::Reflect.apply(1.25, 0.0, 1.0), 0.75);
+        assert_eq!(Boundary::Reflect.apply(-0.25, 0.0, 1.0), 0.25);
+        assert_eq!(Boundary::Reflect.apply(2.5, 0.0, 1.0), 0.5);
+        assert_eq!(Boundary::Reflect.apply(0.5, 0.0, 1.0), 0.5);
+    }
+
     #[test]
     fn flip_bit() {
         let mut test_input = vec![false; 4];
@@ -0,0 +1,296 @@
+use std::cell::RefCell;
+
+use rand::RngCore;
+
+use crate::algorithms::{Control, EvolutionObserver};
+use crate::crossover::Crossover;
+use crate::mutation::Mutator;
+use crate::population::Population;
+use crate::selection::*;
+use crate::stats::StatsRecord;
+
+/// Fitness sharing, dividing each fitness by the crowding of its niche
+///
+/// The niche count of an individual is `sum_j sh(dist(i, j))` over the whole
+/// population, where `sh(d) = 1 - (d / sigma_share)^alpha` for `d < sigma_share`
+/// and 0 otherwise. Individuals always share with themselves, so the count is
+/// at least 1. Fitnesses should be non-negative and bigger-is-better.
+///
+/// The niche count and adjusted fitness buffers are kept between calls, so one
+/// `FitnessSharing` can be reused every generation without reallocating.
+///
+/// # Examples
+/// ```
+/// use dears::niching::FitnessSharing;
+/// let genomes = vec![0.0, 0.1, 5.0];
+/// let fitnesses = vec![1.0, 1.0, 1.0];
+/// let mut sharing = FitnessSharing::new(1.0, |a: &f64, b: &f64| (a - b).abs());
+/// let adjusted = sharing.adjust(&genomes, &fitnesses);
+/// assert!(adjusted[2] > adjusted[0]);
+/// ```
+pub struct FitnessSharing<D> {
+    pub sigma_share: f64,
+    pub alpha: f64,
+    pub dist: D,
+    niche_counts: Vec<f64>,
+    adjusted: Vec<f64>,
+}
+
+impl<D> FitnessSharing<D> {
+    /// Creates a sharing pre-processor with the usual triangular sharing function (`alpha = 1`)
+    pub fn new(sigma_share: f64, dist: D) -> Self {
+        FitnessSharing {
+            sigma_share,
+            alpha: 1.0,
+            dist,
+            niche_counts: Vec::new(),
+            adjusted: Vec::new(),
+        }
+    }
+
+    /// Returns the fitnesses divided by their niche counts
+    pub fn adjust<G>(&mut self, genomes: &[G], fitnesses: &[f64]) -> &[f64]
+    where
+        D: Fn(&G, &G) -> f64
+    {
+        let len = genomes.len();
+        assert_eq!(len, fitnesses.len(), "Need exactly one fitness per genome");
+        self.niche_counts.clear();
+        self.niche_counts.resize(len, 1.0);
+        // Distances are symmetric, so only compute each pair once
+        for i in 0..len {
+            for j in (i + 1)..len {
+                let d = (self.dist)(&genomes[i], &genomes[j]);
+                if d < self.sigma_share {
+                    let share = 1.0 - (d / self.sigma_share).powf(self.alpha);
+                    self.niche_counts[i] += share;
+                    self.niche_counts[j] += share;
+                }
+            }
+        }
+        self.adjusted.clear();
+        self.adjusted.extend(fitnesses.iter().zip(&self.niche_counts).map(|(f, m)| f / m));
+        &self.adjusted
+    }
+
+    /// Wraps `inner` so it selects on shared fitnesses of `genomes`
+    pub fn selector<'a, G, S>(&'a mut self, genomes: &'a [G], inner: S) -> SharedSelection<'a, G, D, S> {
+        SharedSelection { sharing: RefCell::new(self), genomes, inner }
+    }
+}
+
+/// A selector that applies [`FitnessSharing`] before delegating to `inner`
+///
+/// Created by [`FitnessSharing::selector`] for a given set of genomes; the
+/// fitnesses passed to it must line up with those genomes.
+pub struct SharedSelection<'a, G, D, S> {
+    sharing: RefCell<&'a mut FitnessSharing<D>>,
+    genomes: &'a [G],
+    pub inner: S,
+}
+
+impl<G, D, S> SelectOne<f64> for SharedSelection<'_, G, D, S>
+where
+    D: Fn(&G, &G) -> f64,
+    S: SelectMany<f64>
+{
+    fn select_with(&self, fitnesses: &[f64], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[f64], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut sharing = self.sharing.borrow_mut();
+        let adjusted = sharing.adjust(self.genomes, fitnesses);
+        self.inner.select_n_with(adjusted, n, rng)
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[f64], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        let mut sharing = self.sharing.borrow_mut();
+        let adjusted = sharing.adjust(self.genomes, fitnesses);
+        self.inner.select_n_distinct_with(adjusted, n, rng)
+    }
+}
+
+/// NEAT-style speciation: clusters genomes by distance so each species breeds separately
+///
+/// Each generation, every genome joins the first species whose
+/// representative is within `threshold` of it by `dist`, or founds a new
+/// species if there's none. Representatives are checked in the order the
+/// species were founded, starting with the ones kept from the previous
+/// generation, so species persist from one generation to the next; each
+/// species' first member then represents it in the next generation, and
+/// species left empty die out.
+///
+/// Within a species fitnesses are shared explicitly, each divided by the
+/// species' size, so the adjusted fitnesses of a species add up to its mean
+/// fitness. Offspring are allocated to species in proportion to that.
+/// Fitnesses should be bigger-is-better; negative and NaN ones count as 0.
+///
+/// As an [`EvolutionObserver`] it picks parents for
+/// [`evolve_with_hooks`](crate::algorithms::evolve_with_hooks) in place of
+/// the population's selector: each species gets its allocation of parents,
+/// chosen by the selector from that species alone, so parents are mostly
+/// paired with their own species. The species sizes are logged as
+/// `"species_sizes"`, one value per species.
+///
+/// # Examples
+/// ```
+/// use dears::niching::Speciation;
+/// let mut speciation = Speciation::new(1.0, |a: &f64, b: &f64| (a - b).abs());
+/// let genomes = vec![0.0, 0.5, 10.0, 0.2];
+/// assert_eq!(speciation.speciate(&genomes), &[vec![0, 1, 3], vec![2]]);
+/// assert_eq!(speciation.allocate(&[1.0, 1.0, 3.0, 1.0], 8), vec![2, 6]);
+/// ```
+pub struct Speciation<G, D> {
+    pub threshold: f64,
+    pub dist: D,
+    representatives: Vec<G>,
+    species: Vec<Vec<usize>>,
+}
+
+impl<G, D> Speciation<G, D> {
+    pub fn new(threshold: f64, dist: D) -> Self {
+        Speciation { threshold, dist, representatives: Vec::new(), species: Vec::new() }
+    }
+
+    /// The members of each species from the last [`Speciation::speciate`]
+    pub fn species(&self) -> &[Vec<usize>] {
+        &self.species
+    }
+
+    /// Each fitness divided by the size of its species
+    pub fn shared_fitnesses(&self, fitnesses: &[f64]) -> Vec<f64> {
+        let mut shared = vec![0.0; fitnesses.len()];
+        for members in &self.species {
+            for &i in members {
+                shared[i] = fitnesses[i].max(0.0) / members.len() as f64;
+            }
+        }
+        shared
+    }
+
+    /// Splits `n` offspring between the species in proportion to their total shared fitness
+    ///
+    /// Uses the largest remainder method, so the counts add up to exactly
+    /// `n`, with ties going to the earlier species. If no species has any
+    /// fitness, they're allocated in proportion to their sizes instead.
+    pub fn allocate(&self, fitnesses: &[f64], n: usize) -> Vec<usize> {
+        let shared = self.shared_fitnesses(fitnesses);
+        let mut shares: Vec<f64> = self.species.iter()
+            .map(|members| members.iter().map(|&i| shared[i]).sum())
+            .collect();
+        if shares.iter().sum::<f64>() <= 0.0 {
+            shares = self.species.iter().map(|members| members.len() as f64).collect();
+        }
+        let total: f64 = shares.iter().sum();
+        let exact: Vec<f64> = shares.iter().map(|share| share / total * n as f64).collect();
+        let mut counts: Vec<usize> = exact.iter().map(|e| e.floor() as usize).collect();
+        let left = n - counts.iter().sum::<usize>();
+        let mut order: Vec<usize> = (0..counts.len()).collect();
+        order.sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+        for &i in order.iter().take(left) {
+            counts[i] += 1;
+        }
+        counts
+    }
+}
+
+impl<G: Clone, D: Fn(&G, &G) -> f64> Speciation<G, D> {
+    /// Assigns every genome to a species, returning the members of each
+    pub fn speciate(&mut self, genomes: &[G]) -> &[Vec<usize>] {
+        let mut species: Vec<Vec<usize>> = vec![Vec::new(); self.representatives.len()];
+        for (i, genome) in genomes.iter().enumerate() {
+            match self.representatives.iter().position(|rep| (self.dist)(rep, genome) <= self.threshold) {
+                Some(s) => species[s].push(i),
+                None => {
+                    self.representatives.push(genome.clone());
+                    species.push(vec![i]);
+                }
+            }
+        }
+        species.retain(|members| !members.is_empty());
+        self.representatives = species.iter().map(|members| genomes[members[0]].clone()).collect();
+        self.species = species;
+        &self.species
+    }
+}
+
+impl<G, M, C, S, D> EvolutionObserver<G, M, C, S, f64> for Speciation<G, D>
+where
+    G: Clone,
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<f64>,
+    D: Fn(&G, &G) -> f64
+{
+    fn select_parents(&mut self, pop: &Population<G, M, C, S, f64>, n: usize, rng: &mut dyn RngCore)
+                      -> Option<Vec<usize>> {
+        self.speciate(pop.individuals());
+        let counts = self.allocate(pop.fitnesses(), n);
+        let mut parents = Vec::with_capacity(n);
+        for (members, count) in self.species.iter().zip(counts) {
+            let fitnesses: Vec<f64> = members.iter().map(|&i| pop.fitnesses()[i]).collect();
+            parents.extend(pop.selector().select_n_with(&fitnesses, count, rng).into_iter().map(|i| members[i]));
+        }
+        Some(parents)
+    }
+
+    fn on_generation_end(&mut self, _pop: &mut Population<G, M, C, S, f64>, stats: &mut StatsRecord) -> Control {
+        stats.insert("species_sizes", self.species.iter().map(|members| members.len() as f64).collect());
+        Control::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::niching::*;
+
+    fn distance(a: &f64, b: &f64) -> f64 {
+        (a - b).abs()
+    }
+
+    #[test]
+    fn sharing_equalises_clusters() {
+        // A crowded cluster around 0 and a sparse one around 10
+        let genomes = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 10.0, 10.0];
+        let fitnesses = vec![1.0; 10];
+        let mut sharing = FitnessSharing::new(1.0, distance);
+        let adjusted = sharing.adjust(&genomes, &fitnesses).to_vec();
+        let crowded: f64 = adjusted[..8].iter().sum();
+        let sparse: f64 = adjusted[8..].iter().sum();
+        assert!((crowded - sparse).abs() < 1e-12, "{} vs {}", crowded, sparse);
+
+        // The buffers are reused on the next generation
+        let adjusted = sharing.adjust(&genomes[..3], &fitnesses[..3]);
+        assert_eq!(adjusted, &[1.0 / 3.0; 3]);
+    }
+
+    #[test]
+    fn shared_selector_prefers_sparse_niche() {
+        let genomes = vec![0.0, 0.1, 0.2, 0.3, 10.0];
+        let fitnesses = vec![1.0, 1.0, 1.0, 1.0, 0.9];
+        assert_ne!(SelBest::default().select(&fitnesses), 4);
+        let mut sharing = FitnessSharing::new(1.0, distance);
+        let selector = sharing.selector(&genomes, SelBest::default());
+        assert_eq!(selector.select(&fitnesses), 4);
+        assert_eq!(selector.select_n_distinct(&fitnesses, 2).unwrap()[0], 4);
+    }
+
+    #[test]
+    fn separated_clusters_form_two_species() {
+        let genomes = vec![0.0, 10.0, 0.3, 10.2, -0.1, 9.9, 0.1, 10.1];
+        let fitnesses = vec![1.0, 3.0, 1.0, 3.0, 1.0, 3.0, 1.0, 3.0];
+        let mut speciation = Speciation::new(1.0, distance);
+        let species = speciation.speciate(&genomes).to_vec();
+        assert_eq!(species, vec![vec![0, 2, 4, 6], vec![1, 3, 5, 7]]);
+        // Means of 1 and 3 split the offspring 1:3
+        assert_eq!(speciation.allocate(&fitnesses, 20), vec![5, 15]);
+        assert_eq!(speciation.allocate(&fitnesses, 7).iter().sum::<usize>(), 7);
+
+        // Representatives carry over, so the species keep their order
+        let moved = vec![10.0, 0.0, 10.1];
+        assert_eq!(speciation.speciate(&moved), &[vec![1], vec![0, 2]]);
+        assert_eq!(speciation.allocate(&[0.0, f64::NAN, -1.0], 4), vec![1, 3]);
+    }
+}
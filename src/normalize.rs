@@ -0,0 +1,238 @@
+use std::cmp::Ordering;
+
+use crate::algorithms::EvolutionObserver;
+use crate::crossover::Crossover;
+use crate::mutation::Mutator;
+use crate::population::Population;
+use crate::selection::SelectMany;
+
+/// Values rescaled to `[0, 1]`, the smallest finite one to 0 and the largest to 1
+///
+/// If every finite value is the same they all become 0.5. Infinities become
+/// 0 or 1 with the ends of the finite range, and NaN stays NaN.
+///
+/// # Examples
+/// ```
+/// use dears::normalize::min_max;
+/// assert_eq!(min_max(&[2.0, 4.0, 3.0]), vec![0.0, 1.0, 0.5]);
+/// assert_eq!(min_max(&[7.0, 7.0]), vec![0.5, 0.5]);
+/// ```
+pub fn min_max(values: &[f64]) -> Vec<f64> {
+    let finite = || values.iter().copied().filter(|v| v.is_finite());
+    let min = finite().fold(f64::INFINITY, f64::min);
+    let max = finite().fold(f64::NEG_INFINITY, f64::max);
+    values.iter().map(|&v| match v {
+        v if v == f64::INFINITY => 1.0,
+        v if v == f64::NEG_INFINITY => 0.0,
+        v if v.is_nan() => f64::NAN,
+        _ if max == min => 0.5,
+        v => (v - min) / (max - min),
+    }).collect()
+}
+
+/// How many population standard deviations each value is from the mean
+///
+/// The mean and standard deviation are taken over the finite values. If they
+/// are all the same they all become 0. Infinities and NaN are kept as they are.
+///
+/// # Examples
+/// ```
+/// use dears::normalize::z_score;
+/// assert_eq!(z_score(&[1.0, 3.0]), vec![-1.0, 1.0]);
+/// assert_eq!(z_score(&[7.0, 7.0]), vec![0.0, 0.0]);
+/// ```
+pub fn z_score(values: &[f64]) -> Vec<f64> {
+    let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let mean = finite.iter().sum::<f64>() / finite.len() as f64;
+    let std = (finite.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / finite.len() as f64).sqrt();
+    values.iter().map(|&v| match v {
+        v if !v.is_finite() => v,
+        _ if std == 0.0 => 0.0,
+        v => (v - mean) / std,
+    }).collect()
+}
+
+/// Each value's rank, the smallest being 0 and the largest 1, evenly spaced in between
+///
+/// Tied values share the mean of their ranks, so if every value is the same
+/// they all become 0.5, as does a single value. Infinities rank like any
+/// other value. NaN stays NaN and doesn't count towards the ranks of the rest.
+///
+/// # Examples
+/// ```
+/// use dears::normalize::rank_transform;
+/// assert_eq!(rank_transform(&[10.0, -5.0, 10.0]), vec![0.75, 0.0, 0.75]);
+/// assert_eq!(rank_transform(&[f64::INFINITY, 1.0, 2.0]), vec![1.0, 0.0, 0.5]);
+/// ```
+pub fn rank_transform(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).filter(|&i| !values[i].is_nan()).collect();
+    order.sort_by(|&a, &b| values[a].partial_cmp(&values[b]).unwrap_or(Ordering::Equal));
+    let mut ranks = vec![f64::NAN; values.len()];
+    let last = order.len().saturating_sub(1) as f64;
+    let mut start = 0;
+    while start < order.len() {
+        let end = start + order[start..].iter().take_while(|&&i| values[i] == values[order[start]]).count();
+        let rank = if last == 0.0 { 0.5 } else { (start + end - 1) as f64 / 2.0 / last };
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// Which of the normalisations in this module to apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    /// [`min_max`]
+    MinMax,
+    /// [`z_score`]
+    ZScore,
+    /// [`rank_transform`]
+    Rank,
+}
+
+impl Normalization {
+    pub fn apply(&self, values: &[f64]) -> Vec<f64> {
+        match self {
+            Normalization::MinMax => min_max(values),
+            Normalization::ZScore => z_score(values),
+            Normalization::Rank => rank_transform(values),
+        }
+    }
+}
+
+/// A fitness together with its value normalised across the population
+///
+/// Compares by the raw value, so the hall of fame and the rank-based
+/// selectors see the fitness as evaluated, while converting to `f64` gives the
+/// normalised value, which is what [`Roulette`](crate::selection::Roulette)
+/// and the other fitness-proportionate selectors weigh individuals by. Both
+/// start out as the raw value until [`Normalizing`] fills in the other.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizedFitness {
+    pub raw: f64,
+    pub normalized: f64,
+}
+
+impl From<f64> for NormalizedFitness {
+    fn from(raw: f64) -> Self {
+        NormalizedFitness { raw, normalized: raw }
+    }
+}
+
+impl From<NormalizedFitness> for f64 {
+    fn from(fitness: NormalizedFitness) -> Self {
+        fitness.normalized
+    }
+}
+
+impl PartialEq for NormalizedFitness {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl PartialOrd for NormalizedFitness {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.raw.partial_cmp(&other.raw)
+    }
+}
+
+/// An [`EvolutionObserver`] that renormalises the whole population's fitnesses every time any are evaluated
+///
+/// Evaluate to [`NormalizedFitness`], for instance with
+/// `|g| NormalizedFitness::from(f(g))`, and every fitness's `normalized`
+/// value is recomputed from all the raw values of the population.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{evolve_with_hooks, Elitism};
+/// use dears::archive::HallOfFame;
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::normalize::*;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::{Direction, Roulette, Scaling};
+/// use dears::termination::MaxGenerations;
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(10, || vec![false; 8])
+///     .mutator(FlipBit { indpb: 0.2 })
+///     .crossover(OnePoint)
+///     .selector(Roulette { scaling: Scaling::None, direction: Direction::Maximize })
+///     .build()
+///     .unwrap();
+/// // Raw fitnesses far from zero, which roulette alone would barely tell apart
+/// let eval = |g: &Vec<bool>| NormalizedFitness::from(1000.0 + g.iter().filter(|&&b| b).count() as f64);
+/// let mut hof = HallOfFame::new(1);
+/// let mut normalizing = Normalizing { normalization: Normalization::MinMax };
+/// evolve_with_hooks(&mut pop, MaxGenerations(5), 0.5, 0.5, Elitism::default(), eval, None, Some(&mut hof),
+///                   &mut normalizing, &mut rand::thread_rng());
+/// assert!(pop.fitnesses().iter().all(|f| (0.0..=1.0).contains(&f.normalized)));
+/// assert!(hof.best().unwrap().1.raw >= 1000.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Normalizing {
+    pub normalization: Normalization,
+}
+
+impl<G, M, C, S> EvolutionObserver<G, M, C, S, NormalizedFitness> for Normalizing
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<NormalizedFitness>
+{
+    fn on_evaluated(&mut self, pop: &mut Population<G, M, C, S, NormalizedFitness>) {
+        // Individuals a budget left unevaluated still hold a stale fitness, which mustn't move the bounds
+        let invalid = pop.invalid_indices();
+        let valid: Vec<usize> = (0..pop.len()).filter(|i| invalid.binary_search(i).is_err()).collect();
+        let raw: Vec<f64> = valid.iter().map(|&i| pop.fitnesses()[i].raw).collect();
+        for (&i, normalized) in valid.iter().zip(self.normalization.apply(&raw)) {
+            pop.fitnesses_mut()[i].normalized = normalized;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::archive::HallOfFame;
+    use crate::normalize::*;
+
+    #[test]
+    fn degenerate_and_infinite_inputs() {
+        for normalization in [Normalization::MinMax, Normalization::ZScore, Normalization::Rank] {
+            assert!(normalization.apply(&[]).is_empty());
+        }
+        assert_eq!(min_max(&[3.0; 4]), vec![0.5; 4]);
+        assert_eq!(z_score(&[3.0; 4]), vec![0.0; 4]);
+        assert_eq!(rank_transform(&[3.0; 4]), vec![0.5; 4]);
+        assert_eq!(min_max(&[3.0]), vec![0.5]);
+        assert_eq!(rank_transform(&[3.0]), vec![0.5]);
+
+        let values = [f64::NEG_INFINITY, 1.0, 3.0, f64::INFINITY, 2.0];
+        assert_eq!(min_max(&values), vec![0.0, 0.0, 1.0, 1.0, 0.5]);
+        let z = z_score(&values);
+        assert_eq!((z[0], z[3], z[4]), (f64::NEG_INFINITY, f64::INFINITY, 0.0));
+        assert!((z[2] - 1.5f64.sqrt()).abs() < 1e-12);
+        assert_eq!(rank_transform(&values), vec![0.0, 0.25, 0.75, 1.0, 0.5]);
+        assert_eq!(min_max(&[f64::INFINITY, f64::INFINITY]), vec![1.0, 1.0]);
+
+        let with_nan = [2.0, f64::NAN, 1.0];
+        for normalized in [min_max(&with_nan), z_score(&with_nan), rank_transform(&with_nan)] {
+            assert!(normalized[1].is_nan());
+            assert!(normalized[2] < normalized[0]);
+        }
+    }
+
+    #[test]
+    fn hall_of_fame_ranks_by_raw_fitness() {
+        // Normalised values left over from an earlier population, in the opposite order
+        let low = NormalizedFitness { raw: 1.0, normalized: 1.0 };
+        let high = NormalizedFitness { raw: 5.0, normalized: 0.0 };
+        let fitnesses = [low, high];
+        let mut hof = HallOfFame::new(1);
+        hof.update(&["low", "high"], &fitnesses);
+        assert_eq!(hof.best().map(|(g, f)| (*g, f.raw)), Some(("high", 5.0)));
+        assert_eq!(f64::from(fitnesses[1]), 0.0);
+    }
+}
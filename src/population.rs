@@ -1,11 +1,139 @@
-use rand::thread_rng;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use rand::{Rng, RngCore, SeedableRng};
+#[cfg(feature = "parallel")]
+use rand::rngs::SmallRng;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+
+#[cfg(feature = "serde")]
+use crate::checkpoint::CheckpointError;
+use crate::cache::{answered_by_cache, cache_hits};
 use crate::crossover::*;
+use crate::csv::{numbered, write_record, FitnessColumns};
+use crate::initializer::Init;
 use crate::mutation::*;
+use crate::rng::{Pcg64, ThreadLocal};
+#[cfg(feature = "parallel")]
+use crate::rng::RngPool;
 use crate::selection::*;
 
-pub type Fitness = [f64; 3];
+/// Calls `eval` on `genome`, returning the result and the number of fitness function calls it cost
+///
+/// That's one, unless a [`FitnessCache`](crate::cache::FitnessCache) answered it.
+fn call<G, T>(eval: impl FnOnce(&G) -> T, genome: &G) -> (T, usize) {
+    let (result, cached) = answered_by_cache(|| eval(genome));
+    (result, !cached as usize)
+}
+
+/// Errors from [`PopulationBuilder::build`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// No individuals were given, or `init_with` was asked for zero
+    EmptyPopulation,
+    /// The named operator was never set
+    MissingOperator(&'static str),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::EmptyPopulation => write!(f, "a population needs at least one individual"),
+            BuildError::MissingOperator(name) => write!(f, "no {} was given for the population", name),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// How many of the seeds given to [`PopulationBuilder::seed_individuals`] made it into the population
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Seeding {
+    /// Every seed was kept
+    Kept(usize),
+    /// There were more seeds than places, so the ones past the population size were dropped
+    Truncated { given: usize, kept: usize },
+}
+
+/// [`Population::retain`] would have left fewer individuals than allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetainError {
+    pub kept: usize,
+    pub min: usize,
+}
+
+impl fmt::Display for RetainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Only {} individuals would be kept, fewer than the minimum of {}", self.kept, self.min)
+    }
+}
 
+impl std::error::Error for RetainError {}
+
+/// A batch fitness function from [`Population::evaluate_batch`] returned the wrong number of fitnesses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchLengthError {
+    pub expected: usize,
+    pub returned: usize,
+}
+
+impl fmt::Display for BatchLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Batch evaluation of {} individuals returned {} fitnesses", self.expected, self.returned)
+    }
+}
+
+impl std::error::Error for BatchLengthError {}
+
+/// What [`Population::try_evaluate`] does when the fitness function returns an error
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailurePolicy<F> {
+    /// Gives up at the first error and returns it
+    Propagate,
+    /// Gives the individual this fitness, which should be one that loses to any real one
+    AssignWorst(F),
+    /// Calls the fitness function up to `attempts` more times, then returns the last error
+    Retry { attempts: usize },
+}
+
+/// What a fallible evaluation got through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Evaluation {
+    /// Individuals given a fitness
+    pub evaluated: usize,
+    /// Calls to the fitness function that returned an error, retries included
+    pub failures: usize,
+}
+
+/// A population from [`PopulationBuilder::build_seeded`], with what became of its seeds
+pub type SeededPopulation<G, M, C, S, F> = (Population<G, M, C, S, F>, Seeding);
+
+/// A set of individuals together with the operators that evolve them
+///
+/// Fitnesses start empty and are filled in once the individuals are evaluated.
+/// The population owns a generator, which the methods that aren't handed one
+/// draw from and [`Population::run`] lends to an evolve loop. It's a [`Pcg64`]
+/// when the population is given a seed, through [`PopulationBuilder::rng_seed`]
+/// or [`Population::reseed`], and the thread-local generator otherwise. Checkpoints save a seeded generator's
+/// state, so a resumed population goes on drawing where it left off.
+///
+/// # Examples
+/// ```
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::selection::TournamentSelection;
+/// let pop = PopulationBuilder::new()
+///     .init_with(10, || vec![false; 8])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build::<f64>()
+///     .unwrap();
+/// assert_eq!(pop.len(), 10);
+/// ```
 pub struct Population<G, M, C, S, F>
 where
     M: Mutator<G>,
@@ -15,9 +143,16 @@ where
 {
     individuals: Vec<G>,
     fitnesses: Vec<F>,
+    /// Whether each fitness is up to date with its genome
+    valid: Vec<bool>,
+    ages: Vec<u32>,
     mutator: M,
     crossover: C,
-    selector: S
+    selector: S,
+    evaluations: usize,
+    generation: usize,
+    /// The population's own generator, `None` for the thread-local one
+    rng: Mutex<Option<Pcg64>>,
 }
 
 impl<G, M, C, S, F> Population<G, M, C, S, F>
@@ -27,23 +162,1320 @@ where
     S: SelectMany<F>,
     F: Clone
 {
-    fn mutate_with_chance(&mut self, indpb: f64) {
-        self.individuals.iter_mut().for_each(|x| {
-            if rand::random::<f64>() > indpb {
-                self.mutator.mutate(x);
+    /// Creates an unevaluated population, panicking if `individuals` is empty
+    pub fn new(individuals: Vec<G>, mutator: M, crossover: C, selector: S) -> Self {
+        assert!(!individuals.is_empty(), "{}", BuildError::EmptyPopulation);
+        let ages = vec![0; individuals.len()];
+        Population {
+            individuals,
+            fitnesses: Vec::new(),
+            valid: Vec::new(),
+            ages,
+            mutator,
+            crossover,
+            selector,
+            evaluations: 0,
+            generation: 0,
+            rng: Mutex::new(None),
+        }
+    }
+
+    /// Puts back a population's saved state along with freshly supplied operators
+    ///
+    /// Fitnesses may be missing if the population was saved before it was
+    /// evaluated, and ages missing from older checkpoints start again from 0.
+    /// Anything else that doesn't line up with the individuals is rejected.
+    #[cfg(feature = "serde")]
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn restore(individuals: Vec<G>, fitnesses: Vec<F>, mut ages: Vec<u32>, evaluations: usize,
+                          generation: usize, rng: Option<Pcg64>, mutator: M, crossover: C, selector: S)
+                          -> Result<Self, CheckpointError> {
+        let len = individuals.len();
+        if len == 0 {
+            return Err(CheckpointError::EmptyPopulation);
+        }
+        let (n_fitnesses, n_ages) = (fitnesses.len(), ages.len());
+        if ![0, len].contains(&n_fitnesses) || ![0, len].contains(&n_ages) {
+            return Err(CheckpointError::LengthMismatch { individuals: len, fitnesses: n_fitnesses, ages: n_ages });
+        }
+        ages.resize(len, 0);
+        let valid = vec![true; fitnesses.len()];
+        let rng = Mutex::new(rng);
+        Ok(Population {
+            individuals, fitnesses, valid, ages, mutator, crossover, selector, evaluations, generation, rng,
+        })
+    }
+
+    /// Makes the population's own generator a [`Pcg64`] seeded with `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        *self.rng.get_mut().unwrap() = Some(Pcg64::seed_from_u64(seed));
+    }
+
+    /// The state of the population's own generator, `None` if it's the thread-local one
+    pub fn rng_state(&self) -> Option<Pcg64> {
+        self.rng.lock().unwrap().clone()
+    }
+
+    /// Calls `f` with the population and its own generator, for an evolve loop to draw from
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::algorithms::{ea_simple, Elitism};
+    /// use dears::crossover::OnePoint;
+    /// use dears::mutation::FlipBit;
+    /// use dears::population::PopulationBuilder;
+    /// use dears::selection::TournamentSelection;
+    /// use dears::termination::MaxGenerations;
+    /// let ones = |g: &Vec<bool>| g.iter().filter(|&&b| b).count() as f64;
+    /// let run = || {
+    ///     let mut pop = PopulationBuilder::new()
+    ///         .init_with(10, || vec![false; 8])
+    ///         .mutator(FlipBit { indpb: 0.1 })
+    ///         .crossover(OnePoint)
+    ///         .selector(TournamentSelection::new(3).unwrap())
+    ///         .rng_seed(7)
+    ///         .build()
+    ///         .unwrap();
+    ///     pop.run(|pop, rng| ea_simple(pop, MaxGenerations(5), 0.5, 0.2, Elitism::none(), ones, None, None, rng));
+    ///     pop.fitnesses().to_vec()
+    /// };
+    /// assert_eq!(run(), run());
+    /// ```
+    pub fn run<T>(&mut self, f: impl FnOnce(&mut Self, &mut dyn RngCore) -> T) -> T {
+        match self.rng.get_mut().unwrap().take() {
+            Some(mut rng) => {
+                let result = f(self, &mut rng);
+                *self.rng.get_mut().unwrap() = Some(rng);
+                result
+            }
+            None => f(self, &mut ThreadLocal),
+        }
+    }
+
+    /// Calls `f` with the population's own generator
+    fn with_rng<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        match self.rng.lock().unwrap().as_mut() {
+            Some(rng) => f(rng),
+            None => f(&mut ThreadLocal),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.individuals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.individuals.is_empty()
+    }
+
+    pub fn individuals(&self) -> &[G] {
+        &self.individuals
+    }
+
+    /// Mutable access to the genomes, without being able to change how many there are
+    ///
+    /// Changed genomes keep their old fitnesses until they're passed to
+    /// [`Population::invalidate`] and re-evaluated.
+    pub fn individuals_mut(&mut self) -> &mut [G] {
+        &mut self.individuals
+    }
+
+    /// The fitness of each individual, or empty before evaluation
+    pub fn fitnesses(&self) -> &[F] {
+        &self.fitnesses
+    }
+
+    /// Mutable access to the fitnesses, for instance to rescale them together
+    ///
+    /// Changing a fitness here doesn't mark it as out of date.
+    pub fn fitnesses_mut(&mut self) -> &mut [F] {
+        &mut self.fitnesses
+    }
+
+    /// How many generations each individual's lineage has lasted
+    ///
+    /// Individuals start at age 0. The evolve loops make every survivor a
+    /// generation older, and give each child one more than its oldest parent.
+    pub fn ages(&self) -> &[u32] {
+        &self.ages
+    }
+
+    /// Panics unless there's exactly one age per individual
+    pub fn set_ages(&mut self, ages: Vec<u32>) {
+        assert_eq!(ages.len(), self.individuals.len(), "Need exactly one age per individual");
+        self.ages = ages;
+    }
+
+    /// Whether every individual has a fitness
+    pub fn is_evaluated(&self) -> bool {
+        self.fitnesses.len() == self.individuals.len()
+    }
+
+    /// Total number of fitness function calls made through this population
+    ///
+    /// Calls answered by a [`FitnessCache`](crate::cache::FitnessCache)
+    /// without calling the fitness function it wraps aren't counted.
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    /// Number of generations this population has been evolved for
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Moves the generation counter on by one, returning the new generation
+    pub fn next_generation(&mut self) -> usize {
+        self.generation += 1;
+        self.generation
+    }
+
+    /// Computes the fitness of every individual without an up-to-date one, returning how many that was
+    ///
+    /// The first call evaluates everyone; after that only individuals that
+    /// have been [invalidated](Population::invalidate) are evaluated again.
+    pub fn evaluate<E: Fn(&G) -> F>(&mut self, eval: E) -> usize {
+        let idxs = self.invalid_indices();
+        let (fitnesses, costs): (Vec<F>, Vec<usize>) =
+            idxs.iter().map(|&idx| call(&eval, &self.individuals[idx])).unzip();
+        self.store_fitnesses(&idxs, fitnesses, costs.iter().sum())
+    }
+
+    /// As [`Population::evaluate`], computing the fitnesses across the rayon thread pool
+    ///
+    /// Results are written back by position, so they're identical to
+    /// [`Population::evaluate`] whatever order the work runs in.
+    #[cfg(feature = "parallel")]
+    pub fn evaluate_par<E>(&mut self, eval: E) -> usize
+    where
+        E: Fn(&G) -> F + Sync + Send,
+        G: Sync,
+        F: Send
+    {
+        let idxs = self.invalid_indices();
+        let individuals = &self.individuals;
+        let (fitnesses, costs): (Vec<F>, Vec<usize>) =
+            idxs.par_iter().map(|&idx| call(&eval, &individuals[idx])).unzip();
+        self.store_fitnesses(&idxs, fitnesses, costs.iter().sum())
+    }
+
+    /// As [`Population::evaluate_par`], for fitness functions that draw random numbers, such as noisy ones
+    ///
+    /// The individual at position `idx` draws from `pool.rng(idx)`, so the
+    /// fitnesses don't depend on the number of threads. Pass a different
+    /// [stream](RngPool::stream) of the pool each generation, or the same
+    /// positions will draw the same numbers again.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::crossover::OnePoint;
+    /// use dears::mutation::FlipBit;
+    /// use dears::population::PopulationBuilder;
+    /// use dears::rng::RngPool;
+    /// use dears::selection::TournamentSelection;
+    /// use rand::rngs::SmallRng;
+    /// use rand::Rng;
+    /// let build = || PopulationBuilder::new()
+    ///     .init_with(20, || vec![true; 8])
+    ///     .mutator(FlipBit { indpb: 0.1 })
+    ///     .crossover(OnePoint)
+    ///     .selector(TournamentSelection::new(3).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// let noisy = |g: &Vec<bool>, rng: &mut SmallRng| g.len() as f64 + rng.gen_range(-1.0..1.0);
+    /// let (mut first, mut second) = (build(), build());
+    /// first.evaluate_par_with(&RngPool { seed: 3 }, noisy);
+    /// second.evaluate_par_with(&RngPool { seed: 3 }, noisy);
+    /// assert_eq!(first.fitnesses(), second.fitnesses());
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn evaluate_par_with<E>(&mut self, pool: &RngPool, eval: E) -> usize
+    where
+        E: Fn(&G, &mut SmallRng) -> F + Sync + Send,
+        G: Sync,
+        F: Send
+    {
+        let idxs = self.invalid_indices();
+        let individuals = &self.individuals;
+        let (fitnesses, costs): (Vec<F>, Vec<usize>) =
+            idxs.par_iter().map(|&idx| call(|g| eval(g, &mut pool.rng(idx)), &individuals[idx])).unzip();
+        self.store_fitnesses(&idxs, fitnesses, costs.iter().sum())
+    }
+
+    /// As [`Population::evaluate`], handing every individual that needs a fitness to `eval` in one call
+    ///
+    /// `eval` gets the individuals without an up-to-date fitness in
+    /// population order, copied out unless that's all of them, and must return
+    /// a fitness for each in the same order. They're written back to the
+    /// individuals they came from. If `eval` returns the wrong number of
+    /// fitnesses, none are stored and the individuals stay invalid.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::crossover::OnePoint;
+    /// use dears::mutation::Gaussian;
+    /// use dears::population::PopulationBuilder;
+    /// use dears::selection::TournamentSelection;
+    /// let mut pop = PopulationBuilder::new()
+    ///     .individuals(vec![vec![1.0, 2.0], vec![3.0, 4.0]])
+    ///     .mutator(Gaussian { mu: 0.0, sigma: 0.1, indpb: 1.0 })
+    ///     .crossover(OnePoint)
+    ///     .selector(TournamentSelection::new(2).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// let sums = |batch: &[Vec<f64>]| batch.iter().map(|g| g.iter().sum::<f64>()).collect();
+    /// assert_eq!(pop.evaluate_batch(sums), Ok(2));
+    /// assert_eq!(pop.fitnesses(), &[3.0, 7.0]);
+    /// ```
+    pub fn evaluate_batch<E>(&mut self, eval: E) -> Result<usize, BatchLengthError>
+    where
+        G: Clone,
+        E: Fn(&[G]) -> Vec<F>
+    {
+        let idxs = self.invalid_indices();
+        if idxs.is_empty() {
+            return Ok(0);
+        }
+        let (fitnesses, hits) = cache_hits(|| if idxs.len() == self.individuals.len() {
+            eval(&self.individuals)
+        } else {
+            let dirty: Vec<G> = idxs.iter().map(|&idx| self.individuals[idx].clone()).collect();
+            eval(&dirty)
+        });
+        if fitnesses.len() != idxs.len() {
+            return Err(BatchLengthError { expected: idxs.len(), returned: fitnesses.len() });
+        }
+        Ok(self.store_fitnesses(&idxs, fitnesses, idxs.len().saturating_sub(hits)))
+    }
+
+    /// As [`Population::evaluate`], one individual at a time, stopping early once `stop` returns true
+    ///
+    /// `stop` is asked after every evaluation but the last, and whatever
+    /// hasn't been evaluated by then stays invalid. The first evaluation of a
+    /// population can't be cut short, since there are no fitnesses to fall
+    /// back on.
+    pub fn evaluate_until<E: Fn(&G) -> F>(&mut self, eval: E, mut stop: impl FnMut(&Self) -> bool) -> usize {
+        if !self.is_evaluated() {
+            return self.evaluate(eval);
+        }
+        let idxs = self.invalid_indices();
+        for (n, &idx) in idxs.iter().enumerate() {
+            let (fitness, cost) = call(&eval, &self.individuals[idx]);
+            self.fitnesses[idx] = fitness;
+            self.valid[idx] = true;
+            self.evaluations += cost;
+            if n + 1 < idxs.len() && stop(self) {
+                return n + 1;
             }
+        }
+        idxs.len()
+    }
+
+    /// As [`Population::evaluate`], with a fitness function that may fail, handling failures as `policy` says
+    ///
+    /// Every call counts towards [`Population::evaluations`], failed or not.
+    /// When an error is returned, the fitnesses computed before it are kept,
+    /// unless this was the population's first evaluation, and the rest stay
+    /// invalid.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::crossover::OnePoint;
+    /// use dears::mutation::Gaussian;
+    /// use dears::population::*;
+    /// use dears::selection::TournamentSelection;
+    /// let mut pop = PopulationBuilder::new()
+    ///     .individuals(vec![vec![1.0], vec![-1.0], vec![4.0]])
+    ///     .mutator(Gaussian { mu: 0.0, sigma: 0.1, indpb: 1.0 })
+    ///     .crossover(OnePoint)
+    ///     .selector(TournamentSelection::new(2).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// let root = |g: &Vec<f64>| if g[0] < 0.0 { Err("negative") } else { Ok(g[0].sqrt()) };
+    /// let evaluation = pop.try_evaluate(root, &FailurePolicy::AssignWorst(0.0)).unwrap();
+    /// assert_eq!(evaluation, Evaluation { evaluated: 3, failures: 1 });
+    /// assert_eq!(pop.fitnesses(), &[1.0, 0.0, 2.0]);
+    /// ```
+    pub fn try_evaluate<E, Err>(&mut self, eval: E, policy: &FailurePolicy<F>) -> Result<Evaluation, Err>
+    where
+        E: Fn(&G) -> Result<F, Err>
+    {
+        self.try_evaluate_until(eval, policy, |_| false)
+    }
+
+    /// [`Population::try_evaluate`], stopping early as in [`Population::evaluate_until`]
+    pub fn try_evaluate_until<E, Err>(&mut self, eval: E, policy: &FailurePolicy<F>,
+                                      mut stop: impl FnMut(&Self) -> bool) -> Result<Evaluation, Err>
+    where
+        E: Fn(&G) -> Result<F, Err>
+    {
+        let idxs = self.invalid_indices();
+        let mut failures = 0;
+        if !self.is_evaluated() {
+            let mut fitnesses = Vec::with_capacity(idxs.len());
+            for &idx in &idxs {
+                fitnesses.push(self.try_fitness(idx, &eval, policy, &mut failures)?);
+            }
+            self.fitnesses = fitnesses;
+            self.valid = vec![true; self.individuals.len()];
+            return Ok(Evaluation { evaluated: idxs.len(), failures });
+        }
+        for (n, &idx) in idxs.iter().enumerate() {
+            self.fitnesses[idx] = self.try_fitness(idx, &eval, policy, &mut failures)?;
+            self.valid[idx] = true;
+            if n + 1 < idxs.len() && stop(self) {
+                return Ok(Evaluation { evaluated: n + 1, failures });
+            }
+        }
+        Ok(Evaluation { evaluated: idxs.len(), failures })
+    }
+
+    /// Calls `eval` on the individual at `idx` for as long as `policy` allows, counting every call and failure
+    fn try_fitness<E, Err>(&mut self, idx: usize, eval: &E, policy: &FailurePolicy<F>, failures: &mut usize)
+                           -> Result<F, Err>
+    where
+        E: Fn(&G) -> Result<F, Err>
+    {
+        let retries = match policy {
+            FailurePolicy::Retry { attempts } => *attempts,
+            _ => 0,
+        };
+        let (mut result, cost) = call(eval, &self.individuals[idx]);
+        self.evaluations += cost;
+        for _ in 0..retries {
+            if result.is_ok() {
+                break;
+            }
+            *failures += 1;
+            let (retried, cost) = call(eval, &self.individuals[idx]);
+            result = retried;
+            self.evaluations += cost;
+        }
+        result.or_else(|err| {
+            *failures += 1;
+            match policy {
+                FailurePolicy::AssignWorst(worst) => Ok(worst.clone()),
+                _ => Err(err),
+            }
+        })
+    }
+
+    /// Writes freshly computed fitnesses for the individuals at `idxs`, which took `calls` fitness function calls
+    fn store_fitnesses(&mut self, idxs: &[usize], fitnesses: Vec<F>, calls: usize) -> usize {
+        debug_assert_eq!(idxs.len(), fitnesses.len());
+        if !self.is_evaluated() {
+            debug_assert_eq!(fitnesses.len(), self.individuals.len());
+            self.fitnesses = fitnesses;
+        } else {
+            for (&idx, fitness) in idxs.iter().zip(fitnesses) {
+                self.fitnesses[idx] = fitness;
+            }
+        }
+        self.valid = vec![true; self.individuals.len()];
+        self.evaluations += calls;
+        idxs.len()
+    }
+
+    /// Recomputes the fitness of just the individuals at `idxs`
+    ///
+    /// Meant for re-evaluating after variation, so the population must already
+    /// have been evaluated.
+    pub fn evaluate_indices<E: Fn(&G) -> F>(&mut self, idxs: &[usize], eval: E) {
+        assert!(self.is_evaluated(), "Evaluate the whole population before part of it");
+        for &idx in idxs {
+            let (fitness, cost) = call(&eval, &self.individuals[idx]);
+            self.fitnesses[idx] = fitness;
+            self.valid[idx] = true;
+            self.evaluations += cost;
+        }
+    }
+
+    /// Marks the fitnesses at `idxs` as out of date, so the next [`Population::evaluate`] recomputes them
+    ///
+    /// Does nothing before the first evaluation, when every fitness is missing anyway.
+    pub fn invalidate(&mut self, idxs: &[usize]) {
+        if self.is_evaluated() {
+            for &idx in idxs {
+                self.valid[idx] = false;
+            }
+        }
+    }
+
+    /// Marks every fitness as out of date, for instance after changing the fitness function
+    pub fn invalidate_all(&mut self) {
+        self.valid.fill(false);
+    }
+
+    /// Indices of the individuals without an up-to-date fitness
+    pub fn invalid_indices(&self) -> Vec<usize> {
+        if !self.is_evaluated() {
+            return (0..self.individuals.len()).collect();
+        }
+        (0..self.valid.len()).filter(|&idx| !self.valid[idx]).collect()
+    }
+
+    /// Swaps in a whole new generation along with its fitnesses
+    ///
+    /// Fitnesses carried over from parents may be stale, so changed
+    /// individuals should then be [invalidated](Population::invalidate) and
+    /// re-evaluated.
+    /// Every age goes back to 0 until given with [`Population::set_ages`].
+    pub fn replace_all(&mut self, individuals: Vec<G>, fitnesses: Vec<F>) {
+        assert!(!individuals.is_empty(), "{}", BuildError::EmptyPopulation);
+        assert_eq!(individuals.len(), fitnesses.len(), "Need exactly one fitness per individual");
+        self.ages.clear();
+        self.ages.resize(individuals.len(), 0);
+        self.valid.clear();
+        self.valid.resize(individuals.len(), true);
+        self.individuals = individuals;
+        self.fitnesses = fitnesses;
+    }
+
+    /// As [`Population::replace_all`], but hands the outgoing generation back through the arguments
+    ///
+    /// Lets a loop build each generation in the buffers of the one before.
+    pub fn swap_all(&mut self, individuals: &mut Vec<G>, fitnesses: &mut Vec<F>) {
+        assert!(!individuals.is_empty(), "{}", BuildError::EmptyPopulation);
+        assert_eq!(individuals.len(), fitnesses.len(), "Need exactly one fitness per individual");
+        std::mem::swap(&mut self.individuals, individuals);
+        std::mem::swap(&mut self.fitnesses, fitnesses);
+        let n = self.individuals.len();
+        self.ages.clear();
+        self.ages.resize(n, 0);
+        self.valid.clear();
+        self.valid.resize(n, true);
+    }
+
+    /// Adds an already evaluated individual to an evaluated population, at age 0
+    pub fn push(&mut self, genome: G, fitness: F) {
+        assert!(self.is_evaluated(), "Can't add an evaluated individual to an unevaluated population");
+        self.individuals.push(genome);
+        self.fitnesses.push(fitness);
+        self.valid.push(true);
+        self.ages.push(0);
+    }
+
+    /// Puts an evaluated individual in place of the one at `idx`, at age 0, returning the old one
+    pub fn replace_at(&mut self, idx: usize, genome: G, fitness: F) -> (G, F) {
+        assert!(self.is_evaluated(), "Can't add an evaluated individual to an unevaluated population");
+        let genome = std::mem::replace(&mut self.individuals[idx], genome);
+        let fitness = std::mem::replace(&mut self.fitnesses[idx], fitness);
+        self.valid[idx] = true;
+        self.ages[idx] = 0;
+        (genome, fitness)
+    }
+
+    /// Removes the individual at `idx`, along with its fitness if it has one
+    pub fn remove(&mut self, idx: usize) -> (G, Option<F>) {
+        let fitness = self.is_evaluated().then(|| {
+            self.valid.remove(idx);
+            self.fitnesses.remove(idx)
         });
+        self.ages.remove(idx);
+        (self.individuals.remove(idx), fitness)
+    }
+
+    /// Calls `f` on every genome, which returns whether it changed it, and invalidates those that changed
+    ///
+    /// For repairs such as renormalising weights after variation. Returns the
+    /// indices of the changed individuals, which keep their ages.
+    pub fn for_each_individual_mut(&mut self, mut f: impl FnMut(&mut G) -> bool) -> Vec<usize> {
+        let changed: Vec<usize> = (0..self.individuals.len()).filter(|&idx| f(&mut self.individuals[idx])).collect();
+        self.invalidate(&changed);
+        changed
     }
 
-    fn select(&self, n: usize) -> Vec<usize>{
-        todo!()
+    /// Replaces every genome with what `f` makes of it, invalidating every fitness
+    ///
+    /// Individuals keep their ages.
+    pub fn map_individuals(&mut self, f: impl FnMut(G) -> G) {
+        self.individuals = std::mem::take(&mut self.individuals).into_iter().map(f).collect();
+        self.invalidate_all();
+    }
+
+    /// Removes the individuals `keep` returns false for, along with their fitnesses and ages
+    ///
+    /// Returns how many were removed, or an error leaving the population as
+    /// it was if fewer than `min` would be left; a population is never left
+    /// empty, whatever `min` is. The survivors stay in order.
+    pub fn retain(&mut self, min: usize, keep: impl FnMut(&G) -> bool) -> Result<usize, RetainError> {
+        let keep: Vec<bool> = self.individuals.iter().map(keep).collect();
+        let kept = keep.iter().filter(|&&k| k).count();
+        let min = min.max(1);
+        if kept < min {
+            return Err(RetainError { kept, min });
+        }
+        retain_flagged(&mut self.individuals, &keep);
+        retain_flagged(&mut self.ages, &keep);
+        if !self.fitnesses.is_empty() {
+            retain_flagged(&mut self.fitnesses, &keep);
+            retain_flagged(&mut self.valid, &keep);
+        }
+        Ok(keep.len() - kept)
+    }
+
+    /// Replaces the `n` oldest individuals with fresh random ones at age 0, returning their indices
+    ///
+    /// The ALPS way of keeping the youngest layer supplied. Among equally old
+    /// individuals the earliest go first. The newcomers are invalidated, so
+    /// the next [`Population::evaluate`] computes their fitnesses.
+    pub fn inject_random(&mut self, n: usize, init: &impl Init<G>, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut oldest: Vec<usize> = (0..self.len()).collect();
+        oldest.sort_by(|&a, &b| self.ages[b].cmp(&self.ages[a]));
+        oldest.truncate(n);
+        for &idx in &oldest {
+            self.individuals[idx] = init.init_with(rng);
+            self.ages[idx] = 0;
+        }
+        self.invalidate(&oldest);
+        oldest
+    }
+
+    /// Reorders the population best first, keeping every individual with its fitness and age
+    ///
+    /// The sort is stable, and NaN fitnesses count as the worst. Panics if
+    /// the population hasn't been evaluated.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::crossover::OnePoint;
+    /// use dears::mutation::FlipBit;
+    /// use dears::population::Population;
+    /// use dears::selection::{Direction, SelBest};
+    /// let mut pop = Population::new(vec![vec![true], vec![false]], FlipBit { indpb: 0.1 }, OnePoint,
+    ///                               SelBest::default());
+    /// pop.evaluate(|g| if g[0] { 1.0 } else { 0.0 });
+    /// pop.sort_by_fitness(Direction::Minimize);
+    /// assert_eq!((pop.individuals(), pop.fitnesses()), (&[vec![false], vec![true]][..], &[0.0, 1.0][..]));
+    /// ```
+    pub fn sort_by_fitness(&mut self, direction: Direction)
+    where
+        F: PartialOrd
+    {
+        assert!(self.is_evaluated(), "Evaluate the population before sorting it");
+        let order = RankedFitnesses::new(&self.fitnesses, &direction).order().to_vec();
+        self.individuals = reorder(std::mem::take(&mut self.individuals), &order);
+        self.fitnesses = reorder(std::mem::take(&mut self.fitnesses), &order);
+        self.valid = reorder(std::mem::take(&mut self.valid), &order);
+        self.ages = reorder(std::mem::take(&mut self.ages), &order);
+    }
+
+    /// Keeps only the best `n` individuals, leaving them sorted best first
+    ///
+    /// Panics if `n` is 0, or the population hasn't been evaluated.
+    pub fn truncate_to(&mut self, n: usize, direction: Direction)
+    where
+        F: PartialOrd
+    {
+        assert!(n > 0, "{}", BuildError::EmptyPopulation);
+        self.sort_by_fitness(direction);
+        self.individuals.truncate(n);
+        self.fitnesses.truncate(n);
+        self.valid.truncate(n);
+        self.ages.truncate(n);
+    }
+
+    /// The best individual, or `None` before evaluation; the first wins ties
+    ///
+    /// Individuals waiting to be evaluated again are passed over, since their
+    /// fitnesses are out of date.
+    pub fn best(&self, direction: Direction) -> Option<(usize, &G, &F)>
+    where
+        F: PartialOrd
+    {
+        self.extreme(|a, b| direction.compare(a, b).is_gt())
+    }
+
+    /// The worst individual, or `None` before evaluation; the first loses ties
+    ///
+    /// Like [`Population::best`], individuals waiting to be evaluated are passed over.
+    pub fn worst(&self, direction: Direction) -> Option<(usize, &G, &F)>
+    where
+        F: PartialOrd
+    {
+        self.extreme(|a, b| direction.compare(a, b).is_lt())
+    }
+
+    /// The first valid individual that no other `beats`
+    fn extreme(&self, beats: impl Fn(&F, &F) -> bool) -> Option<(usize, &G, &F)> {
+        if !self.is_evaluated() {
+            return None;
+        }
+        (0..self.len())
+            .filter(|&i| self.valid[i])
+            .reduce(|kept, i| if beats(&self.fitnesses[i], &self.fitnesses[kept]) { i } else { kept })
+            .map(|i| (i, &self.individuals[i], &self.fitnesses[i]))
+    }
+
+    /// Writes one line of CSV per individual: the fields `genome_to_row` makes of its genome, then its fitness
+    ///
+    /// The header names the genome fields `gene_0`, `gene_1` and so on, as
+    /// many as the first individual has, and the fitness `fitness`, or
+    /// `fit_0`, `fit_1` and so on with several objectives. Before evaluation
+    /// the fitness columns are left out.
+    pub fn write_csv<W: Write>(&self, mut w: W, genome_to_row: impl Fn(&G) -> Vec<String>) -> io::Result<()>
+    where
+        F: FitnessColumns
+    {
+        let rows: Vec<Vec<String>> = self.individuals.iter().map(genome_to_row).collect();
+        let fitnesses: Vec<Vec<f64>> = self.fitnesses.iter().map(F::columns).collect();
+        let genes = rows.first().map_or(0, Vec::len);
+        let objectives = fitnesses.first().map_or(0, Vec::len);
+        let mut header: Vec<String> = (0..genes).map(|i| format!("gene_{}", i)).collect();
+        header.extend(numbered("fitness", "fit", objectives));
+        write_record(&mut w, &header)?;
+        for (i, mut fields) in rows.into_iter().enumerate() {
+            if let Some(fitness) = fitnesses.get(i) {
+                fields.extend(fitness.iter().map(f64::to_string));
+            }
+            write_record(&mut w, &fields)?;
+        }
+        Ok(())
+    }
+
+    pub fn mutator(&self) -> &M {
+        &self.mutator
+    }
+
+    pub fn crossover(&self) -> &C {
+        &self.crossover
+    }
+
+    pub fn selector(&self) -> &S {
+        &self.selector
+    }
+
+    /// Mutates each individual with probability `mutpb`, returning the indices mutated
+    ///
+    /// `mutpb` is per individual; the mutator's own `indpb` then decides how
+    /// much of each chosen genome changes. The mutated individuals are
+    /// invalidated, so the next [`Population::evaluate`] recomputes them.
+    pub fn mutate_with_chance(&mut self, mutpb: f64, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut mutated = Vec::new();
+        for (idx, genome) in self.individuals.iter_mut().enumerate() {
+            if rng.gen::<f64>() < mutpb {
+                self.mutator.mutate_with(genome, rng);
+                mutated.push(idx);
+            }
+        }
+        self.invalidate(&mutated);
+        mutated
+    }
+
+    /// Picks `n` individuals with the population's selector, returning their indices
+    ///
+    /// Errors if the population hasn't been evaluated, or has individuals
+    /// without fitnesses.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::crossover::OnePoint;
+    /// use dears::mutation::FlipBit;
+    /// use dears::population::PopulationBuilder;
+    /// use dears::selection::{SelBest, SelectionError};
+    /// let mut pop = PopulationBuilder::new()
+    ///     .individuals(vec![vec![false, false], vec![true, true], vec![true, false]])
+    ///     .mutator(FlipBit { indpb: 0.1 })
+    ///     .crossover(OnePoint)
+    ///     .selector(SelBest::default())
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(pop.select(2), Err(SelectionError::Unevaluated));
+    /// pop.evaluate(|g| g.iter().filter(|&&b| b).count() as f64);
+    /// assert_eq!(pop.select(2), Ok(vec![1, 2]));
+    /// assert_eq!(pop.select_clone(1).unwrap(), vec![vec![true, true]]);
+    /// ```
+    pub fn select_with(&self, n: usize, rng: &mut dyn RngCore) -> Result<Vec<usize>, SelectionError> {
+        self.check_evaluated()?;
+        Ok(self.selector.select_n_with(&self.fitnesses, n, rng))
+    }
+
+    fn check_evaluated(&self) -> Result<(), SelectionError> {
+        if self.fitnesses.is_empty() && !self.individuals.is_empty() {
+            return Err(SelectionError::Unevaluated);
+        }
+        if !self.is_evaluated() {
+            return Err(SelectionError::FitnessMismatch {
+                individuals: self.individuals.len(),
+                fitnesses: self.fitnesses.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// As [`Population::select_with`], drawing from the population's own generator
+    pub fn select(&self, n: usize) -> Result<Vec<usize>, SelectionError> {
+        self.with_rng(|rng| self.select_with(n, rng))
+    }
+
+    /// Picks `n` parents for one age layer of `layers`, as for [`Population::select_with`]
+    pub fn select_in_layer_with<L>(&self, layers: &AgeLayeredSelection<L>, layer: usize, n: usize,
+                                   rng: &mut dyn RngCore) -> Result<Vec<usize>, SelectionError>
+    where
+        L: SelectMany<F>
+    {
+        self.check_evaluated()?;
+        layers.select_n_in_layer_with(&self.fitnesses, &self.ages, layer, n, rng)
+    }
+
+    /// Picks `n` individuals as for [`Population::select_with`], returning clones of them
+    pub fn select_clone_with(&self, n: usize, rng: &mut dyn RngCore) -> Result<Vec<G>, SelectionError>
+    where
+        G: Clone
+    {
+        self.check_evaluated()?;
+        Ok(select_individuals_with(&self.selector, &self.individuals, &self.fitnesses, n, rng))
+    }
+
+    pub fn select_clone(&self, n: usize) -> Result<Vec<G>, SelectionError>
+    where
+        G: Clone
+    {
+        self.with_rng(|rng| self.select_clone_with(n, rng))
+    }
+}
+
+/// Keeps the items whose flag in `keep` is set
+fn retain_flagged<T>(items: &mut Vec<T>, keep: &[bool]) {
+    let mut flags = keep.iter();
+    items.retain(|_| *flags.next().unwrap());
+}
+
+/// Moves `items` into the order given by a permutation of their indices
+fn reorder<T>(items: Vec<T>, order: &[usize]) -> Vec<T> {
+    let mut slots: Vec<Option<T>> = items.into_iter().map(Some).collect();
+    order.iter().map(|&i| slots[i].take().expect("order must be a permutation")).collect()
+}
+
+/// Step-by-step construction of a [`Population`]
+///
+/// Every piece must be given before [`PopulationBuilder::build`]; the
+/// operator types are checked against the genome type at compile time.
+pub struct PopulationBuilder<G, M, C, S> {
+    individuals: Vec<G>,
+    seeds: Vec<G>,
+    mutator: Option<M>,
+    crossover: Option<C>,
+    selector: Option<S>,
+    rng_seed: Option<u64>,
+}
+
+impl<G, M, C, S> Default for PopulationBuilder<G, M, C, S> {
+    fn default() -> Self {
+        PopulationBuilder {
+            individuals: Vec::new(),
+            seeds: Vec::new(),
+            mutator: None,
+            crossover: None,
+            selector: None,
+            rng_seed: None,
+        }
     }
 }
 
-// impl<T: Individual> Population<T> {
-//     fn evaluate(&mut self, eval: fn(T) -> ???) {
-//         for ind in self.individuals.iter_mut() {
-//             ind.fitness = eval(ind);
-//         }
-//     }
-// }
+impl<G, M, C, S> PopulationBuilder<G, M, C, S> {
+    pub fn new() -> Self {
+        PopulationBuilder::default()
+    }
+
+    pub fn individuals(self, individuals: Vec<G>) -> Self {
+        PopulationBuilder { individuals, ..self }
+    }
+
+    /// Creates `n` individuals by calling `init` for each
+    pub fn init_with(self, n: usize, init: impl FnMut() -> G) -> Self {
+        let individuals = std::iter::repeat_with(init).take(n).collect();
+        PopulationBuilder { individuals, ..self }
+    }
+
+    /// Creates `n` random individuals from an initializer
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::crossover::OnePoint;
+    /// use dears::initializer::RandomBits;
+    /// use dears::mutation::FlipBit;
+    /// use dears::population::PopulationBuilder;
+    /// use dears::selection::SelBest;
+    /// let pop = PopulationBuilder::new()
+    ///     .init_from(10, &RandomBits { len: 8, p_true: 0.5 }, &mut rand::thread_rng())
+    ///     .mutator(FlipBit { indpb: 0.1 })
+    ///     .crossover(OnePoint)
+    ///     .selector(SelBest::default())
+    ///     .build::<f64>()
+    ///     .unwrap();
+    /// assert_eq!(pop.len(), 10);
+    /// ```
+    pub fn init_from(self, n: usize, init: &impl Init<G>, rng: &mut dyn RngCore) -> Self {
+        let individuals = crate::initializer::init_population(n, init, rng);
+        PopulationBuilder { individuals, ..self }
+    }
+
+    /// Places known individuals first in the population, ahead of the initialised ones
+    ///
+    /// The population keeps the size given to the initializer, with the seeds
+    /// replacing the last initialised individuals, or is made of just the
+    /// seeds if there's no initializer. Seeds past the population size are
+    /// dropped, which [`PopulationBuilder::build_seeded`] reports.
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::crossover::OnePoint;
+    /// use dears::mutation::FlipBit;
+    /// use dears::population::{PopulationBuilder, Seeding};
+    /// use dears::selection::SelBest;
+    /// let (pop, seeding) = PopulationBuilder::new()
+    ///     .init_with(4, || vec![false; 3])
+    ///     .seed_individuals(vec![vec![true; 3]])
+    ///     .mutator(FlipBit { indpb: 0.1 })
+    ///     .crossover(OnePoint)
+    ///     .selector(SelBest::default())
+    ///     .build_seeded::<f64>()
+    ///     .unwrap();
+    /// assert_eq!(seeding, Seeding::Kept(1));
+    /// assert_eq!(pop.individuals()[0], vec![true; 3]);
+    /// assert_eq!(pop.len(), 4);
+    /// ```
+    pub fn seed_individuals(self, seeds: Vec<G>) -> Self {
+        PopulationBuilder { seeds, ..self }
+    }
+
+    pub fn mutator(self, mutator: M) -> Self {
+        PopulationBuilder { mutator: Some(mutator), ..self }
+    }
+
+    pub fn crossover(self, crossover: C) -> Self {
+        PopulationBuilder { crossover: Some(crossover), ..self }
+    }
+
+    pub fn selector(self, selector: S) -> Self {
+        PopulationBuilder { selector: Some(selector), ..self }
+    }
+
+    /// Seeds the population's own generator, as [`Population::reseed`] does
+    pub fn rng_seed(self, seed: u64) -> Self {
+        PopulationBuilder { rng_seed: Some(seed), ..self }
+    }
+
+    /// Checks everything was given and creates the population
+    pub fn build<F>(self) -> Result<Population<G, M, C, S, F>, BuildError>
+    where
+        M: Mutator<G>,
+        C: Crossover<G>,
+        S: SelectMany<F>,
+        F: Clone
+    {
+        self.build_seeded().map(|(pop, _)| pop)
+    }
+
+    /// As [`PopulationBuilder::build`], also saying whether any seeds were dropped
+    pub fn build_seeded<F>(self) -> Result<SeededPopulation<G, M, C, S, F>, BuildError>
+    where
+        M: Mutator<G>,
+        C: Crossover<G>,
+        S: SelectMany<F>,
+        F: Clone
+    {
+        let mut individuals = self.seeds;
+        let given = individuals.len();
+        let size = if self.individuals.is_empty() { given } else { self.individuals.len() };
+        individuals.truncate(size);
+        let kept = individuals.len();
+        individuals.extend(self.individuals.into_iter().take(size - kept));
+        if individuals.is_empty() {
+            return Err(BuildError::EmptyPopulation);
+        }
+        let mutator = self.mutator.ok_or(BuildError::MissingOperator("mutator"))?;
+        let crossover = self.crossover.ok_or(BuildError::MissingOperator("crossover"))?;
+        let selector = self.selector.ok_or(BuildError::MissingOperator("selector"))?;
+        let seeding = if kept < given { Seeding::Truncated { given, kept } } else { Seeding::Kept(kept) };
+        let mut pop = Population::new(individuals, mutator, crossover, selector);
+        if let Some(seed) = self.rng_seed {
+            pop.reseed(seed);
+        }
+        Ok((pop, seeding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::population::*;
+
+    /// Operator that leaves genomes untouched, for exercising the type plumbing
+    struct Noop;
+
+    impl<G> Mutator<G> for Noop {
+        fn mutate_with(&self, _genome: &mut G, _rng: &mut dyn RngCore) {}
+    }
+
+    impl<G> Crossover<G> for Noop {
+        fn crossover_with(&self, _a: &mut G, _b: &mut G, _rng: &mut dyn RngCore) {}
+    }
+
+    #[test]
+    fn population_accepts_bare_select_one() {
+        let pop: Population<Vec<f64>, Noop, Noop, TournamentSelection, f64> = Population {
+            individuals: vec![vec![0.0; 3]; 4],
+            fitnesses: vec![1.0, 2.0, 3.0, 4.0],
+            valid: vec![true; 4],
+            ages: vec![0; 4],
+            mutator: Noop,
+            crossover: Noop,
+            selector: TournamentSelection::new(2).unwrap(),
+            evaluations: 0,
+            generation: 0,
+            rng: Mutex::new(None),
+        };
+        let picked = pop.selector.select_n(&pop.fitnesses, 3);
+        assert_eq!(picked.len(), 3);
+    }
+
+    #[test]
+    fn builder_reports_missing_pieces() {
+        let empty = PopulationBuilder::new()
+            .individuals(Vec::<Vec<f64>>::new())
+            .mutator(Noop)
+            .crossover(Noop)
+            .selector(SelBest::default())
+            .build::<f64>();
+        assert_eq!(empty.err(), Some(BuildError::EmptyPopulation));
+
+        let missing = PopulationBuilder::<_, Noop, Noop, SelBest>::new()
+            .init_with(3, || vec![0.0; 2])
+            .mutator(Noop)
+            .crossover(Noop)
+            .build::<f64>();
+        assert_eq!(missing.err(), Some(BuildError::MissingOperator("selector")));
+
+        let pop = Population::new(vec![1, 2, 3], Noop, Noop, SelBest::default());
+        let _: &Population<_, _, _, _, f64> = &pop;
+        assert_eq!((pop.len(), pop.individuals(), pop.fitnesses()), (3, &[1, 2, 3][..], &[][..]));
+    }
+
+    #[test]
+    fn seeds_come_first_verbatim() {
+        let seeds = vec![vec![1.0, 1.0], vec![2.0, 2.0]];
+        let (pop, seeding) = PopulationBuilder::new()
+            .init_with(5, || vec![0.0, 0.0])
+            .seed_individuals(seeds.clone())
+            .mutator(Noop)
+            .crossover(Noop)
+            .selector(SelBest::default())
+            .build_seeded::<f64>()
+            .unwrap();
+        assert_eq!(seeding, Seeding::Kept(2));
+        assert_eq!(pop.len(), 5);
+        assert_eq!(&pop.individuals()[..2], seeds.as_slice());
+        assert!(pop.individuals()[2..].iter().all(|g| *g == vec![0.0, 0.0]));
+
+        let (pop, seeding) = PopulationBuilder::new()
+            .seed_individuals(vec![vec![3.0]; 6])
+            .init_with(4, || vec![0.0])
+            .mutator(Noop)
+            .crossover(Noop)
+            .selector(SelBest::default())
+            .build_seeded::<f64>()
+            .unwrap();
+        assert_eq!(seeding, Seeding::Truncated { given: 6, kept: 4 });
+        assert_eq!(pop.individuals(), vec![vec![3.0]; 4].as_slice());
+
+        let only_seeds = PopulationBuilder::new()
+            .seed_individuals(seeds.clone())
+            .mutator(Noop)
+            .crossover(Noop)
+            .selector(SelBest::default())
+            .build::<f64>()
+            .unwrap();
+        assert_eq!(only_seeds.individuals(), seeds.as_slice());
+    }
+
+    #[test]
+    fn select_uses_stored_selector() {
+        let individuals = vec![vec![3.0], vec![1.0], vec![2.0], vec![0.5]];
+        let mut pop = Population::new(individuals, Noop, Noop, TournamentSelection::new(2).unwrap());
+        assert_eq!(pop.select(2), Err(SelectionError::Unevaluated));
+        assert_eq!(pop.select_clone(2), Err(SelectionError::Unevaluated));
+        pop.evaluate(|g: &Vec<f64>| g[0]);
+
+        let picked = pop.select_with(10, &mut StdRng::seed_from_u64(161)).unwrap();
+        let direct = pop.selector().select_n_with(pop.fitnesses(), 10, &mut StdRng::seed_from_u64(161));
+        assert_eq!(picked, direct);
+        let clones = pop.select_clone_with(10, &mut StdRng::seed_from_u64(161)).unwrap();
+        assert_eq!(clones, direct.iter().map(|&i| pop.individuals()[i].clone()).collect::<Vec<_>>());
+
+        let mut partial = Population::new(vec![vec![1.0]], Noop, Noop, SelBest::default());
+        partial.evaluate(|g: &Vec<f64>| g[0]);
+        partial.individuals.push(vec![2.0]);
+        assert_eq!(partial.select(1), Err(SelectionError::FitnessMismatch { individuals: 2, fitnesses: 1 }));
+    }
+
+    #[test]
+    fn inject_random_replaces_oldest() {
+        let mut pop = Population::new(vec![vec![1.0]; 5], Noop, Noop, SelBest::default());
+        pop.evaluate(|g: &Vec<f64>| g[0]);
+        pop.set_ages(vec![3, 7, 1, 7, 5]);
+        let fresh = |_: &mut dyn RngCore| vec![0.0];
+        let replaced = pop.inject_random(3, &fresh, &mut StdRng::seed_from_u64(163));
+        assert_eq!(replaced, vec![1, 3, 4]);
+        assert_eq!(pop.ages(), &[3, 0, 1, 0, 0]);
+        assert_eq!(pop.individuals(), &[vec![1.0], vec![0.0], vec![1.0], vec![0.0], vec![0.0]]);
+
+        // Newcomers start young, and removals keep ages aligned
+        pop.push(vec![2.0], 2.0);
+        pop.remove(0);
+        assert_eq!(pop.ages(), &[0, 1, 0, 0, 0]);
+        pop.replace_all(vec![vec![4.0]; 2], vec![4.0; 2]);
+        assert_eq!(pop.ages(), &[0, 0]);
+    }
+
+    #[test]
+    fn sorting_keeps_individuals_with_fitnesses() {
+        let fitnesses = [3.0, f64::NAN, -1.0, 7.0, 3.0, 0.5];
+        let ids: Vec<usize> = (0..fitnesses.len()).collect();
+        let mut pop = Population::new(ids, Noop, Noop, SelBest::default());
+        pop.evaluate(|&id| fitnesses[id]);
+        pop.set_ages(vec![10, 11, 12, 13, 14, 15]);
+
+        pop.sort_by_fitness(Direction::Maximize);
+        assert_eq!(pop.individuals(), &[3, 0, 4, 5, 2, 1]);
+        assert_eq!(pop.ages(), &[13, 10, 14, 15, 12, 11]);
+        for (&id, &fitness) in pop.individuals().iter().zip(pop.fitnesses()) {
+            assert_eq!(fitnesses[id].to_bits(), fitness.to_bits());
+        }
+
+        pop.sort_by_fitness(Direction::Minimize);
+        assert_eq!(pop.individuals(), &[2, 5, 0, 4, 3, 1]);
+        assert_eq!(pop.best(Direction::Minimize), Some((0, &2, &-1.0)));
+        assert_eq!(pop.worst(Direction::Minimize).map(|(i, &id, _)| (i, id)), Some((5, 1)));
+        assert_eq!(pop.best(Direction::Maximize), Some((4, &3, &7.0)));
+
+        pop.truncate_to(3, Direction::Maximize);
+        assert_eq!((pop.individuals(), pop.fitnesses()), (&[3, 0, 4][..], &[7.0, 3.0, 3.0][..]));
+        assert_eq!(pop.ages(), &[13, 10, 14]);
+
+        let unevaluated = Population::<_, _, _, _, f64>::new(vec![0], Noop, Noop, SelBest::default());
+        assert_eq!(unevaluated.best(Direction::Maximize), None);
+    }
+
+    fn sphere<G: AsRef<[f64]>>(genome: &G) -> f64 {
+        genome.as_ref().iter().map(|x| x * x).sum()
+    }
+
+    #[test]
+    fn evaluate_keeps_fitnesses_aligned() {
+        let individuals = vec![vec![0.0, 1.0], vec![2.0, 0.0], vec![1.0, 1.0]];
+        let mut pop = Population::new(individuals, Noop, Noop, SelBest::default());
+        assert!(!pop.is_evaluated());
+        pop.evaluate(sphere);
+        assert_eq!(pop.fitnesses(), &[1.0, 4.0, 2.0]);
+
+        pop.push(vec![3.0, 0.0], 9.0);
+        assert_eq!(pop.remove(0), (vec![0.0, 1.0], Some(1.0)));
+        pop.individuals_mut()[1][1] = 2.0;
+        pop.evaluate_indices(&[1], sphere);
+        assert_eq!(pop.len(), 3);
+        for (genome, &fitness) in pop.individuals().iter().zip(pop.fitnesses()) {
+            assert_eq!(sphere(genome), fitness);
+        }
+        assert_eq!(pop.evaluations(), 4);
+    }
+
+    #[test]
+    fn evaluate_skips_valid_fitnesses() {
+        let calls = std::cell::Cell::new(0);
+        let counted = |genome: &Vec<f64>| {
+            calls.set(calls.get() + 1);
+            sphere(genome)
+        };
+        let mut pop = Population::new(vec![vec![1.0]; 6], Noop, Noop, SelBest::default());
+        assert_eq!(pop.evaluate(counted), 6);
+        assert_eq!(pop.evaluate(counted), 0);
+
+        pop.individuals_mut()[2][0] = 3.0;
+        pop.individuals_mut()[4][0] = 2.0;
+        pop.invalidate(&[2, 4]);
+        assert_eq!(pop.invalid_indices(), vec![2, 4]);
+        assert_eq!(pop.evaluate(counted), 2);
+        assert_eq!(pop.fitnesses(), &[1.0, 1.0, 9.0, 1.0, 4.0, 1.0]);
+
+        pop.sort_by_fitness(Direction::Minimize);
+        pop.invalidate(&[5]);
+        pop.truncate_to(5, Direction::Minimize);
+        assert!(pop.invalid_indices().is_empty());
+        pop.invalidate_all();
+        assert_eq!(pop.evaluate(counted), 5);
+        assert_eq!((calls.get(), pop.evaluations()), (13, 13));
+    }
+
+    #[test]
+    fn retain_removes_metadata_with_individuals() {
+        let individuals: Vec<Vec<f64>> = (0..8).map(|x| vec![x as f64]).collect();
+        let mut pop = Population::new(individuals, Noop, Noop, SelBest::default());
+        pop.evaluate(|g| g[0] * 10.0);
+        pop.set_ages((0..8).collect());
+        pop.invalidate(&[4, 6]);
+
+        let odd = |g: &Vec<f64>| g[0] as usize % 2 == 1;
+        assert_eq!(pop.retain(5, odd), Err(RetainError { kept: 4, min: 5 }));
+        assert_eq!(pop.len(), 8);
+
+        let scattered = |g: &Vec<f64>| ![0.0, 3.0, 4.0, 7.0].contains(&g[0]);
+        assert_eq!(pop.retain(4, scattered), Ok(4));
+        assert_eq!(pop.individuals(), &[vec![1.0], vec![2.0], vec![5.0], vec![6.0]]);
+        assert_eq!(pop.fitnesses(), &[10.0, 20.0, 50.0, 60.0]);
+        assert_eq!(pop.ages(), &[1, 2, 5, 6]);
+        assert_eq!(pop.invalid_indices(), vec![3]);
+        assert_eq!(pop.retain(0, |_| false), Err(RetainError { kept: 0, min: 1 }));
+    }
+
+    #[test]
+    fn changed_individuals_are_invalidated() {
+        let individuals = vec![vec![2.0, 2.0], vec![0.5, 0.5], vec![1.0, 3.0]];
+        let mut pop = Population::new(individuals, Noop, Noop, SelBest::default());
+        pop.evaluate(sphere);
+        // Renormalise to sum to 1, leaving those that already do alone
+        let changed = pop.for_each_individual_mut(|g| {
+            let total: f64 = g.iter().sum();
+            g.iter_mut().for_each(|x| *x /= total);
+            total != 1.0
+        });
+        assert_eq!(changed, vec![0, 2]);
+        assert_eq!(pop.invalid_indices(), vec![0, 2]);
+        assert_eq!(pop.individuals()[2], vec![0.25, 0.75]);
+        pop.evaluate(sphere);
+        assert_eq!(pop.fitnesses()[0], 0.5);
+
+        pop.map_individuals(|g| g.into_iter().map(|x| x * 2.0).collect());
+        assert_eq!(pop.invalid_indices(), vec![0, 1, 2]);
+        assert_eq!(pop.evaluate(sphere), 3);
+        assert_eq!(pop.fitnesses(), &[2.0, 2.0, 2.5]);
+    }
+
+    #[test]
+    fn batch_results_go_back_to_dirty_individuals() {
+        let individuals: Vec<Vec<f64>> = (0..5).map(|x| vec![x as f64]).collect();
+        let mut pop = Population::<_, _, _, _, f64>::new(individuals, Noop, Noop, SelBest::default());
+        let batches = RefCell::new(Vec::new());
+        let doubled = |batch: &[Vec<f64>]| {
+            batches.borrow_mut().push(batch.to_vec());
+            batch.iter().map(|g| g[0] * 2.0).collect()
+        };
+        assert_eq!(pop.evaluate_batch(doubled), Ok(5));
+        assert_eq!(pop.fitnesses(), &[0.0, 2.0, 4.0, 6.0, 8.0]);
+
+        pop.individuals_mut()[1] = vec![10.0];
+        pop.individuals_mut()[3] = vec![30.0];
+        pop.invalidate(&[3, 1]);
+        assert_eq!(pop.evaluate_batch(doubled), Ok(2));
+        assert_eq!(batches.borrow()[1], vec![vec![10.0], vec![30.0]]);
+        assert_eq!(pop.fitnesses(), &[0.0, 20.0, 4.0, 60.0, 8.0]);
+        assert_eq!(pop.evaluate_batch(doubled), Ok(0));
+        assert_eq!(batches.borrow().len(), 2);
+        assert_eq!(pop.evaluations(), 7);
+
+        pop.invalidate(&[0, 4]);
+        let short = |batch: &[Vec<f64>]| vec![batch[0][0]];
+        assert_eq!(pop.evaluate_batch(short), Err(BatchLengthError { expected: 2, returned: 1 }));
+        assert_eq!(pop.invalid_indices(), vec![0, 4]);
+        assert_eq!(pop.fitnesses()[0], 0.0);
+    }
+
+    #[test]
+    fn failure_policies() {
+        let individuals: Vec<Vec<f64>> = (0..10).map(|x| vec![x as f64]).collect();
+        let population = || {
+            let selector = TournamentSelection::new(4).unwrap().without_replacement();
+            Population::<_, _, _, _, f64>::new(individuals.clone(), Noop, Noop, selector)
+        };
+        let unstable = |g: &Vec<f64>| if [2.0, 5.0, 7.0].contains(&g[0]) { Err("unstable") } else { Ok(g[0]) };
+
+        let mut pop = population();
+        assert_eq!(pop.try_evaluate(unstable, &FailurePolicy::Propagate), Err("unstable"));
+        assert!(!pop.is_evaluated());
+        assert_eq!(pop.evaluations(), 3);
+
+        let mut pop = population();
+        let evaluation = pop.try_evaluate(unstable, &FailurePolicy::AssignWorst(-1.0)).unwrap();
+        assert_eq!(evaluation, Evaluation { evaluated: 10, failures: 3 });
+        assert_eq!(pop.fitnesses()[..3], [0.0, 1.0, -1.0]);
+        // Every tournament of 4 draws someone who didn't fail
+        let mut rng = StdRng::seed_from_u64(176);
+        let selected = pop.select_with(500, &mut rng).unwrap();
+        assert!(selected.iter().all(|i| ![2, 5, 7].contains(i)));
+
+        pop.invalidate(&[1, 2, 3]);
+        assert_eq!(pop.try_evaluate(unstable, &FailurePolicy::Retry { attempts: 2 }), Err("unstable"));
+        assert_eq!(pop.invalid_indices(), vec![2, 3]);
+        assert_eq!(pop.evaluations(), 14);
+
+        // Fails the first time it sees each genome
+        let seen = RefCell::new(Vec::new());
+        let flaky = |g: &Vec<f64>| {
+            let mut seen = seen.borrow_mut();
+            if seen.contains(g) { Ok(g[0]) } else { seen.push(g.clone()); Err("flaky") }
+        };
+        let mut pop = population();
+        let evaluation = pop.try_evaluate(flaky, &FailurePolicy::Retry { attempts: 1 }).unwrap();
+        assert_eq!(evaluation, Evaluation { evaluated: 10, failures: 10 });
+        assert_eq!(pop.fitnesses()[9], 9.0);
+        assert_eq!(pop.evaluations(), 20);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_evaluation_matches_serial() {
+        let slow_sphere = |genome: &Vec<f64>| {
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            sphere(genome)
+        };
+        let individuals: Vec<Vec<f64>> = (0..32).map(|i| vec![i as f64, -(i as f64) / 2.0]).collect();
+        let mut serial = Population::new(individuals.clone(), Noop, Noop, SelBest::default());
+        let mut parallel = Population::new(individuals, Noop, Noop, SelBest::default());
+        serial.evaluate(slow_sphere);
+        parallel.evaluate_par(slow_sphere);
+        assert_eq!(serial.fitnesses(), parallel.fitnesses());
+        assert_eq!(parallel.evaluations(), 32);
+    }
+
+    /// Mutator that always changes the genome
+    struct Increment;
+
+    impl Mutator<u32> for Increment {
+        fn mutate_with(&self, genome: &mut u32, _rng: &mut dyn RngCore) {
+            *genome += 1;
+        }
+    }
+
+    #[test]
+    fn mutate_with_chance_respects_mutpb() {
+        let mut pop = Population::<_, _, _, _, f64>::new(vec![0u32; 10_000], Increment, Noop, SelBest::default());
+        let mut rng = StdRng::seed_from_u64(141);
+        let mutated = pop.mutate_with_chance(0.1, &mut rng);
+        let changed = pop.individuals().iter().filter(|&&g| g == 1).count();
+        assert_eq!(changed, mutated.len());
+        // Binomial std dev is 30, so this is over 6 sigma
+        assert!((changed as i64 - 1000).abs() < 200, "{} of 10000 mutated", changed);
+    }
+
+    #[test]
+    fn writes_genomes_and_objectives_as_csv() {
+        let individuals = vec![vec![1.5, -2.0], vec![0.25, 3.0]];
+        let mut pop = Population::new(individuals, Noop, Noop, SelBest::default());
+        let to_row = |g: &Vec<f64>| g.iter().map(f64::to_string).collect();
+        let mut out = Vec::new();
+        pop.write_csv(&mut out, to_row).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "gene_0,gene_1\n1.5,-2\n0.25,3\n");
+
+        pop.evaluate(|g| [g[0] + g[1], g[0] * 0.1]);
+        let mut out = Vec::new();
+        pop.write_csv(&mut out, to_row).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines, vec!["gene_0,gene_1,fit_0,fit_1", "1.5,-2,-0.5,0.15000000000000002", "0.25,3,3.25,0.025"]);
+    }
+}
@@ -0,0 +1,41 @@
+//! The traits, operators and entry points most runs need, in one import
+//!
+//! # Examples
+//! ```
+//! use dears::prelude::*;
+//! let mut pop = PopulationBuilder::new()
+//!     .init_from(50, &RandomBits { len: 20, p_true: 0.5 }, &mut rand::thread_rng())
+//!     .mutator(FlipBit { indpb: 0.05 })
+//!     .crossover(OnePoint)
+//!     .selector(TournamentSelection::new(3).unwrap())
+//!     .build()
+//!     .unwrap();
+//! let ones = |genome: &Vec<bool>| genome.iter().filter(|&&bit| bit).count() as f64;
+//! let stats = Statistics::new(|f: &f64| *f).register("max", max);
+//! let mut hof = HallOfFame::new(1);
+//! let elitism = Elitism::new(1, Direction::Maximize);
+//! let (logbook, _) = ea_simple(&mut pop, MaxGenerations(30), 0.5, 0.2, elitism, ones, Some(&stats), Some(&mut hof),
+//!                              &mut rand::thread_rng());
+//! // The elite carries the best genome found into the last generation
+//! assert_eq!(logbook.select("max").last(), hof.best().map(|(_, f)| f));
+//! ```
+
+pub use rand::{Rng, RngCore};
+
+pub use crate::algorithms::{ea_mu_comma_lambda, ea_mu_plus_lambda, ea_simple, evolve_with_hooks, Elitism,
+                            EvolutionObserver};
+pub use crate::archive::{HallOfFame, ParetoFront};
+pub use crate::bitstring::BitString;
+pub use crate::crossover::{Crossover, OnePoint, Ordered, PartiallyMatched, SimulatedBinary, Uniform};
+pub use crate::fitness::{Fitness, MultiFitness, OrderedFitness, ScalarFitness};
+pub use crate::genome::{BoundedVec, Genome, Permutation};
+pub use crate::initializer::{init_population, Init, RandomBits, RandomPermutation, UniformFloat};
+pub use crate::mutation::{FlipBit, Gaussian, Inversion, Mutator, Polynomial, Shuffle};
+pub use crate::population::{Population, PopulationBuilder};
+pub use crate::rng::{EvolutionConfig, Stream};
+pub use crate::selection::{select_individuals, Direction, Roulette, SelBest, SelectMany, SelectOne,
+                           TournamentSelection};
+pub use crate::stats::{max, mean, min, Logbook, Statistics};
+pub use crate::termination::{Any, MaxGenerations, StopReason, Stagnation, TargetFitness, Termination};
+#[cfg(feature = "derive")]
+pub use crate::CrossoverFields;
@@ -0,0 +1,320 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use rand::{Rng, RngCore};
+use rand::seq::index::sample;
+
+use crate::selection::Direction;
+
+/// Trait defining how offspring are written back into a population
+///
+/// Unlike selection this needs the genomes as well as the fitnesses.
+/// Implementors overwrite slots of `population` (and the matching `fitnesses`)
+/// with some of the `offspring`, returning the indices that were replaced.
+pub trait Replacement<G, F> {
+    fn replace_with(&self, population: &mut [G], fitnesses: &mut [F],
+                    offspring: Vec<G>, offspring_fits: Vec<F>, rng: &mut dyn RngCore) -> Vec<usize>;
+
+    fn replace(&self, population: &mut [G], fitnesses: &mut [F],
+               offspring: Vec<G>, offspring_fits: Vec<F>) -> Vec<usize> {
+        self.replace_with(population, fitnesses, offspring, offspring_fits, &mut rand::thread_rng())
+    }
+}
+
+/// Deterministic crowding: offspring only compete with their most similar parent
+///
+/// `population` holds the parents and must line up with `offspring`: the
+/// children at `2k` and `2k + 1` came from mating the parents at `2k` and
+/// `2k + 1`. Each pair of children is matched to the pair of parents so the
+/// total `dist` between matches is smallest, and each child replaces its
+/// matched parent if it is at least as fit. An unpaired final child just
+/// competes with the final parent.
+///
+/// # Examples
+/// ```
+/// use dears::replacement::*;
+/// use dears::selection::Direction;
+/// let crowding = DeterministicCrowding {
+///     dist: |a: &f64, b: &f64| (a - b).abs(),
+///     direction: Direction::Maximize,
+/// };
+/// let mut parents = vec![0.0, 10.0];
+/// let mut fitnesses = vec![1.0, 1.0];
+/// // The child near 10 is fitter, so it replaces the parent at 10
+/// let replaced = crowding.replace(&mut parents, &mut fitnesses, vec![9.0, 1.0], vec![2.0, 0.5]);
+/// assert_eq!(replaced, vec![1]);
+/// assert_eq!(parents, vec![0.0, 9.0]);
+/// ```
+pub struct DeterministicCrowding<D> {
+    pub dist: D,
+    pub direction: Direction,
+}
+
+impl<G, F, D> Replacement<G, F> for DeterministicCrowding<D>
+where
+    F: PartialOrd,
+    D: Fn(&G, &G) -> f64
+{
+    fn replace_with(&self, population: &mut [G], fitnesses: &mut [F],
+                    offspring: Vec<G>, offspring_fits: Vec<F>, _rng: &mut dyn RngCore) -> Vec<usize> {
+        assert_eq!(population.len(), offspring.len(), "Each offspring needs a parent slot");
+        assert_eq!(offspring.len(), offspring_fits.len(), "Need exactly one fitness per offspring");
+        assert_eq!(population.len(), fitnesses.len(), "Need exactly one fitness per parent");
+
+        // Work out which parent each child competes against
+        let mut matches: Vec<usize> = (0..offspring.len()).collect();
+        for pair in (0..offspring.len() / 2).map(|k| 2 * k) {
+            let (p1, p2, c1, c2) = (&population[pair], &population[pair + 1],
+                                    &offspring[pair], &offspring[pair + 1]);
+            let straight = (self.dist)(p1, c1) + (self.dist)(p2, c2);
+            let crossed = (self.dist)(p1, c2) + (self.dist)(p2, c1);
+            if crossed < straight {
+                matches.swap(pair, pair + 1);
+            }
+        }
+
+        let mut replaced = Vec::new();
+        for ((child, fitness), parent) in offspring.into_iter().zip(offspring_fits).zip(matches) {
+            if self.direction.compare(&fitness, &fitnesses[parent]) != Ordering::Less {
+                population[parent] = child;
+                fitnesses[parent] = fitness;
+                replaced.push(parent);
+            }
+        }
+        replaced.sort();
+        replaced
+    }
+}
+
+/// Steady-state replacement of one of the `k` worst individuals
+///
+/// Each offspring overwrites a uniformly chosen member of the `k` worst
+/// individuals of the current population, regardless of its own fitness.
+/// Later offspring see the population as updated by earlier ones. With `k`
+/// of 1 this is plain worst replacement; so long as `k` is smaller than the
+/// population the best individual is never replaced.
+///
+/// # Examples
+/// ```
+/// use dears::replacement::*;
+/// use dears::selection::Direction;
+/// let mut population = vec!["a", "b", "c"];
+/// let mut fitnesses = vec![2.0, 1.0, 3.0];
+/// let replaced = ReplaceWorst { k: 1, direction: Direction::Maximize }
+///     .replace(&mut population, &mut fitnesses, vec!["d"], vec![0.5]);
+/// assert_eq!(replaced, vec![1]);
+/// assert_eq!(population, vec!["a", "d", "c"]);
+/// ```
+pub struct ReplaceWorst {
+    pub k: usize,
+    pub direction: Direction,
+}
+
+/// Heap entry ordered so the better fitness is greater, ties broken by index
+struct Ranked<'a, F> {
+    index: usize,
+    fitness: &'a F,
+    direction: Direction,
+}
+
+impl<F: PartialOrd> Ord for Ranked<'_, F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.direction.compare(self.fitness, other.fitness)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl<F: PartialOrd> PartialOrd for Ranked<'_, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: PartialOrd> PartialEq for Ranked<'_, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<F: PartialOrd> Eq for Ranked<'_, F> {}
+
+/// Returns the indices of the `k` worst fitnesses, in no particular order
+///
+/// Keeps a heap of at most `k` entries whose top is the best of the worst
+/// seen so far, so it runs in `O(n log k)` without sorting everything.
+pub fn worst_k<F: PartialOrd>(fitnesses: &[F], k: usize, direction: Direction) -> Vec<usize> {
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for (index, fitness) in fitnesses.iter().enumerate() {
+        heap.push(Ranked { index, fitness, direction });
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    heap.into_iter().map(|entry| entry.index).collect()
+}
+
+impl<G, F: PartialOrd> Replacement<G, F> for ReplaceWorst {
+    fn replace_with(&self, population: &mut [G], fitnesses: &mut [F],
+                    offspring: Vec<G>, offspring_fits: Vec<F>, rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(self.k > 0, "Must replace one of at least 1 worst individuals");
+        assert!(!population.is_empty(), "Can't replace into an empty population");
+        assert_eq!(population.len(), fitnesses.len(), "Need exactly one fitness per individual");
+        assert_eq!(offspring.len(), offspring_fits.len(), "Need exactly one fitness per offspring");
+        let mut replaced = Vec::with_capacity(offspring.len());
+        for (child, fitness) in offspring.into_iter().zip(offspring_fits) {
+            let worst = worst_k(fitnesses, self.k, self.direction);
+            let target = worst[rng.gen_range(0..worst.len())];
+            population[target] = child;
+            fitnesses[target] = fitness;
+            replaced.push(target);
+        }
+        replaced
+    }
+}
+
+/// Restricted tournament replacement, keeping offspring in their own niche
+///
+/// Each offspring is compared against the individual most similar to it, by
+/// `dist`, out of `window_size` members sampled without replacement. It takes
+/// that individual's place only if strictly fitter, so a niche can only be
+/// overwritten by something that lands in it. [`crate::distance`] has common
+/// distances for bit strings and real vectors.
+///
+/// # Examples
+/// ```
+/// use dears::distance::hamming;
+/// use dears::replacement::*;
+/// use dears::selection::Direction;
+/// let rts = RestrictedTournament { window_size: 2, dist: hamming::<Vec<bool>>, direction: Direction::Maximize };
+/// let mut population = vec![vec![false, false], vec![true, true]];
+/// let mut fitnesses = vec![1.0, 1.0];
+/// let replaced = rts.replace(&mut population, &mut fitnesses, vec![vec![true, false]], vec![2.0]);
+/// assert_eq!(replaced.len(), 1);
+/// assert_eq!(fitnesses[replaced[0]], 2.0);
+/// ```
+pub struct RestrictedTournament<D> {
+    pub window_size: usize,
+    pub dist: D,
+    pub direction: Direction,
+}
+
+impl<G, F, D> Replacement<G, F> for RestrictedTournament<D>
+where
+    F: PartialOrd,
+    D: Fn(&G, &G) -> f64
+{
+    fn replace_with(&self, population: &mut [G], fitnesses: &mut [F],
+                    offspring: Vec<G>, offspring_fits: Vec<F>, rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(self.window_size > 0, "Window must hold at least 1 individual");
+        assert!(!population.is_empty(), "Can't replace into an empty population");
+        assert_eq!(population.len(), fitnesses.len(), "Need exactly one fitness per individual");
+        assert_eq!(offspring.len(), offspring_fits.len(), "Need exactly one fitness per offspring");
+        let window = self.window_size.min(population.len());
+        let mut replaced = Vec::new();
+        for (child, fitness) in offspring.into_iter().zip(offspring_fits) {
+            let nearest = sample(rng, population.len(), window)
+                .into_iter()
+                .map(|idx| (idx, (self.dist)(&child, &population[idx])))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(idx, _)| idx)
+                .unwrap();
+            if self.direction.compare(&fitness, &fitnesses[nearest]) == Ordering::Greater {
+                population[nearest] = child;
+                fitnesses[nearest] = fitness;
+                replaced.push(nearest);
+            }
+        }
+        replaced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::distance::*;
+    use crate::replacement::*;
+
+    fn crowding() -> DeterministicCrowding<fn(&f64, &f64) -> f64> {
+        DeterministicCrowding {
+            dist: |a, b| (a - b).abs(),
+            direction: Direction::Maximize,
+        }
+    }
+
+    #[test]
+    fn crowding_only_replaces_similar_parent() {
+        // Children come out in the opposite order to the parents they resemble
+        let mut parents = vec![0.0, 10.0];
+        let mut fitnesses = vec![5.0, 1.0];
+        let offspring = vec![9.5, 0.5];
+        // The child near 0 is fitter than the parent at 10, but must not replace it
+        let replaced = crowding().replace(&mut parents, &mut fitnesses, offspring, vec![0.5, 3.0]);
+        assert!(replaced.is_empty());
+        assert_eq!(parents, vec![0.0, 10.0]);
+        assert_eq!(fitnesses, vec![5.0, 1.0]);
+    }
+
+    #[test]
+    fn crowding_replaces_on_equal_fitness() {
+        let mut parents = vec![0.0, 10.0, 20.0];
+        let mut fitnesses = vec![1.0, 2.0, 3.0];
+        let offspring = vec![1.0, 11.0, 21.0];
+        let replaced = crowding().replace(&mut parents, &mut fitnesses, offspring, vec![1.0, 1.9, 3.0]);
+        assert_eq!(replaced, vec![0, 2]);
+        assert_eq!(parents, vec![1.0, 10.0, 21.0]);
+        assert_eq!(fitnesses, vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn replace_worst_spares_the_best() {
+        let fitnesses: Vec<f64> = vec![3.0, 1.0, 4.0, 1.5, 5.0, 9.0, 2.0, 6.0];
+        let mut worst = worst_k(&fitnesses, 3, Direction::Maximize);
+        worst.sort();
+        assert_eq!(worst, vec![1, 3, 6]);
+
+        let mut rng = StdRng::seed_from_u64(125);
+        let replacer = ReplaceWorst { k: 7, direction: Direction::Maximize };
+        for _ in 0..100 {
+            let mut population: Vec<usize> = (0..8).collect();
+            let mut fits = fitnesses.clone();
+            let replaced = replacer.replace_with(&mut population, &mut fits, vec![100], vec![0.0], &mut rng);
+            assert_ne!(replaced[0], 5);
+            assert_eq!(population[replaced[0]], 100);
+            assert_eq!(population[5], 5);
+        }
+
+        let replacer = ReplaceWorst { k: 3, direction: Direction::Minimize };
+        let mut population: Vec<usize> = (0..8).collect();
+        let mut fits = fitnesses.clone();
+        let replaced = replacer.replace_with(&mut population, &mut fits, vec![100], vec![0.0], &mut rng);
+        assert!([4, 5, 7].contains(&replaced[0]), "Replaced {}", replaced[0]);
+    }
+
+    #[test]
+    fn restricted_tournament_keeps_both_niches() {
+        let niche_a = vec![false; 8];
+        let niche_b = vec![true; 8];
+        let mut population = vec![niche_a.clone(); 5];
+        population.extend(vec![niche_b.clone(); 5]);
+        let fitnesses = vec![1.0; 10];
+        // A stream of slightly fitter offspring, all from the first niche
+        let mut child = niche_a.clone();
+        child[0] = true;
+        let offspring = vec![child; 20];
+        let offspring_fits = vec![2.0; 20];
+
+        let rts = RestrictedTournament { window_size: 10, dist: hamming::<Vec<bool>>, direction: Direction::Maximize };
+        let (mut pop, mut fits) = (population.clone(), fitnesses.clone());
+        rts.replace(&mut pop, &mut fits, offspring.clone(), offspring_fits.clone());
+        assert_eq!(pop.iter().filter(|g| **g == niche_b).count(), 5);
+
+        let worst = ReplaceWorst { k: 1, direction: Direction::Maximize };
+        let (mut pop, mut fits) = (population, fitnesses);
+        worst.replace(&mut pop, &mut fits, offspring, offspring_fits);
+        assert_eq!(pop.iter().filter(|g| **g == niche_b).count(), 0);
+
+        assert_eq!(euclidean(&[0.0, 0.0][..], &[3.0, 4.0][..]), 5.0);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::algorithms::{Control, EvolutionObserver};
+use crate::crossover::Crossover;
+use crate::mutation::Mutator;
+use crate::population::Population;
+use crate::selection::{Direction, SelectMany};
+use crate::stats::{format_values, StatsRecord};
+
+/// Receives a summary of every generation as a run goes
+///
+/// Reporters see the generation's row of the logbook along with its best
+/// fitness, and format through the row, so fitnesses needn't be `Display`.
+/// They're installed in an evolve loop by wrapping them in [`Reporting`].
+pub trait ProgressReporter<F> {
+    fn report(&mut self, gen: usize, stats: &StatsRecord, best: &F);
+}
+
+/// Prints one line per generation to stderr, such as `gen 12 | max 18.0000 | evaluations 540.0000`
+///
+/// # Examples
+/// ```
+/// use dears::report::StderrReporter;
+/// use dears::stats::StatsRecord;
+/// let mut stats = StatsRecord::default();
+/// stats.insert("max", vec![3.5]);
+/// stats.insert("min", vec![1.0, 2.0]);
+/// assert_eq!(StderrReporter::line(4, &stats), "gen 4 | max 3.5000 | min [1.0000, 2.0000]");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StderrReporter;
+
+impl StderrReporter {
+    /// The line printed for a generation
+    pub fn line(gen: usize, stats: &StatsRecord) -> String {
+        let mut line = format!("gen {}", gen);
+        for (name, values) in stats.iter() {
+            line.push_str(&format!(" | {} {}", name, format_values(values)));
+        }
+        line
+    }
+}
+
+impl<F> ProgressReporter<F> for StderrReporter {
+    fn report(&mut self, gen: usize, stats: &StatsRecord, _best: &F) {
+        eprintln!("{}", StderrReporter::line(gen, stats));
+    }
+}
+
+/// An [`EvolutionObserver`] that passes each finished generation to a [`ProgressReporter`]
+///
+/// The initial population is reported too, whenever the loop logs a row for it.
+/// The best fitness is picked according to the direction, which is
+/// [`Direction::Maximize`] by default.
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{evolve_with_hooks, Elitism};
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::report::{Reporting, StderrReporter};
+/// use dears::selection::TournamentSelection;
+/// use dears::termination::MaxGenerations;
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(10, || vec![false; 8])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap())
+///     .build()
+///     .unwrap();
+/// let ones = |g: &Vec<bool>| g.iter().filter(|&&b| b).count() as f64;
+/// evolve_with_hooks(&mut pop, MaxGenerations(3), 0.5, 0.2, Elitism::default(), ones, None, None,
+///                   &mut Reporting::new(StderrReporter), &mut rand::thread_rng());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Reporting<R> {
+    pub reporter: R,
+    pub direction: Direction,
+}
+
+impl<R> Reporting<R> {
+    pub fn new(reporter: R) -> Self {
+        Reporting { reporter, direction: Direction::default() }
+    }
+
+    /// Sets whether bigger or smaller fitnesses are reported as best
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+}
+
+impl<G, M, C, S, F, R> EvolutionObserver<G, M, C, S, F> for Reporting<R>
+where
+    M: Mutator<G>,
+    C: Crossover<G>,
+    S: SelectMany<F>,
+    F: Clone + PartialOrd,
+    R: ProgressReporter<F>
+{
+    fn on_start(&mut self, pop: &mut Population<G, M, C, S, F>, stats: &mut StatsRecord) {
+        self.report(pop, stats);
+    }
+
+    fn on_generation_end(&mut self, pop: &mut Population<G, M, C, S, F>, stats: &mut StatsRecord) -> Control {
+        self.report(pop, stats);
+        Control::Continue
+    }
+}
+
+impl<R> Reporting<R> {
+    fn report<G, M, C, S, F>(&mut self, pop: &Population<G, M, C, S, F>, stats: &StatsRecord)
+    where
+        M: Mutator<G>,
+        C: Crossover<G>,
+        S: SelectMany<F>,
+        F: Clone + PartialOrd,
+        R: ProgressReporter<F>
+    {
+        if let Some((_, _, best)) = pop.best(self.direction) {
+            self.reporter.report(pop.generation(), stats, best);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use rand::rngs::{SmallRng, StdRng};
+use rand::{RngCore, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// A small, fast generator whose state can be saved in a checkpoint, and which seeded populations own
+pub use rand_pcg::Pcg64;
+
+/// Derives an independent seed for stream `stream` of a master seed
+///
+/// Uses the SplitMix64 finaliser so neighbouring streams get unrelated seeds.
+/// This lets parallel code give every work item its own generator while the
+/// results stay a pure function of the master seed, however work is scheduled.
+///
+/// # Examples
+/// ```
+/// use dears::rng::derive_seed;
+/// assert_eq!(derive_seed(42, 7), derive_seed(42, 7));
+/// assert_ne!(derive_seed(42, 7), derive_seed(42, 8));
+/// ```
+pub fn derive_seed(master: u64, stream: u64) -> u64 {
+    let mut z = master ^ stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// One of the independent random streams of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// Creating the initial population
+    Initialization,
+    /// Selection, crossover and mutation, which the evolve loops draw from
+    /// one generator in a fixed order
+    Evolution,
+    /// Anything else, such as one island or one restart of a run
+    Custom(u64),
+}
+
+impl Stream {
+    fn id(self) -> u64 {
+        match self {
+            Stream::Initialization => 0,
+            Stream::Evolution => 1,
+            Stream::Custom(n) => n.wrapping_add(2),
+        }
+    }
+}
+
+/// A single switch for reproducible runs
+///
+/// With a seed, each [`Stream`] gets its own [`StdRng`] seeded by
+/// [`derive_seed`] from the master seed, so a whole run is a pure function of
+/// that one number and adding draws to one stream doesn't shift the others.
+/// Without one, every stream is the thread-local generator, as with the
+/// operators' methods that don't take a generator.
+///
+/// Those methods, and populations that weren't given a seed, are the only
+/// places the crate reaches for the thread-local generator itself. Everything
+/// else, the evolve loops, selectors, operators and initialisers alike, draws
+/// only from the generator it's handed, so a seeded run gives the same result
+/// on any thread and in any process.
+///
+/// The operators' `*_with` methods take `&mut dyn RngCore` rather than being
+/// generic over the generator, so that selectors can be boxed as trait
+/// objects; any generator converts, as `&mut rng`.
+///
+/// # Examples
+/// ```
+/// use dears::initializer::{init_population, UniformFloat};
+/// use dears::rng::*;
+/// let init = UniformFloat { low: 0.0, up: 1.0, len: 3 };
+/// let config = EvolutionConfig::seeded(42);
+/// let first = init_population(5, &init, &mut config.rng(Stream::Initialization));
+/// let again = init_population(5, &init, &mut config.rng(Stream::Initialization));
+/// assert_eq!(first, again);
+/// assert_ne!(first, init_population(5, &init, &mut config.rng(Stream::Custom(0))));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EvolutionConfig {
+    pub seed: Option<u64>,
+}
+
+impl EvolutionConfig {
+    pub fn seeded(seed: u64) -> Self {
+        EvolutionConfig { seed: Some(seed) }
+    }
+
+    /// A fresh generator for `stream`, starting from the same state every time when seeded
+    pub fn rng(&self, stream: Stream) -> Box<dyn RngCore> {
+        match self.seed {
+            Some(seed) => Box::new(StdRng::seed_from_u64(derive_seed(seed, stream.id()))),
+            None => Box::new(rand::thread_rng()),
+        }
+    }
+}
+
+/// The thread-local generator, looked up afresh on every draw
+///
+/// Unlike [`rand::rngs::ThreadRng`] it can be sent between threads, so it's
+/// what an unseeded [`Population`](crate::population::Population) draws from:
+/// each draw comes from the generator of the thread making it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ThreadLocal;
+
+impl RngCore for ThreadLocal {
+    fn next_u32(&mut self) -> u32 {
+        rand::thread_rng().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand::thread_rng().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand::thread_rng().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        rand::thread_rng().try_fill_bytes(dest)
+    }
+}
+
+/// One generator per work item, all from one master seed, for code running on the rayon thread pool
+///
+/// Item `i` gets a [`SmallRng`] seeded with `derive_seed(seed, i)`, so work
+/// that draws for each item from that item's generator gives the same results
+/// whatever the number of threads and whichever worker picks the item up.
+/// Seeding a `SmallRng` per item also costs less than reaching the
+/// thread-local generator on every draw. [`Population::evaluate_par_with`],
+/// [`parallel_vary`] and [`SelectMany::par_select_n`] all share this scheme.
+///
+/// [`Population::evaluate_par_with`]: crate::population::Population::evaluate_par_with
+/// [`parallel_vary`]: crate::algorithms::parallel_vary
+/// [`SelectMany::par_select_n`]: crate::selection::SelectMany::par_select_n
+///
+/// # Examples
+/// ```
+/// use dears::rng::RngPool;
+/// use rand::Rng;
+/// let pool = RngPool { seed: 42 };
+/// assert_eq!(pool.rng(7).gen::<u64>(), pool.rng(7).gen::<u64>());
+/// assert_ne!(pool.rng(7).gen::<u64>(), pool.rng(8).gen::<u64>());
+/// assert_ne!(pool.rng(7).gen::<u64>(), pool.stream(1).rng(7).gen::<u64>());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RngPool {
+    pub seed: u64,
+}
+
+impl RngPool {
+    /// The generator for work item `item`, starting from the same state every time
+    pub fn rng(&self, item: usize) -> SmallRng {
+        SmallRng::seed_from_u64(derive_seed(self.seed, item as u64))
+    }
+
+    /// An independent pool for stream `stream` of this one, such as one generation of a run
+    pub fn stream(&self, stream: u64) -> RngPool {
+        RngPool { seed: derive_seed(self.seed, stream) }
+    }
+
+    /// `f` applied to each of `0..n` across the rayon thread pool, item `i` drawing from [`RngPool::rng`]`(i)`
+    ///
+    /// # Examples
+    /// ```
+    /// use dears::rng::RngPool;
+    /// use rand::Rng;
+    /// let pool = RngPool { seed: 42 };
+    /// let draws = pool.par_map(100, |i, rng| i as f64 + rng.gen::<f64>());
+    /// assert_eq!(draws, pool.par_map(100, |i, rng| i as f64 + rng.gen::<f64>()));
+    /// assert_eq!(draws[7], 7.0 + pool.rng(7).gen::<f64>());
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn par_map<T, F>(&self, n: usize, f: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(usize, &mut SmallRng) -> T + Sync + Send
+    {
+        (0..n).into_par_iter().map(|i| f(i, &mut self.rng(i))).collect()
+    }
+}
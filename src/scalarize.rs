@@ -0,0 +1,137 @@
+/// Turns `N` objectives into one, so single-objective machinery can drive a multi-objective problem
+///
+/// Every scalarizer here treats smaller objectives as better and gives a
+/// value to minimise.
+pub trait Scalarizer<const N: usize> {
+    fn scalarize(&self, f: &[f64; N]) -> f64;
+}
+
+/// The objectives multiplied by their weights and summed
+///
+/// Only finds points on the convex parts of the Pareto front.
+///
+/// # Examples
+/// ```
+/// use dears::scalarize::*;
+/// assert_eq!(WeightedSum([0.5, 2.0]).scalarize(&[4.0, 1.0]), 4.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedSum<const N: usize>(pub [f64; N]);
+
+impl<const N: usize> Scalarizer<N> for WeightedSum<N> {
+    fn scalarize(&self, f: &[f64; N]) -> f64 {
+        f.iter().zip(&self.0).map(|(f, w)| f * w).sum()
+    }
+}
+
+/// The largest weighted distance of any objective from the reference point
+///
+/// With positive weights, every Pareto-optimal point is the minimum for some
+/// choice of weights, convex part of the front or not. The reference is
+/// usually the best value seen for each objective.
+///
+/// # Examples
+/// ```
+/// use dears::scalarize::*;
+/// let tchebycheff = Tchebycheff { weights: [1.0, 2.0], reference: [0.0, 0.0] };
+/// assert_eq!(tchebycheff.scalarize(&[3.0, 1.0]), 3.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tchebycheff<const N: usize> {
+    pub weights: [f64; N],
+    pub reference: [f64; N],
+}
+
+impl<const N: usize> Scalarizer<N> for Tchebycheff<N> {
+    fn scalarize(&self, f: &[f64; N]) -> f64 {
+        let terms = f.iter().zip(&self.weights).zip(&self.reference).map(|((f, w), z)| w * (f - z).abs());
+        terms.fold(f64::NEG_INFINITY, f64::max)
+    }
+}
+
+/// One objective on its own, so long as every other stays within its bound
+///
+/// A point breaking any bound scores infinity. The bound of the objective
+/// being minimised is ignored.
+///
+/// # Examples
+/// ```
+/// use dears::scalarize::*;
+/// let constraint = EpsilonConstraint { objective: 0, bounds: [0.0, 2.0] };
+/// assert_eq!(constraint.scalarize(&[5.0, 1.5]), 5.0);
+/// assert_eq!(constraint.scalarize(&[1.0, 2.5]), f64::INFINITY);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpsilonConstraint<const N: usize> {
+    pub objective: usize,
+    pub bounds: [f64; N],
+}
+
+impl<const N: usize> Scalarizer<N> for EpsilonConstraint<N> {
+    fn scalarize(&self, f: &[f64; N]) -> f64 {
+        let within = (0..N).all(|i| i == self.objective || f[i] <= self.bounds[i]);
+        if within { f[self.objective] } else { f64::INFINITY }
+    }
+}
+
+/// A fitness function giving `scalarizer`'s value of the objectives from `eval`
+///
+/// Select on the result with [`Direction::Minimize`](crate::selection::Direction::Minimize).
+///
+/// # Examples
+/// ```
+/// use dears::algorithms::{ea_simple, Elitism};
+/// use dears::crossover::OnePoint;
+/// use dears::mutation::FlipBit;
+/// use dears::population::PopulationBuilder;
+/// use dears::scalarize::*;
+/// use dears::selection::*;
+/// use dears::termination::MaxGenerations;
+/// let mut pop = PopulationBuilder::new()
+///     .init_with(10, || vec![false; 8])
+///     .mutator(FlipBit { indpb: 0.1 })
+///     .crossover(OnePoint)
+///     .selector(TournamentSelection::new(3).unwrap().with_direction(Direction::Minimize))
+///     .build()
+///     .unwrap();
+/// // Zeros in the first half and ones in the second, half as much
+/// let objectives = |g: &Vec<bool>| {
+///     [g[..4].iter().filter(|&&b| b).count() as f64, g[4..].iter().filter(|&&b| !b).count() as f64]
+/// };
+/// let eval = scalarized(objectives, WeightedSum([1.0, 0.5]));
+/// ea_simple(&mut pop, MaxGenerations(5), 0.5, 0.2, Elitism::default(), eval, None, None, &mut rand::thread_rng());
+/// ```
+pub fn scalarized<G, E, S, const N: usize>(eval: E, scalarizer: S) -> impl Fn(&G) -> f64
+where
+    G: ?Sized,
+    E: Fn(&G) -> [f64; N],
+    S: Scalarizer<N>
+{
+    move |genome| scalarizer.scalarize(&eval(genome))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scalarize::*;
+
+    #[test]
+    fn scalarizers_on_hand_computed_points() {
+        assert_eq!(WeightedSum([1.0, 2.0, 3.0]).scalarize(&[1.0, 1.0, 1.0]), 6.0);
+        assert_eq!(WeightedSum([0.25, 0.75]).scalarize(&[4.0, -4.0]), -2.0);
+
+        let tchebycheff = Tchebycheff { weights: [0.5, 0.5], reference: [1.0, 1.0] };
+        assert_eq!(tchebycheff.scalarize(&[5.0, 2.0]), 2.0);
+        assert_eq!(tchebycheff.scalarize(&[0.0, 3.0]), 1.0);
+        // Both terms tie for the max
+        assert_eq!(tchebycheff.scalarize(&[3.0, -1.0]), 1.0);
+        let skewed = Tchebycheff { weights: [2.0, 1.0], reference: [0.0, 0.0] };
+        assert_eq!(skewed.scalarize(&[1.0, 2.0]), 2.0);
+
+        let constraint = EpsilonConstraint { objective: 1, bounds: [1.0, f64::NAN, 3.0] };
+        assert_eq!(constraint.scalarize(&[1.0, 7.0, 3.0]), 7.0);
+        assert_eq!(constraint.scalarize(&[1.5, 7.0, 0.0]), f64::INFINITY);
+
+        let eval = scalarized(|g: &[f64]| [g[0], g[1]], WeightedSum([1.0, 1.0]));
+        assert_eq!(eval(&[2.0, 3.0][..]), 5.0);
+    }
+}
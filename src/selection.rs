@@ -1,51 +1,2275 @@
-use std::usize;
+use std::cmp::Ordering as CmpOrdering;
+use std::fmt;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
-use rand::Rng;
+use rand::{Rng, RngCore};
 
-use crate::population::*;
+use crate::fitness::MultiFitness;
+#[cfg(feature = "parallel")]
+use crate::rng::RngPool;
 
-/// Trait defining a selection function that uses fitnesses in a population
-pub trait SelectOne<F> {
-    fn select(&self, fitnesses: &Vec<F>) -> usize;
+/// Errors returned by selection functions that can't satisfy a request
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectionError {
+    /// More distinct individuals were requested than the population holds
+    NotEnoughIndividuals { requested: usize, available: usize },
+    /// Rejection sampling gave up before finding enough distinct individuals
+    RejectionLimit { requested: usize, found: usize },
+    /// A fitness that can't be compared (e.g. NaN) took part in the selection
+    NaNFitness { index: usize },
+    /// Tournaments need at least one competitor
+    InvalidTournamentSize(usize),
+    /// A probability parameter was outside its allowed range
+    InvalidProbability(f64),
+    /// A proportionate selector was given a negative weight without a scaling policy
+    NegativeWeight { index: usize },
+    /// Every weight on the wheel was zero, so there's nothing to select proportionally
+    DegenerateWeights,
+    /// A population was selected from before it was evaluated
+    Unevaluated,
+    /// A population's fitnesses don't line up with its individuals
+    FitnessMismatch { individuals: usize, fitnesses: usize },
+    /// An age layer was asked for that the layered selector doesn't have
+    InvalidLayer { layer: usize, layers: usize },
+    /// Sigma scaling was given a constant that isn't positive and finite
+    InvalidSigmaScaling(f64),
 }
 
-pub trait SelectMany<F> {
-    fn select_n(&self, fitnesses: &Vec<F>, n: usize) -> Vec<usize>;
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionError::NotEnoughIndividuals { requested, available } =>
+                write!(f, "Can't select {} distinct individuals from {}", requested, available),
+            SelectionError::RejectionLimit { requested, found } =>
+                write!(f, "Gave up after finding {} of {} distinct individuals", found, requested),
+            SelectionError::NaNFitness { index } =>
+                write!(f, "Fitness of individual {} can't be compared, is it NaN?", index),
+            SelectionError::InvalidTournamentSize(size) =>
+                write!(f, "Invalid tournament size {}, it must be at least 1", size),
+            SelectionError::InvalidProbability(p) =>
+                write!(f, "Probability {} is outside the allowed range", p),
+            SelectionError::NegativeWeight { index } =>
+                write!(f, "Fitness of individual {} is negative, use a Scaling policy", index),
+            SelectionError::DegenerateWeights =>
+                write!(f, "All selection weights are zero"),
+            SelectionError::Unevaluated =>
+                write!(f, "Can't select from a population that hasn't been evaluated"),
+            SelectionError::FitnessMismatch { individuals, fitnesses } =>
+                write!(f, "Population has {} individuals but {} fitnesses", individuals, fitnesses),
+            SelectionError::InvalidLayer { layer, layers } =>
+                write!(f, "Age layer {} doesn't exist, there are {} layers", layer, layers),
+            SelectionError::InvalidSigmaScaling(c) =>
+                write!(f, "Sigma scaling constant {} must be positive and finite", c),
+        }
+    }
+}
+
+impl std::error::Error for SelectionError {}
+
+/// Compares two fitnesses, treating incomparable values such as NaN as the worst
+///
+/// This is the crate-wide NaN policy: a value that can't be compared with
+/// itself loses to every comparable value and ties with other such values,
+/// which makes the ordering total for floats so selection never panics.
+///
+/// # Examples
+/// ```
+/// use std::cmp::Ordering;
+/// use dears::selection::cmp_fitness;
+/// assert_eq!(cmp_fitness(&f64::NAN, &-1.0), Ordering::Less);
+/// assert_eq!(cmp_fitness(&f64::NAN, &f64::NAN), Ordering::Equal);
+/// assert_eq!(cmp_fitness(&2.0, &1.0), Ordering::Greater);
+/// ```
+pub fn cmp_fitness<F: PartialOrd + ?Sized>(a: &F, b: &F) -> CmpOrdering {
+    a.partial_cmp(b).unwrap_or_else(|| {
+        is_nan(b).cmp(&is_nan(a))
+    })
+}
+
+/// Whether bigger or smaller fitness values are better
+///
+/// Selectors default to maximisation. Under either direction incomparable
+/// fitnesses (NaN) are still treated as the worst.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    Maximize,
+    Minimize,
+}
+
+impl Direction {
+    /// Compares two fitnesses so that `Greater` means `a` is the better one
+    ///
+    /// # Examples
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use dears::selection::Direction;
+    /// assert_eq!(Direction::Maximize.compare(&2.0, &1.0), Ordering::Greater);
+    /// assert_eq!(Direction::Minimize.compare(&2.0, &1.0), Ordering::Less);
+    /// assert_eq!(Direction::Minimize.compare(&f64::NAN, &1.0), Ordering::Less);
+    /// ```
+    pub fn compare<F: PartialOrd + ?Sized>(&self, a: &F, b: &F) -> CmpOrdering {
+        match (self, a.partial_cmp(b)) {
+            (Direction::Maximize, _) => cmp_fitness(a, b),
+            (Direction::Minimize, Some(ordering)) => ordering.reverse(),
+            (Direction::Minimize, None) => cmp_fitness(a, b),
+        }
+    }
+
+    /// Maps a fitness onto a scale where bigger is always better
+    pub fn signed(&self, fitness: f64) -> f64 {
+        match self {
+            Direction::Maximize => fitness,
+            Direction::Minimize => -fitness,
+        }
+    }
+}
+
+/// An ordering of fitnesses used to decide which individual is better
+///
+/// `compare(a, b)` returns `Greater` when `a` is the better fitness. It is
+/// implemented by [`Direction`], for any `PartialOrd` fitness, and by closures
+/// `Fn(&F, &F) -> Ordering`, so fitness types with a bespoke ordering don't
+/// need a newtype.
+///
+/// # Examples
+/// ```
+/// use std::cmp::Ordering;
+/// use dears::selection::*;
+/// // Higher accuracy wins, then lower complexity
+/// let cmp = |a: &(f64, u32), b: &(f64, u32)| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1));
+/// assert_eq!(Comparator::compare(&cmp, &(0.9, 3), &(0.9, 5)), Ordering::Greater);
+/// let selector = TournamentSelection::with_comparator(4, cmp).unwrap();
+/// assert!(selector.select(&[(0.9, 3), (0.5, 1)]) < 2);
+/// ```
+pub trait Comparator<F: ?Sized> {
+    fn compare(&self, a: &F, b: &F) -> CmpOrdering;
+}
+
+impl<F: PartialOrd + ?Sized> Comparator<F> for Direction {
+    fn compare(&self, a: &F, b: &F) -> CmpOrdering {
+        Direction::compare(self, a, b)
+    }
+}
+
+impl<F: ?Sized, C: Fn(&F, &F) -> CmpOrdering> Comparator<F> for C {
+    fn compare(&self, a: &F, b: &F) -> CmpOrdering {
+        self(a, b)
+    }
+}
+
+/// Orders fitnesses by their `Ord` implementation, skipping the NaN checks of [`Direction`]
+///
+/// For fitness types with a total order, such as
+/// [`OrderedFitness`](crate::fitness::OrderedFitness), where there's nothing
+/// incomparable to guard against. Values that mark a
+/// [failed](TotalFitness::failed) evaluation, such as an `OrderedFitness`
+/// NaN, are the worst in either direction.
+///
+/// # Examples
+/// ```
+/// use dears::fitness::OrderedFitness;
+/// use dears::selection::*;
+/// let fitnesses = vec![OrderedFitness(1.0), OrderedFitness(f64::NAN), OrderedFitness(3.0)];
+/// let best = SelBest { comparator: TotalOrder::default() }.select_n(&fitnesses, 3);
+/// assert_eq!(best, vec![2, 0, 1]);
+/// let smallest = SelBest { comparator: TotalOrder { direction: Direction::Minimize } }.select_n(&fitnesses, 3);
+/// assert_eq!(smallest, vec![0, 2, 1]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TotalOrder {
+    pub direction: Direction,
+}
+
+impl<F: TotalFitness + ?Sized> Comparator<F> for TotalOrder {
+    fn compare(&self, a: &F, b: &F) -> CmpOrdering {
+        match (a.failed(), b.failed(), self.direction) {
+            (false, false, Direction::Maximize) => a.cmp(b),
+            (false, false, Direction::Minimize) => b.cmp(a),
+            (a_failed, b_failed, _) => b_failed.cmp(&a_failed),
+        }
+    }
 }
 
-impl<F> SelectMany<F> for dyn SelectOne<F> {
-    fn select_n(&self, fitnesses: &Vec<F>, n: usize) -> Vec<usize> {
+/// A fitness with a total order, for [`TotalOrder`]
+///
+/// Some values may stand for a failed evaluation rather than a score, as NaN
+/// does for [`OrderedFitness`](crate::fitness::OrderedFitness); those rank
+/// last whichever way the ordering goes. Other types need only an empty impl.
+pub trait TotalFitness: Ord {
+    /// Whether this value marks a failed evaluation
+    fn failed(&self) -> bool {
+        false
+    }
+}
+
+macro_rules! total_fitness {
+    ($($t:ty),*) => {
+        $(impl TotalFitness for $t {})*
+    };
+}
+
+total_fitness!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Whether a fitness is incomparable with itself, as NaN is
+fn is_nan<F: PartialOrd + ?Sized>(f: &F) -> bool {
+    f.partial_cmp(f).is_none()
+}
+
+/// Number of draws allowed per requested index before rejection sampling gives up
+const REJECTION_ATTEMPTS: usize = 100;
+
+/// Fills a vector with `n` distinct indices by discarding repeated draws
+fn reject_duplicates(n: usize, len: usize, mut draw: impl FnMut() -> usize)
+                     -> Result<Vec<usize>, SelectionError> {
+    if n > len {
+        return Err(SelectionError::NotEnoughIndividuals { requested: n, available: len });
+    }
+    let mut seen = vec![false; len];
+    let mut selected = Vec::with_capacity(n);
+    for _ in 0..(n * REJECTION_ATTEMPTS) {
+        if selected.len() == n {
+            break;
+        }
+        let idx = draw();
+        if !seen[idx] {
+            seen[idx] = true;
+            selected.push(idx);
+        }
+    }
+    if selected.len() < n {
+        return Err(SelectionError::RejectionLimit { requested: n, found: selected.len() });
+    }
+    Ok(selected)
+}
+
+/// Trait defining a selection function that uses fitnesses in a population
+///
+/// Fitnesses are taken as a slice, so they can live in a `Vec`, an array, or
+/// a column of some other container.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = [1.0, 5.0, 2.0, 3.0];
+/// let selector = TournamentSelection::new(2).unwrap();
+/// let winner = selector.select(&fitnesses[1..]);
+/// assert!(winner < 3);
+/// ```
+///
+/// Implementors provide [`SelectOne::select_with`], drawing randomness from the
+/// given generator so runs can be seeded; `select` falls back to `thread_rng`.
+/// Every `SelectOne` is also a [`SelectMany`] through the batch methods, which
+/// selectors that work on the whole population at once (like [`SelBest`])
+/// override rather than implementing `SelectMany` directly.
+pub trait SelectOne<F> {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize;
+
+    fn select(&self, fitnesses: &[F]) -> usize {
+        self.select_with(fitnesses, &mut rand::thread_rng())
+    }
+
+    /// Draws `n` indices, backing the blanket [`SelectMany`] impl
+    ///
+    /// Defaults to calling `select_with` `n` times. Override it when the draws
+    /// can share work, such as building a cumulative table once.
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
         let mut selected = Vec::with_capacity(n);
         for _ in 0..n {
-            selected.push(self.select(fitnesses));
+            selected.push(self.select_with(fitnesses, rng));
         }
         selected
     }
+
+    /// Draws `n` distinct indices, backing the blanket [`SelectMany`] impl
+    ///
+    /// Defaults to rejection sampling over `select_with`.
+    fn select_distinct_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        reject_duplicates(n, fitnesses.len(), || self.select_with(fitnesses, rng))
+    }
+
+    /// Draws `n` indices from an already ranked population, backing the blanket [`SelectMany`] impl
+    ///
+    /// Defaults to `select_batch_with` on the underlying fitnesses. Selectors
+    /// that only care about rank order override it to reuse the ranking.
+    fn select_ranked_batch_with(&self, ranked: &RankedFitnesses<F>, n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        self.select_batch_with(ranked.fitnesses(), n, rng)
+    }
 }
 
+/// Trait defining a selection function that picks `n` individuals at once
+pub trait SelectMany<F> {
+    fn select_n_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize>;
+
+    fn select_n(&self, fitnesses: &[F], n: usize) -> Vec<usize> {
+        self.select_n_with(fitnesses, n, &mut rand::thread_rng())
+    }
+
+    /// Selects `n` pairwise distinct indices, e.g. when choosing survivors
+    ///
+    /// Defaults to rejection sampling over single draws, giving up with
+    /// [`SelectionError::RejectionLimit`] if the selector keeps repeating itself.
+    /// Errors with [`SelectionError::NotEnoughIndividuals`] when `n` exceeds
+    /// the population size.
+    fn select_n_distinct_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                              -> Result<Vec<usize>, SelectionError> {
+        reject_duplicates(n, fitnesses.len(), || self.select_n_with(fitnesses, 1, rng)[0])
+    }
+
+    fn select_n_distinct(&self, fitnesses: &[F], n: usize) -> Result<Vec<usize>, SelectionError> {
+        self.select_n_distinct_with(fitnesses, n, &mut rand::thread_rng())
+    }
 
+    /// As [`SelectMany::select_n_with`] on `ranked.fitnesses()`, reusing the ranking where the selector can
+    ///
+    /// Rank-based selectors such as [`SelBest`] and [`Elitist`] skip their own
+    /// sort when `ranked` is in their order, and sort afresh otherwise, so the
+    /// result is always the same as selecting from the fitnesses. The evolve
+    /// loops rank each generation once and hand it to the selector this way.
+    fn select_n_from_ranked_with(&self, ranked: &RankedFitnesses<F>, n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        self.select_n_with(ranked.fitnesses(), n, rng)
+    }
+
+    /// Runs the `n` selection events in parallel, one rayon task each
+    ///
+    /// Event `i` draws from generator `i` of an
+    /// [`RngPool`] seeded with `seed`, so the result only
+    /// depends on `seed`, not on thread scheduling. Worth it for selectors
+    /// where a single event is expensive, like large tournaments; selectors
+    /// that build a table per call should stick with `select_n`.
+    #[cfg(feature = "parallel")]
+    fn par_select_n(&self, fitnesses: &[F], n: usize, seed: u64) -> Vec<usize>
+    where
+        Self: Sync,
+        F: Sync
+    {
+        RngPool { seed }.par_map(n, |_, rng| self.select_n_with(fitnesses, 1, rng)[0])
+    }
+}
+
+impl<F, T: SelectOne<F> + ?Sized> SelectMany<F> for T {
+    fn select_n_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        self.select_batch_with(fitnesses, n, rng)
+    }
+
+    fn select_n_distinct_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                              -> Result<Vec<usize>, SelectionError> {
+        self.select_distinct_batch_with(fitnesses, n, rng)
+    }
+
+    fn select_n_from_ranked_with(&self, ranked: &RankedFitnesses<F>, n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        self.select_ranked_batch_with(ranked, n, rng)
+    }
+}
+
+
+
+/// Selects `n` individuals, returning clones alongside the indices they came from
+///
+/// Saves writing the clone-by-index loop by hand; the indices are handy for
+/// logging where each parent came from. `population` and `fitnesses` must line up.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let population = vec!["a", "b", "c"];
+/// let fitnesses = [1.0, 3.0, 2.0];
+/// let mut rng = rand::thread_rng();
+/// let (parents, indices) = select_indexed_with(&SelBest::default(), &population, &fitnesses, 2, &mut rng);
+/// assert_eq!(parents, vec!["b", "c"]);
+/// assert_eq!(indices, vec![1, 2]);
+/// ```
+pub fn select_indexed_with<G, F, S>(selector: &S, population: &[G], fitnesses: &[F], n: usize,
+                                    rng: &mut dyn RngCore) -> (Vec<G>, Vec<usize>)
+where
+    G: Clone,
+    S: SelectMany<F> + ?Sized
+{
+    assert_eq!(population.len(), fitnesses.len(), "Need exactly one fitness per individual");
+    let indices = selector.select_n_with(fitnesses, n, rng);
+    let mut individuals = Vec::with_capacity(n);
+    clone_selected_into(&mut individuals, population, &indices);
+    (individuals, indices)
+}
+
+/// Clones `population[indices[i]]` into `buffer[i]`, reusing what's already in the buffer
+///
+/// The cloning half of [`select_indexed_with`], for loops that keep their
+/// selected individuals in the same buffers from one generation to the next.
+pub fn clone_selected_into<T: Clone>(buffer: &mut Vec<T>, population: &[T], indices: &[usize]) {
+    buffer.truncate(indices.len());
+    let reused = buffer.len();
+    for (slot, &i) in buffer.iter_mut().zip(indices) {
+        slot.clone_from(&population[i]);
+    }
+    buffer.extend(indices[reused..].iter().map(|&i| population[i].clone()));
+}
+
+/// Selects `n` individuals, returning clones of them
+pub fn select_individuals_with<G, F, S>(selector: &S, population: &[G], fitnesses: &[F], n: usize,
+                                        rng: &mut dyn RngCore) -> Vec<G>
+where
+    G: Clone,
+    S: SelectMany<F> + ?Sized
+{
+    select_indexed_with(selector, population, fitnesses, n, rng).0
+}
+
+pub fn select_individuals<G, F, S>(selector: &S, population: &[G], fitnesses: &[F], n: usize) -> Vec<G>
+where
+    G: Clone,
+    S: SelectMany<F> + ?Sized
+{
+    select_individuals_with(selector, population, fitnesses, n, &mut rand::thread_rng())
+}
+
+/// Selects the fittest of `tournament_size` randomly drawn individuals
+///
+/// Candidates are drawn with replacement by default, so the same individual
+/// can appear more than once in a tournament. [`without_replacement`] draws
+/// distinct candidates instead, which requires the tournament to fit in the
+/// population. NaN fitnesses lose every comparison (see [`cmp_fitness`]); use
+/// [`TournamentSelection::try_select_with`] to be told about them instead.
+///
+/// Tournaments are built with [`TournamentSelection::new`], which rejects a
+/// size of 0, and read back through [`tournament_size`], [`direction`] and
+/// [`draws_with_replacement`].
+///
+/// [`without_replacement`]: TournamentSelection::without_replacement
+/// [`tournament_size`]: TournamentSelection::tournament_size
+/// [`direction`]: TournamentSelection::direction
+/// [`draws_with_replacement`]: TournamentSelection::draws_with_replacement
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let selector = TournamentSelection::new(2).unwrap()
+///     .with_direction(Direction::Minimize);
+/// let parents = selector.select_n(&fitnesses, 4);
+/// assert_eq!(parents.len(), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TournamentSelection<C = Direction> {
+    tournament_size: usize,
+    comparator: C,
+    replacement: bool,
+}
+
+impl TournamentSelection {
+    /// Creates a maximising tournament, rejecting a size of 0
+    pub fn new(tournament_size: usize) -> Result<Self, SelectionError> {
+        TournamentSelection::with_comparator(tournament_size, Direction::Maximize)
+    }
+
+    /// Creates a tournament decided by `comparator` rather than `PartialOrd`
+    pub fn with_comparator<C>(tournament_size: usize, comparator: C)
+                              -> Result<TournamentSelection<C>, SelectionError> {
+        if tournament_size == 0 {
+            return Err(SelectionError::InvalidTournamentSize(tournament_size));
+        }
+        Ok(TournamentSelection { tournament_size, comparator, replacement: true })
+    }
+
+    pub fn with_direction(self, direction: Direction) -> Self {
+        TournamentSelection { comparator: direction, ..self }
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.comparator
+    }
+
+    /// Runs a tournament, returning an error if a NaN fitness was drawn into it
+    /// or distinct candidates were requested from too small a population
+    pub fn try_select_with<F: PartialOrd>(&self, fitnesses: &[F], rng: &mut dyn RngCore)
+                                          -> Result<usize, SelectionError> {
+        let len = fitnesses.len();
+        assert!(len > 0, "Can't select from empty fitnesses vector");
+        let candidates = draw_candidates(len, self.tournament_size, self.replacement, rng)?;
+        let mut best = candidates[0];
+        for candidate in candidates {
+            if is_nan(&fitnesses[candidate]) {
+                return Err(SelectionError::NaNFitness { index: candidate });
+            }
+            if self.comparator.compare(&fitnesses[candidate], &fitnesses[best]) == CmpOrdering::Greater {
+                best = candidate;
+            }
+        }
+        Ok(best)
+    }
+
+    pub fn try_select<F: PartialOrd>(&self, fitnesses: &[F]) -> Result<usize, SelectionError> {
+        self.try_select_with(fitnesses, &mut rand::thread_rng())
+    }
+}
+
+impl<C> TournamentSelection<C> {
+    /// Draws distinct candidates for each tournament
+    ///
+    /// Selection then panics (or errors, through `try_select_with`) when the
+    /// tournament is bigger than the population.
+    pub fn without_replacement(self) -> Self {
+        TournamentSelection { replacement: false, ..self }
+    }
+
+    pub fn tournament_size(&self) -> usize {
+        self.tournament_size
+    }
+
+    pub fn comparator(&self) -> &C {
+        &self.comparator
+    }
+
+    /// Whether the same individual can be drawn more than once into a tournament
+    pub fn draws_with_replacement(&self) -> bool {
+        self.replacement
+    }
+}
+
+/// Draws the indices taking part in one tournament
+fn draw_candidates(len: usize, size: usize, replacement: bool, rng: &mut dyn RngCore)
+                   -> Result<Vec<usize>, SelectionError> {
+    if replacement {
+        Ok((0..size).map(|_| rng.gen_range(0..len)).collect())
+    } else if size > len {
+        Err(SelectionError::NotEnoughIndividuals { requested: size, available: len })
+    } else {
+        Ok(rand::seq::index::sample(rng, len, size).into_vec())
+    }
+}
+
+/// Returns the winner of a tournament of `size` draws from `0..len`
+///
+/// `compare(a, b)` returns `Greater` when individual `a` beats individual `b`.
+fn run_tournament(len: usize, size: usize, replacement: bool, rng: &mut dyn RngCore,
+                  compare: impl Fn(usize, usize) -> CmpOrdering) -> usize {
+    assert!(len > 0, "Can't select from empty fitnesses vector");
+    assert!(size > 0, "Tournament size can't be 0");
+    if replacement {
+        // Skip allocating the candidates in the common case
+        (0..size).map(|_| rng.gen_range(0..len))
+                 .max_by(|&a, &b| compare(a, b))
+                 .unwrap()
+    } else {
+        draw_candidates(len, size, replacement, rng)
+            .unwrap_or_else(|err| panic!("{}", err))
+            .into_iter()
+            .max_by(|&a, &b| compare(a, b))
+            .unwrap()
+    }
+}
+
+/// Runs `n` tournaments, each over the individuals that haven't won yet
+fn run_distinct_tournaments(len: usize, n: usize, size: usize, replacement: bool, rng: &mut dyn RngCore,
+                            compare: impl Fn(usize, usize) -> CmpOrdering)
+                            -> Result<Vec<usize>, SelectionError> {
+    if n > len {
+        return Err(SelectionError::NotEnoughIndividuals { requested: n, available: len });
+    }
+    let mut pool: Vec<usize> = (0..len).collect();
+    let mut selected = Vec::with_capacity(n);
+    for _ in 0..n {
+        // Distinct candidates can't outnumber what's left in the pool
+        let size = if replacement { size } else { size.min(pool.len()) };
+        let winner = run_tournament(pool.len(), size, replacement, rng, |a, b| compare(pool[a], pool[b]));
+        selected.push(pool.swap_remove(winner));
+    }
+    Ok(selected)
+}
+
+impl<F, C: Comparator<F>> SelectOne<F> for TournamentSelection<C> {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        run_tournament(fitnesses.len(), self.tournament_size, self.replacement, rng, |a, b| {
+            self.comparator.compare(&fitnesses[a], &fitnesses[b])
+        })
+    }
 
-pub struct TournamentSelection {
+    /// Runs each tournament over the individuals that haven't won yet
+    fn select_distinct_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        if !self.replacement && self.tournament_size > fitnesses.len() {
+            return Err(SelectionError::NotEnoughIndividuals {
+                requested: self.tournament_size,
+                available: fitnesses.len(),
+            });
+        }
+        run_distinct_tournaments(fitnesses.len(), n, self.tournament_size, self.replacement, rng, |a, b| {
+            self.comparator.compare(&fitnesses[a], &fitnesses[b])
+        })
+    }
+}
+
+/// Binary tournament where the better candidate only wins with probability `p`
+///
+/// Two distinct individuals are drawn (or the same one twice, in a population
+/// of one). The fitter wins with probability `p` and the other wins otherwise,
+/// so `p` tunes selection pressure continuously between random selection at
+/// 0.5 and a deterministic binary tournament at 1.0.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let selector = StochasticTournament::new(0.75).unwrap();
+/// let parents = selector.select_n(&fitnesses, 4);
+/// assert_eq!(parents.len(), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StochasticTournament {
+    p: f64,
+    direction: Direction,
+}
+
+impl StochasticTournament {
+    /// Creates a maximising stochastic tournament, rejecting `p` outside `[0.5, 1.0]`
+    pub fn new(p: f64) -> Result<Self, SelectionError> {
+        if !(0.5..=1.0).contains(&p) {
+            return Err(SelectionError::InvalidProbability(p));
+        }
+        Ok(StochasticTournament { p, direction: Direction::Maximize })
+    }
+
+    pub fn with_direction(self, direction: Direction) -> Self {
+        StochasticTournament { direction, ..self }
+    }
+
+    pub fn p(&self) -> f64 {
+        self.p
+    }
+}
+
+impl<F: PartialOrd> SelectOne<F> for StochasticTournament {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        let len = fitnesses.len();
+        assert!(len > 0, "Can't select from empty fitnesses vector");
+        let (a, b) = if len == 1 {
+            (0, 0)
+        } else {
+            let pair = rand::seq::index::sample(rng, len, 2);
+            (pair.index(0), pair.index(1))
+        };
+        let (better, worse) = match self.direction.compare(&fitnesses[a], &fitnesses[b]) {
+            CmpOrdering::Less => (b, a),
+            _ => (a, b),
+        };
+        if rng.gen::<f64>() < self.p { better } else { worse }
+    }
+}
+
+/// How per-objective weights combine when comparing multi-objective fitnesses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightedComparison {
+    /// Compare the weighted sums of the objectives
+    Sum,
+    /// Compare weighted objectives in order, later ones only breaking ties (as DEAP does)
+    Lexicographic,
+}
+
+/// Tournament selection over multi-objective `[f64; N]` fitnesses
+///
+/// Each objective is multiplied by its weight, so positive weights maximise and
+/// negative weights minimise that objective. The weighted values are then
+/// compared as set by `comparison`. NaN objectives and `None` fitnesses lose
+/// every comparison.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// // Maximise the first objective, minimise the second at half the importance
+/// let selector = WeightedTournament {
+///     tournament_size: 3,
+///     weights: [1.0, -0.5],
+///     comparison: WeightedComparison::Sum,
+/// };
+/// let fitnesses = vec![[1.0, 2.0], [3.0, 0.5], [2.0, 2.0]];
+/// let parents = selector.select_n(&fitnesses, 2);
+/// assert_eq!(parents.len(), 2);
+/// ```
+pub struct WeightedTournament<const N: usize> {
     pub tournament_size: usize,
+    pub weights: [f64; N],
+    pub comparison: WeightedComparison,
+}
+
+impl<const N: usize> WeightedTournament<N> {
+    /// Compares two fitnesses so that `Greater` means `a` is the better one
+    pub fn compare(&self, a: &[f64; N], b: &[f64; N]) -> CmpOrdering {
+        let weighted = |f: &[f64; N], i: usize| f[i] * self.weights[i];
+        match self.comparison {
+            WeightedComparison::Sum => {
+                let sum = |f| (0..N).map(|i| weighted(f, i)).sum::<f64>();
+                cmp_fitness(&sum(a), &sum(b))
+            }
+            WeightedComparison::Lexicographic => {
+                // A NaN anywhere makes the whole fitness the worst
+                match (a.iter().any(|x| x.is_nan()), b.iter().any(|x| x.is_nan())) {
+                    (false, false) => (0..N)
+                        .map(|i| cmp_fitness(&weighted(a, i), &weighted(b, i)))
+                        .find(|&ordering| ordering != CmpOrdering::Equal)
+                        .unwrap_or(CmpOrdering::Equal),
+                    (a_nan, b_nan) => b_nan.cmp(&a_nan),
+                }
+            }
+        }
+    }
+
+    fn compare_option(&self, a: &Option<[f64; N]>, b: &Option<[f64; N]>) -> CmpOrdering {
+        match (a, b) {
+            (Some(a), Some(b)) => self.compare(a, b),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        }
+    }
+}
+
+impl<const N: usize> SelectOne<[f64; N]> for WeightedTournament<N> {
+    fn select_with(&self, fitnesses: &[[f64; N]], rng: &mut dyn RngCore) -> usize {
+        run_tournament(fitnesses.len(), self.tournament_size, true, rng, |a, b| {
+            self.compare(&fitnesses[a], &fitnesses[b])
+        })
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[[f64; N]], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        run_distinct_tournaments(fitnesses.len(), n, self.tournament_size, true, rng, |a, b| {
+            self.compare(&fitnesses[a], &fitnesses[b])
+        })
+    }
+}
+
+impl<const N: usize> SelectOne<MultiFitness<N>> for WeightedTournament<N> {
+    fn select_with(&self, fitnesses: &[MultiFitness<N>], rng: &mut dyn RngCore) -> usize {
+        run_tournament(fitnesses.len(), self.tournament_size, true, rng, |a, b| {
+            self.compare(&fitnesses[a].0, &fitnesses[b].0)
+        })
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[MultiFitness<N>], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        run_distinct_tournaments(fitnesses.len(), n, self.tournament_size, true, rng, |a, b| {
+            self.compare(&fitnesses[a].0, &fitnesses[b].0)
+        })
+    }
+}
+
+impl<const N: usize> SelectOne<Option<[f64; N]>> for WeightedTournament<N> {
+    fn select_with(&self, fitnesses: &[Option<[f64; N]>], rng: &mut dyn RngCore) -> usize {
+        run_tournament(fitnesses.len(), self.tournament_size, true, rng, |a, b| {
+            self.compare_option(&fitnesses[a], &fitnesses[b])
+        })
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[Option<[f64; N]>], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        run_distinct_tournaments(fitnesses.len(), n, self.tournament_size, true, rng, |a, b| {
+            self.compare_option(&fitnesses[a], &fitnesses[b])
+        })
+    }
+}
+
+/// A shared, cloneable handle to an annealing temperature
+///
+/// Clones of a `Temperature` all point at the same value, so a schedule
+/// can lower the temperature between generations while selectors (or
+/// mutators) holding a clone see the new value on their next call.
+///
+/// # Examples
+/// ```
+/// use dears::selection::Temperature;
+/// let temp = Temperature::new(10.0);
+/// let shared = temp.clone();
+/// temp.cool(0.5);
+/// assert_eq!(shared.get(), 5.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Temperature(Arc<AtomicU64>);
+
+impl Temperature {
+    pub fn new(value: f64) -> Self {
+        Temperature(Arc::new(AtomicU64::new(value.to_bits())))
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Applies one step of a geometric cooling schedule, `T <- T * rate`
+    pub fn cool(&self, rate: f64) {
+        self.set(self.get() * rate);
+    }
+}
+
+impl From<f64> for Temperature {
+    fn from(value: f64) -> Self {
+        Temperature::new(value)
+    }
+}
+
+/// Selects individuals with probability proportional to `exp(fitness / T)`
+///
+/// At high temperatures selection is close to uniform, and as the temperature
+/// falls the pressure increases until selection is effectively greedy. The
+/// temperature is read from a shared [`Temperature`] handle on every call, so
+/// it can be annealed externally; [`Boltzmann::select_n_at`] overrides it for
+/// a single call. Under [`Direction::Minimize`] the weights are `exp(-fitness / T)`.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let selector = Boltzmann { temperature: Temperature::new(1.0), direction: Direction::Maximize };
+/// let parents = selector.select_n(&fitnesses, 4);
+/// assert_eq!(parents.len(), 4);
+/// ```
+pub struct Boltzmann {
+    pub temperature: Temperature,
+    pub direction: Direction,
+}
+
+impl Boltzmann {
+    /// Selects `n` indices using the given temperature instead of the handle
+    pub fn select_n_at<F: Into<f64> + Copy>(&self, fitnesses: &[F], n: usize, temperature: f64,
+                                            rng: &mut dyn RngCore) -> Vec<usize> {
+        let cumulative = boltzmann_table(fitnesses, temperature, self.direction);
+        (0..n).map(|_| spin(&cumulative, rng.gen())).collect()
+    }
+}
+
+/// Finds the slot of a cumulative weight table that `fraction` of the way round lands in
+fn spin(cumulative: &[f64], fraction: f64) -> usize {
+    let target = fraction * cumulative.last().unwrap();
+    // Clamp in case of rounding at the very top of the wheel
+    cumulative.partition_point(|&c| c <= target).min(cumulative.len() - 1)
+}
+
+/// Builds the cumulative table of Boltzmann weights for the given fitnesses
+///
+/// The maximum fitness is subtracted before exponentiation so the largest
+/// weight is always exactly 1 and nothing can overflow. Fitnesses equal to
+/// the maximum get weight 1 outright, so an infinite best shares the wheel
+/// with its equals rather than turning into `inf - inf`. NaN fitnesses get
+/// zero weight.
+fn boltzmann_table<F: Into<f64> + Copy>(fitnesses: &[F], temperature: f64, direction: Direction) -> Vec<f64> {
+    assert!(!fitnesses.is_empty(), "Can't select from empty fitnesses vector");
+    assert!(temperature > 0.0 && temperature.is_finite(),
+            "Boltzmann temperature must be positive and finite, got {}", temperature);
+    let max = fitnesses.iter()
+        .map(|&f| direction.signed(f.into()))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let mut total = 0.0;
+    fitnesses.iter().map(|&f| {
+        let signed = direction.signed(f.into());
+        let weight = if signed == max { 1.0 } else { ((signed - max) / temperature).exp() };
+        if !weight.is_nan() {
+            total += weight;
+        }
+        total
+    }).collect()
+}
+
+impl<F: Into<f64> + Copy> SelectOne<F> for Boltzmann {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        self.select_n_at(fitnesses, 1, self.temperature.get(), rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        self.select_n_at(fitnesses, n, self.temperature.get(), rng)
+    }
+}
+
+/// Worst fitnesses of the last few generations, shared between clones
+///
+/// Used by [`Scaling::Windowing`]. Every wheel built folds the worst fitness
+/// it sees into the current generation, however many times it's built, and
+/// [`advance`](FitnessWindow::advance) closes that generation. Call it once per
+/// generation, e.g. from an observer's `on_generation_end`.
+#[derive(Clone, Debug)]
+pub struct FitnessWindow {
+    pub size: usize,
+    state: Arc<Mutex<WindowState>>,
+}
+
+#[derive(Debug)]
+struct WindowState {
+    past: VecDeque<f64>,
+    current: f64,
+}
+
+impl FitnessWindow {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "Fitness window must cover at least one generation");
+        let state = WindowState { past: VecDeque::with_capacity(size), current: f64::INFINITY };
+        FitnessWindow { size, state: Arc::new(Mutex::new(state)) }
+    }
+
+    /// Ends the current generation, dropping the oldest one once the window is full
+    pub fn advance(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.current == f64::INFINITY {
+            return;
+        }
+        if state.past.len() + 1 == self.size {
+            state.past.pop_front();
+        }
+        if self.size > 1 {
+            let current = state.current;
+            state.past.push_back(current);
+        }
+        state.current = f64::INFINITY;
+    }
+
+    /// Records a worst fitness for this generation, returning the worst in the window
+    fn record(&self, worst: f64) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        state.current = state.current.min(worst);
+        state.past.iter().copied().fold(state.current, f64::min)
+    }
+}
+
+/// How fitnesses are turned into weights for proportionate selection
+#[derive(Clone, Debug)]
+pub enum Scaling {
+    /// Use fitnesses as weights directly; negative fitnesses are an error
+    None,
+    /// Subtract the minimum fitness and add `epsilon`, so every weight is positive
+    ShiftToPositive { epsilon: f64 },
+    /// Subtract the worst fitness seen over the last few generations
+    Windowing(FitnessWindow),
+    /// Goldberg's sigma scaling, `max(0, 1 + (f - mean) / (c * std))`
+    ///
+    /// All weights are 1 when every fitness is the same. `c` must be positive
+    /// and finite.
+    SigmaScaling { c: f64 },
+}
+
+impl Scaling {
+    /// Applies the policy to bigger-is-better fitnesses, giving NaN zero weight
+    pub fn weights(&self, fitnesses: &[f64]) -> Result<Vec<f64>, SelectionError> {
+        let valid = || fitnesses.iter().copied().filter(|f| !f.is_nan());
+        let min = valid().fold(f64::INFINITY, f64::min);
+        let weigh = |transform: &dyn Fn(f64) -> f64| -> Vec<f64> {
+            fitnesses.iter().map(|&f| if f.is_nan() { 0.0 } else { transform(f) }).collect()
+        };
+        let weights = match self {
+            Scaling::None => {
+                if let Some(index) = fitnesses.iter().position(|&f| f < 0.0) {
+                    return Err(SelectionError::NegativeWeight { index });
+                }
+                weigh(&|f| f)
+            }
+            Scaling::ShiftToPositive { epsilon } => weigh(&|f| f - min + epsilon),
+            Scaling::Windowing(window) => {
+                let baseline = window.record(min);
+                weigh(&|f| (f - baseline).max(0.0))
+            }
+            Scaling::SigmaScaling { c } => {
+                if !c.is_finite() || *c <= 0.0 {
+                    return Err(SelectionError::InvalidSigmaScaling(*c));
+                }
+                let count = valid().count() as f64;
+                let mean = valid().sum::<f64>() / count;
+                let std = (valid().map(|f| (f - mean).powi(2)).sum::<f64>() / count).sqrt();
+                if std == 0.0 {
+                    weigh(&|_| 1.0)
+                } else {
+                    weigh(&|f| (1.0 + (f - mean) / (c * std)).max(0.0))
+                }
+            }
+        };
+        if weights.iter().all(|&w| w == 0.0) {
+            return Err(SelectionError::DegenerateWeights);
+        }
+        Ok(weights)
+    }
+}
+
+/// Works out the (scaled) weight of every slot on a proportionate wheel
+fn proportional_weights<F: Into<f64> + Copy>(fitnesses: &[F], scaling: &Scaling, direction: Direction)
+                                            -> Result<Vec<f64>, SelectionError> {
+    assert!(!fitnesses.is_empty(), "Can't select from empty fitnesses vector");
+    let signed: Vec<f64> = fitnesses.iter().map(|&f| direction.signed(f.into())).collect();
+    scaling.weights(&signed)
+}
+
+/// Builds the cumulative wheel for proportionate selection
+fn proportional_table<F: Into<f64> + Copy>(fitnesses: &[F], scaling: &Scaling, direction: Direction)
+                                          -> Result<Vec<f64>, SelectionError> {
+    let mut total = 0.0;
+    Ok(proportional_weights(fitnesses, scaling, direction)?.into_iter().map(|w| {
+        total += w;
+        total
+    }).collect())
+}
+
+/// Batches at least this big are drawn from a [`PreparedWheel`] by [`Roulette`]
+const ALIAS_THRESHOLD: usize = 32;
+
+/// A roulette wheel prepared once for constant-time spins (Walker's alias method)
+///
+/// Building the table is `O(n)`, after which every [`sample`](PreparedWheel::sample)
+/// takes one random slot and one coin flip, with no search. This pays off when
+/// many draws are made from the same weights. Zero-weight entries are never
+/// drawn, and NaN weights count as zero.
+///
+/// # Examples
+/// ```
+/// use dears::selection::PreparedWheel;
+/// let wheel = PreparedWheel::new(&[0.0, 3.0, 1.0]).unwrap();
+/// let mut rng = rand::thread_rng();
+/// assert_ne!(wheel.sample(&mut rng), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PreparedWheel {
+    probability: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl PreparedWheel {
+    /// Builds the alias table, failing on negative or all-zero weights
+    pub fn new(weights: &[f64]) -> Result<Self, SelectionError> {
+        assert!(!weights.is_empty(), "Can't build a wheel with no slots");
+        if let Some(index) = weights.iter().position(|&w| w < 0.0) {
+            return Err(SelectionError::NegativeWeight { index });
+        }
+        let weights: Vec<f64> = weights.iter().map(|&w| if w.is_nan() { 0.0 } else { w }).collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return Err(SelectionError::DegenerateWeights);
+        }
+
+        let len = weights.len();
+        let mut scaled: Vec<f64> = weights.iter().map(|w| w * len as f64 / total).collect();
+        let mut probability = vec![1.0; len];
+        let mut alias: Vec<usize> = (0..len).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..len).partition(|&i| scaled[i] < 1.0);
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            probability[s] = scaled[s];
+            alias[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // Anything left over is only short of 1 through rounding, except that
+        // a zero weight must still never be drawn
+        let heaviest = (0..len).max_by(|&a, &b| weights[a].total_cmp(&weights[b])).unwrap();
+        for i in small {
+            if weights[i] == 0.0 {
+                probability[i] = 0.0;
+                alias[i] = heaviest;
+            }
+        }
+        Ok(PreparedWheel { probability, alias })
+    }
+
+    /// Builds the wheel [`Roulette`] would use for these fitnesses
+    pub fn from_fitnesses<F: Into<f64> + Copy>(fitnesses: &[F], scaling: &Scaling, direction: Direction)
+                                               -> Result<Self, SelectionError> {
+        PreparedWheel::new(&proportional_weights(fitnesses, scaling, direction)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.probability.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probability.is_empty()
+    }
+
+    /// Draws one slot index in constant time
+    pub fn sample(&self, rng: &mut dyn RngCore) -> usize {
+        let slot = rng.gen_range(0..self.probability.len());
+        if rng.gen::<f64>() < self.probability[slot] {
+            slot
+        } else {
+            self.alias[slot]
+        }
+    }
+}
+
+/// Fitness proportionate (roulette wheel) selection
+///
+/// Each draw picks an individual with probability proportional to its weight,
+/// where weights come from applying `scaling` to the fitnesses. Minimising
+/// negates fitnesses first, so it needs a policy that copes with negatives.
+/// The wheel is built once per `select_n` call; the `SelectOne` methods panic
+/// if it can't be built, use [`Roulette::try_select_n_with`] to get the error.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![-1.0, 5.0, 0.0, 3.0];
+/// let selector = Roulette {
+///     scaling: Scaling::ShiftToPositive { epsilon: 0.1 },
+///     direction: Direction::Maximize,
+/// };
+/// let parents = selector.select_n(&fitnesses, 4);
+/// assert_eq!(parents.len(), 4);
+/// ```
+pub struct Roulette {
+    pub scaling: Scaling,
+    pub direction: Direction,
+}
+
+impl Roulette {
+    pub fn try_select_n_with<F: Into<f64> + Copy>(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                                                  -> Result<Vec<usize>, SelectionError> {
+        if n >= ALIAS_THRESHOLD {
+            let wheel = PreparedWheel::from_fitnesses(fitnesses, &self.scaling, self.direction)?;
+            return Ok((0..n).map(|_| wheel.sample(rng)).collect());
+        }
+        let cumulative = proportional_table(fitnesses, &self.scaling, self.direction)?;
+        Ok((0..n).map(|_| spin(&cumulative, rng.gen())).collect())
+    }
+}
+
+impl<F: Into<f64> + Copy> SelectOne<F> for Roulette {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        self.try_select_n_with(fitnesses, n, rng).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+/// Stochastic universal sampling, a lower variance alternative to [`Roulette`]
+///
+/// Rather than spinning the wheel `n` times, a single spin places `n` evenly
+/// spaced pointers around it. Weights are computed as for `Roulette`, and the
+/// selected indices come out in wheel order.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let selector = StochasticUniversalSampling {
+///     scaling: Scaling::None,
+///     direction: Direction::Maximize,
+/// };
+/// let parents = selector.select_n(&fitnesses, 4);
+/// // Evenly spaced pointers always land on the widest slot
+/// assert!(parents.contains(&1));
+/// ```
+pub struct StochasticUniversalSampling {
+    pub scaling: Scaling,
+    pub direction: Direction,
+}
+
+impl StochasticUniversalSampling {
+    pub fn try_select_n_with<F: Into<f64> + Copy>(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                                                  -> Result<Vec<usize>, SelectionError> {
+        let cumulative = proportional_table(fitnesses, &self.scaling, self.direction)?;
+        let start: f64 = rng.gen();
+        Ok((0..n).map(|i| spin(&cumulative, (start + i as f64) / n as f64)).collect())
+    }
+}
+
+impl<F: Into<f64> + Copy> SelectOne<F> for StochasticUniversalSampling {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        self.try_select_n_with(fitnesses, n, rng).unwrap_or_else(|err| panic!("{}", err))
+    }
 }
 
-impl<F: PartialOrd + Copy> SelectOne<F> for TournamentSelection {
-    fn select(&self, fitnesses: &Vec<F>) -> usize {
+/// Selects indices uniformly at random, ignoring fitness entirely
+///
+/// Useful as a baseline and for reproduction paths that shouldn't apply any
+/// selection pressure. With `replacement` set to false, `select_n` returns
+/// distinct indices and panics if more are requested than there are individuals.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let selector = RandomSelection { replacement: false };
+/// let mut picked = selector.select_n(&fitnesses, 4);
+/// picked.sort();
+/// assert_eq!(picked, vec![0, 1, 2, 3]);
+/// ```
+pub struct RandomSelection {
+    pub replacement: bool,
+}
+
+impl<F> SelectOne<F> for RandomSelection {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
         let len = fitnesses.len();
         assert!(len > 0, "Can't select from empty fitnesses vector");
+        rng.gen_range(0..len)
+    }
 
-        let mut rng = rand::thread_rng();
-        let options = (0..self.tournament_size).map(|_| rng.gen_range(0..len));
-        options.max_by(|&a, &b| {
-            fitnesses[a].partial_cmp(&fitnesses[b])
-                        .expect("Failed to compare fitnesses, are they NaN?")
-        }).expect("Tournament size can't be 0")
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        let len = fitnesses.len();
+        assert!(len > 0, "Can't select from empty fitnesses vector");
+        if self.replacement {
+            (0..n).map(|_| rng.gen_range(0..len)).collect()
+        } else {
+            assert!(n <= len, "Can't select {} distinct indices from {} individuals", n, len);
+            rand::seq::index::sample(rng, len, n).into_vec()
+        }
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        let len = fitnesses.len();
+        if n > len {
+            return Err(SelectionError::NotEnoughIndividuals { requested: n, available: len });
+        }
+        Ok(rand::seq::index::sample(rng, len, n).into_vec())
     }
 }
 
-pub struct SelBest {}
+/// A fitness slice sorted once, for sharing between rank-based selectors
+///
+/// Holds the indices in best-first order and each individual's rank, with
+/// tied fitnesses sharing the average of their positions (the best is rank 0).
+/// The view borrows the fitnesses, so it can't outlive or go stale against
+/// them. Pass it to anything implementing [`SelectRanked`] that orders
+/// fitnesses the same way as the [`Comparator`] it was built with.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 1.0, 3.0];
+/// let ranked = RankedFitnesses::new(&fitnesses, &Direction::Maximize);
+/// assert_eq!(ranked.order(), &[1, 3, 0, 2]);
+/// assert_eq!(ranked.rank(0), 2.5);
+/// assert_eq!(SelBest::default().select_n_ranked(&ranked, 2), vec![1, 3]);
+/// ```
+///
+/// Fitnesses can't change while a view of them is alive:
+/// ```compile_fail
+/// use dears::selection::*;
+/// let mut fitnesses = vec![1.0, 5.0];
+/// let ranked = RankedFitnesses::new(&fitnesses, &Direction::Maximize);
+/// fitnesses[0] = 9.0;
+/// SelBest::default().select_n_ranked(&ranked, 1);
+/// ```
+pub struct RankedFitnesses<'a, F> {
+    fitnesses: &'a [F],
+    order: Vec<usize>,
+    ranks: Vec<f64>,
+}
+
+impl<'a, F> RankedFitnesses<'a, F> {
+    pub fn new<C: Comparator<F> + ?Sized>(fitnesses: &'a [F], comparator: &C) -> Self {
+        let mut order: Vec<usize> = (0..fitnesses.len()).collect();
+        order.sort_by(|&a, &b| comparator.compare(&fitnesses[b], &fitnesses[a]));
+        let mut ranks = vec![0.0; fitnesses.len()];
+        let mut start = 0;
+        while start < order.len() {
+            let mut end = start + 1;
+            while end < order.len()
+                && comparator.compare(&fitnesses[order[start]], &fitnesses[order[end]]) == CmpOrdering::Equal {
+                end += 1;
+            }
+            let average = (start + end - 1) as f64 / 2.0;
+            for &idx in &order[start..end] {
+                ranks[idx] = average;
+            }
+            start = end;
+        }
+        RankedFitnesses { fitnesses, order, ranks }
+    }
+
+    pub fn fitnesses(&self) -> &'a [F] {
+        self.fitnesses
+    }
+
+    /// Indices from best to worst, with ties kept in index order
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// The tie-averaged rank of individual `idx`, where 0 is the best
+    pub fn rank(&self, idx: usize) -> f64 {
+        self.ranks[idx]
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Whether this is the order `comparator` would have given: best first, with ties in index order
+    ///
+    /// One pass over the view rather than a fresh sort, so selectors can
+    /// check they were handed a ranking built for their own ordering.
+    pub fn is_ordered_by<C: Comparator<F> + ?Sized>(&self, comparator: &C) -> bool {
+        self.order.windows(2).all(|pair| {
+            match comparator.compare(&self.fitnesses[pair[0]], &self.fitnesses[pair[1]]) {
+                CmpOrdering::Greater => true,
+                CmpOrdering::Equal => pair[0] < pair[1],
+                CmpOrdering::Less => false,
+            }
+        })
+    }
+}
+
+/// Selection from a prepared [`RankedFitnesses`], skipping the sort
+///
+/// Implemented by the selectors that only care about rank order, so several
+/// of them can share one sort per generation. Results are identical to
+/// calling [`SelectMany::select_n_with`] on the underlying fitnesses.
+pub trait SelectRanked<F> {
+    fn select_n_ranked_with(&self, ranked: &RankedFitnesses<F>, n: usize, rng: &mut dyn RngCore) -> Vec<usize>;
+
+    fn select_n_ranked(&self, ranked: &RankedFitnesses<F>, n: usize) -> Vec<usize> {
+        self.select_n_ranked_with(ranked, n, &mut rand::thread_rng())
+    }
+}
+
+/// Deterministically selects the fittest individuals, best first
+///
+/// NaN fitnesses are ranked last, following [`cmp_fitness`].
+/// Selections are always distinct while `n` doesn't exceed the population
+/// size; beyond that the ranking is repeated from the top.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let best = SelBest { comparator: Direction::Maximize }.select_n(&fitnesses, 2);
+/// assert_eq!(best, vec![1, 3]);
+/// ```
+pub struct SelBest<C = Direction> {
+    pub comparator: C,
+}
+
+impl Default for SelBest {
+    fn default() -> Self {
+        SelBest { comparator: Direction::default() }
+    }
+}
+
+impl<C> SelBest<C> {
+    fn ranking<F>(&self, fitnesses: &[F]) -> Vec<usize>
+    where
+        C: Comparator<F>
+    {
+        RankedFitnesses::new(fitnesses, &self.comparator).order
+    }
+}
+
+impl<F, C: Comparator<F>> SelectRanked<F> for SelBest<C> {
+    fn select_n_ranked_with(&self, ranked: &RankedFitnesses<F>, n: usize, _rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(!ranked.is_empty(), "Can't select from empty fitnesses vector");
+        assert!(ranked.is_ordered_by(&self.comparator), "Ranking was built for a different ordering than SelBest's");
+        ranked.order.iter().copied().cycle().take(n).collect()
+    }
+}
+
+impl<F, C: Comparator<F>> SelectOne<F> for SelBest<C> {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, _rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(!fitnesses.is_empty(), "Can't select from empty fitnesses vector");
+        self.ranking(fitnesses).into_iter().cycle().take(n).collect()
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[F], n: usize, _rng: &mut dyn RngCore)
+                              -> Result<Vec<usize>, SelectionError> {
+        let len = fitnesses.len();
+        if n > len {
+            return Err(SelectionError::NotEnoughIndividuals { requested: n, available: len });
+        }
+        let mut order = self.ranking(fitnesses);
+        order.truncate(n);
+        Ok(order)
+    }
 
-// impl<const N: usize, T: PartialOrd> Selector<[T; N]> for TournamentSelection {
-//     fn select(&self, fitnesses: Vec<[f64; N]>) -> usize {
-//     }
-// }
\ No newline at end of file
+    fn select_ranked_batch_with(&self, ranked: &RankedFitnesses<F>, n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        match ranked.is_ordered_by(&self.comparator) {
+            true => self.select_n_ranked_with(ranked, n, rng),
+            false => self.select_batch_with(ranked.fitnesses(), n, rng),
+        }
+    }
+}
+
+
+/// Always carries the best `k` individuals forward, filling the rest from `inner`
+///
+/// The elites come first in the output, ranked as by [`SelBest`]. If `k` is at
+/// least `n` this is just the top `n`.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let selector = Elitist {
+///     k: 1,
+///     inner: RandomSelection { replacement: true },
+///     direction: Direction::Maximize,
+/// };
+/// let picked = selector.select_n(&fitnesses, 4);
+/// assert_eq!(picked[0], 1);
+/// ```
+pub struct Elitist<S> {
+    pub k: usize,
+    pub inner: S,
+    pub direction: Direction,
+}
+
+impl<S> Elitist<S> {
+    fn elites<F: PartialOrd>(&self, fitnesses: &[F], n: usize) -> Vec<usize> {
+        let mut order = SelBest { comparator: self.direction }.ranking(fitnesses);
+        order.truncate(self.k.min(n));
+        order
+    }
+}
+
+impl<F: PartialOrd, S: SelectMany<F>> SelectRanked<F> for Elitist<S> {
+    fn select_n_ranked_with(&self, ranked: &RankedFitnesses<F>, n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(ranked.is_ordered_by(&self.direction), "Ranking was built for a different direction than Elitist's");
+        self.select_ranked_batch_with(ranked, n, rng)
+    }
+}
+
+impl<F: PartialOrd, S: SelectMany<F>> SelectOne<F> for Elitist<S> {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut selected = self.elites(fitnesses, n);
+        let rest = n - selected.len();
+        selected.extend(self.inner.select_n_with(fitnesses, rest, rng));
+        selected
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        let mut selected = self.elites(fitnesses, n);
+        let rest = n - selected.len();
+        // The inner selection holds at most `k` elites, so enough others remain
+        let others: Vec<usize> = self.inner.select_n_distinct_with(fitnesses, n, rng)?
+            .into_iter()
+            .filter(|idx| !selected.contains(idx))
+            .take(rest)
+            .collect();
+        selected.extend(others);
+        Ok(selected)
+    }
+
+    fn select_ranked_batch_with(&self, ranked: &RankedFitnesses<F>, n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut selected = match ranked.is_ordered_by(&self.direction) {
+            true => ranked.order.iter().copied().take(self.k.min(n)).collect(),
+            false => self.elites(ranked.fitnesses(), n),
+        };
+        let rest = n - selected.len();
+        selected.extend(self.inner.select_n_from_ranked_with(ranked, rest, rng));
+        selected
+    }
+}
+
+/// Evolutionary programming style survivor selection by round-robin bouts
+///
+/// Every individual meets `q` opponents drawn uniformly (with replacement)
+/// from the rest of the population, scoring a win each time it is strictly
+/// better. Individuals are ranked by wins, with fitness and then index
+/// breaking ties, and the top `n` are returned. Drawn bouts score nothing for
+/// either side, so equal fitnesses never depend on who was drawn first.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let selector = RoundRobinTournament { q: 10, direction: Direction::Maximize };
+/// let survivors = selector.select_n_distinct(&fitnesses, 2).unwrap();
+/// assert_eq!(survivors[0], 1);
+/// ```
+pub struct RoundRobinTournament {
+    pub q: usize,
+    pub direction: Direction,
+}
+
+impl RoundRobinTournament {
+    /// Plays `q` bouts for every individual and returns the win counts
+    pub fn win_counts_with<F: PartialOrd>(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> Vec<usize> {
+        let len = fitnesses.len();
+        let mut wins = vec![0; len];
+        if len < 2 {
+            return wins;
+        }
+        for (i, win) in wins.iter_mut().enumerate() {
+            for _ in 0..self.q {
+                // Skip over `i` so nobody fights themselves
+                let mut opponent = rng.gen_range(0..len - 1);
+                if opponent >= i {
+                    opponent += 1;
+                }
+                if self.direction.compare(&fitnesses[i], &fitnesses[opponent]) == CmpOrdering::Greater {
+                    *win += 1;
+                }
+            }
+        }
+        wins
+    }
+
+    fn ranking<F: PartialOrd>(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> Vec<usize> {
+        let wins = self.win_counts_with(fitnesses, rng);
+        let mut order: Vec<usize> = (0..fitnesses.len()).collect();
+        order.sort_by(|&a, &b| {
+            wins[b].cmp(&wins[a])
+                .then_with(|| self.direction.compare(&fitnesses[b], &fitnesses[a]))
+        });
+        order
+    }
+}
+
+impl<F: PartialOrd> SelectOne<F> for RoundRobinTournament {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        assert!(!fitnesses.is_empty(), "Can't select from empty fitnesses vector");
+        self.ranking(fitnesses, rng).into_iter().cycle().take(n).collect()
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        let len = fitnesses.len();
+        if n > len {
+            return Err(SelectionError::NotEnoughIndividuals { requested: n, available: len });
+        }
+        let mut order = self.ranking(fitnesses, rng);
+        order.truncate(n);
+        Ok(order)
+    }
+}
+
+/// Makes sure the single best individual is in every selection from `inner`
+///
+/// If the best (the first one, for ties) wasn't picked, a uniformly chosen
+/// slot of the selection is overwritten with it. Every other pick is left
+/// alone, so the rest of the sample keeps the inner selector's distribution.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let selector = IncludeBest {
+///     inner: RandomSelection { replacement: true },
+///     direction: Direction::Maximize,
+/// };
+/// assert!(selector.select_n(&fitnesses, 2).contains(&1));
+/// ```
+pub struct IncludeBest<S> {
+    pub inner: S,
+    pub direction: Direction,
+}
+
+impl<S> IncludeBest<S> {
+    fn best<F: PartialOrd>(&self, fitnesses: &[F]) -> usize {
+        (0..fitnesses.len())
+            .reduce(|best, i| {
+                match self.direction.compare(&fitnesses[i], &fitnesses[best]) {
+                    CmpOrdering::Greater => i,
+                    _ => best,
+                }
+            })
+            .expect("Can't select from empty fitnesses vector")
+    }
+
+    fn include_best<F: PartialOrd>(&self, fitnesses: &[F], selected: &mut [usize], rng: &mut dyn RngCore) {
+        if !selected.is_empty() {
+            self.include(self.best(fitnesses), selected, rng);
+        }
+    }
+
+    fn include(&self, best: usize, selected: &mut [usize], rng: &mut dyn RngCore) {
+        if !selected.contains(&best) {
+            selected[rng.gen_range(0..selected.len())] = best;
+        }
+    }
+}
+
+impl<F: PartialOrd, S: SelectMany<F>> SelectOne<F> for IncludeBest<S> {
+    fn select_with(&self, fitnesses: &[F], rng: &mut dyn RngCore) -> usize {
+        self.select_batch_with(fitnesses, 1, rng)[0]
+    }
+
+    fn select_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut selected = self.inner.select_n_with(fitnesses, n, rng);
+        self.include_best(fitnesses, &mut selected, rng);
+        selected
+    }
+
+    fn select_distinct_batch_with(&self, fitnesses: &[F], n: usize, rng: &mut dyn RngCore)
+                                  -> Result<Vec<usize>, SelectionError> {
+        // Overwriting only happens when the best is absent, so picks stay distinct
+        let mut selected = self.inner.select_n_distinct_with(fitnesses, n, rng)?;
+        self.include_best(fitnesses, &mut selected, rng);
+        Ok(selected)
+    }
+
+    fn select_ranked_batch_with(&self, ranked: &RankedFitnesses<F>, n: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut selected = self.inner.select_n_from_ranked_with(ranked, n, rng);
+        if !selected.is_empty() {
+            let best = match ranked.is_ordered_by(&self.direction) {
+                true => ranked.order[0],
+                false => self.best(ranked.fitnesses()),
+            };
+            self.include(best, &mut selected, rng);
+        }
+        selected
+    }
+}
+
+/// ALPS style selection restricted to age layers
+///
+/// `layer_limits` holds the maximum age of each layer, in increasing order.
+/// Parents for layer `k` are drawn by `inner` from every individual no older
+/// than `layer_limits[k]`, that is from layers `0..=k`. Individuals older than
+/// the last limit are never selected, so make it `u32::MAX` for an open top
+/// layer.
+///
+/// # Examples
+/// ```
+/// use dears::selection::*;
+/// let fitnesses = vec![1.0, 5.0, 2.0, 3.0];
+/// let ages = vec![0, 12, 3, 7];
+/// let selector = AgeLayeredSelection { inner: SelBest::default(), layer_limits: vec![5, 10, u32::MAX] };
+/// assert_eq!(selector.select_n_in_layer(&fitnesses, &ages, 0, 1).unwrap(), vec![2]);
+/// assert_eq!(selector.select_n_in_layer(&fitnesses, &ages, 1, 1).unwrap(), vec![3]);
+/// assert_eq!(selector.layer_of(12), Some(2));
+/// ```
+pub struct AgeLayeredSelection<S> {
+    pub inner: S,
+    pub layer_limits: Vec<u32>,
+}
+
+impl<S> AgeLayeredSelection<S> {
+    /// The lowest layer an individual of this age belongs to
+    pub fn layer_of(&self, age: u32) -> Option<usize> {
+        self.layer_limits.iter().position(|&limit| age <= limit)
+    }
+
+    /// Indices of the individuals allowed to breed for `layer`
+    ///
+    /// Fails if there's no such layer.
+    pub fn eligible(&self, ages: &[u32], layer: usize) -> Result<Vec<usize>, SelectionError> {
+        let layers = self.layer_limits.len();
+        let limit = *self.layer_limits.get(layer).ok_or(SelectionError::InvalidLayer { layer, layers })?;
+        Ok((0..ages.len()).filter(|&i| ages[i] <= limit).collect())
+    }
+
+    /// Selects `n` parents for `layer` from those young enough
+    ///
+    /// Fails if there's no such layer or nobody is young enough for it.
+    pub fn select_n_in_layer_with<F: Clone>(&self, fitnesses: &[F], ages: &[u32], layer: usize, n: usize,
+                                            rng: &mut dyn RngCore) -> Result<Vec<usize>, SelectionError>
+    where
+        S: SelectMany<F>
+    {
+        let (eligible, sub_fitnesses) = self.restrict(fitnesses, ages, layer)?;
+        if eligible.is_empty() && n > 0 {
+            return Err(SelectionError::NotEnoughIndividuals { requested: n, available: 0 });
+        }
+        Ok(self.inner.select_n_with(&sub_fitnesses, n, rng).into_iter().map(|i| eligible[i]).collect())
+    }
+
+    pub fn select_n_in_layer<F: Clone>(&self, fitnesses: &[F], ages: &[u32], layer: usize, n: usize)
+                                       -> Result<Vec<usize>, SelectionError>
+    where
+        S: SelectMany<F>
+    {
+        self.select_n_in_layer_with(fitnesses, ages, layer, n, &mut rand::thread_rng())
+    }
+
+    /// As [`AgeLayeredSelection::select_n_in_layer_with`], but without repeats
+    pub fn select_n_distinct_in_layer_with<F: Clone>(&self, fitnesses: &[F], ages: &[u32], layer: usize, n: usize,
+                                                     rng: &mut dyn RngCore) -> Result<Vec<usize>, SelectionError>
+    where
+        S: SelectMany<F>
+    {
+        let (eligible, sub_fitnesses) = self.restrict(fitnesses, ages, layer)?;
+        Ok(self.inner.select_n_distinct_with(&sub_fitnesses, n, rng)?.into_iter().map(|i| eligible[i]).collect())
+    }
+
+    fn restrict<F: Clone>(&self, fitnesses: &[F], ages: &[u32], layer: usize)
+                          -> Result<(Vec<usize>, Vec<F>), SelectionError> {
+        assert_eq!(fitnesses.len(), ages.len(), "Need exactly one age per fitness");
+        let eligible = self.eligible(ages, layer)?;
+        let sub_fitnesses = eligible.iter().map(|&i| fitnesses[i].clone()).collect();
+        Ok((eligible, sub_fitnesses))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::selection::*;
+
+    /// Pearson's chi-squared statistic of observed counts against a uniform expectation
+    fn chi_squared_uniform(counts: &[usize]) -> f64 {
+        let total: usize = counts.iter().sum();
+        let expected = total as f64 / counts.len() as f64;
+        counts.iter().map(|&c| (c as f64 - expected).powi(2) / expected).sum()
+    }
+
+    #[test]
+    fn boltzmann_high_temperature_is_uniform() {
+        let fitnesses = vec![0.0, 1.0, 2.0, 3.0];
+        let selector = Boltzmann { temperature: Temperature::new(1e6), direction: Direction::Maximize };
+        let mut counts = [0usize; 4];
+        for idx in selector.select_n(&fitnesses, 40_000) {
+            counts[idx] += 1;
+        }
+        // 3 degrees of freedom, p = 0.001
+        assert!(chi_squared_uniform(&counts) < 16.27, "Counts not uniform: {:?}", counts);
+    }
+
+    #[test]
+    fn boltzmann_low_temperature_is_greedy() {
+        let fitnesses = vec![0.0, 1.0, 3.0, 2.0];
+        let temperature = Temperature::new(1.0);
+        let selector = Boltzmann { temperature: temperature.clone(), direction: Direction::Maximize };
+        // Cooling the shared handle raises the pressure on the next call
+        temperature.set(1e-3);
+        assert!(selector.select_n(&fitnesses, 1000).iter().all(|&i| i == 2));
+        // Huge fitnesses would overflow exp() without the max subtraction
+        let fitnesses = vec![1e4, 2e4, 3e4];
+        let mut rng = rand::thread_rng();
+        assert!(selector.select_n_at(&fitnesses, 100, 1.0, &mut rng).iter().all(|&i| i == 2));
+        // Infinite fitnesses take the whole wheel at the good end and none at the bad
+        let fitnesses = vec![1.0, f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY];
+        let picked = selector.select_n_at(&fitnesses, 100, 1.0, &mut rng);
+        assert!(picked.iter().all(|&i| i == 1 || i == 3), "{:?}", picked);
+        let minimising = Boltzmann { temperature, direction: Direction::Minimize };
+        assert!(minimising.select_n_at(&fitnesses, 100, 1.0, &mut rng).iter().all(|&i| i == 2));
+        let all_infinite = vec![f64::INFINITY; 3];
+        assert_eq!(boltzmann_table(&all_infinite, 1.0, Direction::Maximize), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn random_selection_is_uniform() {
+        let fitnesses = vec![0.0; 10];
+        let selector = RandomSelection { replacement: true };
+        let mut counts = [0usize; 10];
+        for idx in selector.select_n(&fitnesses, 100_000) {
+            counts[idx] += 1;
+        }
+        // 9 degrees of freedom, p = 0.001
+        assert!(chi_squared_uniform(&counts) < 27.88, "Counts not uniform: {:?}", counts);
+    }
+
+    #[test]
+    fn random_selection_without_replacement_is_distinct() {
+        let fitnesses = vec![0.0; 10];
+        let selector = RandomSelection { replacement: false };
+        let mut picked = selector.select_n(&fitnesses, 10);
+        picked.sort();
+        assert_eq!(picked, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn random_selection_empty_panics() {
+        let fitnesses: Vec<f64> = vec![];
+        SelectOne::select(&RandomSelection { replacement: true }, &fitnesses);
+    }
+
+    #[test]
+    fn seeded_selection_is_reproducible() {
+        let fitnesses = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+        let tournament: &dyn SelectOne<f64> = &TournamentSelection::new(3).unwrap();
+        let boltzmann = Boltzmann { temperature: Temperature::new(2.0), direction: Direction::Maximize };
+        let random = RandomSelection { replacement: true };
+        let roulette = Roulette { scaling: Scaling::None, direction: Direction::Maximize };
+        let sus = StochasticUniversalSampling { scaling: Scaling::None, direction: Direction::Maximize };
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut picked = tournament.select_n_with(&fitnesses, 20, &mut rng);
+            picked.extend(boltzmann.select_n_with(&fitnesses, 20, &mut rng));
+            picked.extend(random.select_n_with(&fitnesses, 20, &mut rng));
+            picked.extend(roulette.select_n_with(&fitnesses, 20, &mut rng));
+            picked.extend(sus.select_n_with(&fitnesses, 20, &mut rng));
+            picked
+        };
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+    }
+
+    fn assert_distinct(indices: &[usize]) {
+        let mut sorted = indices.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), indices.len(), "Indices not distinct: {:?}", indices);
+    }
+
+    #[test]
+    fn select_n_distinct_returns_distinct_indices() {
+        let fitnesses = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+        let selectors: Vec<Box<dyn SelectMany<f64>>> = vec![
+            Box::new(TournamentSelection::new(3).unwrap()),
+            Box::new(Boltzmann { temperature: Temperature::new(10.0), direction: Direction::Maximize }),
+            Box::new(RandomSelection { replacement: true }),
+            Box::new(SelBest::default()),
+        ];
+        for selector in selectors.iter() {
+            assert_distinct(&selector.select_n_distinct(&fitnesses, 8).unwrap());
+            assert_distinct(&selector.select_n_distinct(&fitnesses, 5).unwrap());
+        }
+        assert_eq!(SelBest::default().select_n_distinct(&fitnesses, 3).unwrap(), vec![5, 7, 4]);
+    }
+
+    #[test]
+    fn select_n_distinct_errors() {
+        let fitnesses = vec![1.0, 2.0, 3.0];
+        let tournament = TournamentSelection::new(2).unwrap();
+        assert_eq!(tournament.select_n_distinct(&fitnesses, 4),
+                   Err(SelectionError::NotEnoughIndividuals { requested: 4, available: 3 }));
+        assert_eq!(SelBest::default().select_n_distinct(&fitnesses, 4),
+                   Err(SelectionError::NotEnoughIndividuals { requested: 4, available: 3 }));
+        // A selector that's effectively greedy can't be rejection sampled into variety
+        let greedy = Boltzmann { temperature: Temperature::new(1e-6), direction: Direction::Maximize };
+        assert_eq!(greedy.select_n_distinct(&fitnesses, 2),
+                   Err(SelectionError::RejectionLimit { requested: 2, found: 1 }));
+    }
+
+    #[test]
+    fn nan_fitness_never_panics() {
+        let all_nan = vec![f64::NAN; 4];
+        let partial_nan = vec![f64::NAN, 1.0, f64::NAN];
+        let tournament = TournamentSelection::new(50).unwrap();
+        assert!(tournament.select(&all_nan) < 4);
+        for _ in 0..100 {
+            assert_eq!(tournament.select(&partial_nan), 1);
+        }
+        assert_eq!(SelBest::default().select_n_distinct(&partial_nan, 3).unwrap()[0], 1);
+        assert_eq!(SelBest::default().select_n(&all_nan, 2).len(), 2);
+    }
+
+    #[test]
+    fn try_select_reports_nan_in_tournament() {
+        let tournament = TournamentSelection::new(3).unwrap();
+        let all_nan = vec![f64::NAN; 4];
+        assert!(matches!(tournament.try_select(&all_nan), Err(SelectionError::NaNFitness { .. })));
+        let partial_nan = vec![f64::NAN, 1.0, 2.0];
+        for _ in 0..100 {
+            match tournament.try_select(&partial_nan) {
+                Ok(idx) => assert_ne!(idx, 0),
+                Err(err) => assert_eq!(err, SelectionError::NaNFitness { index: 0 }),
+            }
+        }
+        assert_eq!(tournament.try_select(&[1.0, 2.0]).map(|i| i < 2), Ok(true));
+    }
+
+    #[test]
+    fn minimize_selects_argmin() {
+        let fitnesses = vec![4.0, 2.0, 7.0, 1.0, 5.0];
+        for (direction, expected) in [(Direction::Maximize, 2), (Direction::Minimize, 3)] {
+            let tournament = TournamentSelection::new(100).unwrap().with_direction(direction);
+            assert_eq!(tournament.select_n_distinct(&fitnesses, 5).unwrap()[0], expected);
+            assert_eq!(SelBest { comparator: direction }.select(&fitnesses), expected);
+            let boltzmann = Boltzmann { temperature: Temperature::new(1e-3), direction };
+            assert!(boltzmann.select_n(&fitnesses, 50).iter().all(|&i| i == expected));
+        }
+        // NaN stays the worst when minimising
+        let with_nan = vec![f64::NAN, 3.0, 2.0];
+        let best = SelBest { comparator: Direction::Minimize }.select_n(&with_nan, 3);
+        assert_eq!(best, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn weighted_tournament_conflicting_objectives() {
+        let fitnesses = vec![[1.0, 0.0], [0.0, 1.0]];
+        let first = WeightedTournament {
+            tournament_size: 100,
+            weights: [1.0, 0.5],
+            comparison: WeightedComparison::Sum,
+        };
+        let second = WeightedTournament { weights: [0.5, 1.0], ..first };
+        assert_eq!(first.select(&fitnesses), 0);
+        assert_eq!(second.select(&fitnesses), 1);
+
+        // Lexicographic only looks at the second objective to break ties
+        let fitnesses = vec![[1.0, 5.0], [1.0, 9.0], [0.5, 100.0]];
+        let sum = WeightedTournament { weights: [1.0, 1.0], ..first };
+        let lex = WeightedTournament { comparison: WeightedComparison::Lexicographic, ..sum };
+        assert_eq!(sum.select_n_distinct(&fitnesses, 1).unwrap(), vec![2]);
+        assert_eq!(lex.select_n_distinct(&fitnesses, 1).unwrap(), vec![1]);
+        // Negative weights minimise
+        let min = WeightedTournament { weights: [-1.0, 0.0], ..lex };
+        assert_eq!(min.select(&fitnesses), 2);
+    }
+
+    #[test]
+    fn weighted_tournament_invalid_fitness_loses() {
+        let selector = WeightedTournament {
+            tournament_size: 100,
+            weights: [1.0, 1.0],
+            comparison: WeightedComparison::Lexicographic,
+        };
+        let fitnesses = vec![None, Some([0.0, 0.0]), Some([f64::NAN, 9.0]), None];
+        for _ in 0..20 {
+            assert_eq!(selector.select(&fitnesses), 1);
+        }
+    }
+
+    #[test]
+    fn elitist_always_keeps_best() {
+        let fitnesses = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+        let selector = Elitist {
+            k: 2,
+            inner: RandomSelection { replacement: true },
+            direction: Direction::Maximize,
+        };
+        for _ in 0..100 {
+            let picked = selector.select_n(&fitnesses, 4);
+            assert_eq!(picked.len(), 4);
+            assert_eq!(&picked[..2], &[5, 7]);
+            let distinct = selector.select_n_distinct(&fitnesses, 6).unwrap();
+            assert_eq!(&distinct[..2], &[5, 7]);
+            assert_distinct(&distinct);
+        }
+        // Asking for fewer than k just returns the top n
+        assert_eq!(selector.select_n(&fitnesses, 1), vec![5]);
+        let min = Elitist { direction: Direction::Minimize, ..selector };
+        assert!(min.select_n(&fitnesses, 3).contains(&1));
+    }
+
+    #[test]
+    fn scaling_policies() {
+        let mixed = [-2.0, 0.0, 2.0];
+        let identical = [3.0, 3.0, 3.0];
+        let zeros = [0.0, 0.0, 0.0];
+
+        assert_eq!(Scaling::None.weights(&mixed), Err(SelectionError::NegativeWeight { index: 0 }));
+        assert_eq!(Scaling::None.weights(&zeros), Err(SelectionError::DegenerateWeights));
+
+        let shift = Scaling::ShiftToPositive { epsilon: 0.5 };
+        assert_eq!(shift.weights(&mixed), Ok(vec![0.5, 2.5, 4.5]));
+        assert_eq!(shift.weights(&identical), Ok(vec![0.5; 3]));
+        let no_epsilon = Scaling::ShiftToPositive { epsilon: 0.0 };
+        assert_eq!(no_epsilon.weights(&zeros), Err(SelectionError::DegenerateWeights));
+
+        let history = FitnessWindow::new(2);
+        let window = Scaling::Windowing(history.clone());
+        assert_eq!(window.weights(&mixed), Ok(vec![0.0, 2.0, 4.0]));
+        history.advance();
+        // -2 is still inside the window, then it drops out
+        assert_eq!(window.weights(&[1.0, 0.0, 2.0]), Ok(vec![3.0, 2.0, 4.0]));
+        history.advance();
+        assert_eq!(window.weights(&[1.0, 1.0, 2.0]), Ok(vec![1.0, 1.0, 2.0]));
+        // Building the wheel again in the same generation doesn't move the window
+        assert_eq!(window.weights(&[1.0, 1.0, 2.0]), Ok(vec![1.0, 1.0, 2.0]));
+        history.advance();
+        assert_eq!(window.weights(&identical), Ok(vec![2.0; 3]));
+        history.advance();
+        assert_eq!(window.weights(&identical), Err(SelectionError::DegenerateWeights));
+
+        let sigma = Scaling::SigmaScaling { c: 2.0 };
+        let std = (8.0f64 / 3.0).sqrt();
+        let expected = [1.0 - 1.0 / std, 1.0, 1.0 + 1.0 / std];
+        for (w, e) in sigma.weights(&mixed).unwrap().iter().zip(expected) {
+            assert!((w - e).abs() < 1e-12);
+        }
+        assert_eq!(sigma.weights(&identical), Ok(vec![1.0; 3]));
+        assert_eq!(sigma.weights(&zeros), Ok(vec![1.0; 3]));
+        assert_eq!(sigma.weights(&[f64::NAN, 1.0, 1.0]), Ok(vec![0.0, 1.0, 1.0]));
+        for c in [0.0, -1.0, f64::INFINITY] {
+            let sigma = Scaling::SigmaScaling { c };
+            assert_eq!(sigma.weights(&mixed), Err(SelectionError::InvalidSigmaScaling(c)));
+        }
+        assert!(matches!(Scaling::SigmaScaling { c: f64::NAN }.weights(&mixed),
+                         Err(SelectionError::InvalidSigmaScaling(c)) if c.is_nan()));
+    }
+
+    #[test]
+    fn proportionate_selection_follows_weights() {
+        let fitnesses = vec![1.0, 0.0, 3.0];
+        let roulette = Roulette { scaling: Scaling::None, direction: Direction::Maximize };
+        let mut counts = [0usize; 3];
+        for idx in roulette.select_n(&fitnesses, 40_000) {
+            counts[idx] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((9_000..11_000).contains(&counts[0]), "Counts: {:?}", counts);
+
+        // SUS hands out exactly the expected number of copies
+        let sus = StochasticUniversalSampling { scaling: Scaling::None, direction: Direction::Maximize };
+        let picked = sus.select_n(&fitnesses, 4);
+        assert_eq!(picked, vec![0, 2, 2, 2]);
+
+        let minimising = Roulette {
+            scaling: Scaling::ShiftToPositive { epsilon: 0.0 },
+            direction: Direction::Minimize,
+        };
+        assert!(minimising.select_n(&fitnesses, 100).iter().all(|&i| i != 2));
+        let all_zero = vec![0.0; 3];
+        assert_eq!(roulette.try_select_n_with(&all_zero, 1, &mut rand::thread_rng()),
+                   Err(SelectionError::DegenerateWeights));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn par_select_n_is_reproducible() {
+        let fitnesses: Vec<f64> = (0..1000).map(|i| ((i * 37) % 101) as f64).collect();
+        let tournament = TournamentSelection::new(4).unwrap();
+        let picked = tournament.par_select_n(&fitnesses, 500, 1234);
+        assert_eq!(picked, tournament.par_select_n(&fitnesses, 500, 1234));
+        // A single thread schedules the events completely differently
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        assert_eq!(picked, pool.install(|| tournament.par_select_n(&fitnesses, 500, 1234)));
+        assert_ne!(picked, tournament.par_select_n(&fitnesses, 500, 4321));
+    }
+
+    #[test]
+    fn tournament_validation_and_modes() {
+        assert_eq!(TournamentSelection::new(0).unwrap_err(), SelectionError::InvalidTournamentSize(0));
+
+        // A tournament of one is just uniform random selection
+        let fitnesses: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let single = TournamentSelection::new(1).unwrap();
+        let mut counts = [0usize; 10];
+        for idx in single.select_n(&fitnesses, 100_000) {
+            counts[idx] += 1;
+        }
+        assert!(chi_squared_uniform(&counts) < 27.88, "Counts not uniform: {:?}", counts);
+
+        // Drawing every individual without replacement always finds the best
+        let everyone = TournamentSelection::new(10).unwrap().without_replacement();
+        assert_eq!((everyone.tournament_size(), everyone.draws_with_replacement()), (10, false));
+        assert!(single.draws_with_replacement());
+        assert!(everyone.select_n(&fitnesses, 100).iter().all(|&i| i == 9));
+        assert_eq!(everyone.select_n_distinct(&fitnesses, 10).unwrap(), (0..10).rev().collect::<Vec<_>>());
+
+        let too_big = TournamentSelection::new(11).unwrap().without_replacement();
+        assert_eq!(too_big.try_select(&fitnesses),
+                   Err(SelectionError::NotEnoughIndividuals { requested: 11, available: 10 }));
+        assert_eq!(too_big.select_n_distinct(&fitnesses, 2),
+                   Err(SelectionError::NotEnoughIndividuals { requested: 11, available: 10 }));
+    }
+
+    #[test]
+    fn stochastic_tournament_win_rate() {
+        assert_eq!(StochasticTournament::new(0.4).unwrap_err(), SelectionError::InvalidProbability(0.4));
+        assert!(StochasticTournament::new(1.1).is_err());
+        assert!(StochasticTournament::new(f64::NAN).is_err());
+
+        let fitnesses = vec![0.0, 1.0];
+        for p in [0.5, 0.8, 1.0] {
+            let selector = StochasticTournament::new(p).unwrap();
+            let trials = 100_000;
+            let wins = selector.select_n(&fitnesses, trials).iter().filter(|&&i| i == 1).count();
+            let rate = wins as f64 / trials as f64;
+            assert!((rate - p).abs() < 0.01, "Win rate {} for p = {}", rate, p);
+        }
+        let minimising = StochasticTournament::new(1.0).unwrap().with_direction(Direction::Minimize);
+        assert!(minimising.select_n(&fitnesses, 100).iter().all(|&i| i == 0));
+    }
+
+    #[test]
+    fn selected_individuals_match_indices() {
+        let population: Vec<Vec<usize>> = (0..8).map(|i| vec![i; 3]).collect();
+        let original = population.clone();
+        let fitnesses = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+        let selector = TournamentSelection::new(3).unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let (individuals, indices) = select_indexed_with(&selector, &population, &fitnesses, 20, &mut rng);
+        assert_eq!(individuals.len(), 20);
+        for (individual, idx) in individuals.iter().zip(&indices) {
+            assert_eq!(individual, &population[*idx]);
+        }
+        assert_eq!(population, original);
+        assert_eq!(select_individuals(&SelBest::default(), &population, &fitnesses, 1), vec![vec![5; 3]]);
+    }
+
+    #[test]
+    fn round_robin_converges_to_sel_best() {
+        let fitnesses = vec![3.0, 1.0, 4.0, 1.5, 5.0, 9.0, 2.0, 6.0];
+        let mut rng = StdRng::seed_from_u64(126);
+        for direction in [Direction::Maximize, Direction::Minimize] {
+            let selector = RoundRobinTournament { q: 10_000, direction };
+            assert_eq!(selector.select_n_distinct_with(&fitnesses, 5, &mut rng).unwrap(),
+                       SelBest { comparator: direction }.select_n_distinct(&fitnesses, 5).unwrap());
+        }
+    }
+
+    #[test]
+    fn round_robin_ties_are_deterministic() {
+        // Equal fitnesses never win against each other, so only index breaks the tie
+        let selector = RoundRobinTournament { q: 3, direction: Direction::Maximize };
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            assert_eq!(selector.win_counts_with(&[1.0; 4], &mut rng), vec![0; 4]);
+            assert_eq!(selector.select_n_distinct_with(&[2.0; 5], 3, &mut rng).unwrap(), vec![0, 1, 2]);
+        }
+        let single = RoundRobinTournament { q: 5, direction: Direction::Maximize };
+        assert_eq!(single.select(&[1.0]), 0);
+    }
+
+    #[test]
+    fn prepared_wheel_matches_skewed_weights() {
+        let weights = [1.0, 0.0, 2.0, 97.0, 0.0];
+        let wheel = PreparedWheel::new(&weights).unwrap();
+        let mut rng = StdRng::seed_from_u64(129);
+        let draws = 100_000;
+        let mut counts = [0usize; 5];
+        for _ in 0..draws {
+            counts[wheel.sample(&mut rng)] += 1;
+        }
+        assert_eq!((counts[1], counts[4]), (0, 0));
+        let chi_squared: f64 = [0, 2, 3].iter()
+            .map(|&i| {
+                let expected = weights[i] / 100.0 * draws as f64;
+                (counts[i] as f64 - expected).powi(2) / expected
+            })
+            .sum();
+        // 2 degrees of freedom, p = 0.001
+        assert!(chi_squared < 13.82, "Chi-squared {} for {:?}", chi_squared, counts);
+
+        assert_eq!(PreparedWheel::new(&[0.0, 0.0]).unwrap_err(), SelectionError::DegenerateWeights);
+        assert_eq!(PreparedWheel::new(&[1.0, -1.0]).unwrap_err(), SelectionError::NegativeWeight { index: 1 });
+        let roulette = Roulette { scaling: Scaling::None, direction: Direction::Maximize };
+        assert!(roulette.select_n(&[0.0, 1.0, 0.0], 100).iter().all(|&i| i == 1));
+    }
+
+    #[test]
+    fn ranked_view_matches_uncached_selection() {
+        let fitnesses = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0, f64::NAN];
+        for direction in [Direction::Maximize, Direction::Minimize] {
+            let ranked = RankedFitnesses::new(&fitnesses, &direction);
+            let best = SelBest { comparator: direction };
+            assert_eq!(best.select_n_ranked(&ranked, 12), best.select_n(&fitnesses, 12));
+
+            let elitist = Elitist { k: 2, inner: RandomSelection { replacement: true }, direction };
+            let cached = elitist.select_n_ranked_with(&ranked, 6, &mut StdRng::seed_from_u64(130));
+            let uncached = elitist.select_n_with(&fitnesses, 6, &mut StdRng::seed_from_u64(130));
+            assert_eq!(cached, uncached);
+        }
+        let ranked = RankedFitnesses::new(&fitnesses, &Direction::Maximize);
+        assert_eq!((ranked.rank(1), ranked.rank(3), ranked.rank(5), ranked.rank(8)), (6.5, 6.5, 0.0, 8.0));
+        assert!(ranked.is_ordered_by(&Direction::Maximize));
+        assert!(!ranked.is_ordered_by(&Direction::Minimize));
+    }
+
+    #[test]
+    fn selecting_from_a_ranked_view_matches_selecting_from_fitnesses() {
+        let fitnesses = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0, f64::NAN];
+        let selectors: Vec<Box<dyn SelectMany<f64>>> = vec![
+            Box::new(SelBest { comparator: Direction::Minimize }),
+            Box::new(Elitist { k: 2, inner: SelBest::default(), direction: Direction::Maximize }),
+            Box::new(IncludeBest { inner: RandomSelection { replacement: true }, direction: Direction::Maximize }),
+            Box::new(IncludeBest {
+                inner: Elitist { k: 1, inner: TournamentSelection::new(3).unwrap(), direction: Direction::Minimize },
+                direction: Direction::Minimize,
+            }),
+        ];
+        // Views in the selector's own order are reused, the others are ignored
+        for direction in [Direction::Maximize, Direction::Minimize] {
+            let ranked = RankedFitnesses::new(&fitnesses, &direction);
+            for selector in &selectors {
+                let cached = selector.select_n_from_ranked_with(&ranked, 7, &mut StdRng::seed_from_u64(130));
+                let uncached = selector.select_n_with(&fitnesses, 7, &mut StdRng::seed_from_u64(130));
+                assert_eq!(cached, uncached);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "different ordering")]
+    fn ranked_view_for_the_other_direction_is_rejected() {
+        let fitnesses = vec![3.0, 1.0, 4.0];
+        let ranked = RankedFitnesses::new(&fitnesses, &Direction::Minimize);
+        SelBest { comparator: Direction::Maximize }.select_n_ranked(&ranked, 2);
+    }
+
+    #[test]
+    fn include_best_always_picks_best_without_bias() {
+        let fitnesses = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0, 5.0, 3.0];
+        let inner = RandomSelection { replacement: true };
+        let mut rng = StdRng::seed_from_u64(132);
+        let (trials, n) = (5_000, 20);
+        let mut wrapped = [0usize; 10];
+        let mut unwrapped = [0usize; 10];
+        for _ in 0..trials {
+            let picked = IncludeBest { inner: RandomSelection { replacement: true }, direction: Direction::Maximize }
+                .select_n_with(&fitnesses, n, &mut rng);
+            assert!(picked.contains(&5));
+            picked.iter().for_each(|&i| wrapped[i] += 1);
+            inner.select_n_with(&fitnesses, n, &mut rng).iter().for_each(|&i| unwrapped[i] += 1);
+        }
+        let others = |counts: &[usize; 10]| -> Vec<usize> {
+            counts.iter().enumerate().filter(|&(i, _)| i != 5).map(|(_, &c)| c).collect()
+        };
+        // 8 degrees of freedom, p = 0.001
+        assert!(chi_squared_uniform(&others(&wrapped)) < 26.12, "{:?}", wrapped);
+        for (w, u) in others(&wrapped).into_iter().zip(others(&unwrapped)) {
+            assert!((w as f64 / u as f64 - 1.0).abs() < 0.05, "{:?} vs {:?}", wrapped, unwrapped);
+        }
+
+        let minimising = IncludeBest { inner: RandomSelection { replacement: false }, direction: Direction::Minimize };
+        for _ in 0..100 {
+            let picked = minimising.select_n_distinct_with(&fitnesses, 3, &mut rng).unwrap();
+            assert!(picked.contains(&1));
+            assert_distinct(&picked);
+        }
+    }
+
+    #[test]
+    fn age_layers_never_select_too_old() {
+        let fitnesses: Vec<f64> = (0..50).map(|i| ((i * 37) % 50) as f64).collect();
+        let ages: Vec<u32> = (0..50).map(|i| (i * 13) % 40).collect();
+        let limits = vec![3, 8, 20, u32::MAX];
+        let selector = AgeLayeredSelection {
+            inner: TournamentSelection::new(3).unwrap(),
+            layer_limits: limits.clone(),
+        };
+        let mut rng = StdRng::seed_from_u64(133);
+        for (layer, &limit) in limits.iter().enumerate() {
+            let picked = selector.select_n_in_layer_with(&fitnesses, &ages, layer, 200, &mut rng).unwrap();
+            assert!(picked.iter().all(|&i| ages[i] <= limit), "Layer {} picked {:?}", layer, picked);
+            let distinct = selector.select_n_distinct_in_layer_with(&fitnesses, &ages, layer, 2, &mut rng).unwrap();
+            assert!(distinct.iter().all(|&i| ages[i] <= limit));
+        }
+        let too_young = AgeLayeredSelection { inner: SelBest::default(), layer_limits: vec![0] };
+        assert_eq!(too_young.select_n_in_layer(&[1.0, 2.0], &[1, 2], 0, 1),
+                   Err(SelectionError::NotEnoughIndividuals { requested: 1, available: 0 }));
+        assert_eq!(selector.select_n_in_layer_with(&fitnesses, &ages, 4, 1, &mut rng),
+                   Err(SelectionError::InvalidLayer { layer: 4, layers: 4 }));
+        assert_eq!(selector.select_n_distinct_in_layer_with(&fitnesses, &ages, 9, 1, &mut rng),
+                   Err(SelectionError::InvalidLayer { layer: 9, layers: 4 }));
+    }
+
+    #[test]
+    fn comparator_drives_tournament_and_ranking() {
+        // Higher accuracy wins, then lower complexity; plain PartialOrd prefers higher complexity
+        let lexicographic = |a: &(f64, u32), b: &(f64, u32)| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1));
+        let fitnesses = vec![(0.9, 5), (0.9, 2), (0.5, 0), (0.1, 1)];
+        assert_eq!(SelBest::default().select(&fitnesses), 0);
+        assert_eq!(SelBest { comparator: lexicographic }.select(&fitnesses), 1);
+
+        let mut rng = StdRng::seed_from_u64(134);
+        let naive = TournamentSelection::new(100).unwrap();
+        let custom = TournamentSelection::with_comparator(100, lexicographic).unwrap();
+        assert!(naive.select_n_with(&fitnesses, 20, &mut rng).iter().all(|&i| i == 0));
+        assert!(custom.select_n_with(&fitnesses, 20, &mut rng).iter().all(|&i| i == 1));
+
+        let ranked = RankedFitnesses::new(&fitnesses, &lexicographic);
+        assert_eq!(ranked.order(), &[1, 0, 2, 3]);
+        assert_eq!(SelBest { comparator: lexicographic }.select_n_ranked(&ranked, 2), vec![1, 0]);
+    }
+}
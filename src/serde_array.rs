@@ -0,0 +1,108 @@
+//! Serde support for const-generic arrays, which serde only implements up to length 32
+//!
+//! Arrays are written as tuples, the same as serde writes the short ones, so
+//! the formats agree. Use as `#[serde(with = "crate::serde_array")]`.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S, T, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize
+{
+    let mut tuple = serializer.serialize_tuple(N)?;
+    for element in array {
+        tuple.serialize_element(element)?;
+    }
+    tuple.end()
+}
+
+pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>
+{
+    deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+    type Value = [T; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of length {}", N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[T; N], A::Error> {
+        let mut elements = Vec::with_capacity(N);
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+        let len = elements.len();
+        elements.try_into().map_err(|_| A::Error::invalid_length(len, &self))
+    }
+}
+
+/// An array that serializes through this module, for arrays nested in other types
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+pub struct Array<T, const N: usize>(#[serde(with = "crate::serde_array")] pub [T; N]);
+
+/// As the parent module, for `Option<[T; N]>`
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S, T, const N: usize>(array: &Option<[T; N]>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize
+    {
+        array.as_ref().map(|array| ArrayRef(array)).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<Option<[T; N]>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>
+    {
+        Ok(Option::<Array<T, N>>::deserialize(deserializer)?.map(|array| array.0))
+    }
+}
+
+/// As the parent module, for `Vec<(G, [T; N])>`
+pub mod entries {
+    use super::*;
+
+    pub fn serialize<S, G, T, const N: usize>(entries: &[(G, [T; N])], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        G: Serialize,
+        T: Serialize
+    {
+        serializer.collect_seq(entries.iter().map(|(genome, array)| (genome, ArrayRef(array))))
+    }
+
+    pub fn deserialize<'de, D, G, T, const N: usize>(deserializer: D) -> Result<Vec<(G, [T; N])>, D::Error>
+    where
+        D: Deserializer<'de>,
+        G: Deserialize<'de>,
+        T: Deserialize<'de>
+    {
+        let entries = Vec::<(G, Array<T, N>)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().map(|(genome, array)| (genome, array.0)).collect())
+    }
+}
+
+struct ArrayRef<'a, T, const N: usize>(&'a [T; N]);
+
+impl<T: Serialize, const N: usize> Serialize for ArrayRef<'_, T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.0, serializer)
+    }
+}
@@ -0,0 +1,567 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::csv::{numbered, write_record};
+use crate::display::format_number;
+use crate::multiobjective::{hypervolume, non_dominated_indices};
+use crate::selection::Direction;
+
+/// A named summary of a set of values, such as [`mean`]
+pub type Aggregation = Box<dyn Fn(&[f64]) -> f64 + Send + Sync>;
+
+/// Turns a fitness into one value per objective
+type Key<F> = Box<dyn Fn(&F) -> Vec<f64> + Send + Sync>;
+
+/// Smallest value, or NaN if there are none
+pub fn min(values: &[f64]) -> f64 {
+    values.iter().copied().fold(f64::NAN, f64::min)
+}
+
+/// Largest value, or NaN if there are none
+pub fn max(values: &[f64]) -> f64 {
+    values.iter().copied().fold(f64::NAN, f64::max)
+}
+
+/// Arithmetic mean, or NaN if there are no values
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Population standard deviation, or NaN if there are no values
+pub fn std(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+}
+
+/// Summaries of the fitnesses of a population, computed every generation
+///
+/// A key function turns each fitness into one `f64` per objective, and every
+/// registered [`Aggregation`] is applied to each objective separately. NaN
+/// values are left out before aggregating, so one failed evaluation doesn't
+/// turn every statistic into NaN; an objective with only NaN values
+/// aggregates over nothing.
+///
+/// # Examples
+/// ```
+/// use dears::stats::Statistics;
+/// let stats = Statistics::new(|f: &f64| *f).with_defaults();
+/// let record = stats.compile(&[1.0, 2.0, 3.0, f64::NAN]);
+/// assert_eq!(record.get("max"), Some(3.0));
+/// assert_eq!(record.get("mean"), Some(2.0));
+///
+/// let multi = Statistics::per_objective(|f: &[f64; 2]| f.to_vec())
+///     .register("range", |v| dears::stats::max(v) - dears::stats::min(v));
+/// let record = multi.compile(&[[1.0, 10.0], [4.0, 20.0]]);
+/// assert_eq!(record.get_objectives("range"), Some(&[3.0, 10.0][..]));
+/// ```
+pub struct Statistics<F> {
+    key: Key<F>,
+    aggregations: Vec<(String, Aggregation)>,
+}
+
+impl<F> Statistics<F> {
+    /// Statistics over a single value taken from each fitness
+    pub fn new(key: impl Fn(&F) -> f64 + Send + Sync + 'static) -> Self {
+        Statistics::per_objective(move |f| vec![key(f)])
+    }
+
+    /// Statistics over each objective of a multi-objective fitness
+    pub fn per_objective(key: impl Fn(&F) -> Vec<f64> + Send + Sync + 'static) -> Self {
+        Statistics { key: Box::new(key), aggregations: Vec::new() }
+    }
+
+    /// Adds an aggregation, replacing any earlier one with the same name
+    pub fn register(mut self, name: impl Into<String>, aggregation: impl Fn(&[f64]) -> f64 + Send + Sync + 'static)
+                    -> Self {
+        let name = name.into();
+        self.aggregations.retain(|(existing, _)| *existing != name);
+        self.aggregations.push((name, Box::new(aggregation)));
+        self
+    }
+
+    /// Registers `min`, `max`, `mean` and `std`
+    pub fn with_defaults(self) -> Self {
+        self.register("min", min)
+            .register("max", max)
+            .register("mean", mean)
+            .register("std", std)
+    }
+
+    /// Computes every aggregation over the given fitnesses
+    pub fn compile(&self, fitnesses: &[F]) -> StatsRecord {
+        let keys: Vec<Vec<f64>> = fitnesses.iter().map(|f| (self.key)(f)).collect();
+        let objectives = keys.first().map_or(1, Vec::len);
+        let columns: Vec<Vec<f64>> = (0..objectives)
+            .map(|j| keys.iter().map(|k| k[j]).filter(|v| !v.is_nan()).collect())
+            .collect();
+        let entries = self.aggregations.iter()
+            .map(|(name, aggregate)| (name.clone(), columns.iter().map(|c| aggregate(c)).collect()))
+            .collect();
+        StatsRecord { entries }
+    }
+}
+
+/// Anything that sums up a generation's fitnesses as a [`StatsRecord`]
+pub trait Compile<F> {
+    fn compile(&self, fitnesses: &[F]) -> StatsRecord;
+}
+
+impl<F> Compile<F> for Statistics<F> {
+    fn compile(&self, fitnesses: &[F]) -> StatsRecord {
+        Statistics::compile(self, fitnesses)
+    }
+}
+
+/// Summaries of a multi-objective population: each objective, then its first front
+///
+/// Records `obj0_min`, `obj0_max`, `obj0_mean` and `obj0_std` for every
+/// objective, leaving out NaN values as [`Statistics`] does. Then, over the
+/// non-dominated points with no NaN objective, `front_size` and `spread`:
+/// the mean absolute difference between the gaps of neighbouring points,
+/// ordered by the first objective, relative to the mean gap. An evenly
+/// spaced front has a spread of 0. With a reference point there is a
+/// `hypervolume` entry as well, which only works for 2 or 3 objectives.
+///
+/// # Examples
+/// ```
+/// use dears::selection::Direction;
+/// use dears::stats::MultiStats;
+/// let stats = MultiStats::new(Direction::Minimize).with_reference_point([4.0, 4.0]);
+/// let record = stats.compile(&[[1.0, 3.0], [2.0, 2.0], [3.0, 1.0], [3.0, 3.0]]);
+/// assert_eq!(record.get("obj1_max"), Some(3.0));
+/// assert_eq!(record.get("front_size"), Some(3.0));
+/// assert_eq!(record.get("spread"), Some(0.0));
+/// assert_eq!(record.get("hypervolume"), Some(6.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultiStats<const N: usize> {
+    direction: Direction,
+    reference: Option<[f64; N]>,
+}
+
+impl<const N: usize> MultiStats<N> {
+    pub fn new(direction: Direction) -> Self {
+        MultiStats { direction, reference: None }
+    }
+
+    /// Adds a `hypervolume` entry, measured against `reference`
+    pub fn with_reference_point(mut self, reference: [f64; N]) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    pub fn compile(&self, fitnesses: &[[f64; N]]) -> StatsRecord {
+        let mut record = StatsRecord::default();
+        for objective in 0..N {
+            let column: Vec<f64> = fitnesses.iter().map(|f| f[objective]).filter(|v| !v.is_nan()).collect();
+            for (name, aggregate) in [("min", min as fn(&[f64]) -> f64), ("max", max), ("mean", mean), ("std", std)] {
+                record.insert(format!("obj{}_{}", objective, name), vec![aggregate(&column)]);
+            }
+        }
+        let points: Vec<[f64; N]> = fitnesses.iter().copied().filter(|f| f.iter().all(|v| !v.is_nan())).collect();
+        let mut front: Vec<[f64; N]> =
+            non_dominated_indices(&points, &[self.direction; N]).into_iter().map(|i| points[i]).collect();
+        front.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        record.insert("front_size", vec![front.len() as f64]);
+        record.insert("spread", vec![spread(&front)]);
+        if let Some(reference) = self.reference {
+            record.insert("hypervolume", vec![hypervolume(&front, reference, self.direction)]);
+        }
+        record
+    }
+}
+
+impl<const N: usize> Compile<[f64; N]> for MultiStats<N> {
+    fn compile(&self, fitnesses: &[[f64; N]]) -> StatsRecord {
+        MultiStats::compile(self, fitnesses)
+    }
+}
+
+/// How unevenly the points of a sorted front are spaced, 0 if evenly or if there are fewer than 3
+fn spread<const N: usize>(front: &[[f64; N]]) -> f64 {
+    let gaps: Vec<f64> = front.windows(2)
+        .map(|pair| pair[0].iter().zip(&pair[1]).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt())
+        .collect();
+    let mean_gap = mean(&gaps);
+    if gaps.is_empty() || mean_gap == 0.0 {
+        return 0.0;
+    }
+    gaps.iter().map(|gap| (gap - mean_gap).abs()).sum::<f64>() / (gaps.len() as f64 * mean_gap)
+}
+
+/// The output of [`Statistics::compile`]: one value per objective for each aggregation
+///
+/// Entries keep the order the aggregations were registered in.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StatsRecord {
+    entries: Vec<(String, Vec<f64>)>,
+}
+
+impl StatsRecord {
+    /// The value of an aggregation for the first (or only) objective
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.get_objectives(name).and_then(|values| values.first().copied())
+    }
+
+    /// The value of an aggregation for every objective
+    pub fn get_objectives(&self, name: &str) -> Option<&[f64]> {
+        self.entries.iter().find(|(n, _)| n == name).map(|(_, values)| values.as_slice())
+    }
+
+    /// Sets the values of an entry, replacing any with the same name
+    ///
+    /// For logging figures that don't come from [`Statistics`], such as cache counters.
+    pub fn insert(&mut self, name: impl Into<String>, values: Vec<f64>) {
+        let name = name.into();
+        match self.entries.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, old)) => *old = values,
+            None => self.entries.push((name, values)),
+        }
+    }
+
+    /// Takes an entry out, returning its values if it was there
+    pub fn remove(&mut self, name: &str) -> Option<Vec<f64>> {
+        let idx = self.entries.iter().position(|(n, _)| n == name)?;
+        Some(self.entries.remove(idx).1)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[f64])> {
+        self.entries.iter().map(|(name, values)| (name.as_str(), values.as_slice()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Writes one line per entry, the names and each objective's values lined up in columns
+///
+/// Values are written to 4 decimal places unless given a precision, and
+/// right-aligned so their decimal points line up.
+///
+/// # Examples
+/// ```
+/// use dears::stats::StatsRecord;
+/// let mut stats = StatsRecord::default();
+/// stats.insert("max", vec![12.5, -0.0]);
+/// stats.insert("mean", vec![3.25, -1.5]);
+/// assert_eq!(format!("{:.2}", stats), "max   12.50   0.00\nmean   3.25  -1.50");
+/// ```
+impl fmt::Display for StatsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(4);
+        let cells: Vec<Vec<String>> = self.entries
+            .iter()
+            .map(|(_, values)| values.iter().map(|&v| format_number(v, precision)).collect())
+            .collect();
+        let name_width = self.names().map(str::len).max().unwrap_or(0);
+        let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|col| cells.iter().filter_map(|row| row.get(col)).map(String::len).max().unwrap_or(0))
+            .collect();
+        for (i, (name, row)) in self.names().zip(&cells).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:<name_width$}", name)?;
+            for (cell, &width) in row.iter().zip(&widths) {
+                write!(f, "  {:>width$}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One row of a [`Logbook`]: what happened in a single generation
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRow {
+    pub gen: usize,
+    /// Number of fitness evaluations made during this generation
+    pub nevals: usize,
+    pub stats: StatsRecord,
+}
+
+/// An append-only history of an evolutionary run, one row per generation
+///
+/// Chapters hold separate histories under the same logbook, for example
+/// statistics on genome size alongside fitness, or one per island of a
+/// multi-population run. Printing a logbook gives an aligned table.
+///
+/// # Examples
+/// ```
+/// use dears::stats::*;
+/// let stats = Statistics::new(|f: &f64| *f).register("max", max);
+/// let mut logbook = Logbook::new();
+/// logbook.record(0, 3, stats.compile(&[1.0, 2.0, 3.0]));
+/// logbook.record(1, 2, stats.compile(&[2.0, 3.0, 4.0]));
+/// assert_eq!(logbook.select("max"), vec![3.0, 4.0]);
+/// assert_eq!(logbook.to_string().lines().next(), Some("gen\tnevals\tmax"));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Logbook {
+    rows: Vec<LogRow>,
+    chapters: Vec<(String, Logbook)>,
+}
+
+impl Logbook {
+    pub fn new() -> Self {
+        Logbook::default()
+    }
+
+    /// Appends the row for a generation
+    pub fn record(&mut self, gen: usize, nevals: usize, stats: StatsRecord) {
+        self.rows.push(LogRow { gen, nevals, stats });
+    }
+
+    pub fn rows(&self) -> &[LogRow] {
+        &self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The generation numbers, in the order they were recorded
+    pub fn gens(&self) -> Vec<usize> {
+        self.rows.iter().map(|row| row.gen).collect()
+    }
+
+    /// The number of evaluations made in each generation
+    pub fn nevals(&self) -> Vec<usize> {
+        self.rows.iter().map(|row| row.nevals).collect()
+    }
+
+    /// A statistic's value for the first objective in every row, NaN where missing
+    pub fn select(&self, name: &str) -> Vec<f64> {
+        self.rows.iter().map(|row| row.stats.get(name).unwrap_or(f64::NAN)).collect()
+    }
+
+    /// A statistic's per-objective values in every row, empty where missing
+    pub fn select_objectives(&self, name: &str) -> Vec<Vec<f64>> {
+        self.rows.iter()
+            .map(|row| row.stats.get_objectives(name).map_or_else(Vec::new, <[f64]>::to_vec))
+            .collect()
+    }
+
+    /// Drops a statistic from every row, for instance to compare runs without their `"elapsed"` times
+    pub fn remove(&mut self, name: &str) {
+        for row in &mut self.rows {
+            row.stats.remove(name);
+        }
+    }
+
+    pub fn chapter(&self, name: &str) -> Option<&Logbook> {
+        self.chapters.iter().find(|(n, _)| n == name).map(|(_, chapter)| chapter)
+    }
+
+    /// The named chapter, created empty if it doesn't exist yet
+    pub fn chapter_mut(&mut self, name: &str) -> &mut Logbook {
+        let idx = match self.chapters.iter().position(|(n, _)| n == name) {
+            Some(idx) => idx,
+            None => {
+                self.chapters.push((name.to_string(), Logbook::new()));
+                self.chapters.len() - 1
+            }
+        };
+        &mut self.chapters[idx].1
+    }
+
+    pub fn chapters(&self) -> impl Iterator<Item = (&str, &Logbook)> {
+        self.chapters.iter().map(|(name, chapter)| (name.as_str(), chapter))
+    }
+
+    /// Writes the rows as CSV, under a header of `gen`, `nevals` and every statistic
+    ///
+    /// Statistics come in the order they first appear, and any with several
+    /// objectives is split into `name_0`, `name_1` and so on. Values are
+    /// written in full precision, and left empty where a row doesn't have
+    /// them. Chapters aren't included.
+    pub fn to_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut columns: Vec<(&str, usize)> = Vec::new();
+        for row in &self.rows {
+            for (name, values) in row.stats.iter() {
+                match columns.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, width)) => *width = (*width).max(values.len()),
+                    None => columns.push((name, values.len())),
+                }
+            }
+        }
+        let mut header = vec!["gen".to_string(), "nevals".to_string()];
+        for &(name, width) in &columns {
+            header.extend(numbered(name, name, width));
+        }
+        write_record(&mut w, &header)?;
+        for row in &self.rows {
+            let mut fields = vec![row.gen.to_string(), row.nevals.to_string()];
+            for &(name, width) in &columns {
+                let values = row.stats.get_objectives(name).unwrap_or_default();
+                fields.extend((0..width).map(|i| values.get(i).map_or_else(String::new, f64::to_string)));
+            }
+            write_record(&mut w, &fields)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats one statistic, as a list when there are several objectives
+pub(crate) fn format_values(values: &[f64]) -> String {
+    match values {
+        [value] => format_number(*value, 4),
+        _ => {
+            let parts: Vec<String> = values.iter().map(|&v| format_number(v, 4)).collect();
+            format!("[{}]", parts.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for Logbook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Columns come from the first row that has each statistic
+        let mut names: Vec<&str> = Vec::new();
+        for row in &self.rows {
+            for name in row.stats.names() {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        let mut table: Vec<Vec<String>> = vec![
+            ["gen", "nevals"].iter().chain(&names).map(|s| s.to_string()).collect()
+        ];
+        for row in &self.rows {
+            let mut cells = vec![row.gen.to_string(), row.nevals.to_string()];
+            cells.extend(names.iter().map(|name| row.stats.get_objectives(name).map_or_else(String::new, format_values)));
+            table.push(cells);
+        }
+        let widths: Vec<usize> = (0..table[0].len())
+            .map(|col| table.iter().map(|cells| cells[col].len()).max().unwrap())
+            .collect();
+        for cells in &table {
+            let line: Vec<String> = cells.iter().zip(&widths).map(|(cell, &w)| format!("{:<w$}", cell)).collect();
+            writeln!(f, "{}", line.join("\t").trim_end())?;
+        }
+        for (name, chapter) in &self.chapters {
+            writeln!(f, "\n{}", name)?;
+            write!(f, "{}", chapter)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::*;
+
+    #[test]
+    fn defaults_match_hand_calculation() {
+        let stats = Statistics::new(|f: &f64| *f).with_defaults();
+        let record = stats.compile(&[1.0, 2.0, f64::NAN, 3.0, 4.0]);
+        assert_eq!(record.names().collect::<Vec<_>>(), vec!["min", "max", "mean", "std"]);
+        assert_eq!(record.get("min"), Some(1.0));
+        assert_eq!(record.get("max"), Some(4.0));
+        assert_eq!(record.get("mean"), Some(2.5));
+        assert!((record.get("std").unwrap() - 1.25f64.sqrt()).abs() < 1e-12);
+        assert_eq!(record.get("median"), None);
+
+        let all_nan = stats.compile(&[f64::NAN, f64::NAN]);
+        assert!(all_nan.iter().all(|(_, values)| values[0].is_nan()));
+    }
+
+    #[test]
+    fn per_objective_and_custom_aggregations() {
+        let stats = Statistics::per_objective(|f: &[f64; 2]| f.to_vec())
+            .with_defaults()
+            .register("count", |v| v.len() as f64);
+        let record = stats.compile(&[[1.0, 6.0], [3.0, f64::NAN], [5.0, 2.0]]);
+        assert_eq!(record.get_objectives("mean"), Some(&[3.0, 4.0][..]));
+        assert_eq!(record.get_objectives("count"), Some(&[3.0, 2.0][..]));
+        assert_eq!(record.get_objectives("max"), Some(&[5.0, 6.0][..]));
+    }
+
+    #[test]
+    fn multi_objective_summaries() {
+        let fitnesses = [[1.0, 4.0], [2.0, 2.0], [4.0, 1.0], [3.0, 3.0], [f64::NAN, 0.0]];
+        let record = MultiStats::new(Direction::Minimize).compile(&fitnesses);
+        assert_eq!(record.get("obj0_min"), Some(1.0));
+        assert_eq!(record.get("obj0_max"), Some(4.0));
+        assert_eq!(record.get("obj0_mean"), Some(2.5));
+        assert_eq!(record.get("obj0_std"), Some(1.25f64.sqrt()));
+        assert_eq!(record.get("obj1_min"), Some(0.0));
+        assert_eq!(record.get("obj1_mean"), Some(2.0));
+        assert_eq!(record.get("front_size"), Some(3.0));
+        // Gaps of sqrt(5) either side of (2, 2), so evenly spaced
+        assert_eq!(record.get("spread"), Some(0.0));
+        assert_eq!(record.get("hypervolume"), None);
+
+        let stats = MultiStats::new(Direction::Minimize).with_reference_point([5.0, 5.0]);
+        let record = stats.compile(&[[1.0, 4.0], [2.0, 2.0], [5.0, 1.0]]);
+        assert_eq!(record.get("hypervolume"), Some(4.0 + 6.0));
+        // Gaps of sqrt(5) and sqrt(10) around a mean m: (|sqrt(5) - m| + |sqrt(10) - m|) / 2m
+        let m = (5f64.sqrt() + 10f64.sqrt()) / 2.0;
+        assert!((record.get("spread").unwrap() - (10f64.sqrt() - 5f64.sqrt()) / (2.0 * m)).abs() < 1e-12);
+
+        let mut logbook = Logbook::new();
+        logbook.record(0, 3, record);
+        let mut csv = Vec::new();
+        logbook.to_csv(&mut csv).unwrap();
+        let header = String::from_utf8(csv).unwrap().lines().next().unwrap().to_string();
+        assert!(header.starts_with("gen,nevals,obj0_min,obj0_max,obj0_mean,obj0_std,obj1_min"));
+        assert!(header.ends_with("front_size,spread,hypervolume"));
+    }
+
+    #[test]
+    fn logbook_columns_and_chapters() {
+        let stats = Statistics::new(|f: &f64| *f).register("min", min).register("max", max);
+        let sizes = Statistics::new(|f: &f64| *f).register("mean", mean);
+        let mut logbook = Logbook::new();
+        for gen in 0..3 {
+            let fitnesses = [gen as f64, gen as f64 * 2.0];
+            logbook.record(gen, 2, stats.compile(&fitnesses));
+            logbook.chapter_mut("size").record(gen, 0, sizes.compile(&[10.0 + gen as f64]));
+        }
+        assert_eq!(logbook.gens(), vec![0, 1, 2]);
+        assert_eq!(logbook.select("max"), vec![0.0, 2.0, 4.0]);
+        assert!(logbook.select("mean").iter().all(|v| v.is_nan()));
+        assert_eq!(logbook.chapter("size").unwrap().select("mean"), vec![10.0, 11.0, 12.0]);
+
+        let table = logbook.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "gen\tnevals\tmin   \tmax");
+        assert_eq!(lines[3], "2  \t2     \t2.0000\t4.0000");
+        assert!(table.contains("\nsize\n"));
+    }
+
+    #[test]
+    fn logbook_to_csv() {
+        let mut logbook = Logbook::new();
+        let mut first = StatsRecord::default();
+        first.insert("max", vec![2.5]);
+        first.insert("min", vec![0.125, -1.0]);
+        logbook.record(0, 10, first);
+        let mut second = StatsRecord::default();
+        second.insert("max", vec![3.0]);
+        second.insert("note, quoted", vec![1e-7]);
+        logbook.record(1, 4, second);
+        logbook.chapter_mut("ignored").record(0, 1, StatsRecord::default());
+
+        let mut out = Vec::new();
+        logbook.to_csv(&mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "gen,nevals,max,min_0,min_1,\"note, quoted\"");
+        assert_eq!(lines[1], "0,10,2.5,0.125,-1,");
+        assert_eq!(lines[2], "1,4,3,,,0.0000001");
+    }
+}
@@ -0,0 +1,276 @@
+use std::time::{Duration, Instant};
+
+use crate::selection::Direction;
+
+/// Why an evolutionary run stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    TargetFitness,
+    MaxEvaluations,
+    MaxGenerations,
+    Stagnation,
+    WallClock,
+    /// An [`EvolutionObserver`](crate::algorithms::EvolutionObserver) asked to stop
+    Observer,
+    /// Every criterion of an [`All`] fired, in the order given
+    All(Vec<StopReason>),
+    /// `reason` fired partway through a generation, after `evaluated` of
+    /// its individuals were evaluated and with `pending` still unevaluated
+    MidGeneration { reason: Box<StopReason>, evaluated: usize, pending: usize },
+    /// A user-defined criterion fired
+    Custom(&'static str),
+}
+
+/// The state of a run as seen by a [`Termination`] criterion
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a, F> {
+    /// Generations completed so far
+    pub generation: usize,
+    /// Fitness function calls made so far, leaving out those a [`FitnessCache`](crate::cache::FitnessCache) answered
+    pub evaluations: usize,
+    pub fitnesses: &'a [F],
+}
+
+/// A criterion for ending a run, checked by the evolve loops before each generation
+///
+/// Criteria may keep state between calls, so they're checked exactly once per
+/// generation, including the initial population. Budgets that can't wait for
+/// a generation to end also implement [`Termination::check_evaluation`].
+pub trait Termination<F> {
+    /// Returns the reason to stop, or `None` to carry on
+    fn check(&mut self, progress: &Progress<F>) -> Option<StopReason>;
+
+    /// Checked by [`ea_simple`](crate::algorithms::ea_simple) and its variants after every evaluation within a
+    /// generation, so a run can stop partway through one
+    ///
+    /// `progress.fitnesses` may then include stale fitnesses of individuals
+    /// not yet evaluated. Never fires by default.
+    fn check_evaluation(&mut self, _progress: &Progress<F>) -> Option<StopReason> {
+        None
+    }
+}
+
+/// The best fitness in a population, or `None` if there are only NaNs
+fn best_fitness(fitnesses: &[f64], direction: Direction) -> Option<f64> {
+    fitnesses.iter()
+        .copied()
+        .filter(|f| !f.is_nan())
+        .max_by(|a, b| direction.compare(a, b))
+}
+
+/// Stops once any individual is at least as good as `target`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetFitness {
+    pub target: f64,
+    pub direction: Direction,
+}
+
+impl Termination<f64> for TargetFitness {
+    fn check(&mut self, progress: &Progress<f64>) -> Option<StopReason> {
+        let best = best_fitness(progress.fitnesses, self.direction)?;
+        (self.direction.signed(best) >= self.direction.signed(self.target)).then_some(StopReason::TargetFitness)
+    }
+}
+
+/// Stops once the population has made this many fitness function calls, even partway through a generation
+///
+/// Calls a [`FitnessCache`](crate::cache::FitnessCache) answers cost nothing,
+/// so only real calls of the fitness function count towards the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxEvaluations(pub usize);
+
+impl<F> Termination<F> for MaxEvaluations {
+    fn check(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        (progress.evaluations >= self.0).then_some(StopReason::MaxEvaluations)
+    }
+
+    fn check_evaluation(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        self.check(progress)
+    }
+}
+
+/// Stops once the population reaches this generation
+///
+/// Generations are counted by the population, so a resumed run stops at the
+/// same point as an uninterrupted one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxGenerations(pub usize);
+
+impl<F> Termination<F> for MaxGenerations {
+    fn check(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        (progress.generation >= self.0).then_some(StopReason::MaxGenerations)
+    }
+}
+
+/// Stops when the best fitness hasn't improved by more than `min_delta` for `generations` generations
+///
+/// # Examples
+/// ```
+/// use dears::termination::*;
+/// let mut stop = Stagnation::new(2, 0.0);
+/// let mut check = |generation, best: f64| stop.check(&Progress { generation, evaluations: 0, fitnesses: &[best] });
+/// assert_eq!(check(0, 1.0), None);
+/// assert_eq!(check(1, 2.0), None);
+/// assert_eq!(check(2, 2.0), None);
+/// assert_eq!(check(3, 2.0), Some(StopReason::Stagnation));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stagnation {
+    pub generations: usize,
+    pub min_delta: f64,
+    pub direction: Direction,
+    best: Option<f64>,
+    since_improvement: usize,
+}
+
+impl Stagnation {
+    pub fn new(generations: usize, min_delta: f64) -> Self {
+        Stagnation { generations, min_delta, direction: Direction::default(), best: None, since_improvement: 0 }
+    }
+
+    /// Sets whether bigger or smaller fitnesses count as improvement
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+}
+
+impl Termination<f64> for Stagnation {
+    fn check(&mut self, progress: &Progress<f64>) -> Option<StopReason> {
+        let current = best_fitness(progress.fitnesses, self.direction).map(|f| self.direction.signed(f));
+        match (self.best, current) {
+            (Some(best), Some(current)) if current - best <= self.min_delta => self.since_improvement += 1,
+            (_, None) => self.since_improvement += 1,
+            (_, Some(current)) => {
+                self.best = Some(current);
+                self.since_improvement = 0;
+            }
+        }
+        (self.since_improvement >= self.generations).then_some(StopReason::Stagnation)
+    }
+}
+
+/// Stops once this much time has passed since the first check, even partway through a generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WallClock {
+    pub limit: Duration,
+    started: Option<Instant>,
+}
+
+impl WallClock {
+    pub fn new(limit: Duration) -> Self {
+        WallClock { limit, started: None }
+    }
+}
+
+impl<F> Termination<F> for WallClock {
+    fn check(&mut self, _progress: &Progress<F>) -> Option<StopReason> {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        (started.elapsed() >= self.limit).then_some(StopReason::WallClock)
+    }
+
+    fn check_evaluation(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        self.check(progress)
+    }
+}
+
+/// Stops as soon as any of the criteria fires, giving the first one's reason
+///
+/// Every criterion is still checked each generation, so stateful ones such as
+/// [`Stagnation`] keep counting.
+pub struct Any<F>(pub Vec<Box<dyn Termination<F>>>);
+
+impl<F> Termination<F> for Any<F> {
+    fn check(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        self.0.iter_mut()
+            .map(|criterion| criterion.check(progress))
+            .fold(None, |first, reason| first.or(reason))
+    }
+
+    fn check_evaluation(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        self.0.iter_mut()
+            .map(|criterion| criterion.check_evaluation(progress))
+            .fold(None, |first, reason| first.or(reason))
+    }
+}
+
+/// Stops only once every criterion fires in the same generation
+pub struct All<F>(pub Vec<Box<dyn Termination<F>>>);
+
+impl<F> Termination<F> for All<F> {
+    fn check(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        let reasons: Vec<Option<StopReason>> = self.0.iter_mut().map(|criterion| criterion.check(progress)).collect();
+        reasons.into_iter().collect::<Option<Vec<_>>>().map(StopReason::All)
+    }
+
+    fn check_evaluation(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        let reasons: Vec<Option<StopReason>> =
+            self.0.iter_mut().map(|criterion| criterion.check_evaluation(progress)).collect();
+        reasons.into_iter().collect::<Option<Vec<_>>>().map(StopReason::All)
+    }
+}
+
+impl<F, T: Termination<F> + ?Sized> Termination<F> for &mut T {
+    fn check(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        (**self).check(progress)
+    }
+    fn check_evaluation(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        (**self).check_evaluation(progress)
+    }
+}
+
+impl<F, T: Termination<F> + ?Sized> Termination<F> for Box<T> {
+    fn check(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        (**self).check(progress)
+    }
+    fn check_evaluation(&mut self, progress: &Progress<F>) -> Option<StopReason> {
+        (**self).check_evaluation(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::termination::*;
+
+    fn at<'a>(generation: usize, fitnesses: &'a [f64]) -> Progress<'a, f64> {
+        Progress { generation, evaluations: generation * 10, fitnesses }
+    }
+
+    #[test]
+    fn stagnation_fires_on_flat_fitness() {
+        let mut stop = Stagnation::new(3, 0.5).with_direction(Direction::Minimize);
+        let flat = [4.0, 5.0];
+        let fired: Vec<bool> = (0..6).map(|gen| stop.check(&at(gen, &flat)).is_some()).collect();
+        assert_eq!(fired, vec![false, false, false, true, true, true]);
+
+        // Improvements smaller than min_delta don't reset the count
+        let mut stop = Stagnation::new(2, 0.5).with_direction(Direction::Minimize);
+        for (gen, best) in [4.0, 3.8, 3.6, 2.0, 1.9].into_iter().enumerate() {
+            let reason = stop.check(&at(gen, &[best]));
+            assert_eq!(reason.is_some(), gen == 2, "generation {}", gen);
+        }
+    }
+
+    #[test]
+    fn combinators() {
+        let fitnesses = [1.0, 3.0];
+        let mut any = Any(vec![Box::new(MaxGenerations(5)), Box::new(MaxEvaluations(30))]);
+        assert_eq!(any.check(&at(2, &fitnesses)), None);
+        assert_eq!(any.check(&at(3, &fitnesses)), Some(StopReason::MaxEvaluations));
+        assert_eq!(any.check(&at(5, &fitnesses)), Some(StopReason::MaxGenerations));
+
+        let target = TargetFitness { target: 3.0, direction: Direction::Maximize };
+        let mut all = All(vec![Box::new(MaxGenerations(5)), Box::new(target)]);
+        assert_eq!(all.check(&at(6, &[1.0, 2.0])), None);
+        assert_eq!(all.check(&at(4, &fitnesses)), None);
+        assert_eq!(all.check(&at(5, &fitnesses)),
+                   Some(StopReason::All(vec![StopReason::MaxGenerations, StopReason::TargetFitness])));
+
+        // Nested, with a criterion that never fires
+        let mut nested = Any(vec![
+            Box::new(All::<f64>(vec![])),
+            Box::new(WallClock::new(Duration::from_secs(3600))),
+        ]);
+        assert_eq!(nested.check(&at(0, &fitnesses)), Some(StopReason::All(vec![])));
+    }
+}
@@ -0,0 +1,780 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+
+use dears::adaptive::AdaptiveProbabilities;
+use dears::algorithms::*;
+use dears::archive::HallOfFame;
+use dears::cache::FitnessCache;
+use dears::crossover::*;
+use dears::dedup::{Dedup, Exact};
+use dears::distance::hamming;
+use dears::history::{Aggregate, FitnessHistory};
+use dears::initializer::{init_population, RandomBits};
+use dears::mutation::*;
+use dears::niching::Speciation;
+use dears::population::*;
+use dears::report::{ProgressReporter, Reporting};
+use dears::rng::{EvolutionConfig, Stream};
+use dears::selection::*;
+use dears::stats::*;
+use dears::termination::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const BITS: usize = 20;
+
+fn ones(genome: &[bool]) -> f64 {
+    genome.iter().filter(|&&bit| bit).count() as f64
+}
+
+type Bits<C = OnePoint, S = TournamentSelection> = Population<Vec<bool>, FlipBit, C, S, f64>;
+
+/// Bit strings flipped with probability `indpb`, crossed by `crossover` and picked by `selector`
+fn bits_from<C, S>(individuals: Vec<Vec<bool>>, indpb: f64, crossover: C, selector: S) -> Bits<C, S>
+where
+    C: Crossover<Vec<bool>>,
+    S: SelectMany<f64>
+{
+    PopulationBuilder::new()
+        .individuals(individuals)
+        .mutator(FlipBit { indpb })
+        .crossover(crossover)
+        .selector(selector)
+        .build()
+        .unwrap()
+}
+
+/// `n` bit strings of zeros with one-point crossover and tournaments of `tournament_size`
+fn bits(n: usize, indpb: f64, tournament_size: usize) -> Bits {
+    bits_from(vec![vec![false; BITS]; n], indpb, OnePoint, TournamentSelection::new(tournament_size).unwrap())
+}
+
+fn run(seed: u64) -> Logbook {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut pop = bits(50, 0.05, 3);
+    let stats = Statistics::new(|f: &f64| *f).with_defaults();
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let (logbook, reason) = ea_simple(&mut pop, MaxGenerations(60), 0.5, 0.2, Elitism::default(), eval, Some(&stats),
+                                      None, &mut rng);
+    assert_eq!(reason, StopReason::MaxGenerations);
+    logbook
+}
+
+#[test]
+fn ea_simple_solves_max_ones() {
+    let logbook = run(138);
+    assert_eq!(logbook.len(), 61);
+    assert_eq!(logbook.gens(), (0..=60).collect::<Vec<_>>());
+    assert_eq!(logbook.nevals()[0], 50);
+    let best = logbook.select("max");
+    assert_eq!(best.len(), 61);
+    assert_eq!(*best.last().unwrap(), BITS as f64);
+}
+
+#[test]
+fn ea_simple_is_reproducible() {
+    let (mut first, mut second) = (run(7), run(7));
+    first.remove("elapsed");
+    second.remove("elapsed");
+    assert_eq!(first, second);
+}
+
+fn mu_lambda_population() -> Bits<OnePoint, SelBest> {
+    bits_from(vec![vec![false; BITS]; 10], 0.3, OnePoint, SelBest::default())
+}
+
+#[test]
+fn plus_never_loses_best_but_comma_can() {
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let mut rng = StdRng::seed_from_u64(139);
+
+    let mut pop = mu_lambda_population();
+    let best = ea_mu_plus_lambda(&mut pop, 10, MaxGenerations(40), 0.2, 0.8, eval, Some(&stats), None, &mut rng).0.select("max");
+    assert_eq!(pop.len(), 10);
+    assert!(best.windows(2).all(|w| w[1] >= w[0]), "{:?}", best);
+
+    let mut pop = mu_lambda_population();
+    let best = ea_mu_comma_lambda(&mut pop, 10, MaxGenerations(40), 0.0, 1.0, eval, Some(&stats), None, &mut rng).0.select("max");
+    assert_eq!(pop.len(), 10);
+    assert!(best.windows(2).any(|w| w[1] < w[0]), "{:?}", best);
+}
+
+#[test]
+#[should_panic(expected = "lambda")]
+fn comma_needs_enough_offspring() {
+    let mut pop = mu_lambda_population();
+    let eval = |genome: &Vec<bool>| ones(genome);
+    ea_mu_comma_lambda(&mut pop, 5, MaxGenerations(1), 0.5, 0.5, eval, None, None, &mut StdRng::seed_from_u64(0));
+}
+
+#[test]
+fn hall_of_fame_outlives_regression() {
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let mut hof = HallOfFame::new(5);
+    let mut pop = mu_lambda_population();
+    let mut rng = StdRng::seed_from_u64(144);
+    let stop = MaxGenerations(40);
+    let best = ea_mu_comma_lambda(&mut pop, 10, stop, 0.0, 1.0, eval, Some(&stats), Some(&mut hof), &mut rng)
+        .0
+        .select("max");
+
+    let best_ever = best.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    assert!(*best.last().unwrap() < best_ever, "{:?}", best);
+    assert_eq!(hof.best().map(|(g, f)| (ones(g), *f)), Some((best_ever, best_ever)));
+    assert_eq!(hof.len(), 5);
+    let fits: Vec<f64> = hof.iter().map(|(_, &f)| f).collect();
+    assert!(fits.windows(2).all(|w| w[0] >= w[1]), "{:?}", fits);
+}
+
+#[test]
+fn stops_early_on_target_or_stagnation() {
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let mut rng = StdRng::seed_from_u64(147);
+
+    let mut pop = mu_lambda_population();
+    let target = TargetFitness { target: 8.0, direction: Direction::Maximize };
+    let stop = Any(vec![Box::new(target), Box::new(MaxGenerations(100))]);
+    let (logbook, reason) = ea_mu_plus_lambda(&mut pop, 20, stop, 0.2, 0.8, eval, None, None, &mut rng);
+    assert_eq!(reason, StopReason::TargetFitness);
+    assert!(pop.fitnesses().iter().any(|&f| f >= 8.0));
+    assert_eq!(logbook.len(), pop.generation() + 1);
+    assert!(pop.generation() < 100);
+
+    // Without mutation or crossover nothing can ever improve
+    let mut pop = mu_lambda_population();
+    let stop = Stagnation::new(5, 0.0);
+    let (_, reason) = ea_simple(&mut pop, stop, 0.0, 0.0, Elitism::default(), eval, None, None, &mut rng);
+    assert_eq!(reason, StopReason::Stagnation);
+    assert_eq!(pop.generation(), 5);
+}
+
+#[test]
+fn restarts_keep_elites_within_budget() {
+    let marker = vec![true; BITS];
+    let policy = RestartPolicy { stagnation: Stagnation::new(3, 0.0), keep_elites: 2, max_restarts: 10 };
+    let init = RandomBits { len: BITS, p_true: 0.5 };
+    let run = |policy: &RestartPolicy| {
+        let mut pop = bits(20, 0.2, 2);
+        pop.individuals_mut()[0] = marker.clone();
+        let mut hof = HallOfFame::new(1);
+        let mut rng = StdRng::seed_from_u64(175);
+        let elitism = Elitism::new(2, Direction::Maximize);
+        // Nothing ever improves on a constant fitness, so every attempt stagnates
+        let (logbook, reason) = with_restarts(&mut pop, MaxGenerations(20), policy, &init, &mut hof, &mut rng,
+                                              |pop, stop, hof, rng| {
+            assert_eq!(pop.individuals()[0], marker);
+            ea_simple(pop, stop, 0.5, 1.0, elitism, |_: &Vec<bool>| 1.0, None, Some(hof), rng)
+        });
+        assert_eq!(pop.individuals()[0], marker);
+        let restarts = logbook.select("restarts");
+        // Each attempt starts by logging its fresh individuals, in the generation the last one ended
+        assert_eq!(logbook.len(), pop.generation() + 1 + restarts[restarts.len() - 1] as usize);
+        (pop.generation(), restarts, reason)
+    };
+
+    let (generation, restarts, reason) = run(&policy);
+    assert_eq!(reason, StopReason::MaxGenerations);
+    assert_eq!(generation, 20);
+    assert_eq!(*restarts.last().unwrap(), 6.0);
+    assert!(restarts.windows(2).all(|w| w[1] == w[0] || w[1] == w[0] + 1.0), "{:?}", restarts);
+
+    let (generation, restarts, reason) = run(&RestartPolicy { max_restarts: 2, ..policy.clone() });
+    assert_eq!(reason, StopReason::Stagnation);
+    assert_eq!(generation, 9);
+    assert_eq!(*restarts.last().unwrap(), 2.0);
+}
+
+#[test]
+fn stagnation_in_the_budget_ends_the_run() {
+    let policy = RestartPolicy { stagnation: Stagnation::new(3, 0.0), keep_elites: 2, max_restarts: 10 };
+    let init = RandomBits { len: BITS, p_true: 0.5 };
+    let mut pop = bits(20, 0.2, 2);
+    let mut hof = HallOfFame::new(1);
+    let mut rng = StdRng::seed_from_u64(175);
+    // The caller's own stagnation fires before the policy's
+    let stop: Any<f64> = Any(vec![Box::new(MaxEvaluations(10_000)), Box::new(Stagnation::new(2, 0.0))]);
+    let (logbook, reason) = with_restarts(&mut pop, stop, &policy, &init, &mut hof, &mut rng, |pop, stop, hof, rng| {
+        ea_simple(pop, stop, 0.5, 1.0, Elitism::default(), |_: &Vec<bool>| 1.0, None, Some(hof), rng)
+    });
+    assert_eq!(reason, StopReason::Stagnation);
+    assert_eq!(pop.generation(), 2);
+    assert!(logbook.select("restarts").iter().all(|&r| r == 0.0));
+}
+
+#[test]
+fn cache_evaluates_each_distinct_genome_once() {
+    let seen = RefCell::new(HashSet::new());
+    let calls = Cell::new(0);
+    let cache = FitnessCache::new(10_000);
+    let eval = cache.evaluator(|genome: &Vec<bool>| {
+        calls.set(calls.get() + 1);
+        seen.borrow_mut().insert(genome.clone());
+        ones(genome)
+    });
+    let mut pop = mu_lambda_population();
+    let mut rng = StdRng::seed_from_u64(148);
+    ea_mu_plus_lambda(&mut pop, 20, MaxGenerations(30), 0.5, 0.5, eval, None, None, &mut rng);
+
+    assert_eq!(calls.get(), seen.borrow().len());
+    assert_eq!(cache.misses(), calls.get());
+    assert!(cache.hits() > 0);
+    assert_eq!(pop.evaluations(), cache.misses());
+}
+
+#[test]
+fn evaluation_budget_stops_mid_generation() {
+    let mut pop = bits(40, 0.1, 3);
+    let calls = Cell::new(0);
+    let eval = |genome: &Vec<bool>| {
+        calls.set(calls.get() + 1);
+        ones(genome)
+    };
+    let stop: Any<f64> = Any(vec![Box::new(MaxGenerations(50)), Box::new(MaxEvaluations(60))]);
+    let mut hof = HallOfFame::new(5);
+    let (logbook, reason) = ea_simple(&mut pop, stop, 0.9, 0.9, Elitism::default(), eval, None, Some(&mut hof),
+                                      &mut StdRng::seed_from_u64(169));
+    let StopReason::MidGeneration { reason, evaluated, pending } = reason else { panic!("{:?}", reason) };
+    assert_eq!(*reason, StopReason::MaxEvaluations);
+    assert_eq!((evaluated, pop.generation()), (20, 1));
+    // Evaluated one at a time, so the budget is met exactly
+    assert_eq!((calls.get(), pop.evaluations()), (60, 60));
+    assert_eq!(pop.invalid_indices().len(), pending);
+    assert!(pending > 0);
+    assert_eq!(logbook.nevals(), vec![40, 20]);
+    assert_eq!(logbook.select("evaluations"), vec![40.0, 60.0]);
+    assert!(logbook.select("elapsed").windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(hof.iter().all(|(genome, &fitness)| ones(genome) == fitness));
+}
+
+#[test]
+fn observers_see_the_last_partial_generation() {
+    let mut pop = bits(40, 0.1, 3);
+    let stop: Any<f64> = Any(vec![Box::new(MaxGenerations(50)), Box::new(MaxEvaluations(60))]);
+    let mut observers = (FitnessHistory::new(Aggregate::Mean), AgeStats);
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let (logbook, reason) = evolve_with_hooks(&mut pop, stop, 0.9, 0.9, Elitism::default(), eval, None, None,
+                                              &mut observers, &mut StdRng::seed_from_u64(169));
+    let StopReason::MidGeneration { pending, .. } = reason else { panic!("{:?}", reason) };
+    // Only the individuals that were evaluated got a sample
+    let counts = observers.0.sample_counts();
+    assert_eq!(counts.iter().filter(|&&n| n == 0).count(), pending);
+    assert!(counts.iter().all(|&n| n <= 1));
+    assert_eq!(logbook.select("age_max")[1], 1.0);
+}
+
+#[test]
+fn cache_hits_are_free_against_the_budget() {
+    let cache = FitnessCache::new(10_000);
+    let eval = cache.evaluator(|genome: &Vec<bool>| ones(genome));
+    let mut pop = bits(30, 0.02, 3);
+    let (logbook, reason) = ea_simple(&mut pop, MaxEvaluations(45), 0.5, 0.5, Elitism::default(), &eval, None, None,
+                                      &mut StdRng::seed_from_u64(169));
+    assert!(matches!(reason, StopReason::MidGeneration { .. }), "{:?}", reason);
+    assert_eq!((cache.misses(), pop.evaluations()), (45, 45));
+    assert!(logbook.nevals().iter().sum::<usize>() > 45 + 29);
+    assert_eq!(*logbook.select("evaluations").last().unwrap(), 45.0);
+
+    // A budget on one cache counts its misses wherever they're made
+    cache.reset_counters();
+    let (_, reason) = ea_simple(&mut pop, cache.budget(20), 0.5, 0.5, Elitism::default(), &eval, None, None,
+                                &mut StdRng::seed_from_u64(169));
+    assert!(matches!(reason, StopReason::MidGeneration { .. }), "{:?}", reason);
+    assert_eq!((cache.misses(), pop.evaluations()), (20, 65));
+}
+
+#[test]
+fn mu_lambda_budgets_stop_mid_generation() {
+    let eval = |genome: &Vec<bool>| ones(genome);
+    for plus in [true, false] {
+        let mut pop = mu_lambda_population();
+        let mut hof = HallOfFame::new(3);
+        let mut rng = StdRng::seed_from_u64(169);
+        let stop = MaxEvaluations(25);
+        let (logbook, reason) = match plus {
+            true => ea_mu_plus_lambda(&mut pop, 20, stop, 0.5, 0.5, eval, None, Some(&mut hof), &mut rng),
+            false => ea_mu_comma_lambda(&mut pop, 20, stop, 0.5, 0.5, eval, None, Some(&mut hof), &mut rng),
+        };
+        let StopReason::MidGeneration { reason, evaluated, pending } = reason else { panic!("{:?}", reason) };
+        assert_eq!((*reason, pop.evaluations(), evaluated), (StopReason::MaxEvaluations, 25, 15));
+        assert!(pending > 0);
+        assert_eq!(logbook.nevals(), vec![10, 15]);
+        // Unevaluated children are dropped rather than kept with their parents' fitnesses
+        assert_eq!((pop.len(), pop.invalid_indices().len()), (10, 0));
+        assert!(pop.individuals().iter().zip(pop.fitnesses()).all(|(genome, &fitness)| ones(genome) == fitness));
+        assert!(hof.iter().all(|(genome, &fitness)| ones(genome) == fitness));
+    }
+}
+
+/// Swaps the known optimum in for the worst individual at one generation
+struct Inject {
+    at: usize,
+    starts: Vec<usize>,
+}
+
+impl EvolutionObserver<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64> for Inject {
+    fn on_generation_start(&mut self, pop: &mut Bits, _rng: &mut dyn rand::RngCore) {
+        self.starts.push(pop.generation());
+        if pop.generation() == self.at {
+            let worst = (0..pop.len()).min_by(|&a, &b| pop.fitnesses()[a].total_cmp(&pop.fitnesses()[b])).unwrap();
+            pop.replace_at(worst, vec![true; BITS], BITS as f64);
+        }
+    }
+}
+
+#[test]
+fn try_evolve_handles_failing_evaluations() {
+    // A simulator that can't cope with the first bit set
+    let simulate = |genome: &Vec<bool>| if genome[0] { Err("crashed") } else { Ok(ones(genome)) };
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let population = || {
+        bits(30, 0.1, 3)
+    };
+    let mut rng = StdRng::seed_from_u64(176);
+
+    let mut pop = population();
+    let result = try_evolve(&mut pop, MaxGenerations(30), 0.5, 0.5, Elitism::default(), simulate,
+                            &FailurePolicy::Propagate, None, None, &mut rng);
+    assert_eq!(result, Err("crashed"));
+    assert!(!pop.invalid_indices().is_empty());
+
+    let mut pop = population();
+    let mut hof = HallOfFame::new(1);
+    let (logbook, reason) = try_evolve(&mut pop, MaxGenerations(30), 0.5, 0.5, Elitism::default(), simulate,
+                                       &FailurePolicy::AssignWorst(-1.0), Some(&stats), Some(&mut hof), &mut rng)
+        .unwrap();
+    assert_eq!(reason, StopReason::MaxGenerations);
+    let failures = logbook.select("failures");
+    assert_eq!(failures.len(), 31);
+    assert!(failures.iter().sum::<f64>() > 0.0);
+    assert!(logbook.select("max").iter().all(|&max| max >= 0.0));
+    assert!(!hof.best().unwrap().0[0]);
+}
+
+#[test]
+fn batch_evaluation_matches_one_at_a_time() {
+    let population = || {
+        bits(30, 0.05, 3)
+    };
+    let sizes = RefCell::new(Vec::new());
+    let batch = |genomes: &[Vec<bool>]| {
+        sizes.borrow_mut().push(genomes.len());
+        genomes.iter().map(|g| ones(g)).collect()
+    };
+    let elitism = Elitism::new(2, Direction::Maximize);
+
+    let mut expected = population();
+    let (logbook, _) = ea_simple(&mut expected, MaxGenerations(20), 0.5, 0.2, elitism, |g: &Vec<bool>| ones(g), None,
+                                 None, &mut StdRng::seed_from_u64(178));
+    let mut actual = population();
+    let (batched, reason) = ea_simple_batch(&mut actual, MaxGenerations(20), 0.5, 0.2, elitism, batch, None, None,
+                                            &mut StdRng::seed_from_u64(178)).unwrap();
+    assert_eq!(reason, StopReason::MaxGenerations);
+    assert_eq!(actual.individuals(), expected.individuals());
+    assert_eq!(actual.fitnesses(), expected.fitnesses());
+    assert_eq!(batched.nevals(), logbook.nevals());
+    // One call per generation, with only the individuals that changed
+    assert_eq!(*sizes.borrow(), logbook.nevals().into_iter().filter(|&n| n > 0).collect::<Vec<_>>());
+
+    let mut pop = population();
+    let truncated = |genomes: &[Vec<bool>]| genomes.iter().skip(1).map(|g| ones(g)).collect();
+    let result = ea_simple_batch(&mut pop, MaxGenerations(5), 0.5, 0.2, elitism, truncated, None, None,
+                                 &mut StdRng::seed_from_u64(178));
+    assert_eq!(result.unwrap_err(), BatchLengthError { expected: 30, returned: 29 });
+    assert!(!pop.is_evaluated());
+}
+
+#[test]
+fn hook_injects_optimum() {
+    let mut pop = bits(50, 0.05, 3);
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let mut hook = Inject { at: 3, starts: Vec::new() };
+    let mut rng = StdRng::seed_from_u64(152);
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let (logbook, reason) = evolve_with_hooks(&mut pop, MaxGenerations(8), 0.5, 0.1, Elitism::default(), eval,
+                                              Some(&stats), None, &mut hook, &mut rng);
+    assert_eq!(reason, StopReason::MaxGenerations);
+    assert_eq!(hook.starts, (1..=8).collect::<Vec<_>>());
+    assert!(logbook.select("max")[..3].iter().all(|&f| f < BITS as f64));
+    assert!(pop.individuals().contains(&vec![true; BITS]));
+}
+
+/// Picks the first `n` individuals as parents, counting how often it's asked
+#[derive(Default)]
+struct FirstN {
+    calls: usize,
+}
+
+impl EvolutionObserver<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64> for FirstN {
+    fn select_parents(&mut self, _pop: &Bits, n: usize, _rng: &mut dyn rand::RngCore) -> Option<Vec<usize>> {
+        self.calls += 1;
+        Some((0..n).collect())
+    }
+}
+
+#[test]
+fn tuples_forward_every_hook() {
+    let mut pop = bits(20, 0.05, 3);
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let mut observers = (Inject { at: 2, starts: Vec::new() }, AgeStats, FirstN::default(), FirstN::default());
+    let mut rng = StdRng::seed_from_u64(152);
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let (logbook, reason) = evolve_with_hooks(&mut pop, MaxGenerations(4), 0.5, 0.1, Elitism::default(), eval,
+                                              Some(&stats), None, &mut observers, &mut rng);
+    assert_eq!(reason, StopReason::MaxGenerations);
+    assert_eq!(observers.0.starts, (1..=4).collect::<Vec<_>>());
+    assert_eq!(logbook.select("age_max").len(), 5);
+    assert_eq!((observers.2.calls, observers.3.calls), (4, 0));
+}
+
+#[test]
+fn elitism_keeps_best_so_far() {
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let run = |k: usize| {
+        let mut pop = bits(20, 0.2, 2);
+        let elitism = Elitism::new(k, Direction::Maximize);
+        let mut rng = StdRng::seed_from_u64(154);
+        let (logbook, _) = ea_simple(&mut pop, MaxGenerations(30), 0.5, 1.0, elitism, eval, Some(&stats), None,
+                                     &mut rng);
+        assert_eq!(pop.len(), 20);
+        logbook.select("max")
+    };
+    let kept = run(2);
+    assert!(kept.windows(2).all(|w| w[1] >= w[0]), "{:?}", kept);
+    let lost = run(0);
+    assert!(lost.windows(2).any(|w| w[1] < w[0]), "{:?}", lost);
+}
+
+#[test]
+fn elitism_keeps_lowest_when_minimising() {
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let stats = Statistics::new(|f: &f64| *f).register("min", min);
+    let selector = TournamentSelection::new(2).unwrap().with_direction(Direction::Minimize);
+    let mut pop = bits_from(vec![vec![true; BITS]; 20], 0.2, OnePoint, selector);
+    let mut rng = StdRng::seed_from_u64(154);
+    let (logbook, _) = ea_simple(&mut pop, MaxGenerations(30), 0.5, 1.0, Elitism::new(2, Direction::Minimize), eval,
+                                 Some(&stats), None, &mut rng);
+    let best = logbook.select("min");
+    assert!(best.windows(2).all(|w| w[1] <= w[0]), "{:?}", best);
+    assert!(best.last() < best.first());
+}
+
+#[test]
+fn dedup_reports_replacements() {
+    let mut pop = bits(30, 0.05, 3);
+    let fresh = Reset { init: RandomBits { len: BITS, p_true: 0.5 } };
+    let mut dedup = Dedup::new(Exact, fresh);
+    let mut rng = StdRng::seed_from_u64(155);
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let (logbook, _) = evolve_with_hooks(&mut pop, MaxGenerations(5), 0.5, 0.1, Elitism::default(), eval, None,
+                                         None, &mut dedup, &mut rng);
+    let replaced = logbook.select("duplicates");
+    assert!(replaced[0].is_nan());
+    assert!(replaced[1] > 0.0);
+    assert_eq!(pop.len(), 30);
+    assert!(pop.is_evaluated());
+    let fitnesses: Vec<f64> = pop.individuals().iter().map(|g| ones(g)).collect();
+    assert_eq!(pop.fitnesses(), fitnesses.as_slice());
+}
+
+/// Sets the first unset bit, so always beats its parent until the genome is full
+struct SetNext;
+
+impl Mutator<Vec<bool>> for SetNext {
+    fn mutate_with(&self, genome: &mut Vec<bool>, _rng: &mut dyn rand::RngCore) {
+        if let Some(bit) = genome.iter_mut().find(|bit| !**bit) {
+            *bit = true;
+        }
+    }
+}
+
+/// Clears both genomes, so never beats a parent that has any bits set
+struct Wipe;
+
+impl Crossover<Vec<bool>> for Wipe {
+    fn crossover_with(&self, a: &mut Vec<bool>, b: &mut Vec<bool>, _rng: &mut dyn rand::RngCore) {
+        a.fill(false);
+        b.fill(false);
+    }
+}
+
+#[test]
+fn random_immigrants_replace_the_worst() {
+    let population = || {
+        let mut pop = bits(20, 0.05, 3);
+        for (i, genome) in pop.individuals_mut().iter_mut().enumerate() {
+            genome[..i].fill(true);
+        }
+        pop.evaluate(|g| ones(g));
+        pop.set_ages(vec![7; 20]);
+        pop
+    };
+    // Short genomes can only have come from the initializer
+    let init = RandomBits { len: 5, p_true: 0.5 };
+
+    let mut pop = population();
+    let worst: HashSet<usize> = (0..5).collect();
+    let mut rng = StdRng::seed_from_u64(182);
+    let mut immigrants = RandomImmigrants::new(0.25, init, Direction::Maximize);
+    assert_eq!(immigrants.count(20), 5);
+    immigrants.on_generation_start(&mut pop, &mut rng);
+    let replaced: HashSet<usize> = pop.invalid_indices().into_iter().collect();
+    assert_eq!(replaced, worst);
+    for idx in 0..20 {
+        let arrived = replaced.contains(&idx);
+        assert_eq!(pop.individuals()[idx].len() == 5, arrived);
+        assert_eq!(pop.ages()[idx], if arrived { 0 } else { 7 });
+    }
+
+    let mut pop = population();
+    let mut everyone = RandomImmigrants::new(1.0, RandomBits { len: 5, p_true: 0.5 }, Direction::Maximize);
+    everyone.on_generation_start(&mut pop, &mut rng);
+    assert_eq!(pop.invalid_indices(), (0..19).collect::<Vec<_>>());
+    assert_eq!(pop.individuals()[19], [vec![true; 19], vec![false]].concat());
+
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let run = |seed: u64| {
+        let mut pop = population();
+        let immigrants = RandomImmigrants::new(0.25, RandomBits { len: BITS, p_true: 0.5 }, Direction::Maximize);
+        let dedup = Dedup::new(Exact, Reset { init: RandomBits { len: BITS, p_true: 0.5 } });
+        let mut observers = (immigrants, dedup);
+        let (logbook, _) = evolve_with_hooks(&mut pop, MaxGenerations(10), 0.5, 0.2, Elitism::default(),
+                                             |g: &Vec<bool>| ones(g), Some(&stats), None, &mut observers,
+                                             &mut StdRng::seed_from_u64(seed));
+        assert!(pop.is_evaluated() && pop.invalid_indices().is_empty());
+        (logbook, pop.individuals().to_vec())
+    };
+    let (logbook, individuals) = run(182);
+    assert_eq!(logbook.select("immigrants")[1..], [5.0; 10]);
+    assert_eq!(logbook.select("duplicates").len(), 11);
+    assert!(logbook.nevals()[1..].iter().all(|&n| n >= 5));
+    // Immigrants come from the loop's generator, so one seed decides the run
+    assert_eq!(run(182).1, individuals);
+}
+
+#[test]
+fn adaptive_probabilities_favour_successful_operator() {
+    let mut pop = PopulationBuilder::new()
+        .init_with(30, || vec![false; 200])
+        .mutator(SetNext)
+        .crossover(Wipe)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build()
+        .unwrap();
+    let mut adaptive = AdaptiveProbabilities::new(0.5, 0.5);
+    let mut rng = StdRng::seed_from_u64(162);
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let (logbook, _) = evolve_with_hooks(&mut pop, MaxGenerations(20), 0.5, 0.5, Elitism::default(), eval, None,
+                                         None, &mut adaptive, &mut rng);
+    let (cxpb, mutpb) = (logbook.select("cxpb"), logbook.select("mutpb"));
+    assert_eq!(cxpb.len(), 21);
+    assert_eq!((cxpb[1], mutpb[1]), (0.5, 0.5));
+    assert!(mutpb.windows(2).skip(1).all(|w| w[1] >= w[0]), "{:?}", mutpb);
+    assert!(mutpb[20] > 0.85 && cxpb[20] < 0.1, "{:?} {:?}", cxpb, mutpb);
+}
+
+#[test]
+fn ages_grow_with_lineages() {
+    let mut pop = bits(20, 0.05, 3);
+    let mut rng = StdRng::seed_from_u64(163);
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let elitism = Elitism::new(2, Direction::Maximize);
+    let (logbook, _) = evolve_with_hooks(&mut pop, MaxGenerations(6), 0.5, 0.2, elitism, eval, None, None,
+                                         &mut AgeStats, &mut rng);
+    // Every lineage goes back to the initial population
+    assert_eq!(&logbook.select("age_max")[1..], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    assert_eq!(logbook.select("age_mean")[6], 6.0);
+    assert_eq!(pop.ages(), &[6; 20]);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_variation_ignores_thread_count() {
+    let run = || {
+        let mut pop = bits(40, 0.1, 3);
+        let stats = Statistics::new(|f: &f64| *f).register("max", max);
+        let eval = |genome: &Vec<bool>| ones(genome);
+        let (mut logbook, _) = ea_simple_par(&mut pop, MaxGenerations(15), 0.6, 0.3, Elitism::default(), eval,
+                                             Some(&stats), None, 164);
+        logbook.remove("elapsed");
+        (pop.individuals().to_vec(), logbook)
+    };
+    let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap().install(run);
+    let pooled = run();
+    assert_eq!(single, pooled);
+    assert!(*single.1.select("max").last().unwrap() > 10.0);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn rng_pool_paths_ignore_thread_count() {
+    use dears::rng::RngPool;
+    use rand::rngs::SmallRng;
+    use rand::Rng;
+
+    let run = || {
+        let pool = RngPool { seed: 208 };
+        let parents: Vec<Vec<bool>> = pool.par_map(40, |_, rng| (0..BITS).map(|_| rng.gen_bool(0.3)).collect());
+        let offspring = parallel_vary(&parents, &OnePoint, &FlipBit { indpb: 0.1 }, 0.6, 0.3, 208, 1);
+        let mut pop = bits_from(offspring.genomes, 0.1, OnePoint, TournamentSelection::new(3).unwrap());
+        let noisy = |genome: &Vec<bool>, rng: &mut SmallRng| ones(genome) + rng.gen_range(-0.5..0.5);
+        pop.evaluate_par_with(&pool.stream(1), noisy);
+        let picked = pop.selector().par_select_n(pop.fitnesses(), 40, 208);
+        (pop.individuals().to_vec(), pop.fitnesses().to_vec(), picked)
+    };
+    let pooled = run();
+    for threads in [1, 2, 5] {
+        let threads_pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        assert_eq!(threads_pool.install(run), pooled, "{} threads", threads);
+    }
+    assert!(pooled.1.iter().zip(&pooled.0).all(|(f, g)| (f - ones(g)).abs() <= 0.5));
+}
+
+/// FNV-1a over every bit of every genome, stable across platforms and toolchains
+fn fnv1a(genomes: &[Vec<bool>]) -> u64 {
+    genomes.iter().flatten().fold(0xcbf2_9ce4_8422_2325, |hash, &bit| (hash ^ bit as u64).wrapping_mul(0x100_0000_01b3))
+}
+
+#[test]
+fn seeded_run_matches_stored_hash() {
+    let run = |config: EvolutionConfig| {
+        let init = init_population(30, &RandomBits { len: BITS, p_true: 0.2 }, &mut config.rng(Stream::Initialization));
+        let mut pop = bits_from(init, 0.05, OnePoint, TournamentSelection::new(3).unwrap());
+        let eval = |genome: &Vec<bool>| ones(genome);
+        ea_simple(&mut pop, MaxGenerations(25), 0.5, 0.2, Elitism::default(), eval, None, None,
+                  &mut config.rng(Stream::Evolution));
+        fnv1a(pop.individuals())
+    };
+    let seeded = EvolutionConfig::seeded(165);
+    assert_eq!(run(seeded), run(seeded));
+    assert_eq!(run(seeded), 3840655285800365979);
+    assert_ne!(run(EvolutionConfig::seeded(166)), run(seeded));
+}
+
+/// Everything a fully seeded run produces: the logbook, final population and hall of fame
+type Outcome = (Logbook, Vec<Vec<bool>>, Vec<f64>, Vec<(Vec<bool>, f64)>);
+
+fn seeded_outcome(config: EvolutionConfig) -> Outcome {
+    let init = init_population(30, &RandomBits { len: BITS, p_true: 0.2 }, &mut config.rng(Stream::Initialization));
+    let mut pop = bits_from(init, 0.05, Uniform { indpb: 0.5 }, TournamentSelection::new(3).unwrap());
+    let stats = Statistics::new(|f: &f64| *f).with_defaults();
+    let mut hof = HallOfFame::new(5);
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let elitism = Elitism::new(2, Direction::Maximize);
+    let (mut logbook, _) = ea_simple(&mut pop, MaxGenerations(25), 0.5, 0.2, elitism, eval, Some(&stats),
+                                     Some(&mut hof), &mut config.rng(Stream::Evolution));
+    logbook.remove("elapsed");
+    let hall = hof.iter().map(|(g, &f)| (g.clone(), f)).collect();
+    (logbook, pop.individuals().to_vec(), pop.fitnesses().to_vec(), hall)
+}
+
+#[test]
+fn seeded_runs_draw_only_from_their_own_generators() {
+    // Every thread's thread-local generator is seeded afresh, so a draw from it anywhere in the run would make
+    // runs on different threads disagree
+    let seeded = EvolutionConfig::seeded(207);
+    let runs: Vec<Outcome> = (0..2)
+        .map(|_| std::thread::spawn(move || seeded_outcome(seeded)))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|run| run.join().unwrap())
+        .collect();
+    assert_eq!(runs[0], runs[1]);
+    assert_eq!(runs[0], seeded_outcome(seeded));
+    assert_ne!(runs[0].1, seeded_outcome(EvolutionConfig::seeded(208)).1);
+}
+
+/// The final population of a run drawing only from the population's own generator, and a selection after it
+fn owned_outcome(seed: Option<u64>) -> (Vec<Vec<bool>>, Vec<usize>) {
+    let mut pop = bits(30, 0.05, 3);
+    if let Some(seed) = seed {
+        pop.reseed(seed);
+    }
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let elitism = Elitism::new(1, Direction::Maximize);
+    pop.run(|pop, rng| ea_simple(pop, MaxGenerations(20), 0.5, 0.2, elitism, eval, None, None, rng));
+    let picked = pop.select(10).unwrap();
+    (pop.individuals().to_vec(), picked)
+}
+
+#[test]
+fn seeded_populations_own_their_generator() {
+    let runs: Vec<_> = (0..2)
+        .map(|_| std::thread::spawn(|| owned_outcome(Some(207))))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|run| run.join().unwrap())
+        .collect();
+    assert_eq!(runs[0], runs[1]);
+    assert_eq!(runs[0], owned_outcome(Some(207)));
+    assert_ne!(runs[0], owned_outcome(Some(208)));
+    // Unseeded populations fall back on the thread-local generator
+    assert_ne!(owned_outcome(None), owned_outcome(None));
+}
+
+/// Counts the children flagged as changed in each generation
+struct CountChanged(Vec<usize>);
+
+impl EvolutionObserver<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64> for CountChanged {
+    fn on_offspring(&mut self, offspring: &mut Offspring<Vec<bool>>, _rng: &mut dyn rand::RngCore) {
+        self.0.push(offspring.changed_indices().len());
+    }
+}
+
+#[test]
+fn only_varied_individuals_are_evaluated() {
+    let mut pop = bits(30, 0.05, 3);
+    let calls = Cell::new(0);
+    let eval = |genome: &Vec<bool>| {
+        calls.set(calls.get() + 1);
+        ones(genome)
+    };
+    let mut changed = CountChanged(Vec::new());
+    let elitism = Elitism::new(4, Direction::Maximize);
+    let (logbook, _) = evolve_with_hooks(&mut pop, MaxGenerations(10), 0.3, 0.1, elitism, eval, None, None,
+                                         &mut changed, &mut StdRng::seed_from_u64(167));
+    assert_eq!(logbook.nevals()[0], 30);
+    assert_eq!(&logbook.nevals()[1..], changed.0.as_slice());
+    assert!(changed.0.iter().all(|&n| n < 26));
+    assert_eq!(calls.get(), 30 + changed.0.iter().sum::<usize>());
+    assert_eq!(pop.evaluations(), calls.get());
+}
+
+/// Keeps every report it's given
+#[derive(Default)]
+struct Recording(Vec<(usize, Option<f64>, f64)>);
+
+impl ProgressReporter<f64> for Recording {
+    fn report(&mut self, gen: usize, stats: &StatsRecord, best: &f64) {
+        self.0.push((gen, stats.get("max"), *best));
+    }
+}
+
+#[test]
+fn reporter_sees_every_generation_in_order() {
+    let mut pop = bits(20, 0.05, 3);
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let mut reporting = Reporting::new(Recording::default());
+    evolve_with_hooks(&mut pop, MaxGenerations(12), 0.5, 0.2, Elitism::default(), eval, Some(&stats), None,
+                      &mut reporting, &mut StdRng::seed_from_u64(170));
+    let reports = reporting.reporter.0;
+    assert_eq!(reports.iter().map(|r| r.0).collect::<Vec<_>>(), (0..=12).collect::<Vec<_>>());
+    assert!(reports.iter().all(|&(_, max, best)| max == Some(best)));
+}
+
+#[test]
+fn speciation_allocates_parents_by_species_fitness() {
+    let individuals = (0..20).map(|i| vec![i % 2 == 1; BITS]).collect();
+    let mut pop = bits_from(individuals, 0.0, OnePoint, TournamentSelection::new(2).unwrap());
+    // Species means of 1 and 21 split 20 parents 1:19 after rounding
+    let eval = |genome: &Vec<bool>| 1.0 + ones(genome);
+    let mut speciation = Speciation::new(2.0, hamming::<Vec<bool>>);
+    let (logbook, _) = evolve_with_hooks(&mut pop, MaxGenerations(3), 0.0, 0.0, Elitism::default(), eval, None, None,
+                                         &mut speciation, &mut StdRng::seed_from_u64(173));
+    let sizes = logbook.select_objectives("species_sizes");
+    assert_eq!(&sizes[1..], &[vec![10.0, 10.0], vec![1.0, 19.0], vec![1.0, 19.0]]);
+    assert_eq!(pop.fitnesses().iter().filter(|&&f| f == 1.0).count(), 1);
+}
@@ -0,0 +1,97 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use dears::algorithms::*;
+use dears::crossover::*;
+use dears::mutation::*;
+use dears::population::*;
+use dears::selection::*;
+use dears::termination::MaxGenerations;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Counts the allocations made by each thread, so parallel tests don't disturb each other
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+const POP_SIZE: usize = 100;
+
+type Reals = Population<Vec<f64>, Gaussian, OnePoint, TournamentSelection, f64>;
+
+fn population() -> Reals {
+    let individuals = (0..POP_SIZE).map(|i| vec![i as f64 / 10.0; 50]).collect();
+    let mutator = Gaussian { mu: 0.0, sigma: 0.1, indpb: 0.2 };
+    Population::new(individuals, mutator, OnePoint, TournamentSelection::new(3).unwrap())
+}
+
+fn sphere(genome: &[f64]) -> f64 {
+    genome.iter().map(|x| x * x).sum()
+}
+
+/// The generational loop as written without buffer reuse, cloning every parent afresh
+fn naive(pop: &mut Reals, generations: usize, rng: &mut StdRng) {
+    pop.evaluate(|g| sphere(g));
+    for _ in 0..generations {
+        pop.next_generation();
+        let parents = pop.selector().select_n_with(pop.fitnesses(), pop.len(), rng);
+        let individuals: Vec<Vec<f64>> = parents.iter().map(|&i| pop.individuals()[i].clone()).collect();
+        let fitnesses = parents.iter().map(|&i| pop.fitnesses()[i]).collect();
+        let offspring = var_and(&individuals, pop.crossover(), pop.mutator(), 0.5, 0.3, rng);
+        let changed = offspring.changed_indices();
+        pop.replace_all(offspring.genomes, fitnesses);
+        pop.invalidate(&changed);
+        pop.evaluate(|g| sphere(g));
+    }
+}
+
+fn buffered(pop: &mut Reals, generations: usize, rng: &mut StdRng) {
+    ea_simple(pop, MaxGenerations(generations), 0.5, 0.3, Elitism::default(), |g: &Vec<f64>| sphere(g), None, None,
+              rng);
+}
+
+/// Allocations per generation, from the difference between a short and a long run
+fn per_generation(run: fn(&mut Reals, usize, &mut StdRng)) -> usize {
+    let count = |generations| {
+        let mut pop = population();
+        let before = allocations();
+        run(&mut pop, generations, &mut StdRng::seed_from_u64(168));
+        allocations() - before
+    };
+    (count(30) - count(10)) / 20
+}
+
+#[test]
+fn buffers_are_reused_across_generations() {
+    let naive_rate = per_generation(naive);
+    let buffered_rate = per_generation(buffered);
+    assert!(naive_rate > 2 * POP_SIZE, "naive loop made {} allocations per generation", naive_rate);
+    assert!(buffered_rate < POP_SIZE / 2, "buffered loop made {} allocations per generation", buffered_rate);
+
+    let (mut expected, mut actual) = (population(), population());
+    naive(&mut expected, 25, &mut StdRng::seed_from_u64(168));
+    buffered(&mut actual, 25, &mut StdRng::seed_from_u64(168));
+    assert_eq!(actual.individuals(), expected.individuals());
+    assert_eq!(actual.fitnesses(), expected.fitnesses());
+    assert_eq!(actual.evaluations(), expected.evaluations());
+}
@@ -0,0 +1,219 @@
+use dears::algorithms::*;
+use dears::archive::{HallOfFame, ParetoFront};
+use dears::bitstring::BitString;
+use dears::checkpoint::*;
+use dears::constraints::ConstrainedFitness;
+use dears::crossover::*;
+use dears::fitness::*;
+use dears::genome::Permutation;
+use dears::individual::MixedGenome;
+use dears::mutation::*;
+use dears::population::*;
+use dears::rng::Pcg64;
+use dears::selection::*;
+use dears::stats::*;
+use dears::termination::MaxGenerations;
+use rand::SeedableRng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+type Bits = Population<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64>;
+
+fn ones(genome: &[bool]) -> f64 {
+    genome.iter().filter(|&&bit| bit).count() as f64
+}
+
+fn population() -> Bits {
+    PopulationBuilder::new()
+        .init_with(20, || vec![false; 16])
+        .mutator(FlipBit { indpb: 0.1 })
+        .crossover(OnePoint)
+        .selector(TournamentSelection::new(3).unwrap())
+        .rng_seed(207)
+        .build()
+        .unwrap()
+}
+
+fn evolve(pop: &mut Bits, until: usize, state: &mut RunState<Vec<bool>, f64, Pcg64>) {
+    let stats = Statistics::new(|f: &f64| *f).with_defaults();
+    let eval = |genome: &Vec<bool>| ones(genome);
+    let hof = state.hall_of_fame.as_mut();
+    let stop = MaxGenerations(until);
+    let (mut logbook, _) = ea_simple(pop, stop, 0.5, 0.3, Elitism::default(), eval, Some(&stats), hof, &mut state.rng);
+    // Timings differ between runs, so can't be compared
+    logbook.remove("elapsed");
+    for row in logbook.rows() {
+        state.logbook.record(row.gen, row.nevals, row.stats.clone());
+    }
+}
+
+fn new_state() -> RunState<Vec<bool>, f64, Pcg64> {
+    RunState { rng: Pcg64::seed_from_u64(146), hall_of_fame: Some(HallOfFame::new(3)), logbook: Logbook::new() }
+}
+
+fn hall(state: &RunState<Vec<bool>, f64, Pcg64>) -> Vec<(Vec<bool>, f64)> {
+    state.hall_of_fame.as_ref().unwrap().iter().map(|(g, &f)| (g.clone(), f)).collect()
+}
+
+#[test]
+fn resumed_run_matches_uninterrupted() {
+    let mut pop = population();
+    let mut state = new_state();
+    evolve(&mut pop, 5, &mut state);
+    let picked = pop.select(4).unwrap();
+    evolve(&mut pop, 10, &mut state);
+    let next = pop.select(6).unwrap();
+    assert_eq!(state.logbook.len(), 11);
+
+    for (format, name) in [(Format::Json, "json"), (Format::Bincode, "bin")] {
+        let path = std::env::temp_dir().join(format!("dears-checkpoint-{}.{}", std::process::id(), name));
+        let mut first = population();
+        let mut first_state = new_state();
+        evolve(&mut first, 5, &mut first_state);
+        assert_eq!(first.select(4).unwrap(), picked);
+        first.save_checkpoint(&path, format, &first_state).unwrap();
+        drop((first, first_state));
+
+        let selector = TournamentSelection::new(3).unwrap();
+        let (mut resumed, mut resumed_state): (Bits, _) =
+            Population::load_checkpoint(&path, format, FlipBit { indpb: 0.1 }, OnePoint, selector).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.generation(), 5);
+        evolve(&mut resumed, 10, &mut resumed_state);
+
+        assert_eq!(resumed.individuals(), pop.individuals());
+        assert_eq!(resumed.fitnesses(), pop.fitnesses());
+        assert_eq!(resumed.generation(), 10);
+        assert_eq!(resumed.evaluations(), pop.evaluations());
+        assert_eq!(hall(&resumed_state), hall(&state));
+        assert_eq!(resumed_state.logbook, state.logbook);
+        assert_eq!(resumed_state.logbook.gens(), (0..=10).collect::<Vec<_>>());
+        // The population's own generator carries on from where it was saved
+        assert_eq!(resumed.select(6).unwrap(), next);
+    }
+}
+
+#[test]
+fn pre_evaluated_seeds_are_logged_but_not_evaluated_again() {
+    let mut pop = population();
+    pop.evaluate(|g| ones(g));
+    pop.push(vec![true; 16], 16.0);
+    let mut state = new_state();
+    evolve(&mut pop, 0, &mut state);
+    assert_eq!(pop.evaluations(), 20);
+    assert_eq!(state.logbook.gens(), vec![0]);
+    assert_eq!(state.logbook.nevals(), vec![0]);
+    assert_eq!(state.logbook.select("max"), vec![16.0]);
+    assert_eq!(hall(&state)[0], (vec![true; 16], 16.0));
+}
+
+#[test]
+fn missing_checkpoint_is_an_io_error() {
+    let result: Result<(Bits, RunState<_, _, Pcg64>), _> = Population::load_checkpoint(
+        "/nonexistent/dears.json", Format::Json, FlipBit { indpb: 0.1 }, OnePoint, TournamentSelection::new(3).unwrap());
+    assert!(matches!(result, Err(CheckpointError::Io(_))));
+}
+
+#[test]
+fn inconsistent_checkpoints_are_rejected() {
+    let load = |name: &str, json: &str| {
+        let path = std::env::temp_dir().join(format!("dears-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, json).unwrap();
+        let result: Result<(Bits, RunState<_, _, Pcg64>), _> = Population::load_checkpoint(
+            &path, Format::Json, FlipBit { indpb: 0.1 }, OnePoint, TournamentSelection::new(3).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        result.map(|(pop, _)| pop)
+    };
+    let state = serde_json::to_string(&new_state()).unwrap();
+    let checkpoint = |individuals: &str, fitnesses: &str, ages: &str| format!(
+        r#"{{"generation":3,"evaluations":6,"individuals":{},"fitnesses":{},"ages":{},"state":{}}}"#,
+        individuals, fitnesses, ages, state);
+
+    let empty = load("empty", &checkpoint("[]", "[]", "[]"));
+    assert!(matches!(empty, Err(CheckpointError::EmptyPopulation)));
+    let short = load("short", &checkpoint("[[true],[false]]", "[1.0]", "[0,0]"));
+    assert!(matches!(short, Err(CheckpointError::LengthMismatch { individuals: 2, fitnesses: 1, ages: 2 })));
+    let aged = load("aged", &checkpoint("[[true],[false]]", "[1.0,0.0]", "[4,1,2]"));
+    assert!(matches!(aged, Err(CheckpointError::LengthMismatch { individuals: 2, fitnesses: 2, ages: 3 })));
+    // Unevaluated populations and checkpoints from before ages were saved still load
+    let unevaluated = load("unevaluated", &checkpoint("[[true],[false]]", "[]", "[]")).unwrap();
+    assert_eq!((unevaluated.len(), unevaluated.fitnesses().len(), unevaluated.ages()), (2, 0, &[0, 0][..]));
+}
+
+#[test]
+fn seeds_from_a_json_file() {
+    let path = std::env::temp_dir().join(format!("dears-seeds-{}.json", std::process::id()));
+    let seeds = vec![vec![true; 16], [true, false].repeat(8)];
+    std::fs::write(&path, serde_json::to_string(&seeds).unwrap()).unwrap();
+    let pop: Bits = PopulationBuilder::new()
+        .init_with(20, || vec![false; 16])
+        .seed_from_json(&path)
+        .unwrap()
+        .mutator(FlipBit { indpb: 0.1 })
+        .crossover(OnePoint)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(pop.len(), 20);
+    assert_eq!(&pop.individuals()[..2], seeds.as_slice());
+}
+
+/// `value` after a trip through JSON, then through bincode
+fn round_trips<T: Serialize + DeserializeOwned>(value: &T) -> [T; 2] {
+    let json = serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap();
+    let bincode = bincode::deserialize(&bincode::serialize(value).unwrap()).unwrap();
+    [json, bincode]
+}
+
+#[test]
+fn results_round_trip_without_a_checkpoint() {
+    let weights = [1.0, -1.0, 0.5];
+    for fitness in [Fitness::new(weights), Fitness::new(weights).with_values([0.25, -3.0, 1e300])] {
+        for loaded in round_trips(&fitness) {
+            assert_eq!((loaded.values(), loaded.weights()), (fitness.values(), fitness.weights()));
+        }
+    }
+    // Longer than the arrays serde handles itself
+    let wide = MultiFitness(std::array::from_fn::<f64, 40, _>(|i| i as f64 / 3.0));
+    assert_eq!(round_trips(&wide), [wide; 2]);
+    let evaluated = Evaluated { genome: vec![true, false], fitness: Some(OrderedFitness(2.5)) };
+    assert_eq!(round_trips(&evaluated), [evaluated.clone(), evaluated]);
+    let unevaluated: Evaluated<Vec<bool>, Option<ScalarFitness>> = Evaluated { genome: vec![true], fitness: None };
+    assert_eq!(round_trips(&unevaluated), [unevaluated.clone(), unevaluated]);
+    let mixed = MixedGenome { reals: vec![0.5, -2.0], bits: vec![true], perm: vec![2, 0, 1] };
+    assert_eq!(round_trips(&mixed), [mixed.clone(), mixed]);
+    let perm = Permutation::try_from(vec![2, 0, 1]).unwrap();
+    assert_eq!(round_trips(&perm), [perm.clone(), perm]);
+    assert!(serde_json::from_str::<Permutation>("[0, 0, 1]").is_err());
+    let bits = BitString::from(vec![true; 70]);
+    assert_eq!(round_trips(&bits), [bits.clone(), bits]);
+    let constrained = ConstrainedFitness { objective: 1.5, violation: 0.25 };
+    for loaded in round_trips(&constrained) {
+        assert_eq!((loaded.objective, loaded.violation), (1.5, 0.25));
+    }
+
+    let empty: HallOfFame<Vec<bool>> = HallOfFame::new(3);
+    for loaded in round_trips(&empty) {
+        assert_eq!(loaded.len(), 0);
+    }
+    let mut hof = HallOfFame::new(2);
+    hof.update(&[vec![true], vec![false], vec![true, true]], &[1.0, 0.0, 2.0]);
+    for loaded in round_trips(&hof) {
+        assert!(loaded.iter().eq(hof.iter()));
+    }
+
+    let mut front = ParetoFront::new().with_direction(Direction::Minimize);
+    front.update(&["a", "b", "c"].map(String::from), &[[2.0, 1.0], [1.0, 3.0], [2.0, 2.0]]);
+    for mut loaded in round_trips(&front) {
+        assert!(loaded.iter().eq(front.iter()));
+        // The direction comes back too, so a dominated point is still turned away
+        loaded.update(&["d".to_string()], &[[3.0, 3.0]]);
+        assert_eq!(loaded.len(), 2);
+    }
+
+    let stats = Statistics::new(|f: &f64| *f).register("max", max);
+    let mut logbook = Logbook::new();
+    logbook.record(0, 3, stats.compile(&[1.0, 2.0]));
+    assert_eq!(round_trips(&logbook), [logbook.clone(), logbook]);
+}
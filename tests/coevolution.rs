@@ -0,0 +1,57 @@
+use dears::coevolution::*;
+use dears::crossover::*;
+use dears::mutation::*;
+use dears::population::*;
+use dears::selection::*;
+
+type Coin = Population<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64>;
+
+/// A coin showing heads if most of its bits are set
+fn heads(genome: &[bool]) -> bool {
+    2 * genome.iter().filter(|&&bit| bit).count() > genome.len()
+}
+
+fn side(start: bool) -> Side<Vec<bool>, FlipBit, OnePoint, TournamentSelection> {
+    let pop: Coin = PopulationBuilder::new()
+        .init_with(30, || vec![start; 5])
+        .mutator(FlipBit { indpb: 0.2 })
+        .crossover(OnePoint)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build()
+        .unwrap();
+    Side::new(pop, 0.5, 0.5)
+}
+
+fn share_of_heads(pop: &Coin) -> f64 {
+    pop.individuals().iter().filter(|g| heads(g)).count() as f64 / pop.len() as f64
+}
+
+/// Matching pennies: the matcher scores when the coins agree, the mismatcher when they don't
+fn agreements(genome: &[bool], opponents: &[&Vec<bool>]) -> usize {
+    opponents.iter().filter(|o| heads(o) == heads(genome)).count()
+}
+
+#[test]
+fn matching_pennies_keeps_both_sides_moving() {
+    let matches = |g: &Vec<bool>, opponents: &[&Vec<bool>]| agreements(g, opponents) as f64;
+    let mismatches = |g: &Vec<bool>, opponents: &[&Vec<bool>]| (opponents.len() - agreements(g, opponents)) as f64;
+    for schedule in [Schedule::Alternating, Schedule::Simultaneous] {
+        let mut game = Coevolution::new(side(true), side(false), 174)
+            .with_opponents(Opponents::Random(10))
+            .with_schedule(schedule);
+        let mut history = (Vec::new(), Vec::new());
+        for _ in 0..40 {
+            game.evolve(1, matches, mismatches);
+            history.0.push(share_of_heads(&game.first.population));
+            history.1.push(share_of_heads(&game.second.population));
+        }
+        // Neither side settles: each swings between mostly heads and mostly tails
+        for shares in [&history.0, &history.1] {
+            let (low, high) = shares.iter().fold((1.0, 0.0), |(lo, hi): (f64, f64), &s| (lo.min(s), hi.max(s)));
+            assert!(low < 0.3 && high > 0.7, "{:?}: {:?}", schedule, shares);
+        }
+        assert_eq!(game.first.population.generation(), 40);
+        assert_eq!(game.second.logbook.gens(), (1..=40).collect::<Vec<_>>());
+        assert!(!game.first.hall_of_fame.is_empty());
+    }
+}
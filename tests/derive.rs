@@ -0,0 +1,76 @@
+use dears::algorithms::*;
+use dears::crossover::*;
+use dears::mutation::Mutator;
+use dears::population::*;
+use dears::selection::*;
+use dears::termination::MaxGenerations;
+use dears::CrossoverFields;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+#[derive(Debug, Clone, PartialEq, CrossoverFields)]
+struct Controller {
+    #[crossover(uniform(0.5))]
+    gains: Vec<f64>,
+    #[crossover(one_point)]
+    switches: Vec<bool>,
+    #[crossover(skip)]
+    lineage: String,
+}
+
+#[derive(Debug, Clone, PartialEq, CrossoverFields)]
+struct Pair<T>(#[crossover(one_point)] [T; 4], #[crossover(skip)] u8);
+
+/// Raises the first gain
+struct Nudge;
+
+impl Mutator<Controller> for Nudge {
+    fn mutate_with(&self, genome: &mut Controller, _rng: &mut dyn RngCore) {
+        genome.gains[0] += 1.0;
+    }
+}
+
+fn controller(value: f64, switch: bool, lineage: &str) -> Controller {
+    Controller { gains: vec![value; 6], switches: vec![switch; 6], lineage: lineage.to_string() }
+}
+
+#[test]
+fn fields_cross_with_their_own_kind() {
+    let mut rng = StdRng::seed_from_u64(189);
+    let (mut a, mut b) = (controller(0.0, false, "a"), controller(1.0, true, "b"));
+    let mut exchanged = [false; 2];
+    for _ in 0..50 {
+        FieldWise.crossover_with(&mut a, &mut b, &mut rng);
+        assert_eq!((a.lineage.as_str(), b.lineage.as_str()), ("a", "b"));
+        // Genes only ever move between the same positions of the same field
+        for i in 0..6 {
+            assert_eq!(a.gains[i] + b.gains[i], 1.0);
+            assert_ne!(a.switches[i], b.switches[i]);
+        }
+        exchanged[0] |= a.gains.contains(&1.0);
+        exchanged[1] |= a.switches.contains(&true);
+    }
+    assert_eq!(exchanged, [true, true]);
+
+    let (mut x, mut y) = (Pair([0; 4], 1), Pair([9; 4], 2));
+    FieldWise.crossover_with(&mut x, &mut y, &mut rng);
+    assert_eq!(x.0[0], 0);
+    assert_eq!(x.0[3], 9);
+    assert_eq!((x.1, y.1), (1, 2));
+}
+
+#[test]
+fn derived_crossover_drives_a_population() {
+    let mut pop = PopulationBuilder::new()
+        .init_with(10, || controller(0.0, false, "seed"))
+        .mutator(Nudge)
+        .crossover(FieldWise)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build()
+        .unwrap();
+    let eval = |c: &Controller| c.gains.iter().sum::<f64>();
+    ea_simple(&mut pop, MaxGenerations(5), 0.5, 0.5, Elitism::default(), eval, None, None,
+              &mut StdRng::seed_from_u64(189));
+    assert!(pop.individuals().iter().all(|c| c.lineage == "seed"));
+    assert!(pop.fitnesses().iter().any(|&f| f > 0.0));
+}
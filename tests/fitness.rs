@@ -0,0 +1,232 @@
+use dears::algorithms::*;
+use dears::archive::HallOfFame;
+use dears::crossover::*;
+use dears::fitness::*;
+use dears::mutation::*;
+use dears::population::*;
+use dears::selection::*;
+use dears::stats::*;
+use dears::termination::MaxGenerations;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn ones(genome: &[bool]) -> usize {
+    genome.iter().filter(|&&bit| bit).count()
+}
+
+fn population<S, F>(selector: S) -> Population<Vec<bool>, FlipBit, OnePoint, S, F>
+where
+    S: SelectMany<F>,
+    F: Clone
+{
+    PopulationBuilder::new()
+        .init_with(20, || vec![false; 12])
+        .mutator(FlipBit { indpb: 0.1 })
+        .crossover(OnePoint)
+        .selector(selector)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn scalar_fitness() {
+    let mut pop = population(TournamentSelection::new(3).unwrap());
+    let stats = Statistics::new(|f: &ScalarFitness| f.0).with_defaults();
+    let mut hof = HallOfFame::new(3);
+    let eval = |genome: &Vec<bool>| ScalarFitness(ones(genome) as f64);
+    let mut rng = StdRng::seed_from_u64(153);
+    let (logbook, _) = ea_simple(&mut pop, MaxGenerations(5), 0.5, 0.2, Elitism::default(), eval, Some(&stats),
+                                 Some(&mut hof), &mut rng);
+    assert_eq!(hof.best().map(|(_, f)| f.0), logbook.select("max").into_iter().reduce(f64::max));
+
+    // Proportional selectors take anything that converts into f64
+    let roulette = Roulette { scaling: Scaling::ShiftToPositive { epsilon: 1.0 }, direction: Direction::Maximize };
+    let picked = roulette.select_n(pop.fitnesses(), 5);
+    assert_eq!(picked.len(), 5);
+}
+
+#[test]
+fn multi_fitness() {
+    let selector = WeightedTournament { tournament_size: 3, weights: [1.0, -1.0], comparison: WeightedComparison::Sum };
+    let mut pop = population(selector);
+    let stats = Statistics::per_objective(|f: &MultiFitness<2>| f.0.to_vec()).register("max", max);
+    let eval = |genome: &Vec<bool>| MultiFitness([ones(genome) as f64, genome.len() as f64]);
+    let mut rng = StdRng::seed_from_u64(153);
+    let (logbook, _) = ea_simple(&mut pop, MaxGenerations(5), 0.5, 0.2, Elitism::default(), eval, Some(&stats), None,
+                                 &mut rng);
+    assert_eq!(logbook.select_objectives("max")[0], vec![0.0, 12.0]);
+}
+
+/// A fitness with its own notion of better: fewest errors, then shortest
+#[derive(Clone, Debug, PartialEq)]
+struct Score {
+    errors: usize,
+    length: usize,
+}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(other.errors.cmp(&self.errors).then(other.length.cmp(&self.length)))
+    }
+}
+
+#[test]
+fn custom_struct_fitness() {
+    let mut pop = population(TournamentSelection::new(3).unwrap());
+    let eval = |genome: &Vec<bool>| Score { errors: genome.len() - ones(genome), length: genome.len() };
+    let stats = Statistics::new(|s: &Score| s.errors as f64).register("min", min);
+    let mut rng = StdRng::seed_from_u64(153);
+    let (logbook, _) = ea_simple(&mut pop, MaxGenerations(10), 0.5, 0.2, Elitism::default(), eval, Some(&stats), None,
+                                 &mut rng);
+    let errors = logbook.select("min");
+    assert!(errors.last() < errors.first(), "{:?}", errors);
+
+    let best = &pop.fitnesses()[SelBest::default().select(pop.fitnesses())];
+    assert!(pop.fitnesses().iter().all(|f| best >= f));
+    assert_eq!(Some(best.errors as f64), errors.last().copied());
+}
+
+#[test]
+fn weighted_fitness() {
+    // Maximise the ones, minimise the length
+    let weights = [1.0, -1.0];
+    let fitness = |values| Fitness::new(weights).with_values(values);
+    assert!(fitness([5.0, 10.0]) > fitness([5.0, 12.0]));
+    assert!(fitness([6.0, 20.0]) > fitness([5.0, 10.0]));
+    assert!(fitness([6.0, 10.0]).dominates(&fitness([5.0, 12.0])));
+    assert!(!fitness([6.0, 20.0]).dominates(&fitness([5.0, 10.0])));
+    assert_eq!(fitness([5.0, 10.0]), fitness([5.0, 10.0]));
+    assert_eq!(fitness([5.0, 10.0]).partial_cmp(&fitness([5.0, 10.0])), Some(std::cmp::Ordering::Equal));
+
+    let mut invalid = fitness([-100.0, 100.0]);
+    invalid.invalidate();
+    assert!(!invalid.valid() && invalid.wvalues().is_none());
+    assert_eq!(invalid.partial_cmp(&invalid), None);
+    assert_ne!(invalid, Fitness::new(weights));
+    assert_eq!(invalid.partial_cmp(&fitness([-100.0, 100.0])), None);
+    assert!(!invalid.dominates(&fitness([-100.0, 100.0])) && !fitness([0.0, 0.0]).dominates(&invalid));
+    // Whichever the direction, selection and the hall of fame put invalid fitnesses last
+    for direction in [Direction::Maximize, Direction::Minimize] {
+        let fitnesses = [invalid, fitness([-100.0, 100.0]), invalid];
+        assert_eq!(SelBest { comparator: direction }.select_n(&fitnesses, 3), vec![1, 0, 2]);
+        let mut hof = HallOfFame::new(2).with_direction(direction);
+        hof.update(&["invalid", "valid", "also invalid"], &fitnesses);
+        assert_eq!(hof.iter().map(|(g, _)| *g).collect::<Vec<_>>(), vec!["valid", "invalid"]);
+    }
+
+    let mut pop = population(TournamentSelection::new(3).unwrap());
+    let mut hof = HallOfFame::new(1);
+    // Clear the first half before filling the second
+    let halves = Fitness::new([-1.0, 1.0]);
+    let eval = |genome: &Vec<bool>| halves.with_values([ones(&genome[..6]) as f64, ones(&genome[6..]) as f64]);
+    let mut rng = StdRng::seed_from_u64(184);
+    ea_simple(&mut pop, MaxGenerations(30), 0.5, 0.2, Elitism::default(), eval, None, Some(&mut hof), &mut rng);
+    let (best, best_fitness) = hof.best().unwrap();
+    assert_eq!(ones(&best[..6]), 0);
+    assert!(ones(&best[6..]) > 0);
+    assert!(pop.fitnesses().iter().all(|f| f <= best_fitness));
+}
+
+#[test]
+fn variation_invalidates_evaluated_individuals() {
+    let mut rng = StdRng::seed_from_u64(187);
+    let evaluated = |genome: Vec<bool>| {
+        let fitness = Fitness::new([1.0]).with_values([ones(&genome) as f64]);
+        Evaluated::new(genome, fitness)
+    };
+    let mut individual = evaluated(vec![false; 8]);
+    Invalidating(FlipBit { indpb: 1.0 }).mutate_with(&mut individual, &mut rng);
+    assert_eq!(individual.genome, vec![true; 8]);
+    assert!(!individual.valid() && individual.fitness.values().is_none());
+
+    let (mut a, mut b) = (evaluated(vec![false; 8]), evaluated(vec![true; 8]));
+    Invalidating(OnePoint).crossover_with(&mut a, &mut b, &mut rng);
+    assert_ne!(a.genome, vec![false; 8]);
+    assert!(!a.valid() && !b.valid());
+
+    // An optional fitness works as the slot just the same
+    let mut individual = Evaluated::new(vec![1.0, 2.0], Some(3.0));
+    Invalidating(Gaussian { mu: 0.0, sigma: 1.0, indpb: 1.0 }).mutate_with(&mut individual, &mut rng);
+    assert_eq!(individual.fitness, None);
+}
+
+#[test]
+fn lexicographic_tolerances() {
+    use std::cmp::Ordering::*;
+    // Maximise the first, minimise the second, maximise the third
+    let weights = [1.0, -1.0, 1.0];
+    let fitness = |values| Fitness::new(weights).with_values(values);
+    let (a, b) = (fitness([2.0, 5.0, 0.0]), fitness([2.2, 7.0, 0.0]));
+    assert_eq!(Lexicographic::new([0.0; 3]).compare(&a, &b), Less);
+    // Within the same half, the second objective decides
+    assert_eq!(Lexicographic::new([0.5, 0.0, 0.0]).compare(&a, &b), Greater);
+    let c = fitness([2.2, 7.0, 1.0]);
+    assert_eq!(Lexicographic::new([0.5, 0.0, 0.0]).compare(&b, &c), Less);
+    assert_eq!(Lexicographic::new([0.5, 5.0, 0.0]).compare(&a, &c), Less);
+    assert_eq!(Lexicographic::new([0.5, 5.0, 2.0]).compare(&a, &c), Equal);
+    assert_eq!(Lexicographic::new([0.0; 3]).compare(&Fitness::new(weights), &a), Less);
+
+    // Tournaments through the comparator hook pick by the same order
+    let fitnesses = vec![a, b, c];
+    let selector = TournamentSelection::with_comparator(3, Lexicographic::new([0.5, 0.0, 0.0]))
+        .unwrap()
+        .without_replacement();
+    assert_eq!(selector.select(&fitnesses), 0);
+
+    let mut rng = StdRng::seed_from_u64(190);
+    let lexicographic = Lexicographic::new([0.5, 1.0, 0.0]);
+    let sampled: Vec<Fitness<3>> = (0..30)
+        .map(|_| match rng.gen_range(0..20) {
+            0 => Fitness::new(weights),
+            1 => fitness([f64::NAN, 1.0, 1.0]),
+            _ => fitness([rng.gen_range(0.0..2.0), rng.gen_range(0.0..3.0), rng.gen_range(0..3) as f64]),
+        })
+        .collect();
+    for x in &sampled {
+        assert_eq!(lexicographic.compare(x, x), Equal);
+        for y in &sampled {
+            assert_eq!(lexicographic.compare(x, y), lexicographic.compare(y, x).reverse());
+            for z in &sampled {
+                if lexicographic.compare(x, y).is_ge() && lexicographic.compare(y, z).is_ge() {
+                    assert!(lexicographic.compare(x, z).is_ge(), "{:?} {:?} {:?}", x, y, z);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn ordered_fitness_sorts_totally() {
+    let values = [1.0, f64::NAN, f64::INFINITY, 0.0, -0.0, f64::NEG_INFINITY, -f64::NAN, -2.5];
+    let mut fitnesses: Vec<OrderedFitness> = values.iter().map(|&v| OrderedFitness::from(v)).collect();
+    fitnesses.sort();
+    let sorted: Vec<f64> = fitnesses.iter().map(|&f| f.into()).collect();
+    assert!(sorted[..2].iter().all(|v| v.is_nan()));
+    assert_eq!(&sorted[2..], &[f64::NEG_INFINITY, -2.5, -0.0, 0.0, 1.0, f64::INFINITY]);
+    assert!(sorted[4].is_sign_negative() && sorted[5].is_sign_positive());
+    assert_eq!(OrderedFitness(f64::NAN), OrderedFitness(-f64::NAN));
+    assert_ne!(OrderedFitness(0.0), OrderedFitness(-0.0));
+
+    // The best first, the NaNs last, with no NaN checks
+    let best = SelBest { comparator: TotalOrder::default() }.select_n(&fitnesses, 8);
+    assert_eq!(best, vec![7, 6, 5, 4, 3, 2, 0, 1]);
+    let smallest = SelBest { comparator: TotalOrder { direction: Direction::Minimize } }.select_n(&fitnesses, 3);
+    assert_eq!(smallest, vec![2, 3, 4]);
+    // The NaNs are the worst when minimising too
+    let worst = SelBest { comparator: TotalOrder { direction: Direction::Minimize } }.select_n(&fitnesses, 8);
+    assert_eq!(&worst[6..], &[0, 1]);
+
+    // Proportional selection and statistics go through f64
+    let counts = [OrderedFitness(1.0), OrderedFitness(3.0)];
+    let roulette = Roulette { scaling: Scaling::None, direction: Direction::Maximize };
+    assert_eq!(roulette.select_n(&counts, 4).len(), 4);
+    let record = Statistics::new(|&f: &OrderedFitness| f.into()).with_defaults().compile(&counts);
+    assert_eq!(record.get("mean"), Some(2.0));
+    assert_eq!(counts.iter().copied().sum::<OrderedFitness>() / 2.0, OrderedFitness(2.0));
+
+    let mut pop = population(TournamentSelection::new(3).unwrap());
+    let eval = |genome: &Vec<bool>| OrderedFitness(ones(genome) as f64);
+    let mut rng = StdRng::seed_from_u64(192);
+    ea_simple(&mut pop, MaxGenerations(10), 0.5, 0.2, Elitism::default(), eval, None, None, &mut rng);
+    assert!(pop.fitnesses().iter().max().unwrap().0 > 0.0);
+}
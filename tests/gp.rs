@@ -0,0 +1,34 @@
+use dears::algorithms::{ea_simple, Elitism};
+use dears::archive::HallOfFame;
+use dears::gp::*;
+use dears::population::PopulationBuilder;
+use dears::selection::{Direction, TournamentSelection};
+use dears::termination::MaxGenerations;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// The mean squared error of `tree` from x² + x at 21 points across [-1, 1]
+fn error(tree: &TreeGenome) -> f64 {
+    let points = (-10..=10).map(|i| i as f64 / 10.0);
+    points.map(|x| (tree.eval(&[x]) - (x * x + x)).powi(2)).sum::<f64>() / 21.0
+}
+
+#[test]
+fn symbolic_regression_finds_x_squared_plus_x() {
+    let mut rng = StdRng::seed_from_u64(202);
+    let pset = PrimitiveSet::arithmetic(1);
+    let limits = TreeLimits { max_depth: 8, max_len: 60 };
+    let mut pop = PopulationBuilder::new()
+        .init_from(200, &RampedHalfAndHalf { pset: pset.clone(), min_depth: 1, max_depth: 3 }, &mut rng)
+        .mutator(SubtreeMutation { pset, subtree_depth: 2, limits })
+        .crossover(SubtreeCrossover { limits })
+        .selector(TournamentSelection::new(3).unwrap().with_direction(Direction::Minimize))
+        .build()
+        .unwrap();
+    let mut hof = HallOfFame::new(1).with_direction(Direction::Minimize);
+    let elitism = Elitism::new(1, Direction::Minimize);
+    ea_simple(&mut pop, MaxGenerations(40), 0.5, 0.2, elitism, error, None, Some(&mut hof), &mut rng);
+    let (best, err) = hof.best().unwrap();
+    assert!(*err < 1e-6, "best tree {} has error {}", best, err);
+    assert!(pop.individuals().iter().all(|tree| limits.allow(tree.nodes())));
+}
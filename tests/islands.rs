@@ -0,0 +1,161 @@
+use dears::algorithms::*;
+use dears::crossover::*;
+use dears::islands::*;
+use dears::mutation::*;
+use dears::population::*;
+use dears::replacement::ReplaceWorst;
+use dears::selection::*;
+use dears::termination::MaxGenerations;
+use rand::rngs::StdRng;
+
+const BITS: usize = 16;
+
+type Bits = Population<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64>;
+
+fn ones(genome: &[bool]) -> f64 {
+    genome.iter().filter(|&&bit| bit).count() as f64
+}
+
+fn island(seeded: bool) -> Bits {
+    let mut individuals = vec![vec![false; BITS]; 20];
+    if seeded {
+        individuals[..5].fill(vec![true; BITS]);
+    }
+    PopulationBuilder::new()
+        .individuals(individuals)
+        .mutator(FlipBit { indpb: 0.01 })
+        .crossover(OnePoint)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build()
+        .unwrap()
+}
+
+fn islands(seed: u64) -> Islands<Vec<bool>, FlipBit, OnePoint, TournamentSelection, f64> {
+    let pops = (0..4).map(|i| island(i == 0)).collect();
+    Islands::new(pops, seed).with_migration(3, 2).unwrap()
+}
+
+fn has_solution(pop: &Bits) -> bool {
+    pop.fitnesses().contains(&(BITS as f64))
+}
+
+/// Without variation the solution can only reach another island by migrating
+fn step(pop: &mut Bits, stop: MaxGenerations, rng: &mut StdRng) {
+    ea_simple(pop, stop, 0.0, 0.0, Elitism::default(), |g: &Vec<bool>| ones(g), None, None, rng);
+}
+
+#[test]
+fn migration_spreads_seeded_solution_around_ring() {
+    let mut islands = islands(149);
+    // Each epoch carries the solution one island further round the ring
+    for epoch in 1..=3 {
+        islands.evolve(1, step);
+        let reached: Vec<bool> = islands.islands().iter().map(has_solution).collect();
+        let expected: Vec<bool> = (0..4).map(|i| i <= epoch).collect();
+        assert_eq!(reached, expected, "epoch {}", epoch);
+    }
+    assert_eq!(islands.best().map(|(_, &f)| f), Some(BITS as f64));
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn runs_are_reproducible_from_master_seed() {
+    let mut first = islands(7);
+    let mut second = islands(7);
+    first.evolve(3, |pop, stop, rng| {
+        ea_simple(pop, stop, 0.5, 0.2, Elitism::default(), |g: &Vec<bool>| ones(g), None, None, rng);
+    });
+    second.evolve_par(3, |pop, stop, rng| {
+        ea_simple(pop, stop, 0.5, 0.2, Elitism::default(), |g: &Vec<bool>| ones(g), None, None, rng);
+    });
+    for (a, b) in first.islands().iter().zip(second.islands()) {
+        assert_eq!(a.individuals(), b.individuals());
+    }
+}
+
+#[test]
+fn moving_migrants_keeps_sizes_when_fully_connected() {
+    let pops = (0..4).map(|i| island(i == 0)).collect();
+    let mut islands = Islands::new(pops, 3)
+        .with_topology(Topology::FullyConnected)
+        .with_migration(1, 2)
+        .unwrap()
+        .with_mode(MigrationMode::Move);
+    islands.evolve(2, step);
+    assert!(islands.islands().iter().all(|pop| pop.len() == 20));
+    assert!(islands.islands().iter().all(has_solution));
+}
+
+/// An evaluated island whose every genome has `ones` bits set, so fitnesses tell where individuals came from
+fn marked(set: usize) -> Bits {
+    let genome: Vec<bool> = (0..BITS).map(|bit| bit < set).collect();
+    let mut pop = PopulationBuilder::new()
+        .individuals(vec![genome; 10])
+        .mutator(FlipBit { indpb: 0.0 })
+        .crossover(OnePoint)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build()
+        .unwrap();
+    pop.evaluate(|g| ones(g));
+    pop
+}
+
+fn origins(pop: &Bits) -> Vec<f64> {
+    let mut origins = pop.fitnesses().to_vec();
+    origins.sort_by(f64::total_cmp);
+    origins.dedup();
+    origins
+}
+
+#[test]
+fn star_only_exchanges_through_hub() {
+    let config = MigrationConfig { topology: Topology::Star { hub: 0 }, migrants: 2, ..Default::default() };
+    let mut islands = Islands::with_config((0..4).map(marked).collect(), 171, config).unwrap();
+    islands.migrate();
+    let origins: Vec<Vec<f64>> = islands.islands().iter().map(origins).collect();
+    assert_eq!(origins, vec![vec![0.0, 1.0, 2.0, 3.0], vec![0.0, 1.0], vec![0.0, 2.0], vec![0.0, 3.0]]);
+    assert!(islands.islands().iter().all(|pop| pop.len() == 10));
+}
+
+#[test]
+fn moved_migrants_leave_a_gap_until_backfilled() {
+    let outgoing = vec![vec![false, true, false], vec![false; 3], vec![false; 3]];
+    let config = MigrationConfig {
+        topology: Topology::Matrix(outgoing),
+        migrants: 3,
+        emigration: Emigration::Random,
+        immigration: Immigration::Replacement(Box::new(ReplaceWorst { k: 10, direction: Direction::Maximize })),
+        mode: MigrationMode::Move,
+        ..Default::default()
+    };
+    let mut islands = Islands::with_config((0..3).map(marked).collect(), 171, config).unwrap();
+    islands.migrate();
+    let sizes: Vec<usize> = islands.islands().iter().map(Population::len).collect();
+    assert_eq!(sizes, vec![7, 10, 10]);
+    assert_eq!(islands.islands()[1].fitnesses().iter().filter(|&&f| f == 0.0).count(), 3);
+
+    let incoming = vec![vec![false; 3], vec![false; 3], vec![true, false, false]];
+    let mut islands = islands.with_topology(Topology::Matrix(incoming));
+    islands.migrate();
+    let sizes: Vec<usize> = islands.islands().iter().map(Population::len).collect();
+    assert_eq!(sizes, vec![10, 10, 7]);
+    assert_eq!(origins(&islands.islands()[0]), vec![0.0, 2.0]);
+}
+
+#[test]
+fn config_must_fit_the_islands() {
+    let with = |topology| {
+        let config = MigrationConfig { topology, ..Default::default() };
+        Islands::with_config((0..3).map(marked).collect(), 0, config).err()
+    };
+    assert_eq!(with(Topology::Star { hub: 3 }), Some(MigrationError::InvalidHub(3)));
+    assert_eq!(with(Topology::Matrix(vec![vec![false; 3]; 2])),
+               Some(MigrationError::WrongSize { islands: 3, rows: 2 }));
+    assert_eq!(with(Topology::Matrix(vec![vec![false; 3], vec![false; 2], vec![false; 3]])),
+               Some(MigrationError::NotSquare { row: 1, len: 2 }));
+    assert_eq!(with(Topology::Custom(vec![vec![1], vec![1], vec![0]])),
+               Some(MigrationError::InvalidDestination { island: 1, destination: 1 }));
+    assert!(with(Topology::Star { hub: 2 }).is_none());
+    let zero = Islands::new((0..3).map(marked).collect(), 0).with_migration(0, 2);
+    assert_eq!(zero.err(), Some(MigrationError::ZeroInterval));
+}
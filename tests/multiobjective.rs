@@ -0,0 +1,127 @@
+use dears::algorithms::{nsga2, nsga2_with_stats};
+use dears::archive::ParetoFront;
+use dears::crossover::SimulatedBinary;
+use dears::initializer::UniformFloat;
+use dears::multiobjective::*;
+use dears::mutation::Polynomial;
+use dears::population::{Population, PopulationBuilder};
+use dears::selection::Direction;
+use dears::stats::{self, MultiStats, Statistics};
+use dears::termination::{MaxEvaluations, MaxGenerations, StopReason};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const VARS: usize = 6;
+
+/// ZDT1: the optimal front is `f2 = 1 - sqrt(f1)` where every other variable is 0
+fn zdt1(x: &[f64]) -> [f64; 2] {
+    let g = 1.0 + 9.0 * x[1..].iter().sum::<f64>() / (x.len() - 1) as f64;
+    [x[0], g * (1.0 - (x[0] / g).sqrt())]
+}
+
+type Zdt1 = Population<Vec<f64>, Polynomial, SimulatedBinary, Nsga2Selection, [f64; 2]>;
+
+/// `n` random ZDT1 solutions with the usual NSGA-II operators
+fn zdt1_population(n: usize, rng: &mut StdRng) -> Zdt1 {
+    PopulationBuilder::new()
+        .init_from(n, &UniformFloat { low: 0.0, up: 1.0, len: VARS }, rng)
+        .mutator(Polynomial { eta: 20.0, low: 0.0, up: 1.0, indpb: 1.0 / VARS as f64 })
+        .crossover(SimulatedBinary { eta: 15.0, low: 0.0, up: 1.0 })
+        .selector(Nsga2Selection { direction: Direction::Minimize })
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn nsga2_improves_zdt1_hypervolume() {
+    let mut rng = StdRng::seed_from_u64(157);
+    let mut pop = zdt1_population(40, &mut rng);
+    let reference = [1.1, 11.0];
+    let eval = |genome: &Vec<f64>| zdt1(genome);
+    pop.evaluate(eval);
+    let initial = hypervolume(pop.fitnesses(), reference, Direction::Minimize);
+
+    let stats = Statistics::per_objective(|f: &[f64; 2]| f.to_vec()).register("min", stats::min);
+    let mut front = ParetoFront::new().with_direction(Direction::Minimize);
+    let (logbook, reason) = nsga2(&mut pop, MaxGenerations(60), 0.9, 1.0, Direction::Minimize, eval, Some(&stats),
+                                  Some(&mut front), &mut rng);
+    assert_eq!(reason, StopReason::MaxGenerations);
+    assert_eq!(pop.len(), 40);
+    assert_eq!(logbook.select_objectives("min").len(), 61);
+    assert_eq!(logbook.select_objectives("min")[60].len(), 2);
+
+    let last = hypervolume(pop.fitnesses(), reference, Direction::Minimize);
+    let archived: Vec<[f64; 2]> = front.iter().map(|(_, f)| *f).collect();
+    assert!(last > initial + 1.0, "hypervolume went from {} to {}", initial, last);
+    assert!(hypervolume(&archived, reference, Direction::Minimize) >= last);
+}
+
+#[test]
+fn nsga2_budget_stops_mid_generation() {
+    let mut rng = StdRng::seed_from_u64(169);
+    let mut pop = zdt1_population(20, &mut rng);
+    let eval = |genome: &Vec<f64>| zdt1(genome);
+    let mut front = ParetoFront::new().with_direction(Direction::Minimize);
+    let (logbook, reason) = nsga2(&mut pop, MaxEvaluations(50), 0.9, 1.0, Direction::Minimize, eval, None,
+                                  Some(&mut front), &mut rng);
+    let StopReason::MidGeneration { reason, evaluated, pending } = reason else { panic!("{:?}", reason) };
+    assert_eq!((*reason, pop.evaluations(), evaluated), (StopReason::MaxEvaluations, 50, 10));
+    assert!(pending > 0);
+    assert_eq!(logbook.nevals(), vec![20, 20, 10]);
+    // Without stats the default MultiStats is compiled
+    let front_sizes = logbook.select("front_size");
+    assert_eq!(front_sizes.len(), 3);
+    assert!(front_sizes.iter().all(|&size| size >= 1.0));
+    assert_eq!((pop.len(), pop.invalid_indices().len()), (20, 0));
+    assert!(pop.individuals().iter().zip(pop.fitnesses()).all(|(genome, fitness)| zdt1(genome) == *fitness));
+    assert!(front.iter().all(|(genome, fitness)| zdt1(genome) == *fitness));
+}
+
+#[test]
+fn nsga2_compiles_multi_stats_when_given() {
+    let mut rng = StdRng::seed_from_u64(196);
+    let mut pop = zdt1_population(20, &mut rng);
+    let stats = MultiStats::new(Direction::Minimize).with_reference_point([1.1, 11.0]);
+    let (logbook, _) = nsga2_with_stats(&mut pop, MaxGenerations(5), 0.9, 1.0, Direction::Minimize,
+                                        |genome: &Vec<f64>| zdt1(genome), Some(&stats), None, &mut rng);
+    let hypervolumes = logbook.select("hypervolume");
+    assert_eq!(hypervolumes.len(), 6);
+    assert!(hypervolumes.iter().all(|&hv| hv > 0.0));
+    assert!(logbook.select("obj0_min").iter().all(|&min| (0.0..=1.0).contains(&min)));
+}
+
+/// A point with small integer coordinates, so ties are common, and now and then a NaN
+fn random_point(rng: &mut StdRng, objectives: usize) -> Vec<f64> {
+    (0..objectives).map(|_| if rng.gen_bool(0.03) { f64::NAN } else { rng.gen_range(0..5) as f64 }).collect()
+}
+
+#[test]
+fn dominance_properties_hold_on_random_points() {
+    let mut rng = StdRng::seed_from_u64(185);
+    for _ in 0..200 {
+        let objectives = rng.gen_range(1..4);
+        let directions: Vec<Direction> =
+            (0..objectives).map(|_| if rng.gen() { Direction::Maximize } else { Direction::Minimize }).collect();
+        let points: Vec<Vec<f64>> = (0..rng.gen_range(0..30)).map(|_| random_point(&mut rng, objectives)).collect();
+
+        for a in &points {
+            assert!(!dominates_mixed(a, a, &directions));
+            for b in &points {
+                assert!(!(dominates_mixed(a, b, &directions) && dominates_mixed(b, a, &directions)));
+                if dominates_mixed(a, b, &directions) {
+                    assert!(epsilon_dominates(a, b, 0.0, &directions));
+                    assert!(epsilon_dominates(a, b, 0.5, &directions));
+                }
+            }
+        }
+
+        let brute: Vec<usize> = (0..points.len())
+            .filter(|&i| !points.iter().any(|p| dominates_mixed(p, &points[i], &directions)))
+            .collect();
+        assert_eq!(non_dominated_indices(&points, &directions), brute);
+        match points.iter().position(|p| p.iter().any(|x| x.is_nan())) {
+            Some(point) => assert_eq!(try_non_dominated_indices(&points, &directions), Err(NanObjective { point })),
+            None => assert_eq!(try_non_dominated_indices(&points, &directions), Ok(brute)),
+        }
+    }
+}
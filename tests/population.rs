@@ -0,0 +1,38 @@
+use dears::crossover::*;
+use dears::mutation::*;
+use dears::population::*;
+use dears::selection::*;
+
+fn ones(genome: &[bool]) -> f64 {
+    genome.iter().filter(|&&bit| bit).count() as f64
+}
+
+#[test]
+fn build_and_run_one_generation() {
+    let mut seed = 0;
+    let mut pop = PopulationBuilder::new()
+        .init_with(20, || {
+            seed += 1;
+            (0..8).map(|i| (seed + i) % 3 == 0).collect::<Vec<bool>>()
+        })
+        .mutator(FlipBit { indpb: 0.2 })
+        .crossover(OnePoint)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build::<f64>()
+        .unwrap();
+    assert_eq!(pop.len(), 20);
+
+    let fitnesses: Vec<f64> = pop.individuals().iter().map(|genome| ones(genome)).collect();
+    let mut offspring = select_individuals(pop.selector(), pop.individuals(), &fitnesses, pop.len());
+    for pair in offspring.chunks_exact_mut(2) {
+        let (a, b) = pair.split_at_mut(1);
+        pop.crossover().crossover(&mut a[0], &mut b[0]);
+    }
+    for child in offspring.iter_mut() {
+        pop.mutator().mutate(child);
+    }
+    pop.individuals_mut().clone_from_slice(&offspring);
+
+    assert_eq!(pop.len(), 20);
+    assert!(pop.individuals().iter().all(|genome| genome.len() == 8));
+}
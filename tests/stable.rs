@@ -0,0 +1,58 @@
+//! Builds the core types with nothing beyond the compiler named by `rust-version` in the manifest
+//!
+//! The const-generic fitness arrays are the part most likely to tempt a
+//! nightly-only feature, so each of them is exercised at a couple of sizes.
+
+use dears::algorithms::{ea_simple, Elitism};
+use dears::archive::ParetoFront;
+use dears::bitstring::BitString;
+use dears::crossover::OnePoint;
+use dears::genome::{BoundedVec, Permutation};
+use dears::gp::PrimitiveSet;
+use dears::initializer::{init_population, Builder};
+use dears::multiobjective::non_dominated_sort;
+use dears::mutation::FlipBit;
+use dears::population::PopulationBuilder;
+use dears::scalarize::{Scalarizer, WeightedSum};
+use dears::selection::{Direction, TournamentSelection};
+use dears::stats::MultiStats;
+use dears::termination::MaxGenerations;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+#[test]
+fn core_types_build_on_stable() {
+    let mut rng = StdRng::seed_from_u64(209);
+    let mut pop = PopulationBuilder::new()
+        .init_from(10, &Builder::<Vec<bool>>::with(8, |rng: &mut dyn RngCore, _| rng.gen()), &mut rng)
+        .mutator(FlipBit { indpb: 0.1 })
+        .crossover(OnePoint)
+        .selector(TournamentSelection::new(3).unwrap())
+        .build()
+        .unwrap();
+    let eval = |genome: &Vec<bool>| genome.iter().filter(|&&bit| bit).count() as f64;
+    let (logbook, _) = ea_simple(&mut pop, MaxGenerations(2), 0.5, 0.2, Elitism::default(), eval, None, None,
+                                 &mut rng);
+    assert_eq!(logbook.len(), 3);
+
+    assert_eq!(BitString::from(&[true, false, true][..]).count_ones(), 2);
+    assert_eq!(&*Permutation::identity(3), &[0, 1, 2]);
+    assert!(BoundedVec::uniform(vec![0.5; 3], 0.0, 1.0).unwrap().in_bounds());
+    assert!(PrimitiveSet::arithmetic(1).generate(3, true, &mut rng).depth() <= 3);
+    let arrays: Vec<[usize; 4]> = init_population(2, &Builder::<[usize; 4]>::array(|_, i| i), &mut rng);
+    assert_eq!(arrays, vec![[0, 1, 2, 3]; 2]);
+}
+
+#[test]
+fn fitness_arrays_of_any_length() {
+    let pairs = [[1.0, 4.0], [2.0, 2.0], [3.0, 3.0]];
+    assert_eq!(non_dominated_sort(&pairs, Direction::Minimize), vec![vec![0, 1], vec![2]]);
+    let mut front = ParetoFront::<&str, 2>::new().with_direction(Direction::Minimize);
+    front.update(&["a", "b", "c"], &pairs);
+    assert_eq!(front.len(), 2);
+    assert_eq!(WeightedSum([0.5, 0.5]).scalarize(&pairs[0]), 2.5);
+
+    let triples = [[1.0, 2.0, 3.0], [0.0, 0.0, 0.0]];
+    let record = MultiStats::<3>::new(Direction::Minimize).compile(&triples);
+    assert_eq!(record.get("obj2_max"), Some(3.0));
+}